@@ -17,9 +17,9 @@ fn bench_interval_creation(c: &mut Criterion) {
 
 fn bench_chord_creation(c: &mut Criterion) {
     c.bench_function("major_triad_creation", |b| {
-        let root = MusicalNote::from_midi(60); // C4
+        let root = MusicalNote::from_midi(60).unwrap(); // C4
         b.iter(|| {
-            black_box(Chord::major_triad(black_box(root)))
+            black_box(Chord::major_triad(black_box(root)).unwrap())
         })
     });
 }
@@ -0,0 +1,166 @@
+//! Proof-of-authorship bundles
+//!
+//! An `AuthorshipBundle` binds a musical proof to its creator: the serialized
+//! proof, a digest of the public statement it proves and the creator's
+//! Ed25519 public key are all covered by one signature, so a valid bundle
+//! cannot be re-attributed or have its proof swapped out.
+
+use crate::zk::MusicalProof;
+use crate::{Result, ZyrkomError};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Domain separator for statement digests
+const STATEMENT_DOMAIN: &[u8] = b"zyrkom-statement-v1";
+
+/// Domain separator for authorship signatures
+const SIGNATURE_DOMAIN: &[u8] = b"zyrkom-authorship-v1";
+
+/// A musical proof signed by its creator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorshipBundle {
+    /// The bincode-serialized `MusicalProof`, kept as signed bytes
+    proof_bytes: Vec<u8>,
+    /// SHA-256 digest of the proof's public statement
+    statement_digest: [u8; 32],
+    /// Creator's Ed25519 public key
+    creator_public_key: [u8; 32],
+    /// Ed25519 signature over the digest, the public key and the proof bytes
+    signature: Vec<u8>,
+}
+
+impl AuthorshipBundle {
+    /// Sign a proof with the creator's key
+    pub fn create(proof: &MusicalProof, signing_key: &SigningKey) -> Result<Self> {
+        let proof_bytes = bincode::serialize(proof)?;
+        let statement_digest = statement_digest(proof);
+        let creator_public_key = signing_key.verifying_key().to_bytes();
+
+        let message = signed_message(&statement_digest, &creator_public_key, &proof_bytes);
+        let signature = signing_key.sign(&message).to_bytes().to_vec();
+
+        Ok(Self {
+            proof_bytes,
+            statement_digest,
+            creator_public_key,
+            signature,
+        })
+    }
+
+    /// Check that the digest matches the proof and that the creator signed it
+    ///
+    /// Returns `Ok(false)` for a forged or tampered bundle and an error when
+    /// the bundle is malformed. This does not verify the STARK proof itself.
+    pub fn verify(&self) -> Result<bool> {
+        let proof = self.proof()?;
+        if statement_digest(&proof) != self.statement_digest {
+            return Ok(false);
+        }
+
+        let verifying_key = self.creator_public_key()?;
+        let signature = Signature::from_slice(&self.signature).map_err(|e| ZyrkomError::ProofError {
+            reason: format!("Malformed authorship signature: {}", e),
+        })?;
+
+        let message = signed_message(&self.statement_digest, &self.creator_public_key, &self.proof_bytes);
+        Ok(verifying_key.verify_strict(&message, &signature).is_ok())
+    }
+
+    /// Decode the bundled proof
+    pub fn proof(&self) -> Result<MusicalProof> {
+        Ok(bincode::deserialize(&self.proof_bytes)?)
+    }
+
+    /// Get the statement digest
+    pub fn statement_digest(&self) -> &[u8; 32] {
+        &self.statement_digest
+    }
+
+    /// Get the creator's public key
+    pub fn creator_public_key(&self) -> Result<VerifyingKey> {
+        VerifyingKey::from_bytes(&self.creator_public_key).map_err(|e| ZyrkomError::ProofError {
+            reason: format!("Malformed creator public key: {}", e),
+        })
+    }
+
+    /// Whether the bundle was signed by the given creator
+    pub fn is_signed_by(&self, creator: &VerifyingKey) -> bool {
+        self.creator_public_key == creator.to_bytes()
+    }
+}
+
+/// SHA-256 digest of a proof's public statement: structure, constraint count
+/// and public inputs
+pub fn statement_digest(proof: &MusicalProof) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(STATEMENT_DOMAIN);
+    hasher.update(proof.metadata.structure_type.as_bytes());
+    hasher.update((proof.metadata.constraint_count as u64).to_le_bytes());
+    for input in &proof.public_inputs {
+        hasher.update(input.to_le_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// The exact bytes covered by the creator's signature
+fn signed_message(digest: &[u8; 32], public_key: &[u8; 32], proof_bytes: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(SIGNATURE_DOMAIN.len() + 64 + proof_bytes.len());
+    message.extend_from_slice(SIGNATURE_DOMAIN);
+    message.extend_from_slice(digest);
+    message.extend_from_slice(public_key);
+    message.extend_from_slice(proof_bytes);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::musical::MusicalInterval;
+    use crate::zk::{ToConstraints, ZyrkomProver};
+
+    fn fifth_proof() -> MusicalProof {
+        let system = MusicalInterval::perfect_fifth().to_constraints().unwrap();
+        ZyrkomProver::new(system).unwrap().prove().unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let bundle = AuthorshipBundle::create(&fifth_proof(), &key).unwrap();
+
+        assert!(bundle.verify().unwrap());
+        assert!(bundle.is_signed_by(&key.verifying_key()));
+        assert!(!bundle.is_signed_by(&SigningKey::from_bytes(&[8u8; 32]).verifying_key()));
+
+        // Survives a serialization round trip
+        let bytes = bincode::serialize(&bundle).unwrap();
+        let restored: AuthorshipBundle = bincode::deserialize(&bytes).unwrap();
+        assert!(restored.verify().unwrap());
+    }
+
+    #[test]
+    fn test_reattributed_bundle_fails() {
+        let bundle = AuthorshipBundle::create(&fifth_proof(), &SigningKey::from_bytes(&[7u8; 32])).unwrap();
+
+        let mut stolen = bundle.clone();
+        stolen.creator_public_key = SigningKey::from_bytes(&[9u8; 32]).verifying_key().to_bytes();
+        assert!(!stolen.verify().unwrap());
+    }
+
+    #[test]
+    fn test_tampered_statement_fails() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let bundle = AuthorshipBundle::create(&fifth_proof(), &key).unwrap();
+
+        let mut proof = bundle.proof().unwrap();
+        proof.public_inputs[0] += 1;
+        let mut tampered = bundle.clone();
+        tampered.proof_bytes = bincode::serialize(&proof).unwrap();
+        assert!(!tampered.verify().unwrap());
+
+        let mut truncated = bundle;
+        truncated.signature.pop();
+        assert!(truncated.verify().is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,367 @@
+//! Syntax tree of a Zyrkom DSL program
+//!
+//! The tree mirrors the source closely: names and literals keep their spans
+//! and nothing is resolved or validated yet. `ZyrkomParser` lowers it into
+//! `ParsedElement`s.
+
+use crate::dsl::lexer::Span;
+
+/// A value with the span it was read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    /// The value
+    pub node: T,
+    /// Where it was read
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    /// Attach a span to a value
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+}
+
+/// A name in the source
+pub type Ident = Spanned<String>;
+
+/// A whole source file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Program {
+    /// Statements in source order
+    pub statements: Vec<Statement>,
+}
+
+/// One top-level statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    /// What the statement declares
+    pub kind: StatementKind,
+    /// From the keyword to the last token of the statement
+    pub span: Span,
+}
+
+/// Top-level statements
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    /// `note C4 = 261.63`
+    Note {
+        /// Declared name
+        name: Ident,
+        /// Frequency in Hz
+        frequency: Spanned<f64>,
+    },
+    /// `chord C_major = C4 + E4 + G4`
+    Chord {
+        /// Declared name
+        name: Ident,
+        /// Names of the member notes
+        notes: Vec<Ident>,
+    },
+    /// `interval perfect_fifth = 1.5`
+    Interval {
+        /// Declared name
+        name: Ident,
+        /// Frequency ratio
+        ratio: Spanned<f64>,
+    },
+    /// `constraint harmony = G4 / C4 == perfect_fifth && in_scale(E4)`
+    Constraint {
+        /// Declared name
+        name: Ident,
+        /// The constraint expression
+        expression: Spanned<Expr>,
+    },
+    /// `time 3/4`
+    Time {
+        /// Beats per measure
+        numerator: Spanned<f64>,
+        /// Beat unit
+        denominator: Spanned<f64>,
+    },
+    /// `tempo 76` or `tempo 76 within 72..80`
+    Tempo {
+        /// Quarter notes per minute
+        bpm: Spanned<f64>,
+        /// Permitted BPM range
+        bounds: Option<(Spanned<f64>, Spanned<f64>)>,
+    },
+    /// `rhythm waltz = pickup q | h q | h.`
+    Rhythm {
+        /// Declared name
+        name: Ident,
+        /// Whether the first measure is an anacrusis
+        pickup: bool,
+        /// Duration tokens (`q`, `e.`, `e/3`) grouped by measure
+        measures: Vec<Vec<Spanned<String>>>,
+    },
+    /// `melody phrase = FA:q DO:e~ DO:e [LA:e SOL:e] * 2 rest:q`
+    Melody {
+        /// Declared name
+        name: Ident,
+        /// Notes, rests and repeats in order
+        items: Vec<Spanned<MelodyItem>>,
+    },
+    /// `reference A4 = 415` or `reference 415Hz`
+    Reference {
+        /// Frequency of A4 in Hz
+        frequency: Spanned<f64>,
+    },
+    /// `key F major`
+    Key {
+        /// Source text of the key (`F major`, `FA mayor`, `Bb`)
+        spec: Spanned<String>,
+    },
+    /// `instrument Piano { range: [A0, C8], resonance: overtones(16) }`
+    Instrument {
+        /// Declared name
+        name: Ident,
+        /// Properties in the braces
+        properties: Vec<Property>,
+    },
+    /// `progression ii_V_I(tonalidad: Key) { chord ii = tonalidad.triad(2) ... }`
+    Template(Template),
+    /// `ii_V_I(F major)`
+    Instance {
+        /// Name of the instantiated progression
+        template: Ident,
+        /// Arguments in order; several words (`F major`) read as one name
+        arguments: Vec<Spanned<Expr>>,
+    },
+}
+
+/// A parameterised block of declarations, expanded where it is instantiated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    /// Declared name
+    pub name: Ident,
+    /// Typed parameters in order
+    pub parameters: Vec<Parameter>,
+    /// Declarations in the braces
+    pub body: Vec<Local>,
+}
+
+/// `tonalidad: Key`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    /// Parameter name
+    pub name: Ident,
+    /// Name of the parameter's type
+    pub ty: Ident,
+}
+
+/// A declaration inside a template: `chord ii = tonalidad.triad(2)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Local {
+    /// What is declared
+    pub kind: LocalKind,
+    /// Declared name, local to the template
+    pub name: Ident,
+    /// Value, evaluated when the template is instantiated
+    pub value: Spanned<Expr>,
+}
+
+/// Kinds of template declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalKind {
+    /// `note`
+    Note,
+    /// `interval`
+    Interval,
+    /// `chord`
+    Chord,
+    /// `constraint`
+    Constraint,
+}
+
+impl LocalKind {
+    /// The keyword that declares it
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            LocalKind::Note => "note",
+            LocalKind::Interval => "interval",
+            LocalKind::Chord => "chord",
+            LocalKind::Constraint => "constraint",
+        }
+    }
+}
+
+/// Items of a melody literal
+#[derive(Debug, Clone, PartialEq)]
+pub enum MelodyItem {
+    /// `FA:q`, or `FA:q~` when tied to the next note
+    Note {
+        /// Name of the declared note
+        pitch: Ident,
+        /// Duration token (`q`, `e.`, `e/3`)
+        duration: Spanned<String>,
+        /// Whether the note is held into the next one
+        tied: bool,
+    },
+    /// `rest:q`
+    Rest {
+        /// Duration token
+        duration: Spanned<String>,
+    },
+    /// `[FA:e SOL:e] * 2`
+    Repeat {
+        /// The repeated items
+        items: Vec<Spanned<MelodyItem>>,
+        /// How many times they are played
+        count: Spanned<f64>,
+    },
+}
+
+/// Expressions of constraint declarations
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A number literal
+    Number(f64),
+    /// A declared note, interval or chord
+    Name(String),
+    /// `function(arguments)`
+    Call {
+        /// Function name
+        function: Ident,
+        /// Arguments in order
+        arguments: Vec<Spanned<Expr>>,
+    },
+    /// `receiver.field`
+    Field {
+        /// The value the field is read from
+        receiver: Box<Spanned<Expr>>,
+        /// Field name
+        field: Ident,
+    },
+    /// `receiver.method(arguments)`
+    Method {
+        /// The value the method is called on
+        receiver: Box<Spanned<Expr>>,
+        /// Method name
+        method: Ident,
+        /// Arguments in order
+        arguments: Vec<Spanned<Expr>>,
+    },
+    /// `-operand` or `!operand`
+    Unary {
+        /// The operator
+        operator: UnaryOperator,
+        /// The operand
+        operand: Box<Spanned<Expr>>,
+    },
+    /// `left operator right`
+    Binary {
+        /// The operator
+        operator: BinaryOperator,
+        /// Left operand
+        left: Box<Spanned<Expr>>,
+        /// Right operand
+        right: Box<Spanned<Expr>>,
+    },
+}
+
+/// Prefix operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    /// `-`
+    Negate,
+    /// `!`
+    Not,
+}
+
+/// Infix operators, loosest binding first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    /// `||`
+    Or,
+    /// `&&`
+    And,
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+}
+
+impl BinaryOperator {
+    /// The operator as written
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Or => "||",
+            BinaryOperator::And => "&&",
+            BinaryOperator::Eq => "==",
+            BinaryOperator::Ne => "!=",
+            BinaryOperator::Lt => "<",
+            BinaryOperator::Le => "<=",
+            BinaryOperator::Gt => ">",
+            BinaryOperator::Ge => ">=",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "/",
+        }
+    }
+
+    /// Binding strength: operators with higher precedence group first
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Eq
+            | BinaryOperator::Ne
+            | BinaryOperator::Lt
+            | BinaryOperator::Le
+            | BinaryOperator::Gt
+            | BinaryOperator::Ge => 3,
+            BinaryOperator::Add | BinaryOperator::Sub => 4,
+            BinaryOperator::Mul | BinaryOperator::Div => 5,
+        }
+    }
+
+    /// Whether the operator compares its operands
+    pub fn is_comparison(&self) -> bool {
+        self.precedence() == 3
+    }
+}
+
+/// `name: value` inside an instrument block
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    /// Property name
+    pub name: Ident,
+    /// Property value
+    pub value: Spanned<Value>,
+}
+
+/// Literal values of properties
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A number, possibly negative
+    Number(f64),
+    /// A bare name
+    Ident(String),
+    /// `name(arguments)`
+    Call {
+        /// Function name
+        name: Ident,
+        /// Arguments in order
+        arguments: Vec<Spanned<Value>>,
+    },
+    /// `[a, b]`
+    List(Vec<Spanned<Value>>),
+}
\ No newline at end of file
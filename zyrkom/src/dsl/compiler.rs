@@ -0,0 +1,198 @@
+//! Whole-program compilation
+//!
+//! Lowers every element of a program into one constraint system, the
+//! statement a proof is about, and builds a source map from each constraint
+//! back to the statement it came from. Proof JSON and verification errors use
+//! the map to name the line of the composition a constraint belongs to.
+
+use crate::dsl::ast::Spanned;
+use crate::dsl::diagnostic::{Diagnostic, Diagnostics};
+use crate::dsl::parser::{ParsedElement, ZyrkomParser};
+use crate::zk::constraints::{ConstraintSystem, PITCH_REFERENCE_PARAMETER};
+use crate::zk::source_map::{SourceLocation, SourceMap};
+
+/// A program lowered into a single constraint system
+#[derive(Debug, Clone)]
+pub struct CompiledProgram {
+    elements: Vec<Spanned<ParsedElement>>,
+    system: ConstraintSystem,
+    source_map: SourceMap,
+}
+
+impl CompiledProgram {
+    /// The parsed elements with their statement spans
+    pub fn elements(&self) -> &[Spanned<ParsedElement>] {
+        &self.elements
+    }
+
+    /// The combined constraint system
+    pub fn system(&self) -> &ConstraintSystem {
+        &self.system
+    }
+
+    /// Where each constraint of the system was declared
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    /// Split into the constraint system and its source map
+    pub fn into_parts(self) -> (ConstraintSystem, SourceMap) {
+        (self.system, self.source_map)
+    }
+}
+
+/// Compiles Zyrkom DSL programs into constraint systems
+#[derive(Debug, Clone, Default)]
+pub struct Compiler;
+
+impl Compiler {
+    /// Create a compiler
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Parse `source` and lower every element into one constraint system
+    ///
+    /// Constraints and public parameters keep source order. The concert pitch
+    /// and the key are the exception: the statement holds each once, with
+    /// the last declared value.
+    pub fn compile(&self, source: &str) -> Result<CompiledProgram, Diagnostics> {
+        let elements = ZyrkomParser::new().parse_spanned(source)?;
+
+        let mut system = ConstraintSystem::new();
+        let mut locations = Vec::new();
+        let mut diagnostics = Vec::new();
+        for element in &elements {
+            let compiled = match element.node.to_constraints() {
+                Ok(compiled) => compiled,
+                Err(error) => {
+                    diagnostics.push(Diagnostic::new(error, element.span, source));
+                    continue;
+                }
+            };
+            let location = SourceLocation::new(element.node.name(), element.span.start..element.span.end, source);
+            locations.extend(std::iter::repeat_n(location, compiled.constraint_count()));
+            let reference = compiled
+                .public_parameters
+                .iter()
+                .any(|p| p.name == PITCH_REFERENCE_PARAMETER)
+                .then(|| compiled.pitch_reference());
+            let key = compiled.key();
+            system.merge(compiled);
+            if let Some(reference) = reference {
+                system.declare_pitch_reference(reference);
+            }
+            if let Some(key) = key {
+                system.declare_key(key);
+            }
+        }
+        if !diagnostics.is_empty() {
+            return Err(Diagnostics::new(diagnostics));
+        }
+
+        Ok(CompiledProgram {
+            elements,
+            system,
+            source_map: SourceMap::new(locations),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::expression::Constant;
+    use crate::dsl::parser::ParseError;
+    use crate::musical::{ChordQuality, Key, PitchReference};
+    use crate::zk::{ZyrkomProver, ZyrkomVerifier};
+
+    const PROGRAM: &str = "reference 442\nnote C4 = 262.83\nnote E4 = 331.14\nnote G4 = 393.79\n\
+                           chord C_major = C4 + E4 + G4\nconstraint fifth = G4 / C4 == 1.5 && in_scale(C_major)\nkey C major";
+
+    #[test]
+    fn test_compiles_every_element_with_a_source_map() {
+        let program = Compiler::new().compile(PROGRAM).unwrap();
+        let system = program.system();
+
+        let expected: usize = program
+            .elements()
+            .iter()
+            .map(|element| element.node.to_constraints().unwrap().constraint_count())
+            .sum();
+        assert_eq!(system.constraint_count(), expected);
+        assert_eq!(program.source_map().len(), expected);
+
+        // Three notes, the chord, then the constraint's four comparisons
+        let lines: Vec<usize> = program.source_map().locations().iter().map(|l| l.line).collect();
+        assert_eq!(&lines[..3], &[2, 3, 4]);
+        assert!(lines[3..expected - 4].iter().all(|&line| line == 5));
+        assert_eq!(&lines[expected - 4..], &[6, 6, 6, 6]);
+        assert_eq!(program.source_map().location(expected - 1).unwrap().to_string(), "`fifth` at line 6");
+        assert_eq!(system.relationships.last().unwrap().constraint_indices.first(), Some(&(expected - 4)));
+
+        // One concert pitch and one key, whatever declared them
+        let references = system.public_parameters.iter().filter(|p| p.name == PITCH_REFERENCE_PARAMETER).count();
+        assert_eq!(references, 1);
+        assert_eq!(system.pitch_reference(), PitchReference::new(442.0).unwrap());
+        assert_eq!(system.key(), Some(Key::parse("C major").unwrap()));
+    }
+
+    #[test]
+    fn test_every_element_keeps_its_public_parameters() {
+        let source = "note C4 = 261.63\nnote EB4 = 311.13\nnote E4 = 329.63\nnote G4 = 392.00\n\
+                      chord Cmaj = C4 + E4 + G4\nchord Cmin = C4 + EB4 + G4";
+        let program = Compiler::new().compile(source).unwrap();
+        let qualities: Vec<u32> = program
+            .system()
+            .public_parameters
+            .iter()
+            .filter(|p| p.name == "chord_quality")
+            .map(|p| p.value)
+            .collect();
+        assert_eq!(qualities, vec![ChordQuality::Major.id(), ChordQuality::Minor.id()]);
+        let inversions = program.system().public_parameters.iter().filter(|p| p.name == "chord_inversion").count();
+        assert_eq!(inversions, 2);
+    }
+
+    #[test]
+    fn test_lowering_errors_are_located() {
+        let source = "interval fifth = 1.5\nconstraint wide = fifth * 7";
+        let errors = Compiler::new().compile(source).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        let error = errors.first().unwrap();
+        assert_eq!((error.line(), error.column()), (2, 1));
+        assert!(matches!(error.error(), ParseError::TypeError(_)));
+    }
+
+    #[test]
+    fn test_false_comparison_is_unsatisfied() {
+        // In thousandths the slack is -1200001
+        let program = Compiler::new().compile("constraint bogus = 2000 < 800").unwrap();
+        assert!(matches!(
+            &program.elements()[0].node,
+            ParsedElement::Constraint { value: Constant::Condition(condition), .. } if !condition.holds()
+        ));
+        assert_eq!(program.system().constraint_count(), 1);
+        assert!(!program.system().constraints[0].is_satisfied());
+    }
+
+    #[test]
+    fn test_proof_json_and_verifier_cite_lines() {
+        let (system, source_map) = Compiler::new().compile(PROGRAM).unwrap().into_parts();
+        let prover = ZyrkomProver::new(system.clone()).unwrap().with_source_map(source_map.clone());
+        let proof = prover.prove().unwrap();
+
+        let json = prover
+            .generate_proof_json(&proof, std::path::Path::new("p.zk"), std::path::Path::new("p.zyrkom"), 0)
+            .unwrap();
+        let last = json.musical_constraints.last().unwrap();
+        assert_eq!(last.source.as_ref().map(|s| s.line), Some(6));
+
+        // The same statement with a different fifth fails at the constraint's line
+        let tampered = PROGRAM.replace("== 1.5", "== 1.6");
+        let (other, other_map) = Compiler::new().compile(&tampered).unwrap().into_parts();
+        let verifier = ZyrkomVerifier::new(other).unwrap().with_source_map(other_map);
+        let error = verifier.verify(&proof).unwrap_err().to_string();
+        assert!(error.contains("`fifth` at line 6"), "{}", error);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,241 @@
+//! Located DSL errors and rustc-style rendering
+//!
+//! A `Diagnostic` pairs a `ParseError` with the span it refers to, resolved
+//! to a 1-based line and column, and keeps the offending source line so it
+//! can be rendered on its own:
+//!
+//! ```text
+//! error: expected `=`, found number `261.63`
+//!  --> song.zyrkom:2:9
+//!   |
+//! 2 | note C4 261.63
+//!   |         ^^^^^^ expected `=`
+//! ```
+
+use std::fmt;
+
+use crate::dsl::lexer::Span;
+use crate::dsl::parser::ParseError;
+use crate::ZyrkomError;
+
+/// A parse error located in the source
+///
+/// Boxed so that `Result<_, Diagnostic>` stays small on the happy path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic(Box<Located>);
+
+#[derive(Debug, Clone, PartialEq)]
+struct Located {
+    error: ParseError,
+    span: Span,
+    line: usize,
+    column: usize,
+    source_line: String,
+    label: Option<String>,
+}
+
+impl Diagnostic {
+    /// Locate an error at a span of `source`
+    pub fn new(error: ParseError, span: Span, source: &str) -> Self {
+        let start = span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+        let label = match &error {
+            ParseError::UnexpectedToken { expected, .. } => Some(format!("expected {}", join_expected(expected))),
+            _ => None,
+        };
+        Self(Box::new(Located {
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            source_line: source[line_start..line_end].trim_end_matches('\r').to_string(),
+            error,
+            span,
+            label,
+        }))
+    }
+
+    /// Replace the note shown under the caret
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.0.label = Some(label.to_string());
+        self
+    }
+
+    /// Get the underlying error
+    pub fn error(&self) -> &ParseError {
+        &self.0.error
+    }
+
+    /// Get the byte span
+    pub fn span(&self) -> Span {
+        self.0.span
+    }
+
+    /// Get the 1-based line
+    pub fn line(&self) -> usize {
+        self.0.line
+    }
+
+    /// Get the 1-based column, in characters
+    pub fn column(&self) -> usize {
+        self.0.column
+    }
+
+    /// Render the error with its source line, naming the file it came from
+    pub fn render(&self, path: &str) -> String {
+        self.render_with_location(&format!("{}:{}:{}", path, self.line(), self.column()))
+    }
+
+    fn render_with_location(&self, location: &str) -> String {
+        let Located { error, span, line, column, source_line, label } = &*self.0;
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+        let indent = " ".repeat(column - 1);
+
+        // Underline the span's characters, stopping at the end of the line
+        let start = source_line.char_indices().nth(column - 1).map_or(source_line.len(), |(i, _)| i);
+        let end = (start + span.len()).min(source_line.len());
+        let width = source_line.get(start..end).map_or(0, |text| text.chars().count()).max(1);
+
+        let mut rendered = format!(
+            "error: {}\n{}--> {}\n{} |\n{} | {}\n{} | {}{}",
+            error, gutter, location, gutter, number, source_line, gutter, indent, "^".repeat(width)
+        );
+        if let Some(label) = label {
+            rendered.push(' ');
+            rendered.push_str(label);
+        }
+        rendered
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render_with_location(&format!("line {}:{}", self.line(), self.column())))
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Every error found in one pass over a source, in source order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Collect diagnostics, sorting them by position
+    pub fn new(mut diagnostics: Vec<Diagnostic>) -> Self {
+        diagnostics.sort_by_key(|d| d.span().start);
+        Self { diagnostics }
+    }
+
+    /// Get the diagnostics
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Number of errors
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    /// Whether there are no errors
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// The first error in the source, if any
+    pub fn first(&self) -> Option<&Diagnostic> {
+        self.diagnostics.first()
+    }
+
+    /// Render every error, naming the file they came from
+    pub fn render(&self, path: &str) -> String {
+        let mut rendered: Vec<String> = self.diagnostics.iter().map(|d| d.render(path)).collect();
+        if self.len() > 1 {
+            rendered.push(format!("error: could not parse {} due to {} errors", path, self.len()));
+        }
+        rendered.join("\n\n")
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rendered: Vec<String> = self.diagnostics.iter().map(|d| d.to_string()).collect();
+        write!(f, "{}", rendered.join("\n\n"))
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+impl From<Diagnostic> for ZyrkomError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        ZyrkomError::ParseError {
+            message: diagnostic.error().to_string(),
+            line: diagnostic.line(),
+        }
+    }
+}
+
+impl From<Diagnostics> for ZyrkomError {
+    fn from(diagnostics: Diagnostics) -> Self {
+        let more = diagnostics.len().saturating_sub(1);
+        match diagnostics.diagnostics.into_iter().next() {
+            Some(first) => {
+                let mut error = ZyrkomError::from(first);
+                if let ZyrkomError::ParseError { message, .. } = &mut error {
+                    if more > 0 {
+                        message.push_str(&format!(" (and {} more)", more));
+                    }
+                }
+                error
+            }
+            None => ZyrkomError::ParseError {
+                message: "No diagnostics".to_string(),
+                line: 0,
+            },
+        }
+    }
+}
+
+/// "`=`", "`=` or number", "`a`, `b` or `c`"
+pub(crate) fn join_expected(expected: &[String]) -> String {
+    match expected {
+        [] => "nothing".to_string(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_column_and_rendering() {
+        let source = "note C4 = 261.63\nnote E4 329.63\n";
+        let error = ParseError::UnexpectedToken {
+            expected: vec!["`=`".to_string()],
+            found: "number `329.63`".to_string(),
+        };
+        let diagnostic = Diagnostic::new(error, Span::new(25, 31), source);
+        assert_eq!((diagnostic.line(), diagnostic.column()), (2, 9));
+        assert_eq!(
+            diagnostic.render("song.zyrkom"),
+            "error: expected `=`, found number `329.63`\n \
+             --> song.zyrkom:2:9\n  |\n2 | note E4 329.63\n  |         ^^^^^^ expected `=`"
+        );
+        assert_eq!(ZyrkomError::from(diagnostic).to_string(), "DSL parsing error: expected `=`, found number `329.63` at line 2");
+    }
+
+    #[test]
+    fn test_diagnostics_are_sorted() {
+        let source = "a\nb\nc";
+        let at = |offset: usize| Diagnostic::new(ParseError::UnknownStatement("x".to_string()), Span::new(offset, offset + 1), source);
+        let diagnostics = Diagnostics::new(vec![at(4), at(0), at(2)]);
+        let lines: Vec<usize> = diagnostics.diagnostics().iter().map(|d| d.line()).collect();
+        assert_eq!(lines, vec![1, 2, 3]);
+        assert!(diagnostics.render("f.zyrkom").ends_with("could not parse f.zyrkom due to 3 errors"));
+        assert_eq!(join_expected(&["`a`".to_string(), "`b`".to_string(), "`c`".to_string()]), "`a`, `b` or `c`");
+    }
+}
\ No newline at end of file
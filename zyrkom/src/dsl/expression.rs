@@ -0,0 +1,692 @@
+//! Type checking and compilation of constraint expressions
+//!
+//! Every operand of a constraint expression is a declared note, interval or
+//! chord or a number literal, so an expression is evaluated while it is type
+//! checked. The result is a `Constant`: a value, or a `Condition` built from
+//! comparisons, `in_scale` checks and boolean connectives.
+//!
+//! | operation                         | result   |
+//! |-----------------------------------|----------|
+//! | number `+ - * /` number           | number   |
+//! | note `/` note                     | interval |
+//! | note `±` interval                 | note     |
+//! | interval `±` interval             | interval |
+//! | interval `*` number               | interval |
+//! | note or chord `+` note or chord   | chord    |
+//! | key `.degree(n)`                  | note     |
+//! | key `.triad(n)`                   | chord    |
+//! | chord `.root`                     | note     |
+//! | comparison of like values         | condition|
+//! | condition `&&`, `||`, `!`         | condition|
+//!
+//! Numbers compared with intervals are read as frequency ratios. Notes and
+//! intervals compare by pitch in cents, and `==`/`!=` between them allow
+//! `DEFAULT_TUNING_TOLERANCE_CENTS` of mistuning.
+//!
+//! Built-ins: `interval(a, b)` from note `a` up to note `b`, `cents(x)` of an
+//! interval or ratio, `distance(a, b)` in semitones between two notes or the
+//! voice-leading displacement between two chords, and `in_scale(x)` for a note
+//! or chord against the program's declared key, or `in_scale(x, key)` against
+//! another. Keys are values only inside progressions, where a parameter may
+//! be one; Spanish names (`grado`, `triada`, `fundamental`) are accepted for
+//! the key and chord members.
+
+use std::collections::HashMap;
+
+use crate::dsl::ast::{BinaryOperator, Expr, Spanned, UnaryOperator};
+use crate::dsl::diagnostic::Diagnostic;
+use crate::dsl::lexer::Span;
+use crate::dsl::parser::ParseError;
+use crate::dsl::resolve::{SymbolKind, SymbolTable};
+use crate::musical::{Chord, Key, MusicalInterval, MusicalNote, PitchReference};
+use crate::zk::constraints::{
+    ConstraintRelationship, ConstraintSystem, ConstraintType, MusicalConstraint, RelationshipType,
+    ToConstraints, DEFAULT_TUNING_TOLERANCE_CENTS,
+};
+use crate::Result;
+
+/// Fixed point scale for compared values (thousandths of a unit or cent)
+const FIXED_POINT_SCALE: f64 = 1000.0;
+
+/// Highest scale degree an expression may name: five octaves of a
+/// seven-note scale
+const MAX_SCALE_DEGREE: i32 = 36;
+
+/// Largest magnitude a compared value may have, so that the slack of any
+/// comparison between two of them fits a range check
+const MAX_COMPARED_MAGNITUDE: f64 = 500_000.0;
+
+/// Value of an evaluated expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    /// A plain number
+    Number(f64),
+    /// A pitch
+    Note(MusicalNote),
+    /// A frequency ratio
+    Interval(MusicalInterval),
+    /// Pitches sounding together
+    Chord(Vec<MusicalNote>),
+    /// Something that must hold
+    Condition(Condition),
+    /// A key, passed to a progression
+    Key(Key),
+}
+
+impl Constant {
+    /// Name of the value's type, for messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Constant::Number(_) => "number",
+            Constant::Note(_) => "note",
+            Constant::Interval(_) => "interval",
+            Constant::Chord(_) => "chord",
+            Constant::Condition(_) => "condition",
+            Constant::Key(_) => "key",
+        }
+    }
+
+    /// Compile the value a constraint declaration asserts
+    ///
+    /// Conditions compile to their comparisons; chords and intervals to
+    /// their harmonic constraints. Plain numbers, notes and keys assert nothing.
+    pub fn to_constraints(&self) -> Result<ConstraintSystem> {
+        match self {
+            Constant::Condition(condition) => {
+                let mut system = ConstraintSystem::new();
+                condition.compile(&mut system)?;
+                system.validate()?;
+                Ok(system)
+            }
+            Constant::Chord(pitches) if !pitches.is_empty() => {
+                Chord::from_voicing(pitches[0], pitches.clone())?.to_constraints()
+            }
+            Constant::Interval(interval) => interval.to_constraints(),
+            _ => Ok(ConstraintSystem::new()),
+        }
+    }
+}
+
+/// How two compared values must relate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// Equal, within the tolerance
+    Eq,
+    /// Further apart than the tolerance
+    Ne,
+    /// Strictly less
+    Lt,
+    /// Less or equal
+    Le,
+    /// Strictly greater
+    Gt,
+    /// Greater or equal
+    Ge,
+}
+
+impl Comparison {
+    /// The comparison that holds exactly when this one does not
+    pub fn negate(self) -> Self {
+        match self {
+            Comparison::Eq => Comparison::Ne,
+            Comparison::Ne => Comparison::Eq,
+            Comparison::Lt => Comparison::Ge,
+            Comparison::Le => Comparison::Gt,
+            Comparison::Gt => Comparison::Le,
+            Comparison::Ge => Comparison::Lt,
+        }
+    }
+}
+
+/// A boolean combination of checks, with negations pushed down to the checks
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Two fixed point values compared, `==` and `!=` allowing a tolerance
+    Compare {
+        /// Left value, in thousandths
+        left: i64,
+        /// How the values must relate
+        comparison: Comparison,
+        /// Right value, in thousandths
+        right: i64,
+        /// Permitted difference for `==` and `!=`, in thousandths
+        tolerance: i64,
+    },
+    /// A pitch class belongs, or does not belong, to a key
+    InScale {
+        /// The pitch class checked (0 = C)
+        pitch_class: u8,
+        /// The key
+        key: Key,
+        /// Whether the pitch class must lie outside the key
+        negated: bool,
+    },
+    /// Every condition holds
+    All(Vec<Condition>),
+    /// At least one condition holds
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    /// The condition that holds exactly when this one does not
+    pub fn negate(self) -> Self {
+        match self {
+            Condition::Compare { left, comparison, right, tolerance } => Condition::Compare {
+                left,
+                comparison: comparison.negate(),
+                right,
+                tolerance,
+            },
+            Condition::InScale { pitch_class, key, negated } => Condition::InScale { pitch_class, key, negated: !negated },
+            Condition::All(conditions) => Condition::Any(conditions.into_iter().map(Condition::negate).collect()),
+            Condition::Any(conditions) => Condition::All(conditions.into_iter().map(Condition::negate).collect()),
+        }
+    }
+
+    /// Whether the condition holds
+    pub fn holds(&self) -> bool {
+        match self {
+            Condition::All(conditions) => conditions.iter().all(Condition::holds),
+            Condition::Any(conditions) => conditions.iter().any(Condition::holds),
+            check => check.slack().is_some_and(|slack| slack >= 0),
+        }
+    }
+
+    /// Slack of a single check: non-negative exactly when it holds
+    fn slack(&self) -> Option<i64> {
+        match *self {
+            Condition::Compare { left, comparison, right, tolerance } => Some(match comparison {
+                Comparison::Eq => tolerance - (left - right).abs(),
+                Comparison::Ne => (left - right).abs() - tolerance - 1,
+                Comparison::Lt => right - left - 1,
+                Comparison::Le => right - left,
+                Comparison::Gt => left - right - 1,
+                Comparison::Ge => left - right,
+            }),
+            Condition::InScale { pitch_class, key, negated } => {
+                // Semitones to the nearest pitch class of the key
+                let distance = key
+                    .pitch_classes()
+                    .iter()
+                    .map(|&pc| {
+                        let d = (i64::from(pc) - i64::from(pitch_class)).rem_euclid(12);
+                        d.min(12 - d)
+                    })
+                    .min()
+                    .unwrap_or(0);
+                Some(if negated { distance - 1 } else { -distance })
+            }
+            Condition::All(_) | Condition::Any(_) => None,
+        }
+    }
+
+    /// Add the condition's range checks to `system`, returning their indices
+    ///
+    /// Each check becomes one range-checked slack and conjunctions are
+    /// recorded as relationships. A disjunction is proved by the first branch
+    /// that holds, or by the first branch when none does.
+    pub fn compile(&self, system: &mut ConstraintSystem) -> Result<Vec<usize>> {
+        let indices = match self {
+            Condition::All(conditions) => {
+                let mut indices = Vec::new();
+                for condition in conditions {
+                    indices.extend(condition.compile(system)?);
+                }
+                indices
+            }
+            Condition::Any(conditions) => match conditions.iter().find(|c| c.holds()).or(conditions.first()) {
+                Some(branch) => return branch.compile(system),
+                None => Vec::new(),
+            },
+            check => {
+                let slack = check.slack().unwrap_or_default();
+                vec![system.add_constraint(MusicalConstraint::range_check(slack, ConstraintType::Comparison)?)]
+            }
+        };
+        if indices.len() > 1 {
+            system.add_relationship(ConstraintRelationship {
+                constraint_indices: indices.clone(),
+                relationship_type: RelationshipType::Conjunction,
+            });
+        }
+        Ok(indices)
+    }
+}
+
+/// Values bound inside a progression: its parameters and the declarations
+/// made so far, which shadow the program's declarations
+#[derive(Debug, Clone)]
+pub struct Scope {
+    bindings: HashMap<String, Constant>,
+    reference: PitchReference,
+}
+
+impl Scope {
+    /// An empty scope whose key degrees sound at `reference`
+    pub fn new(reference: PitchReference) -> Self {
+        Self { bindings: HashMap::new(), reference }
+    }
+
+    /// Bind a name
+    pub fn bind(&mut self, name: &str, value: Constant) {
+        self.bindings.insert(name.to_string(), value);
+    }
+
+    /// The value bound to a name
+    pub fn get(&self, name: &str) -> Option<&Constant> {
+        self.bindings.get(name)
+    }
+}
+
+/// Type check and evaluate an expression against the program's declarations
+pub fn evaluate(expression: &Spanned<Expr>, symbols: &SymbolTable, source: &str) -> std::result::Result<Constant, Diagnostic> {
+    evaluate_in(expression, symbols, &Scope::new(PitchReference::standard()), source)
+}
+
+/// Type check and evaluate an expression with the names of a scope in view
+pub fn evaluate_in(
+    expression: &Spanned<Expr>,
+    symbols: &SymbolTable,
+    scope: &Scope,
+    source: &str,
+) -> std::result::Result<Constant, Diagnostic> {
+    Evaluator { symbols, scope }.evaluate(expression).map_err(|(error, span, label)| {
+        let diagnostic = Diagnostic::new(error, span, source);
+        match label {
+            Some(label) => diagnostic.with_label(&label),
+            None => diagnostic,
+        }
+    })
+}
+
+type Evaluated = std::result::Result<Constant, (ParseError, Span, Option<String>)>;
+
+struct Evaluator<'a> {
+    symbols: &'a SymbolTable,
+    scope: &'a Scope,
+}
+
+impl Evaluator<'_> {
+    fn evaluate(&self, expression: &Spanned<Expr>) -> Evaluated {
+        let span = expression.span;
+        let type_error = |message: String| Err((ParseError::TypeError(message), span, None));
+
+        match &expression.node {
+            Expr::Number(value) => Ok(Constant::Number(*value)),
+            Expr::Name(name) => {
+                if let Some(value) = self.scope.get(name) {
+                    return Ok(value.clone());
+                }
+                let symbol = self.symbols.get(name).ok_or_else(|| {
+                    (ParseError::UndefinedReference(name.clone()), span, Some("not declared".to_string()))
+                })?;
+                match &symbol.kind {
+                    SymbolKind::Note(Some(pitch)) => Ok(Constant::Note(*pitch)),
+                    SymbolKind::Interval(ratio) if *ratio > 0.0 => Ok(Constant::Interval(ratio_interval(*ratio))),
+                    SymbolKind::Chord(_) => Ok(Constant::Chord(self.symbols.chord(name).unwrap_or_default())),
+                    SymbolKind::Note(None) => Err((ParseError::InvalidFrequency(name.clone()), span, None)),
+                    SymbolKind::Interval(ratio) => Err((ParseError::InvalidRatio(format!("{} = {}", name, ratio)), span, None)),
+                    other => type_error(format!("{} is declared as a {}, not a value", name, other.describe())),
+                }
+            }
+            Expr::Unary { operator, operand } => match (operator, self.evaluate(operand)?) {
+                (UnaryOperator::Negate, Constant::Number(value)) => Ok(Constant::Number(-value)),
+                (UnaryOperator::Negate, Constant::Interval(interval)) => {
+                    Ok(Constant::Interval(MusicalInterval::from_cents(-interval.cents())))
+                }
+                (UnaryOperator::Not, Constant::Condition(condition)) => Ok(Constant::Condition(condition.negate())),
+                (UnaryOperator::Negate, other) => type_error(format!("cannot negate {}", other.type_name())),
+                (UnaryOperator::Not, other) => type_error(format!("`!` needs a condition, found {}", other.type_name())),
+            },
+            Expr::Binary { operator, left, right } => {
+                let (left, right) = (self.evaluate(left)?, self.evaluate(right)?);
+                let result = match operator {
+                    BinaryOperator::And | BinaryOperator::Or => connect(*operator, left, right),
+                    operator if operator.is_comparison() => compare(*operator, left, right),
+                    operator => arithmetic(*operator, left, right),
+                };
+                result.or_else(type_error)
+            }
+            Expr::Call { function, arguments } => {
+                let values = arguments.iter().map(|argument| self.evaluate(argument)).collect::<std::result::Result<Vec<_>, _>>()?;
+                self.call(&function.node, values).map_err(|error| match error {
+                    CallError::Unknown => (
+                        ParseError::UndefinedReference(function.node.clone()),
+                        function.span,
+                        Some("not a built-in function".to_string()),
+                    ),
+                    CallError::Invalid(message) => (ParseError::TypeError(message), span, None),
+                })
+            }
+            Expr::Field { receiver, field } => match (self.evaluate(receiver)?, field.node.as_str()) {
+                (Constant::Chord(pitches), "root" | "fundamental") => match pitches.first() {
+                    Some(root) => Ok(Constant::Note(*root)),
+                    None => type_error("an empty chord has no root".to_string()),
+                },
+                (Constant::Key(key), "tonic" | "tonica" | "tónica") => self.degree(&key, 1).map(Constant::Note).or_else(type_error),
+                (value, field) => type_error(format!("{} has no field `{}`", value.type_name(), field)),
+            },
+            Expr::Method { receiver, method, arguments } => {
+                let receiver = self.evaluate(receiver)?;
+                let values = arguments.iter().map(|argument| self.evaluate(argument)).collect::<std::result::Result<Vec<_>, _>>()?;
+                let result = match (&receiver, method.node.as_str(), values.as_slice()) {
+                    (Constant::Key(key), "degree" | "grado", [Constant::Number(degree)]) => {
+                        scale_degree(*degree).and_then(|degree| self.degree(key, degree)).map(Constant::Note)
+                    }
+                    // Stacked thirds of the scale, root position
+                    (Constant::Key(key), "triad" | "triada" | "tríada", [Constant::Number(degree)]) => {
+                        scale_degree(*degree).and_then(|degree| {
+                            [degree, degree + 2, degree + 4].iter().map(|&d| self.degree(key, d)).collect()
+                        })
+                        .map(Constant::Chord)
+                    }
+                    (Constant::Key(_), "degree" | "grado" | "triad" | "triada" | "tríada", _) => {
+                        Err(format!("{} takes one scale degree", method.node))
+                    }
+                    (value, method, _) => Err(format!("{} has no method `{}`", value.type_name(), method)),
+                };
+                result.or_else(type_error)
+            }
+        }
+    }
+
+    /// The note at a 1-based degree of a key, its tonic in octave 4
+    fn degree(&self, key: &Key, degree: i32) -> std::result::Result<MusicalNote, String> {
+        key.scale(self.scope.reference).degree(degree).map_err(|e| e.to_string())
+    }
+
+    fn call(&self, function: &str, arguments: Vec<Constant>) -> std::result::Result<Constant, CallError> {
+        let invalid = |message: &str| CallError::Invalid(message.to_string());
+        let value = match (function, arguments.as_slice()) {
+            ("interval", [Constant::Note(low), Constant::Note(high)]) => {
+                Constant::Interval(ratio_interval(high.frequency() / low.frequency()))
+            }
+            ("interval", _) => return Err(invalid("interval takes two notes")),
+            ("cents", [Constant::Interval(interval)]) => Constant::Number(interval.cents()),
+            ("cents", [Constant::Number(ratio)]) if *ratio > 0.0 => Constant::Number(MusicalInterval::ratio_to_cents(*ratio)),
+            ("cents", _) => return Err(invalid("cents takes an interval or a positive ratio")),
+            ("distance", [Constant::Note(a), Constant::Note(b)]) => {
+                Constant::Number(f64::from((i32::from(a.midi_number()) - i32::from(b.midi_number())).abs()))
+            }
+            ("distance", [Constant::Chord(a), Constant::Chord(b)]) => {
+                let voice_leading = chord(a)
+                    .and_then(|a| chord(b).and_then(|b| a.voice_leading_to(&b)))
+                    .map_err(|e| CallError::Invalid(e.to_string()))?;
+                Constant::Number(f64::from(voice_leading.total_displacement()))
+            }
+            ("distance", _) => return Err(invalid("distance takes two notes or two chords")),
+            ("in_scale", [value @ (Constant::Note(_) | Constant::Chord(_)), rest @ ..]) if rest.len() <= 1 => {
+                let key = match rest {
+                    [Constant::Key(key)] => *key,
+                    [_] => return Err(invalid("in_scale takes a note or a chord, and optionally a key")),
+                    _ => self.symbols.key().ok_or_else(|| invalid("in_scale needs a `key` declaration"))?,
+                };
+                let pitches = match value {
+                    Constant::Note(pitch) => vec![*pitch],
+                    Constant::Chord(pitches) => pitches.clone(),
+                    _ => Vec::new(),
+                };
+                let checks = pitches
+                    .iter()
+                    .map(|pitch| Condition::InScale { pitch_class: pitch.midi_number() % 12, key, negated: false })
+                    .collect();
+                Constant::Condition(Condition::All(checks))
+            }
+            ("in_scale", _) => return Err(invalid("in_scale takes a note or a chord, and optionally a key")),
+            _ => return Err(CallError::Unknown),
+        };
+        Ok(value)
+    }
+}
+
+enum CallError {
+    Unknown,
+    Invalid(String),
+}
+
+/// `&&` and `||`, flattening nested connectives of the same kind
+fn connect(operator: BinaryOperator, left: Constant, right: Constant) -> std::result::Result<Constant, String> {
+    let (left, right) = match (left, right) {
+        (Constant::Condition(left), Constant::Condition(right)) => (left, right),
+        (left, right) => {
+            return Err(format!(
+                "`{}` needs conditions, found {} and {}",
+                operator.symbol(),
+                left.type_name(),
+                right.type_name()
+            ))
+        }
+    };
+    let all = operator == BinaryOperator::And;
+    let mut conditions = Vec::new();
+    for condition in [left, right] {
+        match condition {
+            Condition::All(inner) if all => conditions.extend(inner),
+            Condition::Any(inner) if !all => conditions.extend(inner),
+            other => conditions.push(other),
+        }
+    }
+    Ok(Constant::Condition(if all { Condition::All(conditions) } else { Condition::Any(conditions) }))
+}
+
+/// Comparisons between like values
+fn compare(operator: BinaryOperator, left: Constant, right: Constant) -> std::result::Result<Constant, String> {
+    let fixed = |value: f64| (value * FIXED_POINT_SCALE).round() as i64;
+    let cents_tolerance = fixed(DEFAULT_TUNING_TOLERANCE_CENTS);
+    let (left_value, right_value, tolerance) = match (&left, &right) {
+        (Constant::Number(a), Constant::Number(b)) => (*a, *b, 0),
+        (Constant::Interval(a), Constant::Interval(b)) => (a.cents(), b.cents(), cents_tolerance),
+        (Constant::Interval(a), Constant::Number(ratio)) if *ratio > 0.0 => {
+            (a.cents(), MusicalInterval::ratio_to_cents(*ratio), cents_tolerance)
+        }
+        (Constant::Number(ratio), Constant::Interval(b)) if *ratio > 0.0 => {
+            (MusicalInterval::ratio_to_cents(*ratio), b.cents(), cents_tolerance)
+        }
+        // Absolute pitch in cents above 1 Hz
+        (Constant::Note(a), Constant::Note(b)) => (
+            MusicalInterval::ratio_to_cents(a.frequency()),
+            MusicalInterval::ratio_to_cents(b.frequency()),
+            cents_tolerance,
+        ),
+        _ => {
+            return Err(format!(
+                "cannot compare {} with {} using `{}`",
+                left.type_name(),
+                right.type_name(),
+                operator.symbol()
+            ))
+        }
+    };
+    if let Some(value) = [left_value, right_value].into_iter().find(|v| !v.is_finite() || v.abs() > MAX_COMPARED_MAGNITUDE) {
+        return Err(format!(
+            "cannot compare {} with `{}`: compared values must lie within ±{}",
+            value,
+            operator.symbol(),
+            MAX_COMPARED_MAGNITUDE
+        ));
+    }
+    let comparison = match operator {
+        BinaryOperator::Eq => Comparison::Eq,
+        BinaryOperator::Ne => Comparison::Ne,
+        BinaryOperator::Lt => Comparison::Lt,
+        BinaryOperator::Le => Comparison::Le,
+        BinaryOperator::Gt => Comparison::Gt,
+        _ => Comparison::Ge,
+    };
+    Ok(Constant::Condition(Condition::Compare {
+        left: fixed(left_value),
+        comparison,
+        right: fixed(right_value),
+        tolerance,
+    }))
+}
+
+/// Arithmetic on numbers, pitches and intervals
+fn arithmetic(operator: BinaryOperator, left: Constant, right: Constant) -> std::result::Result<Constant, String> {
+    use BinaryOperator::{Add, Div, Mul, Sub};
+    use Constant::{Chord as Chords, Interval, Note, Number};
+
+    let transposed = |note: &MusicalNote, cents: f64| {
+        note.transpose(&MusicalInterval::from_cents(cents))
+            .map(Note)
+            .map_err(|_| format!("{:.2} Hz transposed by {:.0} cents leaves the MIDI range", note.frequency(), cents))
+    };
+    let value = match (operator, &left, &right) {
+        (Add, Number(a), Number(b)) => Number(a + b),
+        (Sub, Number(a), Number(b)) => Number(a - b),
+        (Mul, Number(a), Number(b)) => Number(a * b),
+        (Div, Number(_), Number(b)) if *b == 0.0 => return Err("division by zero".to_string()),
+        (Div, Number(a), Number(b)) => Number(a / b),
+        (Div, Note(a), Note(b)) => Interval(ratio_interval(a.frequency() / b.frequency())),
+        (Add, Note(note), Interval(interval)) | (Add, Interval(interval), Note(note)) => {
+            return transposed(note, interval.cents())
+        }
+        (Sub, Note(note), Interval(interval)) => return transposed(note, -interval.cents()),
+        (Add, Interval(a), Interval(b)) => Interval(a.combine(b)),
+        (Sub, Interval(a), Interval(b)) => Interval(MusicalInterval::from_cents(a.cents() - b.cents())),
+        (Mul, Interval(interval), Number(times)) | (Mul, Number(times), Interval(interval)) => {
+            Interval(MusicalInterval::from_cents(interval.cents() * times))
+        }
+        (Add, Note(_) | Chords(_), Note(_) | Chords(_)) => {
+            let pitches = |value: &Constant| match value {
+                Note(pitch) => vec![*pitch],
+                Chords(pitches) => pitches.clone(),
+                _ => Vec::new(),
+            };
+            Chords([pitches(&left), pitches(&right)].concat())
+        }
+        _ => {
+            return Err(format!(
+                "cannot apply `{}` to {} and {}",
+                operator.symbol(),
+                left.type_name(),
+                right.type_name()
+            ))
+        }
+    };
+    Ok(value)
+}
+
+/// A scale degree written as a number: a whole number from 1 to `MAX_SCALE_DEGREE`
+fn scale_degree(degree: f64) -> std::result::Result<i32, String> {
+    if degree.fract() == 0.0 && (1.0..=f64::from(MAX_SCALE_DEGREE)).contains(&degree) {
+        Ok(degree as i32)
+    } else {
+        Err(format!("scale degrees are whole numbers from 1 to {}, found {}", MAX_SCALE_DEGREE, degree))
+    }
+}
+
+/// The interval of a positive frequency ratio
+fn ratio_interval(ratio: f64) -> MusicalInterval {
+    MusicalInterval::from_cents(MusicalInterval::ratio_to_cents(ratio))
+}
+
+/// A chord voiced as written, rooted on its first pitch
+fn chord(pitches: &[MusicalNote]) -> Result<Chord> {
+    let root = pitches.first().copied().ok_or_else(|| crate::ZyrkomError::PhysicsError {
+        details: "A chord needs at least one note".to_string(),
+    })?;
+    Chord::from_voicing(root, pitches.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::ast::StatementKind;
+    use crate::dsl::resolve::resolve;
+    use crate::dsl::syntax::parse_program;
+    use crate::musical::PitchReference;
+
+    const DECLARATIONS: &str = "key C major\nnote C4 = 261.63\nnote E4 = 329.63\nnote G4 = 392.00\nnote FS4 = 369.99\n\
+                                interval fifth = 1.5\nchord C_major = C4 + E4 + G4\nchord E_minor = E4 + G4 + fifth\n";
+
+    fn evaluated(expression: &str) -> std::result::Result<Constant, Diagnostic> {
+        let source = format!("{}constraint c = {}", DECLARATIONS, expression);
+        let (program, syntax_errors) = parse_program(&source);
+        assert!(syntax_errors.is_empty(), "{:?}", syntax_errors);
+        let (symbols, unresolved) = resolve(&program, PitchReference::standard(), &source);
+        assert!(unresolved.is_empty(), "{:?}", unresolved);
+        match &program.statements.last().unwrap().kind {
+            StatementKind::Constraint { expression, .. } => evaluate(expression, &symbols, &source),
+            other => panic!("Expected constraint, got {:?}", other),
+        }
+    }
+
+    fn condition(expression: &str) -> Condition {
+        match evaluated(expression).unwrap() {
+            Constant::Condition(condition) => condition,
+            other => panic!("Expected condition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_values_and_built_ins() {
+        match evaluated("G4 / C4").unwrap() {
+            Constant::Interval(interval) => assert!((interval.ratio() - 1.5).abs() < 1e-2),
+            other => panic!("Expected interval, got {:?}", other),
+        }
+        match evaluated("C4 + fifth").unwrap() {
+            Constant::Note(note) => assert_eq!(note.midi_number(), 67),
+            other => panic!("Expected note, got {:?}", other),
+        }
+        assert_eq!(evaluated("distance(C4, FS4) * 2 - 1").unwrap(), Constant::Number(11.0));
+        assert_eq!(evaluated("distance(C_major, E_minor)").unwrap(), Constant::Number(11.0));
+        match evaluated("cents(interval(C4, G4))").unwrap() {
+            Constant::Number(cents) => assert!((cents - 700.0).abs() < 0.5, "{}", cents),
+            other => panic!("Expected number, got {:?}", other),
+        }
+        match evaluated("C_major + FS4").unwrap() {
+            Constant::Chord(pitches) => assert_eq!(pitches.len(), 4),
+            other => panic!("Expected chord, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_conditions_hold_and_negate() {
+        assert!(condition("G4 / C4 == fifth").holds());
+        assert!(condition("G4 / C4 == 1.5 && C4 < G4").holds());
+        assert!(!condition("interval(C4, E4) == fifth").holds());
+        assert!(condition("in_scale(C_major)").holds());
+        assert!(!condition("in_scale(FS4)").holds());
+        assert!(condition("!in_scale(FS4) || 1 > 2").holds());
+        // De Morgan: !(a && b) == !a || !b
+        let negated = condition("!(C4 < G4 && in_scale(E4))");
+        assert!(matches!(&negated, Condition::Any(branches) if branches.len() == 2));
+        assert!(!negated.holds());
+    }
+
+    #[test]
+    fn test_compiles_to_range_checks() {
+        let constant = evaluated("G4 / C4 == fifth && in_scale(C_major) && (1 > 2 || E4 != G4)").unwrap();
+        let system = constant.to_constraints().unwrap();
+        // One ratio check, three scale checks and the branch that holds
+        assert_eq!(system.constraint_count(), 5);
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+        assert!(system.constraints.iter().all(|c| c.constraint_type == ConstraintType::Comparison));
+        assert_eq!(system.relationships.len(), 1);
+        assert_eq!(system.relationships[0].constraint_indices, vec![0, 1, 2, 3, 4]);
+        assert_eq!(system.structure_type(), "Comparison");
+
+        // A condition that does not hold compiles to an unsatisfied check
+        let failing = evaluated("in_scale(FS4)").unwrap().to_constraints().unwrap();
+        assert!(!failing.constraints[0].is_satisfied());
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let message = |expression: &str| evaluated(expression).unwrap_err().error().to_string();
+        assert_eq!(message("C4 + 1"), "Type error: cannot apply `+` to note and number");
+        assert_eq!(message("C4 == fifth"), "Type error: cannot compare note with interval using `==`");
+        assert_eq!(message("C4 < G4 && 3"), "Type error: `&&` needs conditions, found condition and number");
+        assert_eq!(message("in_scale(fifth)"), "Type error: in_scale takes a note or a chord, and optionally a key");
+        assert_eq!(message("D4 > C4"), "Undefined reference: D4");
+        assert_eq!(message("loudness(C4)"), "Undefined reference: loudness");
+        assert_eq!(
+            message("99999999999999999999 == 0 - 99999999999999999999"),
+            "Type error: cannot compare 100000000000000000000 with `==`: compared values must lie within ±500000"
+        );
+
+        let error = evaluated("1 + (C4 - E4)").unwrap_err();
+        assert_eq!((error.line(), error.column()), (9, 20));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,332 @@
+//! Lexer for the Zyrkom DSL
+//!
+//! Turns source text into tokens carrying byte spans. Newlines are tokens
+//! because they end statements; `//` comments and other whitespace are
+//! skipped. Identifiers may contain `#` after the first character so that
+//! spelled pitches such as `F#4` read as a single name.
+
+use std::fmt;
+
+use crate::dsl::diagnostic::Diagnostic;
+use crate::dsl::parser::ParseError;
+
+/// A byte range in the source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize)]
+pub struct Span {
+    /// Byte offset of the first character
+    pub start: usize,
+    /// Byte offset one past the last character
+    pub end: usize,
+}
+
+impl Span {
+    /// Create a span from byte offsets
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The span covering both this span and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Length in bytes
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Whether the span covers no characters
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Kinds of token
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// A name or keyword (`note`, `C4`, `F#`, `perfect_fifth`)
+    Ident(String),
+    /// A non-negative number literal
+    Number(f64),
+    /// `=`
+    Equals,
+    /// `==`
+    EqEq,
+    /// `!=`
+    NotEq,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `&&`
+    AndAnd,
+    /// `||`
+    OrOr,
+    /// `!`
+    Bang,
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `*`
+    Star,
+    /// `/`
+    Slash,
+    /// `:`
+    Colon,
+    /// `,`
+    Comma,
+    /// `.`
+    Dot,
+    /// `..`
+    DotDot,
+    /// `|`
+    Pipe,
+    /// `~`
+    Tilde,
+    /// `{`
+    LBrace,
+    /// `}`
+    RBrace,
+    /// `[`
+    LBracket,
+    /// `]`
+    RBracket,
+    /// `(`
+    LParen,
+    /// `)`
+    RParen,
+    /// End of a line
+    Newline,
+    /// End of the input
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            TokenKind::Ident(_) => return write!(f, "identifier"),
+            TokenKind::Number(_) => return write!(f, "number"),
+            TokenKind::Newline => return write!(f, "end of line"),
+            TokenKind::Eof => return write!(f, "end of file"),
+            TokenKind::Equals => "=",
+            TokenKind::EqEq => "==",
+            TokenKind::NotEq => "!=",
+            TokenKind::Lt => "<",
+            TokenKind::Le => "<=",
+            TokenKind::Gt => ">",
+            TokenKind::Ge => ">=",
+            TokenKind::AndAnd => "&&",
+            TokenKind::OrOr => "||",
+            TokenKind::Bang => "!",
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Star => "*",
+            TokenKind::Slash => "/",
+            TokenKind::Colon => ":",
+            TokenKind::Comma => ",",
+            TokenKind::Dot => ".",
+            TokenKind::DotDot => "..",
+            TokenKind::Pipe => "|",
+            TokenKind::Tilde => "~",
+            TokenKind::LBrace => "{",
+            TokenKind::RBrace => "}",
+            TokenKind::LBracket => "[",
+            TokenKind::RBracket => "]",
+            TokenKind::LParen => "(",
+            TokenKind::RParen => ")",
+        };
+        write!(f, "`{}`", symbol)
+    }
+}
+
+/// A token and where it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// What was read
+    pub kind: TokenKind,
+    /// Where it was read
+    pub span: Span,
+}
+
+/// Split source text into tokens, ending with `Eof`
+///
+/// Characters that start no token are reported and skipped, so one pass
+/// finds every lexical error.
+pub fn tokenize(source: &str) -> (Vec<Token>, Vec<Diagnostic>) {
+    let mut tokens = Vec::new();
+    let mut diagnostics = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let (kind, end) = match (c, next) {
+            ('\n', _) => (TokenKind::Newline, start + 1),
+            (c, _) if c.is_whitespace() => continue,
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|&(_, c)| c != '\n') {
+                    chars.next();
+                }
+                continue;
+            }
+            (c, _) if c.is_ascii_digit() => {
+                let mut end = start + 1;
+                let mut seen_dot = false;
+                while let Some(&(i, c)) = chars.peek() {
+                    let fraction = c == '.'
+                        && !seen_dot
+                        && source[i + 1..].chars().next().is_some_and(|d| d.is_ascii_digit());
+                    if !(c.is_ascii_digit() || fraction) {
+                        break;
+                    }
+                    seen_dot |= c == '.';
+                    end = i + 1;
+                    chars.next();
+                }
+                // Only digits and one dot were consumed, so this always parses
+                let value = source[start..end].parse::<f64>().unwrap_or_default();
+                (TokenKind::Number(value), end)
+            }
+            (c, _) if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '#') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                (TokenKind::Ident(source[start..end].to_string()), end)
+            }
+            ('=', Some('=')) => {
+                chars.next();
+                (TokenKind::EqEq, start + 2)
+            }
+            ('!', Some('=')) => {
+                chars.next();
+                (TokenKind::NotEq, start + 2)
+            }
+            ('<', Some('=')) => {
+                chars.next();
+                (TokenKind::Le, start + 2)
+            }
+            ('>', Some('=')) => {
+                chars.next();
+                (TokenKind::Ge, start + 2)
+            }
+            ('&', Some('&')) => {
+                chars.next();
+                (TokenKind::AndAnd, start + 2)
+            }
+            ('|', Some('|')) => {
+                chars.next();
+                (TokenKind::OrOr, start + 2)
+            }
+            ('.', Some('.')) => {
+                chars.next();
+                (TokenKind::DotDot, start + 2)
+            }
+            ('=', _) => (TokenKind::Equals, start + 1),
+            ('<', _) => (TokenKind::Lt, start + 1),
+            ('>', _) => (TokenKind::Gt, start + 1),
+            ('!', _) => (TokenKind::Bang, start + 1),
+            ('+', _) => (TokenKind::Plus, start + 1),
+            ('-', _) => (TokenKind::Minus, start + 1),
+            ('*', _) => (TokenKind::Star, start + 1),
+            ('/', _) => (TokenKind::Slash, start + 1),
+            (':', _) => (TokenKind::Colon, start + 1),
+            (',', _) => (TokenKind::Comma, start + 1),
+            ('.', _) => (TokenKind::Dot, start + 1),
+            ('|', _) => (TokenKind::Pipe, start + 1),
+            ('~', _) => (TokenKind::Tilde, start + 1),
+            ('{', _) => (TokenKind::LBrace, start + 1),
+            ('}', _) => (TokenKind::RBrace, start + 1),
+            ('[', _) => (TokenKind::LBracket, start + 1),
+            (']', _) => (TokenKind::RBracket, start + 1),
+            ('(', _) => (TokenKind::LParen, start + 1),
+            (')', _) => (TokenKind::RParen, start + 1),
+            (c, _) => {
+                diagnostics.push(Diagnostic::new(
+                    ParseError::InvalidSyntax(format!("unexpected character `{}`", c)),
+                    Span::new(start, start + c.len_utf8()),
+                    source,
+                ));
+                continue;
+            }
+        };
+        tokens.push(Token { kind, span: Span::new(start, end) });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        span: Span::new(source.len(), source.len()),
+    });
+    (tokens, diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source).0.into_iter().map(|t| t.kind).collect()
+    }
+
+    #[test]
+    fn test_tokens_and_spans() {
+        let (tokens, diagnostics) = tokenize("note F#4 = 369.99 // sharp\n");
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            tokens.iter().map(|t| t.kind.clone()).collect::<Vec<_>>(),
+            vec![
+                TokenKind::Ident("note".to_string()),
+                TokenKind::Ident("F#4".to_string()),
+                TokenKind::Equals,
+                TokenKind::Number(369.99),
+                TokenKind::Newline,
+                TokenKind::Eof,
+            ]
+        );
+        assert_eq!(tokens[1].span, Span::new(5, 8));
+        assert_eq!(tokens[3].span, Span::new(11, 17));
+    }
+
+    #[test]
+    fn test_ranges_durations_and_operators() {
+        assert_eq!(
+            kinds("72..80 q. e/3 a<=b&&c!=d"),
+            vec![
+                TokenKind::Number(72.0),
+                TokenKind::DotDot,
+                TokenKind::Number(80.0),
+                TokenKind::Ident("q".to_string()),
+                TokenKind::Dot,
+                TokenKind::Ident("e".to_string()),
+                TokenKind::Slash,
+                TokenKind::Number(3.0),
+                TokenKind::Ident("a".to_string()),
+                TokenKind::Le,
+                TokenKind::Ident("b".to_string()),
+                TokenKind::AndAnd,
+                TokenKind::Ident("c".to_string()),
+                TokenKind::NotEq,
+                TokenKind::Ident("d".to_string()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_characters_are_reported() {
+        let (tokens, diagnostics) = tokenize("note C4 = 261.63 $\nnote ?");
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line(), diagnostics[0].column()), (1, 18));
+        assert_eq!((diagnostics[1].line(), diagnostics[1].column()), (2, 6));
+        // Lexing carries on past the bad characters
+        assert_eq!(tokens.len(), 7);
+    }
+}
\ No newline at end of file
@@ -3,7 +3,26 @@
 //! This module contains the musical DSL compiler that converts
 //! musical notation and theory into zero-knowledge constraints.
 
+/// Tokens with byte spans
+pub mod lexer;
+/// Syntax tree of a program
+pub mod ast;
+/// Recursive-descent parsing with error recovery
+pub mod syntax;
+/// Binding names to declarations
+pub mod resolve;
+/// Type checking and compilation of constraint expressions
+pub mod expression;
+/// Parameterised progressions expanded at compile time
+pub mod template;
+/// Located errors and rustc-style rendering
+pub mod diagnostic;
 /// Musical DSL parser implementation
 pub mod parser;
+/// Whole-program compilation with a source map
+pub mod compiler;
 
-pub use parser::{ZyrkomParser, ParsedElement}; 
\ No newline at end of file
+pub use compiler::{CompiledProgram, Compiler};
+pub use diagnostic::{Diagnostic, Diagnostics};
+pub use lexer::Span;
+pub use parser::{ZyrkomParser, ParsedElement, ParseError};
\ No newline at end of file
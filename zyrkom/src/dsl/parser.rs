@@ -1,16 +1,43 @@
 //! Zyrkom DSL Parser
 //! 
 //! This module provides parsing capabilities for the Zyrkom musical domain-specific language.
-//! Currently implements basic placeholder functionality for DSL compilation.
+//! Source text is tokenized, parsed into a syntax tree and lowered into
+//! `ParsedElement`s, with every error located by line and column.
 
 
-use crate::zk::constraints::{ConstraintSystem, MusicalConstraint, ConstraintType};
+use crate::dsl::ast::{Ident, MelodyItem, Property, Spanned, Statement, StatementKind, Value};
+use crate::dsl::diagnostic::{join_expected, Diagnostic, Diagnostics};
+use crate::dsl::expression::{evaluate, Constant};
+use crate::dsl::lexer::Span;
+use crate::dsl::resolve::{resolve, SymbolTable};
+use crate::dsl::syntax::parse_program;
+use crate::dsl::template::expand;
+use crate::musical::melody::DEFAULT_VELOCITY;
+use crate::musical::rhythm::MAX_DOTS;
+use crate::musical::{constants, detect_key, Chord, Instrument, Key, KeyProfile, MeasureSequence, Melody, MusicalNote, NoteDuration, NoteEvent, NoteValue, PitchReference, RhythmicValue, Tempo, TempoBounds, TimeSignature, Timbre, Tuplet};
+use crate::zk::constraints::{ConstraintSystem, MusicalConstraint, ConstraintType, ToConstraints};
 use crate::{ZyrkomError};
 
+/// Distinct pitch classes the declared notes need before a key is inferred
+const MIN_KEY_INFERENCE_PITCH_CLASSES: usize = 3;
+
+/// Most times a melody repeat may be played
+const MAX_MELODY_REPEATS: u32 = 64;
+
+/// Most notes and rests a melody may hold once its repeats are unrolled
+const MAX_MELODY_STEPS: u64 = 65_536;
+
 /// Main parser for Zyrkom DSL
 #[derive(Debug, Clone)]
 pub struct ZyrkomParser {
-    // Using empty struct for now - future versions will add parsing state
+    /// Time signature in effect for subsequent rhythms and melodies (4/4 until declared)
+    time_signature: TimeSignature,
+    /// Tempo in effect for subsequent rhythms and melodies
+    tempo: Option<Tempo>,
+    /// Tempo range in effect for subsequent rhythms and melodies
+    tempo_bounds: Option<TempoBounds>,
+    /// Concert pitch in effect for subsequent notes (A4 = 440 Hz until declared)
+    reference: PitchReference,
 }
 
 /// Parsed elements from the DSL
@@ -21,14 +48,18 @@ pub enum ParsedElement {
         /// Name of the note (e.g., "C4", "A440")
         name: String, 
         /// Frequency in Hz
-        frequency: f64 
+        frequency: f64,
+        /// Concert pitch in effect when the note was declared
+        reference: PitchReference,
     },
     /// A chord definition
     Chord { 
         /// Name of the chord (e.g., "C_major", "Dm7")
         name: String, 
-        /// List of note names in the chord
-        notes: Vec<String> 
+        /// Names of the members, as written
+        notes: Vec<String>,
+        /// Pitches the members resolve to, in the same order
+        pitches: Vec<MusicalNote>,
     },
     /// An interval definition  
     Interval { 
@@ -41,8 +72,59 @@ pub enum ParsedElement {
     Constraint { 
         /// Name of the constraint (e.g., "harmonic_validation")
         name: String, 
-        /// Mathematical expression for the constraint
-        expression: String 
+        /// Source text of the expression
+        expression: String,
+        /// The type-checked value: a condition, chord or interval
+        value: Constant,
+    },
+    /// A time signature declaration (applies to following rhythms)
+    TimeSignature {
+        /// The declared time signature (e.g., 3/4)
+        signature: TimeSignature,
+    },
+    /// A tempo declaration (applies to following rhythms)
+    Tempo {
+        /// The declared tempo
+        tempo: Tempo,
+        /// Optional permitted BPM range
+        bounds: Option<TempoBounds>,
+    },
+    /// A rhythm definition split into measures
+    Rhythm {
+        /// Name of the rhythm (e.g., "phrase1")
+        name: String,
+        /// Measures with the time signature and tempo in effect when declared
+        passage: MeasureSequence,
+    },
+    /// A melody with its repeats unrolled and tied notes merged
+    Melody {
+        /// Name of the melody (e.g., "phrase1")
+        name: String,
+        /// Timed notes under the time signature in effect when declared
+        melody: Melody,
+        /// Tempo in effect when declared
+        tempo: Option<Tempo>,
+        /// Tempo range in effect when declared
+        tempo_bounds: Option<TempoBounds>,
+    },
+    /// A concert pitch declaration (applies to following notes)
+    Reference {
+        /// The declared concert pitch
+        reference: PitchReference,
+    },
+    /// A key declaration, or the key inferred from the notes when none is declared
+    Key {
+        /// The declared or inferred key
+        key: Key,
+        /// Confidence of the inference (0–1); `None` when declared
+        confidence: Option<f64>,
+    },
+    /// An instrument declaration with its range, limits and timbre
+    Instrument {
+        /// Name of the instrument (e.g., "Piano")
+        name: String,
+        /// Range, transposition, polyphony, hand span and timbre
+        instrument: Instrument,
     },
 }
 
@@ -50,127 +132,423 @@ impl ZyrkomParser {
     /// Create a new parser instance
     pub fn new() -> Self {
         Self {
+            time_signature: TimeSignature::common(),
+            tempo: None,
+            tempo_bounds: None,
+            reference: PitchReference::standard(),
         }
     }
 
     /// Parse a string of Zyrkom DSL code
-    pub fn parse(&mut self, input: &str) -> Result<Vec<ParsedElement>, ParseError> {
+    pub fn parse(&mut self, input: &str) -> Result<Vec<ParsedElement>, Diagnostics> {
         self.parse_multiple(input)
     }
 
     /// Parse multiple elements from Zyrkom DSL code (alias for parse)
-    pub fn parse_multiple(&mut self, input: &str) -> Result<Vec<ParsedElement>, ParseError> {
-        // Basic placeholder implementation
-        // TODO: Implement full DSL parsing with LALRPOP or Tree-sitter
-        
-        let lines: Vec<&str> = input.lines()
-            .map(|line| line.trim())
-            .filter(|line| !line.is_empty() && !line.starts_with("//"))
-            .collect();
+    ///
+    /// Every syntax error, undefined or duplicate name and invalid value in
+    /// the source is reported, each located at its line and column.
+    pub fn parse_multiple(&mut self, input: &str) -> Result<Vec<ParsedElement>, Diagnostics> {
+        let elements = self.parse_spanned(input)?;
+        Ok(elements.into_iter().map(|element| element.node).collect())
+    }
+
+    /// Parse Zyrkom DSL code, keeping the span of the statement each element
+    /// came from
+    ///
+    /// An inferred key has the empty span at the end of the source.
+    pub fn parse_spanned(&mut self, input: &str) -> Result<Vec<Spanned<ParsedElement>>, Diagnostics> {
+        let (program, mut diagnostics) = parse_program(input);
+        let (symbols, unresolved) = resolve(&program, self.reference, input);
+        diagnostics.extend(unresolved);
 
         let mut elements = Vec::new();
+        for statement in &program.statements {
+            match self.lower(statement, &symbols, input) {
+                Ok(lowered) => elements.extend(lowered.into_iter().map(|element| Spanned::new(element, statement.span))),
+                Err(diagnostic) => diagnostics.push(diagnostic),
+            }
+        }
+        if !diagnostics.is_empty() {
+            return Err(Diagnostics::new(diagnostics));
+        }
 
-        for line in lines {
-            if let Some(element) = self.parse_line(line)? {
-                elements.push(element);
+        // `key F major` may be omitted: infer it from the declared notes
+        if !elements.iter().any(|e| matches!(e.node, ParsedElement::Key { .. })) {
+            if let Some(element) = infer_key(&elements) {
+                elements.push(Spanned::new(element, Span::new(input.len(), input.len())));
             }
         }
 
         Ok(elements)
     }
 
-    /// Parse a single line of DSL code
-    fn parse_line(&mut self, line: &str) -> Result<Option<ParsedElement>, ParseError> {
-        // Simple pattern matching for basic DSL elements
-        
-        if line.starts_with("note ") {
-            self.parse_note(line)
-        } else if line.starts_with("chord ") {
-            self.parse_chord(line)
-        } else if line.starts_with("interval ") {
-            self.parse_interval(line)
-        } else if line.starts_with("constraint ") {
-            self.parse_constraint(line)
-        } else {
-            // Unknown syntax - for now just ignore
-            Ok(None)
-        }
+    /// Check the values of one statement and apply its declarations
+    ///
+    /// A progression declares nothing until it is instantiated, and an
+    /// instantiation expands into every element of the progression's body.
+    fn lower(&mut self, statement: &Statement, symbols: &SymbolTable, source: &str) -> Result<Vec<ParsedElement>, Diagnostic> {
+        let text = |span: Span| source[span.start..span.end].to_string();
+        let error = |kind: ParseError, span: Span| Diagnostic::new(kind, span, source);
+
+        let element = match &statement.kind {
+            StatementKind::Note { name, frequency } => ParsedElement::Note {
+                name: name.node.clone(),
+                frequency: frequency.node,
+                reference: self.reference,
+            },
+            StatementKind::Chord { name, notes } => ParsedElement::Chord {
+                name: name.node.clone(),
+                notes: notes.iter().map(|note| note.node.clone()).collect(),
+                pitches: symbols.chord_pitches(notes).map_err(|(kind, span)| error(kind, span))?,
+            },
+            StatementKind::Interval { name, ratio } => ParsedElement::Interval {
+                name: name.node.clone(),
+                ratio: ratio.node,
+            },
+            StatementKind::Constraint { name, expression } => {
+                let value = evaluate(expression, symbols, source)?;
+                if !matches!(value, Constant::Condition(_) | Constant::Chord(_) | Constant::Interval(_)) {
+                    let message = format!("a constraint must be a condition, chord or interval, found {}", value.type_name());
+                    return Err(error(ParseError::TypeError(message), expression.span));
+                }
+                ParsedElement::Constraint { name: name.node.clone(), expression: text(expression.span), value }
+            }
+            StatementKind::Time { numerator, denominator } => {
+                let span = numerator.span.to(denominator.span);
+                let invalid = || error(ParseError::InvalidRhythm(text(span)), span);
+                let signature = whole_number(numerator)
+                    .zip(whole_number(denominator))
+                    .and_then(|(n, d)| TimeSignature::new(n, d).ok())
+                    .ok_or_else(invalid)?;
+                self.time_signature = signature;
+                ParsedElement::TimeSignature { signature }
+            }
+            StatementKind::Tempo { bpm, bounds } => {
+                let tempo = Tempo::quarter(bpm.node)
+                    .map_err(|_| error(ParseError::InvalidRhythm(text(bpm.span)), bpm.span))?;
+                let bounds = match bounds {
+                    Some((min, max)) => {
+                        let span = min.span.to(max.span);
+                        Some(
+                            TempoBounds::new(min.node, max.node)
+                                .map_err(|_| error(ParseError::InvalidRhythm(text(span)), span))?,
+                        )
+                    }
+                    None => None,
+                };
+                self.tempo = Some(tempo);
+                self.tempo_bounds = bounds;
+                ParsedElement::Tempo { tempo, bounds }
+            }
+            StatementKind::Rhythm { name, pickup, measures } => {
+                let mut passage = MeasureSequence::new(self.time_signature);
+                if *pickup {
+                    passage = passage.with_anacrusis();
+                }
+                if let Some(tempo) = self.tempo {
+                    passage = passage.with_tempo(tempo);
+                }
+                if let Some(bounds) = self.tempo_bounds {
+                    passage = passage.with_tempo_bounds(bounds);
+                }
+                for measure in measures {
+                    let values = measure
+                        .iter()
+                        .map(|token| parse_rhythmic_value(&token.node).map_err(|e| error(e, token.span)))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    passage.push_measure(values);
+                }
+                ParsedElement::Rhythm { name: name.node.clone(), passage }
+            }
+            StatementKind::Melody { name, items } => ParsedElement::Melody {
+                name: name.node.clone(),
+                melody: lower_melody(items, self.time_signature, symbols).map_err(|(kind, span)| error(kind, span))?,
+                tempo: self.tempo,
+                tempo_bounds: self.tempo_bounds,
+            },
+            StatementKind::Reference { frequency } => {
+                let reference = PitchReference::new(frequency.node)
+                    .map_err(|_| error(ParseError::InvalidFrequency(text(frequency.span)), frequency.span))?;
+                self.reference = reference;
+                ParsedElement::Reference { reference }
+            }
+            StatementKind::Key { spec } => {
+                let key = Key::parse(&spec.node)
+                    .map_err(|_| error(ParseError::InvalidSyntax(format!("key {}", spec.node)), spec.span))?;
+                ParsedElement::Key { key, confidence: None }
+            }
+            StatementKind::Instrument { name, properties } => {
+                let instrument = lower_instrument(name, properties).map_err(|(kind, span)| error(kind, span))?;
+                ParsedElement::Instrument { name: name.node.clone(), instrument }
+            }
+            StatementKind::Template(_) => return Ok(Vec::new()),
+            StatementKind::Instance { template, arguments } => {
+                // An unknown progression is reported by `resolve`
+                return match symbols.template(&template.node) {
+                    Some(definition) => expand(definition, arguments, statement.span, symbols, self.reference, source),
+                    None => Ok(Vec::new()),
+                };
+            }
+        };
+        Ok(vec![element])
     }
+}
 
-    fn parse_note(&self, line: &str) -> Result<Option<ParsedElement>, ParseError> {
-        // Example: "note C4 = 261.63"
-        let parts: Vec<&str> = line.split('=').collect();
-        if parts.len() != 2 {
-            return Err(ParseError::InvalidSyntax(line.to_string()));
-        }
+/// A number that is a non-negative whole `u32`
+fn whole_number(number: &Spanned<f64>) -> Option<u32> {
+    let value = number.node;
+    (value.fract() == 0.0 && value >= 0.0 && value <= u32::MAX as f64).then_some(value as u32)
+}
 
-        let name = parts[0].trim().strip_prefix("note ").unwrap_or("").trim();
-        let freq_str = parts[1].trim();
-        
-        let frequency = freq_str.parse::<f64>()
-            .map_err(|_| ParseError::InvalidFrequency(freq_str.to_string()))?;
+/// Build an instrument from its properties
+///
+/// Known instrument names start from their preset, anything else from the
+/// full MIDI range with overtones(16).
+fn lower_instrument(name: &Ident, properties: &[Property]) -> Result<Instrument, (ParseError, Span)> {
+    let mut instrument = match Instrument::preset(&name.node) {
+        Some(preset) => preset,
+        None => MusicalNote::from_midi(0)
+            .and_then(|lowest| Instrument::new(&name.node, lowest, MusicalNote::from_midi(constants::MAX_MIDI_NUMBER)?))
+            .map_err(|e| (ParseError::InvalidSyntax(e.to_string()), name.span))?,
+    };
 
-        Ok(Some(ParsedElement::Note {
-            name: name.to_string(),
-            frequency,
-        }))
+    for property in properties {
+        let value = &property.value;
+        let invalid = |message: String| (ParseError::InvalidSyntax(message), value.span);
+        let number = || match value.node {
+            Value::Number(number) => Ok(number),
+            _ => Err(invalid(format!("{} must be a number", property.name.node))),
+        };
+        let integer = || {
+            number().and_then(|n| {
+                (n.fract() == 0.0)
+                    .then_some(n as i64)
+                    .ok_or_else(|| invalid(format!("{} must be a whole number", property.name.node)))
+            })
+        };
+
+        instrument = match property.name.node.as_str() {
+            "range" | "rango" => {
+                let names = match &value.node {
+                    Value::List(items) => match items.as_slice() {
+                        [Spanned { node: Value::Ident(low), .. }, Spanned { node: Value::Ident(high), .. }] => Some((low, high)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                let (lowest, highest) = names.ok_or_else(|| invalid("range must be [lowest, highest]".to_string()))?;
+                let range = Instrument::from_range_names(&name.node, lowest, highest).map_err(|e| invalid(e.to_string()))?;
+                instrument.with_range(range.lowest(), range.highest()).map_err(|e| invalid(e.to_string()))?
+            }
+            "transposition" | "transposicion" | "transposición" => instrument.with_transposition(integer()? as i32),
+            "polyphony" | "polifonia" | "polifonía" => {
+                let polyphony = usize::try_from(integer()?).map_err(|_| invalid("polyphony must be positive".to_string()))?;
+                instrument.with_polyphony(polyphony).map_err(|e| invalid(e.to_string()))?
+            }
+            "stretch" | "extension" | "extensión" => {
+                let stretch = u32::try_from(integer()?).map_err(|_| invalid("stretch must be positive".to_string()))?;
+                let hands = instrument.hand_span().map_or(2, |(hands, _)| hands);
+                instrument.with_hand_span(hands, stretch).map_err(|e| invalid(e.to_string()))?
+            }
+            "resonance" | "resonancia" | "timbre" => {
+                let timbre = match &value.node {
+                    Value::Call { name: function, arguments } if function.node == "overtones" => {
+                        let count = match arguments.as_slice() {
+                            [Spanned { node: Value::Number(count), .. }] if count.fract() == 0.0 && *count >= 0.0 => *count as usize,
+                            _ => return Err(invalid("overtones takes one whole number".to_string())),
+                        };
+                        instrument.timbre().clone().with_partial_count(count).map_err(|e| invalid(e.to_string()))?
+                    }
+                    Value::Ident(preset) => Timbre::preset(preset).ok_or_else(|| invalid(format!("unknown timbre {}", preset)))?,
+                    _ => return Err(invalid("expected overtones(n) or a timbre name".to_string())),
+                };
+                instrument.with_timbre(timbre)
+            }
+            "inharmonicity" | "inarmonicidad" => {
+                let timbre = instrument.timbre().clone().with_inharmonicity(number()?).map_err(|e| invalid(e.to_string()))?;
+                instrument.with_timbre(timbre)
+            }
+            other => {
+                return Err((ParseError::InvalidSyntax(format!("unknown instrument property {}", other)), property.name.span));
+            }
+        };
     }
+    Ok(instrument)
+}
 
-    fn parse_chord(&self, line: &str) -> Result<Option<ParsedElement>, ParseError> {
-        // Example: "chord C_major = C + E + G"
-        let parts: Vec<&str> = line.split('=').collect();
-        if parts.len() != 2 {
-            return Err(ParseError::InvalidSyntax(line.to_string()));
-        }
+/// A note of a melody after its repeats are unrolled
+struct MelodyStep<'a> {
+    /// The pitch, or `None` for a rest
+    pitch: Option<(&'a Ident, MusicalNote)>,
+    value: RhythmicValue,
+    tied: bool,
+    span: Span,
+}
 
-        let name = parts[0].trim().strip_prefix("chord ").unwrap_or("").trim();
-        let notes_str = parts[1].trim();
-        
-        let notes: Vec<String> = notes_str
-            .split('+')
-            .map(|note| note.trim().to_string())
-            .collect();
+/// Build a melody from its items, unrolling repeats and merging tied notes
+///
+/// Notes `resolve` rejected are skipped; their errors are already reported.
+fn lower_melody(items: &[Spanned<MelodyItem>], time_signature: TimeSignature, symbols: &SymbolTable) -> Result<Melody, (ParseError, Span)> {
+    let mut steps = Vec::new();
+    unroll(items, symbols, &mut steps)?;
+
+    let mut melody = Melody::new(time_signature);
+    // A tied note waiting for its continuation: pitch, length so far and the tie's span
+    let mut held: Option<(&Ident, MusicalNote, NoteDuration, Span)> = None;
+    for step in steps {
+        let Some((name, pitch)) = step.pitch else {
+            if let Some((tied, _, _, span)) = held {
+                return Err((ParseError::InvalidRhythm(format!("{} is tied to a rest", tied.node)), span));
+            }
+            melody.push_rest(step.value).map_err(|e| (ParseError::InvalidRhythm(e.to_string()), step.span))?;
+            continue;
+        };
 
-        Ok(Some(ParsedElement::Chord {
-            name: name.to_string(),
-            notes,
-        }))
+        let duration = match held.take() {
+            Some((_, tied, length, _)) if tied == pitch => length
+                .checked_add(&step.value.duration())
+                .map_err(|e| (ParseError::InvalidRhythm(e.to_string()), step.span))?,
+            Some((tied, ..)) => {
+                let message = format!("{} is tied to a different note, {}", tied.node, name.node);
+                return Err((ParseError::InvalidRhythm(message), step.span));
+            }
+            None => step.value.duration(),
+        };
+        if step.tied {
+            held = Some((name, pitch, duration, step.span));
+            continue;
+        }
+        let event = NoteEvent::new(pitch, melody.duration(), duration, DEFAULT_VELOCITY)
+            .map_err(|e| (ParseError::InvalidRhythm(e.to_string()), step.span))?;
+        melody.push_event(event).map_err(|e| (ParseError::InvalidRhythm(e.to_string()), step.span))?;
+    }
+    if let Some((tied, _, _, span)) = held {
+        return Err((ParseError::InvalidRhythm(format!("{} is tied past the end of the melody", tied.node)), span));
     }
+    Ok(melody)
+}
 
-    fn parse_interval(&self, line: &str) -> Result<Option<ParsedElement>, ParseError> {
-        // Example: "interval perfect_fifth = 1.5"
-        let parts: Vec<&str> = line.split('=').collect();
-        if parts.len() != 2 {
-            return Err(ParseError::InvalidSyntax(line.to_string()));
+/// Flatten melody items into steps, playing each repeat `count` times
+fn unroll<'a>(items: &'a [Spanned<MelodyItem>], symbols: &SymbolTable, steps: &mut Vec<MelodyStep<'a>>) -> Result<(), (ParseError, Span)> {
+    for item in items {
+        match &item.node {
+            MelodyItem::Note { pitch, duration, tied } => {
+                let value = parse_rhythmic_value(&duration.node).map_err(|e| (e, duration.span))?;
+                if let Some(note) = symbols.note(&pitch.node) {
+                    steps.push(MelodyStep { pitch: Some((pitch, note)), value, tied: *tied, span: item.span });
+                }
+            }
+            MelodyItem::Rest { duration } => {
+                let value = parse_rhythmic_value(&duration.node).map_err(|e| (e, duration.span))?;
+                steps.push(MelodyStep { pitch: None, value, tied: false, span: item.span });
+            }
+            MelodyItem::Repeat { items, count } => {
+                let count = whole_number(count)
+                    .filter(|n| (1..=MAX_MELODY_REPEATS).contains(n))
+                    .ok_or_else(|| {
+                        let message = format!("repeat count must be a whole number from 1 to {}", MAX_MELODY_REPEATS);
+                        (ParseError::InvalidSyntax(message), count.span)
+                    })?;
+                // Nested repeats multiply; refuse before unrolling anything
+                if steps.len() as u64 + unrolled_len(items).saturating_mul(count as u64) > MAX_MELODY_STEPS {
+                    let message = format!("melody unrolls to more than {} notes and rests", MAX_MELODY_STEPS);
+                    return Err((ParseError::InvalidSyntax(message), item.span));
+                }
+                for _ in 0..count {
+                    unroll(items, symbols, steps)?;
+                }
+            }
         }
+    }
+    Ok(())
+}
 
-        let name = parts[0].trim().strip_prefix("interval ").unwrap_or("").trim();
-        let ratio_str = parts[1].trim();
-        
-        let ratio = ratio_str.parse::<f64>()
-            .map_err(|_| ParseError::InvalidRatio(ratio_str.to_string()))?;
+/// Number of notes and rests `items` unroll to (saturating)
+fn unrolled_len(items: &[Spanned<MelodyItem>]) -> u64 {
+    items
+        .iter()
+        .map(|item| match &item.node {
+            MelodyItem::Repeat { items, count } => {
+                unrolled_len(items).saturating_mul(whole_number(count).unwrap_or(1) as u64)
+            }
+            _ => 1,
+        })
+        .fold(0, u64::saturating_add)
+}
 
-        Ok(Some(ParsedElement::Interval {
-            name: name.to_string(),
-            ratio,
-        }))
+/// Most likely key of the declared notes, if they span enough pitch classes
+fn infer_key(elements: &[Spanned<ParsedElement>]) -> Option<ParsedElement> {
+    let notes: Vec<MusicalNote> = elements
+        .iter()
+        .filter_map(|element| match &element.node {
+            ParsedElement::Note { frequency, reference, .. } => {
+                MusicalNote::from_frequency_at(*frequency, *reference).ok()
+            }
+            _ => None,
+        })
+        .collect();
+
+    let pitch_classes: std::collections::BTreeSet<u8> = notes.iter().map(|n| n.midi_number() % 12).collect();
+    if pitch_classes.len() < MIN_KEY_INFERENCE_PITCH_CLASSES {
+        return None;
     }
 
-    fn parse_constraint(&self, line: &str) -> Result<Option<ParsedElement>, ParseError> {
-        // Example: "constraint harmonic = freq1 / freq2 == ratio"
-        let parts: Vec<&str> = line.split('=').collect();
-        if parts.len() != 2 {
-            return Err(ParseError::InvalidSyntax(line.to_string()));
-        }
+    let estimate = detect_key(&notes, KeyProfile::KrumhanslSchmuckler)?;
+    Some(ParsedElement::Key {
+        key: estimate.key,
+        confidence: Some(estimate.confidence),
+    })
+}
+
+/// Parse a duration token: a note value letter (w h q e s t x), optional
+/// augmentation dots, and an optional tuplet suffix (e.g., "q.", "e/3", "e/2:3")
+pub fn parse_rhythmic_value(token: &str) -> Result<RhythmicValue, ParseError> {
+    let invalid = || ParseError::InvalidRhythm(token.to_string());
+
+    let (base, tuplet) = match token.split_once('/') {
+        Some((base, tuplet)) => (base, Some(tuplet)),
+        None => (token, None),
+    };
 
-        let name = parts[0].trim().strip_prefix("constraint ").unwrap_or("").trim();
-        let expression = parts[1].trim();
+    let mut chars = base.chars();
+    let value = match chars.next() {
+        Some('w') => NoteValue::Whole,
+        Some('h') => NoteValue::Half,
+        Some('q') => NoteValue::Quarter,
+        Some('e') => NoteValue::Eighth,
+        Some('s') => NoteValue::Sixteenth,
+        Some('t') => NoteValue::ThirtySecond,
+        Some('x') => NoteValue::SixtyFourth,
+        _ => return Err(invalid()),
+    };
 
-        Ok(Some(ParsedElement::Constraint {
-            name: name.to_string(),
-            expression: expression.to_string(),
-        }))
+    let dots = chars.as_str();
+    if !dots.chars().all(|c| c == '.') || dots.len() > MAX_DOTS as usize {
+        return Err(invalid());
     }
+
+    let mut rhythmic_value = RhythmicValue::dotted(value, dots.len() as u8);
+    if let Some(tuplet) = tuplet {
+        let (actual, normal) = match tuplet.split_once(':') {
+            // Explicit ratio, e.g. "e/2:3" for a duplet
+            Some((actual, normal)) => (
+                actual.parse::<u32>().map_err(|_| invalid())?,
+                normal.parse::<u32>().map_err(|_| invalid())?,
+            ),
+            // n notes in the time of the largest power of two below n
+            None => {
+                let actual = tuplet.parse::<u32>().map_err(|_| invalid())?;
+                if actual < 3 || actual.is_power_of_two() {
+                    return Err(invalid());
+                }
+                (actual, 1u32 << (31 - actual.leading_zeros()))
+            }
+        };
+        rhythmic_value.tuplet = Some(Tuplet::new(actual, normal).map_err(|_| invalid())?);
+    }
+
+    Ok(rhythmic_value)
 }
 
 impl Default for ZyrkomParser {
@@ -190,6 +568,21 @@ pub enum ParseError {
     InvalidRatio(String),
     /// Undefined reference in the DSL
     UndefinedReference(String),
+    /// Invalid rhythm, time signature or tempo
+    InvalidRhythm(String),
+    /// A token other than the ones the grammar allows here
+    UnexpectedToken {
+        /// Descriptions of the tokens that would have been accepted
+        expected: Vec<String>,
+        /// Description of the token that was found
+        found: String,
+    },
+    /// A line that starts with no known statement keyword
+    UnknownStatement(String),
+    /// A name declared more than once
+    DuplicateDefinition(String),
+    /// An operation applied to values of the wrong type
+    TypeError(String),
 }
 
 impl std::fmt::Display for ParseError {
@@ -199,6 +592,13 @@ impl std::fmt::Display for ParseError {
             ParseError::InvalidFrequency(freq) => write!(f, "Invalid frequency: {}", freq),
             ParseError::InvalidRatio(ratio) => write!(f, "Invalid ratio: {}", ratio),
             ParseError::UndefinedReference(name) => write!(f, "Undefined reference: {}", name),
+            ParseError::InvalidRhythm(rhythm) => write!(f, "Invalid rhythm: {}", rhythm),
+            ParseError::UnexpectedToken { expected, found } => {
+                write!(f, "expected {}, found {}", join_expected(expected), found)
+            }
+            ParseError::UnknownStatement(keyword) => write!(f, "Unknown statement: {}", keyword),
+            ParseError::DuplicateDefinition(name) => write!(f, "Duplicate definition: {}", name),
+            ParseError::TypeError(message) => write!(f, "Type error: {}", message),
         }
     }
 }
@@ -206,25 +606,14 @@ impl std::fmt::Display for ParseError {
 impl std::error::Error for ParseError {}
 
 // Convert ParseError to ZyrkomError
+//
+// Errors found while parsing are located and convert through `Diagnostic`;
+// a bare `ParseError` comes from checks after parsing and has no line.
 impl From<ParseError> for ZyrkomError {
     fn from(parse_error: ParseError) -> Self {
-        match parse_error {
-            ParseError::InvalidSyntax(line) => ZyrkomError::ParseError {
-                message: format!("Invalid syntax: {}", line),
-                line: 0, // TODO: Track actual line numbers
-            },
-            ParseError::InvalidFrequency(freq) => ZyrkomError::ParseError {
-                message: format!("Invalid frequency: {}", freq),
-                line: 0,
-            },
-            ParseError::InvalidRatio(ratio) => ZyrkomError::ParseError {
-                message: format!("Invalid ratio: {}", ratio),
-                line: 0,
-            },
-            ParseError::UndefinedReference(name) => ZyrkomError::ParseError {
-                message: format!("Undefined reference: {}", name),
-                line: 0,
-            },
+        ZyrkomError::ParseError {
+            message: parse_error.to_string(),
+            line: 0,
         }
     }
 }
@@ -237,6 +626,13 @@ impl ParsedElement {
             ParsedElement::Chord { name, .. } => name,
             ParsedElement::Interval { name, .. } => name,
             ParsedElement::Constraint { name, .. } => name,
+            ParsedElement::TimeSignature { .. } => "time",
+            ParsedElement::Tempo { .. } => "tempo",
+            ParsedElement::Rhythm { name, .. } => name,
+            ParsedElement::Melody { name, .. } => name,
+            ParsedElement::Reference { .. } => "reference",
+            ParsedElement::Key { .. } => "key",
+            ParsedElement::Instrument { name, .. } => name,
         }
     }
 
@@ -245,18 +641,19 @@ impl ParsedElement {
         let mut system = ConstraintSystem::new();
         
         match self {
-            ParsedElement::Note { frequency, .. } => {
-                // Create a basic frequency validation constraint
-                if let Ok(constraint) = MusicalConstraint::from_ratio(*frequency / 440.0, ConstraintType::HarmonicRatio) {
+            ParsedElement::Note { frequency, reference, .. } => {
+                // Create a basic frequency validation constraint against the concert pitch
+                if let Ok(constraint) = MusicalConstraint::from_ratio(*frequency / reference.a4_frequency(), ConstraintType::HarmonicRatio) {
                     system.add_constraint(constraint);
                 }
+                system.declare_pitch_reference(*reference);
             },
-            ParsedElement::Chord { notes, .. } => {
-                // Create constraints for chord intervals
-                for _ in notes {
-                    if let Ok(constraint) = MusicalConstraint::from_ratio(1.5, ConstraintType::Consonance) {
-                        system.add_constraint(constraint);
-                    }
+            ParsedElement::Chord { pitches, .. } => {
+                // Intervals between the resolved member frequencies, rooted on the first
+                if let Some(&root) = pitches.first() {
+                    system = Chord::from_voicing(root, pitches.clone())
+                        .and_then(|chord| chord.to_constraints())
+                        .map_err(|e| ParseError::InvalidFrequency(e.to_string()))?;
                 }
             },
             ParsedElement::Interval { ratio, .. } => {
@@ -265,12 +662,40 @@ impl ParsedElement {
                     system.add_constraint(constraint);
                 }
             },
-            ParsedElement::Constraint { .. } => {
-                // Create generic constraint - placeholder
-                if let Ok(constraint) = MusicalConstraint::from_ratio(1.0, ConstraintType::TuningConsistency) {
-                    system.add_constraint(constraint);
+            ParsedElement::Constraint { value, .. } => {
+                system = value.to_constraints()
+                    .map_err(|e| ParseError::TypeError(e.to_string()))?;
+            },
+            ParsedElement::TimeSignature { .. } => {
+                // Declarations only; measures are checked on each rhythm
+            },
+            ParsedElement::Tempo { tempo, bounds } => {
+                if let Some(bounds) = bounds {
+                    system = tempo.bounds_constraints(bounds)
+                        .map_err(|e| ParseError::InvalidRhythm(e.to_string()))?;
                 }
             },
+            ParsedElement::Rhythm { passage, .. } => {
+                system = passage.to_constraints()
+                    .map_err(|e| ParseError::InvalidRhythm(e.to_string()))?;
+            },
+            ParsedElement::Melody { melody, tempo, tempo_bounds, .. } => {
+                system = melody.to_constraints()
+                    .map_err(|e| ParseError::InvalidRhythm(e.to_string()))?;
+                if let (Some(tempo), Some(bounds)) = (tempo, tempo_bounds) {
+                    system.merge(tempo.bounds_constraints(bounds)
+                        .map_err(|e| ParseError::InvalidRhythm(e.to_string()))?);
+                }
+            },
+            ParsedElement::Reference { reference } => {
+                system.declare_pitch_reference(*reference);
+            },
+            ParsedElement::Key { key, .. } => {
+                system.declare_key(*key);
+            },
+            ParsedElement::Instrument { .. } => {
+                // Declarations only; parts are checked with InstrumentPart
+            },
         }
         
         Ok(system)
@@ -288,9 +713,10 @@ mod tests {
         
         assert_eq!(result.len(), 1);
         match &result[0] {
-            ParsedElement::Note { name, frequency } => {
+            ParsedElement::Note { name, frequency, reference } => {
                 assert_eq!(name, "C4");
                 assert_eq!(*frequency, 261.63);
+                assert_eq!(*reference, PitchReference::standard());
             },
             _ => panic!("Expected Note element"),
         }
@@ -299,18 +725,56 @@ mod tests {
     #[test]
     fn test_parse_chord() {
         let mut parser = ZyrkomParser::new();
-        let result = parser.parse("chord C_major = C + E + G").unwrap();
+        let dsl_code = "chord C_major = C + E + G\nnote C = 261.63\nnote E = 329.63\nnote G = 392.00";
+        let result = parser.parse(dsl_code).unwrap();
         
-        assert_eq!(result.len(), 1);
+        // Declared in any order, followed by the inferred key
+        assert_eq!(result.len(), 5);
         match &result[0] {
-            ParsedElement::Chord { name, notes } => {
+            ParsedElement::Chord { name, notes, pitches } => {
                 assert_eq!(name, "C_major");
                 assert_eq!(notes, &vec!["C".to_string(), "E".to_string(), "G".to_string()]);
+                let midi: Vec<u8> = pitches.iter().map(|p| p.midi_number()).collect();
+                assert_eq!(midi, vec![60, 64, 67]);
             },
             _ => panic!("Expected Chord element"),
         }
     }
 
+    #[test]
+    fn test_chord_constraints_use_resolved_frequencies() {
+        let mut parser = ZyrkomParser::new();
+        let dsl_code = "note A3 = 220\nnote E4 = 330\ninterval major_tenth = 2.5\nchord open_fifth = A3 + E4 + major_tenth";
+        let elements = parser.parse(dsl_code).unwrap();
+        let system = elements[3].to_constraints().unwrap();
+
+        // Pairwise intervals 3:2, 5:2 and 5:3 above A3
+        let ratios: Vec<f64> = system
+            .constraints
+            .iter()
+            .filter(|c| c.constraint_type == ConstraintType::HarmonicRatio)
+            .map(|c| c.ratio_f64)
+            .collect();
+        assert_eq!(ratios.len(), 3);
+        for (ratio, expected) in ratios.iter().zip([1.5, 2.5, 5.0 / 3.0]) {
+            assert!((ratio - expected).abs() < 1e-3, "{} != {}", ratio, expected);
+        }
+
+        // A chord of undeclared names no longer passes silently
+        let errors = parser.parse("note C4 = 261.63\nnote C4 = 262\nchord C_major = C + E + G").unwrap_err();
+        let found: Vec<&ParseError> = errors.diagnostics().iter().map(Diagnostic::error).collect();
+        assert_eq!(
+            found,
+            vec![
+                &ParseError::DuplicateDefinition("C4".to_string()),
+                &ParseError::UndefinedReference("C".to_string()),
+                &ParseError::UndefinedReference("E".to_string()),
+                &ParseError::UndefinedReference("G".to_string()),
+            ]
+        );
+        assert!(errors.render("song.zyrkom").contains("first declared as a note on line 1"));
+    }
+
     #[test]
     fn test_parse_interval() {
         let mut parser = ZyrkomParser::new();
@@ -332,7 +796,7 @@ mod tests {
         let dsl_code = r#"
             note C4 = 261.63
             note E4 = 329.63
-            chord C_major = C + E + G
+            chord C_major = C4 + E4 + perfect_fifth
             interval perfect_fifth = 1.5
         "#;
         
@@ -340,6 +804,38 @@ mod tests {
         assert_eq!(result.len(), 4);
     }
 
+    #[test]
+    fn test_parse_constraint_expressions() {
+        let mut parser = ZyrkomParser::new();
+        let dsl_code = r#"
+            note freq1 = 392.00
+            note freq2 = 261.63
+            interval ratio = 1.5
+            constraint harmonic = freq1 / freq2 == ratio && freq2 < freq1
+        "#;
+        let elements = parser.parse(dsl_code).unwrap();
+        match &elements[3] {
+            ParsedElement::Constraint { expression, value, .. } => {
+                assert_eq!(expression, "freq1 / freq2 == ratio && freq2 < freq1");
+                assert!(matches!(value, Constant::Condition(condition) if condition.holds()));
+            },
+            _ => panic!("Expected Constraint element"),
+        }
+        let system = elements[3].to_constraints().unwrap();
+        assert_eq!(system.constraint_count(), 2);
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+
+        let errors = parser.parse("note C4 = 261.63\nconstraint c = C4 * 2\nconstraint d = C4").unwrap_err();
+        let found: Vec<String> = errors.diagnostics().iter().map(|d| d.error().to_string()).collect();
+        assert_eq!(
+            found,
+            vec![
+                "Type error: cannot apply `*` to note and number".to_string(),
+                "Type error: a constraint must be a condition, chord or interval, found note".to_string(),
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_with_comments() {
         let mut parser = ZyrkomParser::new();
@@ -353,4 +849,221 @@ mod tests {
         let result = parser.parse(dsl_code).unwrap();
         assert_eq!(result.len(), 2); // Comments should be ignored
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_parse_rhythmic_values() {
+        assert_eq!(parse_rhythmic_value("q").unwrap(), RhythmicValue::new(NoteValue::Quarter));
+        assert_eq!(parse_rhythmic_value("h.").unwrap(), RhythmicValue::dotted(NoteValue::Half, 1));
+        assert_eq!(
+            parse_rhythmic_value("e/3").unwrap(),
+            RhythmicValue::in_tuplet(NoteValue::Eighth, Tuplet::triplet())
+        );
+        assert_eq!(parse_rhythmic_value("s/5").unwrap().tuplet, Some(Tuplet::new(5, 4).unwrap()));
+        assert!(parse_rhythmic_value("z").is_err());
+        assert_eq!(parse_rhythmic_value("e/2:3").unwrap().tuplet, Some(Tuplet::new(2, 3).unwrap()));
+        assert!(parse_rhythmic_value("q/1").is_err());
+        assert!(parse_rhythmic_value("q/4").is_err());
+        assert!(parse_rhythmic_value("e/4294967291").is_err());
+        assert!(parse_rhythmic_value("q/3:65").is_err());
+    }
+
+    #[test]
+    fn test_parse_rhythm_with_meter_and_tempo() {
+        let mut parser = ZyrkomParser::new();
+        let dsl_code = r#"
+            time 3/4
+            tempo 76 within 72..80
+            rhythm waltz = pickup q | h q | q. e q | h
+        "#;
+
+        let result = parser.parse(dsl_code).unwrap();
+        assert_eq!(result.len(), 3);
+
+        let system = result[2].to_constraints().unwrap();
+        // pickup (2 range checks) + 1 full measure + dotted measure + final + tempo (2)
+        assert_eq!(system.constraint_count(), 7);
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+
+        match &result[2] {
+            ParsedElement::Rhythm { passage, .. } => {
+                assert_eq!(passage.time_signature(), TimeSignature::new(3, 4).unwrap());
+                assert!(passage.has_anacrusis());
+            },
+            _ => panic!("Expected Rhythm element"),
+        }
+    }
+
+    #[test]
+    fn test_parse_melody_with_ties_and_repeats() {
+        let mut parser = ZyrkomParser::new();
+        let dsl_code = r#"
+            time 3/4
+            tempo 76 within 72..80
+            note FA = 349.23
+            note DO = 261.63
+            melody phrase = FA:q DO:e~ DO:e [FA:e rest:e] * 2 DO:h
+        "#;
+
+        let result = parser.parse(dsl_code).unwrap();
+        assert_eq!(result.len(), 5);
+        let melody = match &result[4] {
+            ParsedElement::Melody { melody, tempo, .. } => {
+                assert_eq!(tempo.map(|t| t.bpm()), Some(76.0));
+                melody
+            },
+            _ => panic!("Expected Melody element"),
+        };
+
+        // The tied eighths sound as one quarter; the repeat plays twice
+        let onsets: Vec<NoteDuration> = melody.events().iter().map(|e| e.onset()).collect();
+        let at = |n, d| NoteDuration::new(n, d).unwrap();
+        assert_eq!(onsets, vec![at(0, 1), at(1, 4), at(1, 2), at(3, 4), at(1, 1)]);
+        assert_eq!(melody.events()[1].duration(), at(1, 4));
+        assert_eq!(melody.duration(), at(3, 2));
+        assert_eq!(melody.measure_count(), 2);
+
+        // Four steps (interval + timing each) + tempo (2)
+        let system = result[4].to_constraints().unwrap();
+        assert_eq!(system.constraint_count(), 10);
+        let timing: Vec<_> = system.constraints.iter().filter(|c| c.constraint_type == ConstraintType::NoteTiming).collect();
+        assert_eq!(timing.len(), 4);
+        assert!(timing.iter().all(|c| c.is_satisfied()));
+
+        let notes = "note FA = 349.23\nnote DO = 261.63\n";
+        for (melody, expected) in [
+            ("melody m = FA:q~ DO:q", ParseError::InvalidRhythm("FA is tied to a different note, DO".to_string())),
+            ("melody m = FA:q~ rest:q", ParseError::InvalidRhythm("FA is tied to a rest".to_string())),
+            ("melody m = DO:q FA:q~", ParseError::InvalidRhythm("FA is tied past the end of the melody".to_string())),
+            ("melody m = [FA:q] * 0", ParseError::InvalidSyntax("repeat count must be a whole number from 1 to 64".to_string())),
+            (
+                "melody m = [[[[[FA:q]*64]*64]*64]*64]*64",
+                ParseError::InvalidSyntax("melody unrolls to more than 65536 notes and rests".to_string()),
+            ),
+        ] {
+            let errors = ZyrkomParser::new().parse(&format!("{}{}", notes, melody)).unwrap_err();
+            assert_eq!(errors.first().map(Diagnostic::error), Some(&expected), "{}", melody);
+            assert_eq!(errors.first().unwrap().line(), 3);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_time_signature() {
+        let mut parser = ZyrkomParser::new();
+        let errors = parser.parse("time 4/3").unwrap_err();
+        assert!(matches!(errors.first().map(Diagnostic::error), Some(ParseError::InvalidRhythm(_))));
+    }
+
+    #[test]
+    fn test_parse_pitch_reference() {
+        let mut parser = ZyrkomParser::new();
+        let elements = parser.parse("reference A4 = 415\nnote A4 = 415.0").unwrap();
+
+        match &elements[1] {
+            ParsedElement::Note { reference, .. } => assert_eq!(*reference, PitchReference::baroque()),
+            _ => panic!("Expected Note element"),
+        }
+
+        // The note is the reference pitch itself, recorded in the statement
+        let system = elements[1].to_constraints().unwrap();
+        assert_eq!(system.constraints[0].ratio_f64, 1.0);
+        assert_eq!(system.pitch_reference(), PitchReference::baroque());
+
+        assert!(parser.parse("reference A4 = 44").is_err());
+        assert!(parser.parse("reference C4 = 261.6").is_err());
+    }
+
+    #[test]
+    fn test_parse_and_infer_key() {
+        let mut parser = ZyrkomParser::new();
+        let declared = parser.parse("key F major\nnote A4 = 440.0").unwrap();
+        assert_eq!(declared.len(), 2);
+        assert_eq!(declared[0], ParsedElement::Key { key: Key::parse("F major").unwrap(), confidence: None });
+        assert!(parser.parse("key H major").is_err());
+
+        // Without a declaration the key comes from the notes
+        let dsl_code = r#"
+            note FA = 349.23
+            note DO = 261.63
+            note LA = 440.00
+            note SIB = 466.16
+            note SOL = 392.00
+        "#;
+        let elements = parser.parse(dsl_code).unwrap();
+        assert_eq!(elements.len(), 6);
+        match &elements[5] {
+            ParsedElement::Key { key, confidence } => {
+                assert_eq!(key.to_string(), "F major");
+                assert!(confidence.unwrap() > 0.0);
+            },
+            _ => panic!("Expected Key element"),
+        }
+
+        let system = elements[5].to_constraints().unwrap();
+        assert_eq!(system.key(), Some(Key::parse("F major").unwrap()));
+
+        // Too few pitch classes to say anything about the key
+        assert_eq!(parser.parse("note C4 = 261.63\nnote G4 = 392.0").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_instrument_declarations() {
+        let mut parser = ZyrkomParser::new();
+        let dsl_code = r#"
+            instrumento Piano {
+                rango: [A0, C8],         // 88 teclas
+                resonancia: overtones(8),
+                inharmonicity: 0.0002
+            }
+            instrument Lead { resonance: clarinet, range: [D3, Bb6], transposition: -2, polyphony: 1 }
+            instrument Pad
+        "#;
+        let elements = parser.parse(dsl_code).unwrap();
+        assert_eq!(elements.len(), 3);
+        match &elements[0] {
+            ParsedElement::Instrument { name, instrument } => {
+                assert_eq!(name, "Piano");
+                assert_eq!(instrument.lowest().midi_number(), 21);
+                assert_eq!(instrument.highest().midi_number(), 108);
+                assert_eq!(instrument.hand_span(), Instrument::piano().hand_span());
+                assert_eq!(instrument.timbre().partial_count(), 8);
+                assert_eq!(instrument.timbre().inharmonicity(), 0.0002);
+            },
+            _ => panic!("Expected Instrument element"),
+        }
+        match &elements[1] {
+            ParsedElement::Instrument { instrument, .. } => {
+                assert_eq!(instrument.timbre(), &Timbre::clarinet());
+                assert_eq!((instrument.lowest().midi_number(), instrument.highest().midi_number()), (50, 94));
+                assert_eq!((instrument.transposition(), instrument.polyphony()), (-2, 1));
+            },
+            _ => panic!("Expected Instrument element"),
+        }
+        match &elements[2] {
+            ParsedElement::Instrument { instrument, .. } => assert_eq!(instrument.timbre(), &Timbre::default()),
+            _ => panic!("Expected Instrument element"),
+        }
+        assert!(elements[0].to_constraints().unwrap().constraints.is_empty());
+
+        assert!(parser.parse("instrument Piano { resonance: overtones(0) }").is_err());
+        assert!(parser.parse("instrument Piano { range: [C8, A0] }").is_err());
+        assert!(parser.parse("instrument Piano { sustain: 3 }").is_err());
+        assert!(parser.parse("instrument Piano {\n resonance: overtones(4)").is_err());
+    }
+
+    #[test]
+    fn test_errors_are_located_and_collected() {
+        let mut parser = ZyrkomParser::new();
+        let dsl_code = "note C4 = 261.63\nmotif x = C4\nrhythm r = q z q\nreference 0\n";
+        let errors = parser.parse(dsl_code).unwrap_err();
+        assert_eq!(errors.len(), 3);
+
+        let located: Vec<(usize, usize)> = errors.diagnostics().iter().map(|d| (d.line(), d.column())).collect();
+        assert_eq!(located, vec![(2, 1), (3, 14), (4, 11)]);
+        assert_eq!(errors.diagnostics()[0].error(), &ParseError::UnknownStatement("motif".to_string()));
+        assert_eq!(errors.diagnostics()[1].error(), &ParseError::InvalidRhythm("z".to_string()));
+        assert!(errors.render("song.zyrkom").contains(" --> song.zyrkom:3:14"));
+
+        let error = ZyrkomError::from(errors);
+        assert!(matches!(error, ZyrkomError::ParseError { line: 2, .. }));
+    }
+}
\ No newline at end of file
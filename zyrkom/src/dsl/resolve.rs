@@ -0,0 +1,409 @@
+//! Name resolution
+//!
+//! Collects every named declaration of a program into one namespace, then
+//! checks every name a statement refers to. Declarations may appear in any
+//! order; a name declared twice or used without a declaration is an error,
+//! and so is a second `key` statement.
+//! Notes are bound to their pitch at the concert pitch in effect where they
+//! are declared. Names inside constraint expressions are checked when the
+//! expression is type-checked, and names inside progression bodies when the
+//! progression is instantiated.
+
+use std::collections::HashMap;
+
+use crate::dsl::ast::{Ident, MelodyItem, Program, Spanned, StatementKind, Template};
+use crate::dsl::diagnostic::{join_expected, Diagnostic};
+use crate::dsl::lexer::Span;
+use crate::dsl::parser::ParseError;
+use crate::dsl::template::ParameterType;
+use crate::musical::{Key, MusicalInterval, MusicalNote, PitchReference};
+
+/// What a name is bound to
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolKind {
+    /// A note and its pitch (`None` when its frequency is out of range)
+    Note(Option<MusicalNote>),
+    /// An interval and its frequency ratio
+    Interval(f64),
+    /// A chord and its members
+    Chord(Vec<Ident>),
+    /// A constraint
+    Constraint,
+    /// A rhythm
+    Rhythm,
+    /// A melody
+    Melody,
+    /// An instrument
+    Instrument,
+    /// A progression template
+    Template(Template),
+}
+
+impl SymbolKind {
+    /// Name of the kind of declaration, for messages
+    pub fn describe(&self) -> &'static str {
+        match self {
+            SymbolKind::Note(_) => "note",
+            SymbolKind::Interval(_) => "interval",
+            SymbolKind::Chord(_) => "chord",
+            SymbolKind::Constraint => "constraint",
+            SymbolKind::Rhythm => "rhythm",
+            SymbolKind::Melody => "melody",
+            SymbolKind::Instrument => "instrument",
+            SymbolKind::Template(_) => "progression",
+        }
+    }
+}
+
+/// A declared name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    /// What the name is bound to
+    pub kind: SymbolKind,
+    /// Where the name is declared
+    pub span: Span,
+}
+
+/// Every name declared in a program
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    symbols: HashMap<String, Symbol>,
+    key: Option<Key>,
+}
+
+impl SymbolTable {
+    /// Look up a name
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.get(name)
+    }
+
+    /// The pitch of a declared note
+    pub fn note(&self, name: &str) -> Option<MusicalNote> {
+        match self.get(name)?.kind {
+            SymbolKind::Note(pitch) => pitch,
+            _ => None,
+        }
+    }
+
+    /// The ratio of a declared interval
+    pub fn interval(&self, name: &str) -> Option<f64> {
+        match self.get(name)?.kind {
+            SymbolKind::Interval(ratio) => Some(ratio),
+            _ => None,
+        }
+    }
+
+    /// The pitches of a declared chord
+    pub fn chord(&self, name: &str) -> Option<Vec<MusicalNote>> {
+        match &self.get(name)?.kind {
+            SymbolKind::Chord(members) => self.chord_pitches(members).ok(),
+            _ => None,
+        }
+    }
+
+    /// Pitches of chord members: notes as declared, intervals stacked on the root
+    ///
+    /// Members `resolve` rejected are skipped; their errors are already reported.
+    pub fn chord_pitches(&self, members: &[Ident]) -> Result<Vec<MusicalNote>, (ParseError, Span)> {
+        let mut pitches: Vec<MusicalNote> = Vec::with_capacity(members.len());
+        for member in members {
+            let pitch = match self.get(&member.node).map(|symbol| &symbol.kind) {
+                Some(SymbolKind::Note(Some(pitch))) => *pitch,
+                Some(&SymbolKind::Interval(ratio)) => {
+                    let invalid = || (ParseError::InvalidRatio(format!("{} = {}", member.node, ratio)), member.span);
+                    let root = match pitches.first() {
+                        Some(root) if ratio > 0.0 => root,
+                        _ => return Err(invalid()),
+                    };
+                    root.transpose(&MusicalInterval::from_cents(MusicalInterval::ratio_to_cents(ratio)))
+                        .map_err(|_| invalid())?
+                }
+                _ => continue,
+            };
+            pitches.push(pitch);
+        }
+        Ok(pitches)
+    }
+
+    /// A declared progression template
+    pub fn template(&self, name: &str) -> Option<&Template> {
+        match &self.get(name)?.kind {
+            SymbolKind::Template(template) => Some(template),
+            _ => None,
+        }
+    }
+
+    /// The key declared by a `key` statement, if any
+    pub fn key(&self) -> Option<Key> {
+        self.key
+    }
+
+    /// Number of declared names
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Whether nothing is declared
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+/// Bind every declaration and check every reference
+///
+/// `reference` is the concert pitch in effect before the first `reference`
+/// statement.
+pub fn resolve(program: &Program, mut reference: PitchReference, source: &str) -> (SymbolTable, Vec<Diagnostic>) {
+    let mut table = SymbolTable::default();
+    let mut diagnostics = Vec::new();
+    let mut key_span: Option<Span> = None;
+
+    for statement in &program.statements {
+        let (name, kind) = match &statement.kind {
+            StatementKind::Reference { frequency } => {
+                reference = PitchReference::new(frequency.node).unwrap_or(reference);
+                continue;
+            }
+            StatementKind::Note { name, frequency } => {
+                let pitch = MusicalNote::from_frequency_at(frequency.node, reference).ok();
+                if pitch.is_none() {
+                    let text = source[frequency.span.start..frequency.span.end].to_string();
+                    diagnostics.push(Diagnostic::new(ParseError::InvalidFrequency(text), frequency.span, source));
+                }
+                (name, SymbolKind::Note(pitch))
+            }
+            StatementKind::Interval { name, ratio } => (name, SymbolKind::Interval(ratio.node)),
+            StatementKind::Chord { name, notes } => (name, SymbolKind::Chord(notes.clone())),
+            StatementKind::Constraint { name, .. } => (name, SymbolKind::Constraint),
+            StatementKind::Rhythm { name, .. } => (name, SymbolKind::Rhythm),
+            StatementKind::Melody { name, .. } => (name, SymbolKind::Melody),
+            StatementKind::Instrument { name, .. } => (name, SymbolKind::Instrument),
+            StatementKind::Key { spec } => {
+                // A program has one key, so `in_scale` and the statement agree
+                if let Some(first) = key_span {
+                    let first_line = source[..first.start].matches('\n').count() + 1;
+                    diagnostics.push(
+                        Diagnostic::new(ParseError::DuplicateDefinition("key".to_string()), spec.span, source)
+                            .with_label(&format!("key first declared on line {}", first_line)),
+                    );
+                    continue;
+                }
+                // An invalid key is reported when the statement is lowered
+                key_span = Some(spec.span);
+                table.key = Key::parse(&spec.node).ok();
+                continue;
+            }
+            StatementKind::Template(template) => (&template.name, SymbolKind::Template(template.clone())),
+            StatementKind::Time { .. } | StatementKind::Tempo { .. } | StatementKind::Instance { .. } => continue,
+        };
+
+        if let Some(previous) = table.get(&name.node) {
+            let first_line = source[..previous.span.start].matches('\n').count() + 1;
+            diagnostics.push(
+                Diagnostic::new(ParseError::DuplicateDefinition(name.node.clone()), name.span, source).with_label(
+                    &format!("first declared as a {} on line {}", previous.kind.describe(), first_line),
+                ),
+            );
+            continue;
+        }
+        table.symbols.insert(name.node.clone(), Symbol { kind, span: name.span });
+    }
+
+    for statement in &program.statements {
+        if let StatementKind::Template(template) = &statement.kind {
+            check_template(template, source, &mut diagnostics);
+        }
+        if let StatementKind::Instance { template, .. } = &statement.kind {
+            let error = match table.get(&template.node).map(|symbol| &symbol.kind) {
+                None => Some((ParseError::UndefinedReference(template.node.clone()), "not declared".to_string())),
+                Some(SymbolKind::Template(_)) => None,
+                Some(other) => Some((
+                    ParseError::InvalidSyntax(format!("{} is not a progression", template.node)),
+                    format!("declared as a {}", other.describe()),
+                )),
+            };
+            if let Some((kind, label)) = error {
+                diagnostics.push(Diagnostic::new(kind, template.span, source).with_label(&label));
+            }
+        }
+        if let StatementKind::Melody { items, .. } = &statement.kind {
+            let mut pitches = Vec::new();
+            melody_pitches(items, &mut pitches);
+            for pitch in pitches {
+                let error = match table.get(&pitch.node).map(|symbol| &symbol.kind) {
+                    None => (ParseError::UndefinedReference(pitch.node.clone()), "not declared".to_string()),
+                    Some(SymbolKind::Note(_)) => continue,
+                    Some(other) => (
+                        ParseError::InvalidSyntax(format!("{} is not a note", pitch.node)),
+                        format!("declared as a {}", other.describe()),
+                    ),
+                };
+                diagnostics.push(Diagnostic::new(error.0, pitch.span, source).with_label(&error.1));
+            }
+        }
+        if let StatementKind::Chord { notes, .. } = &statement.kind {
+            for (position, member) in notes.iter().enumerate() {
+                let error = match table.get(&member.node).map(|symbol| &symbol.kind) {
+                    None => Some((ParseError::UndefinedReference(member.node.clone()), "not declared".to_string())),
+                    Some(SymbolKind::Note(_)) => None,
+                    // Intervals stack above the first note, which must be a note
+                    Some(SymbolKind::Interval(_)) if position > 0 => None,
+                    Some(SymbolKind::Interval(_)) => Some((
+                        ParseError::InvalidSyntax(format!("chord starts with interval {}", member.node)),
+                        "the first member must be a note".to_string(),
+                    )),
+                    Some(other) => Some((
+                        ParseError::InvalidSyntax(format!("{} is not a note or interval", member.node)),
+                        format!("declared as a {}", other.describe()),
+                    )),
+                };
+                if let Some((kind, label)) = error {
+                    diagnostics.push(Diagnostic::new(kind, member.span, source).with_label(&label));
+                }
+            }
+        }
+    }
+
+    (table, diagnostics)
+}
+
+/// Check parameter types and that parameters and local declarations have
+/// distinct names
+///
+/// Names inside the body are resolved when the template is instantiated.
+fn check_template(template: &Template, source: &str, diagnostics: &mut Vec<Diagnostic>) {
+    for parameter in &template.parameters {
+        if ParameterType::from_name(&parameter.ty.node).is_none() {
+            let error = ParseError::TypeError(format!("unknown parameter type {}", parameter.ty.node));
+            diagnostics.push(Diagnostic::new(error, parameter.ty.span, source).with_label(&format!(
+                "expected {}",
+                join_expected(&ParameterType::ALL.map(|ty| format!("`{}`", ty.name())))
+            )));
+        }
+    }
+
+    let names = template.parameters.iter().map(|p| &p.name).chain(template.body.iter().map(|l| &l.name));
+    let mut seen: HashMap<&str, Span> = HashMap::new();
+    for name in names {
+        match seen.get(name.node.as_str()) {
+            Some(first) => {
+                let first_line = source[..first.start].matches('\n').count() + 1;
+                diagnostics.push(
+                    Diagnostic::new(ParseError::DuplicateDefinition(name.node.clone()), name.span, source)
+                        .with_label(&format!("first declared in {} on line {}", template.name.node, first_line)),
+                );
+            }
+            None => {
+                seen.insert(&name.node, name.span);
+            }
+        }
+    }
+}
+
+/// Every note a melody names, in source order, each repeat listed once
+fn melody_pitches<'a>(items: &'a [Spanned<MelodyItem>], pitches: &mut Vec<&'a Ident>) {
+    for item in items {
+        match &item.node {
+            MelodyItem::Note { pitch, .. } => pitches.push(pitch),
+            MelodyItem::Rest { .. } => {}
+            MelodyItem::Repeat { items, .. } => melody_pitches(items, pitches),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::syntax::parse_program;
+
+    fn resolved(source: &str) -> (SymbolTable, Vec<Diagnostic>) {
+        let (program, syntax_errors) = parse_program(source);
+        assert!(syntax_errors.is_empty());
+        resolve(&program, PitchReference::standard(), source)
+    }
+
+    #[test]
+    fn test_binds_notes_at_their_reference() {
+        let (table, diagnostics) = resolved("note A4 = 440\nreference 415\nnote A4_baroque = 415\ninterval fifth = 1.5");
+        assert!(diagnostics.is_empty());
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.note("A4").unwrap().midi_number(), 69);
+        assert_eq!(table.note("A4_baroque").unwrap().reference(), PitchReference::baroque());
+        assert_eq!(table.interval("fifth"), Some(1.5));
+        assert_eq!(table.note("fifth"), None);
+    }
+
+    #[test]
+    fn test_undefined_and_duplicate_names() {
+        let source = "note C4 = 261.63\nchord Cmaj = C + E4\nrhythm C4 = q\nchord bad = fifth + C4\ninterval fifth = 1.5";
+        let (_, diagnostics) = resolved(source);
+        let errors: Vec<&ParseError> = diagnostics.iter().map(|d| d.error()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                &ParseError::DuplicateDefinition("C4".to_string()),
+                &ParseError::UndefinedReference("C".to_string()),
+                &ParseError::UndefinedReference("E4".to_string()),
+                &ParseError::InvalidSyntax("chord starts with interval fifth".to_string()),
+            ]
+        );
+        assert!(diagnostics[0].to_string().contains("first declared as a note on line 1"));
+        assert_eq!((diagnostics[2].line(), diagnostics[2].column()), (2, 18));
+    }
+
+    #[test]
+    fn test_one_key_per_program() {
+        let (table, diagnostics) = resolved("key F major
+note A4 = 440
+key D minor");
+        assert_eq!(table.key(), Some(Key::parse("F major").unwrap()));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].error(), &ParseError::DuplicateDefinition("key".to_string()));
+        assert_eq!((diagnostics[0].line(), diagnostics[0].column()), (3, 5));
+        assert!(diagnostics[0].to_string().contains("key first declared on line 1"));
+    }
+
+    #[test]
+    fn test_melody_pitches_must_be_notes() {
+        let source = "note FA = 349.23\ninterval fifth = 1.5\nmelody m = FA:q [fifth:e SOL:e] * 2\nmelody m = FA:h";
+        let (table, diagnostics) = resolved(source);
+        let errors: Vec<&ParseError> = diagnostics.iter().map(|d| d.error()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                &ParseError::DuplicateDefinition("m".to_string()),
+                &ParseError::InvalidSyntax("fifth is not a note".to_string()),
+                &ParseError::UndefinedReference("SOL".to_string()),
+            ]
+        );
+        assert_eq!(table.get("m").unwrap().kind, SymbolKind::Melody);
+        assert_eq!((diagnostics[2].line(), diagnostics[2].column()), (3, 26));
+    }
+
+    #[test]
+    fn test_progressions_and_instantiations() {
+        let source = "progression p(k: Mode, x: Note) {\nchord x = k.triad(1)\n}\nnote A4 = 440\np(C, A4)\nA4(C)\nq(C)";
+        let (table, diagnostics) = resolved(source);
+        let errors: Vec<&ParseError> = diagnostics.iter().map(|d| d.error()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                &ParseError::TypeError("unknown parameter type Mode".to_string()),
+                &ParseError::DuplicateDefinition("x".to_string()),
+                &ParseError::InvalidSyntax("A4 is not a progression".to_string()),
+                &ParseError::UndefinedReference("q".to_string()),
+            ]
+        );
+        assert!(diagnostics[0].to_string().contains("expected `Key`, `Note`, `Interval`, `Chord` or `Number`"));
+        assert!(diagnostics[1].to_string().contains("first declared in p on line 1"));
+        assert_eq!(table.template("p").map(|t| t.body.len()), Some(1));
+    }
+
+    #[test]
+    fn test_out_of_range_note() {
+        let (table, diagnostics) = resolved("note low = 0.5\nchord c = low");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].error(), &ParseError::InvalidFrequency("0.5".to_string()));
+        assert_eq!(table.get("low").unwrap().kind, SymbolKind::Note(None));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,812 @@
+//! Recursive-descent parser from tokens to the syntax tree
+//!
+//! Grammar, one statement per line (instrument and progression blocks may
+//! span lines):
+//!
+//! ```text
+//! program    := (statement? NEWLINE)* EOF
+//! statement  := "note" IDENT "=" NUMBER
+//!             | "chord" IDENT "=" IDENT ("+" IDENT)*
+//!             | "interval" IDENT "=" NUMBER
+//!             | "constraint" IDENT "=" expr
+//!             | "time" NUMBER "/" NUMBER
+//!             | "tempo" NUMBER ("within" NUMBER ".." NUMBER)?
+//!             | "rhythm" IDENT "=" "pickup"? duration+ ("|" duration+)*
+//!             | "melody" IDENT "=" item+
+//!             | "reference" ("A4" "=")? NUMBER "Hz"?
+//!             | "key" IDENT IDENT?
+//!             | ("instrument" | "instrumento") IDENT ("{" property ("," property)* "}")?
+//!             | ("progression" | "progresion") IDENT "(" (param ("," param)*)? ")" "{" local* "}"
+//!             | IDENT "(" (argument ("," argument)*)? ")"
+//! param      := IDENT ":" IDENT
+//! local      := ("note" | "interval" | "chord" | "constraint") IDENT "=" expr NEWLINE
+//! argument   := IDENT IDENT+ | expr
+//! property   := IDENT ":" value
+//! value      := "-"? NUMBER | IDENT | IDENT "(" value ("," value)* ")" | "[" value ("," value)* "]"
+//! expr       := unary (binary-op unary)*   (precedence: || && comparisons +- */)
+//! unary      := ("-" | "!")* primary
+//! primary    := atom ("." IDENT ("(" (expr ("," expr)*)? ")")?)*
+//! atom       := NUMBER | IDENT | IDENT "(" (expr ("," expr)*)? ")" | "(" expr ")"
+//! duration   := IDENT ("." | "..")* ("/" NUMBER (":" NUMBER)?)?   (no spaces inside)
+//! item       := IDENT ":" duration "~"? | "rest" ":" duration | "[" item+ "]" "*" NUMBER
+//! ```
+//!
+//! A statement that fails to parse is reported and skipped up to the end of
+//! its line, so every malformed statement in a file is reported at once.
+
+use crate::dsl::ast::{
+    BinaryOperator, Expr, Ident, Local, LocalKind, MelodyItem, Parameter, Program, Property, Spanned, Statement, StatementKind,
+    Template, UnaryOperator, Value,
+};
+use crate::dsl::diagnostic::Diagnostic;
+use crate::dsl::lexer::{tokenize, Span, Token, TokenKind};
+use crate::dsl::parser::ParseError;
+
+/// Statement keywords, in the order they are listed in errors
+pub const STATEMENT_KEYWORDS: [&str; 14] = [
+    "note", "chord", "interval", "constraint", "time", "tempo", "rhythm", "melody", "reference", "key",
+    "instrument", "instrumento", "progression", "progresion",
+];
+
+/// Deepest nesting of melody repeats, e.g. `[[C4:q] * 2] * 2` is 2
+const MAX_REPEAT_DEPTH: usize = 8;
+
+type ParseResult<T> = std::result::Result<T, Diagnostic>;
+
+/// Parse a whole source file, returning the statements that parsed and an
+/// error for each one that did not
+pub fn parse_program(source: &str) -> (Program, Vec<Diagnostic>) {
+    let (tokens, diagnostics) = tokenize(source);
+    let mut parser = SyntaxParser {
+        source,
+        tokens,
+        position: 0,
+        statement_start: 0,
+        diagnostics,
+    };
+
+    let mut program = Program::default();
+    loop {
+        parser.skip_newlines();
+        if parser.at(&TokenKind::Eof) {
+            break;
+        }
+        match parser.statement() {
+            Ok(statement) => program.statements.push(statement),
+            Err(diagnostic) => {
+                parser.diagnostics.push(diagnostic);
+                parser.recover();
+            }
+        }
+    }
+    (program, parser.diagnostics)
+}
+
+struct SyntaxParser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    statement_start: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl SyntaxParser<'_> {
+    fn statement(&mut self) -> ParseResult<Statement> {
+        self.statement_start = self.position;
+        let keyword = self.ident(&["statement keyword"])?;
+        let kind = match keyword.node.as_str() {
+            "note" => {
+                let name = self.declared_name()?;
+                StatementKind::Note { name, frequency: self.number()? }
+            }
+            "chord" => {
+                let name = self.declared_name()?;
+                let mut notes = vec![self.ident(&["note name"])?];
+                while self.eat(&TokenKind::Plus) {
+                    notes.push(self.ident(&["note name"])?);
+                }
+                StatementKind::Chord { name, notes }
+            }
+            "interval" => {
+                let name = self.declared_name()?;
+                StatementKind::Interval { name, ratio: self.number()? }
+            }
+            "constraint" => {
+                let name = self.declared_name()?;
+                StatementKind::Constraint { name, expression: self.expression(0)? }
+            }
+            "time" => {
+                let numerator = self.number()?;
+                self.expect(&TokenKind::Slash)?;
+                StatementKind::Time { numerator, denominator: self.number()? }
+            }
+            "tempo" => {
+                let bpm = self.number()?;
+                let bounds = if self.eat_keyword("within") {
+                    let min = self.number()?;
+                    self.expect(&TokenKind::DotDot)?;
+                    Some((min, self.number()?))
+                } else {
+                    None
+                };
+                StatementKind::Tempo { bpm, bounds }
+            }
+            "rhythm" => self.rhythm()?,
+            "melody" => {
+                let name = self.declared_name()?;
+                StatementKind::Melody { name, items: self.melody_items(0)? }
+            }
+            "reference" => self.reference()?,
+            "key" => {
+                let tonic = self.ident(&["key tonic"])?;
+                let mut span = tonic.span;
+                if let TokenKind::Ident(_) = self.peek().kind {
+                    span = span.to(self.advance().span);
+                }
+                StatementKind::Key {
+                    spec: Spanned::new(self.source[span.start..span.end].to_string(), span),
+                }
+            }
+            "instrument" | "instrumento" => self.instrument()?,
+            "progression" | "progresion" => self.template()?,
+            _ if self.at(&TokenKind::LParen) => {
+                self.advance();
+                StatementKind::Instance { arguments: self.arguments(true)?, template: keyword.clone() }
+            }
+            other => {
+                let error = ParseError::UnknownStatement(other.to_string());
+                let expected: Vec<String> = STATEMENT_KEYWORDS.iter().map(|k| format!("`{}`", k)).collect();
+                return Err(Diagnostic::new(error, keyword.span, self.source)
+                    .with_label(&format!("expected one of {}", crate::dsl::diagnostic::join_expected(&expected))));
+            }
+        };
+
+        let span = keyword.span.to(self.previous_span());
+        self.end_of_statement()?;
+        Ok(Statement { kind, span })
+    }
+
+    fn rhythm(&mut self) -> ParseResult<StatementKind> {
+        let name = self.declared_name()?;
+        let pickup = self.eat_keyword("pickup");
+        let mut measures = vec![Vec::new()];
+        loop {
+            match self.peek().kind {
+                TokenKind::Pipe => {
+                    self.advance();
+                    measures.push(Vec::new());
+                }
+                TokenKind::Ident(_) => {
+                    let duration = self.duration();
+                    if let Some(measure) = measures.last_mut() {
+                        measure.push(duration);
+                    }
+                }
+                _ => break,
+            }
+        }
+        if measures.iter().any(Vec::is_empty) {
+            return Err(self.unexpected(&["duration"]));
+        }
+        Ok(StatementKind::Rhythm { name, pickup, measures })
+    }
+
+    /// Melody items up to the end of the line, or up to and including the
+    /// `]` that closes a repeat when `depth` repeats are open
+    fn melody_items(&mut self, depth: usize) -> ParseResult<Vec<Spanned<MelodyItem>>> {
+        let in_repeat = depth > 0;
+        let mut items = Vec::new();
+        loop {
+            let token = self.peek().clone();
+            match token.kind {
+                TokenKind::Ident(name) => {
+                    self.advance();
+                    self.expect(&TokenKind::Colon)?;
+                    if !matches!(self.peek().kind, TokenKind::Ident(_)) {
+                        return Err(self.unexpected(&["duration"]));
+                    }
+                    let duration = self.duration();
+                    let item = if name == "rest" {
+                        MelodyItem::Rest { duration }
+                    } else {
+                        let pitch = Spanned::new(name, token.span);
+                        MelodyItem::Note { pitch, duration, tied: self.eat(&TokenKind::Tilde) }
+                    };
+                    items.push(Spanned::new(item, token.span.to(self.previous_span())));
+                }
+                TokenKind::LBracket if depth == MAX_REPEAT_DEPTH => {
+                    let message = format!("repeats nested more than {} deep", MAX_REPEAT_DEPTH);
+                    return Err(Diagnostic::new(ParseError::InvalidSyntax(message), token.span, self.source));
+                }
+                TokenKind::LBracket => {
+                    self.advance();
+                    let repeated = self.melody_items(depth + 1)?;
+                    self.expect(&TokenKind::Star)?;
+                    let count = self.number()?;
+                    let span = token.span.to(count.span);
+                    items.push(Spanned::new(MelodyItem::Repeat { items: repeated, count }, span));
+                }
+                TokenKind::RBracket if in_repeat && !items.is_empty() => {
+                    self.advance();
+                    return Ok(items);
+                }
+                _ if in_repeat => return Err(self.unexpected(&["note", "`[`", "`]`"])),
+                _ => break,
+            }
+        }
+        if items.is_empty() {
+            return Err(self.unexpected(&["note", "`[`"]));
+        }
+        Ok(items)
+    }
+
+    /// A duration token: an identifier and any dots and tuplet suffix
+    /// written directly against it
+    fn duration(&mut self) -> Spanned<String> {
+        let mut span = self.advance().span;
+        while self.peek().span.start == span.end
+            && matches!(
+                self.peek().kind,
+                TokenKind::Dot | TokenKind::DotDot | TokenKind::Slash | TokenKind::Colon | TokenKind::Number(_)
+            )
+        {
+            span = span.to(self.advance().span);
+        }
+        Spanned::new(self.source[span.start..span.end].to_string(), span)
+    }
+
+    fn reference(&mut self) -> ParseResult<StatementKind> {
+        if let TokenKind::Ident(pitch) = &self.peek().kind {
+            if pitch != "A4" {
+                let span = self.peek().span;
+                return Err(Diagnostic::new(ParseError::InvalidSyntax(format!("reference pitch {}", pitch)), span, self.source)
+                    .with_label("only A4 can set the concert pitch"));
+            }
+            self.advance();
+            self.expect(&TokenKind::Equals)?;
+        }
+        let frequency = self.number()?;
+        if matches!(&self.peek().kind, TokenKind::Ident(unit) if unit == "Hz") {
+            self.advance();
+        }
+        Ok(StatementKind::Reference { frequency })
+    }
+
+    fn instrument(&mut self) -> ParseResult<StatementKind> {
+        let name = self.ident(&["instrument name"])?;
+        let mut properties = Vec::new();
+        if self.eat(&TokenKind::LBrace) {
+            loop {
+                self.skip_separators();
+                if self.eat(&TokenKind::RBrace) {
+                    break;
+                }
+                let property = self.ident(&["property name", "`}`"])?;
+                self.expect(&TokenKind::Colon)?;
+                properties.push(Property { name: property, value: self.value()? });
+                if !matches!(self.peek().kind, TokenKind::Comma | TokenKind::Newline | TokenKind::RBrace) {
+                    return Err(self.unexpected(&["`,`", "`}`"]));
+                }
+            }
+        }
+        Ok(StatementKind::Instrument { name, properties })
+    }
+
+    fn template(&mut self) -> ParseResult<StatementKind> {
+        let name = self.ident(&["progression name"])?;
+        self.expect(&TokenKind::LParen)?;
+        let mut parameters = Vec::new();
+        if !self.eat(&TokenKind::RParen) {
+            loop {
+                let name = self.ident(&["parameter name"])?;
+                self.expect(&TokenKind::Colon)?;
+                parameters.push(Parameter { name, ty: self.ident(&["parameter type"])? });
+                if self.eat(&TokenKind::RParen) {
+                    break;
+                }
+                if !self.eat(&TokenKind::Comma) {
+                    return Err(self.unexpected(&["`,`", "`)`"]));
+                }
+            }
+        }
+
+        self.expect(&TokenKind::LBrace)?;
+        let mut body = Vec::new();
+        loop {
+            self.skip_newlines();
+            if self.eat(&TokenKind::RBrace) {
+                break;
+            }
+            let keyword = self.ident(&["`note`", "`interval`", "`chord`", "`constraint`", "`}`"])?;
+            let kind = match keyword.node.as_str() {
+                "note" => LocalKind::Note,
+                "interval" => LocalKind::Interval,
+                "chord" => LocalKind::Chord,
+                "constraint" => LocalKind::Constraint,
+                other => {
+                    let error = ParseError::InvalidSyntax(format!("{} inside a progression", other));
+                    return Err(Diagnostic::new(error, keyword.span, self.source)
+                        .with_label("progressions declare notes, intervals, chords and constraints"));
+                }
+            };
+            let name = self.declared_name()?;
+            body.push(Local { kind, name, value: self.expression(0)? });
+            if !matches!(self.peek().kind, TokenKind::Newline | TokenKind::RBrace) {
+                return Err(self.unexpected(&["end of line", "`}`"]));
+            }
+        }
+        Ok(StatementKind::Template(Template { name, parameters, body }))
+    }
+
+    fn value(&mut self) -> ParseResult<Spanned<Value>> {
+        let token = self.peek().clone();
+        match token.kind {
+            TokenKind::Minus => {
+                self.advance();
+                let number = self.number()?;
+                Ok(Spanned::new(Value::Number(-number.node), token.span.to(number.span)))
+            }
+            TokenKind::Number(value) => {
+                self.advance();
+                Ok(Spanned::new(Value::Number(value), token.span))
+            }
+            TokenKind::Ident(name) => {
+                self.advance();
+                if !self.eat(&TokenKind::LParen) {
+                    return Ok(Spanned::new(Value::Ident(name), token.span));
+                }
+                let arguments = self.values(&TokenKind::RParen)?;
+                let name = Spanned::new(name, token.span);
+                Ok(Spanned::new(Value::Call { name, arguments }, token.span.to(self.previous_span())))
+            }
+            TokenKind::LBracket => {
+                self.advance();
+                let items = self.values(&TokenKind::RBracket)?;
+                Ok(Spanned::new(Value::List(items), token.span.to(self.previous_span())))
+            }
+            _ => Err(self.unexpected(&["number", "identifier", "`[`"])),
+        }
+    }
+
+    /// An expression whose operators bind at least as tightly as `min_precedence`
+    fn expression(&mut self, min_precedence: u8) -> ParseResult<Spanned<Expr>> {
+        let mut left = self.unary()?;
+        while let Some(operator) = binary_operator(&self.peek().kind) {
+            let precedence = operator.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            self.advance();
+            let right = self.expression(precedence + 1)?;
+            // `a < b < c` reads as a range but would compare a condition with a value
+            if operator.is_comparison() && binary_operator(&self.peek().kind).is_some_and(|next| next.is_comparison()) {
+                let error = ParseError::InvalidSyntax("comparisons cannot be chained".to_string());
+                return Err(Diagnostic::new(error, self.peek().span, self.source).with_label("combine comparisons with `&&`"));
+            }
+            let span = left.span.to(right.span);
+            left = Spanned::new(Expr::Binary { operator, left: Box::new(left), right: Box::new(right) }, span);
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> ParseResult<Spanned<Expr>> {
+        let token = self.peek().clone();
+        let operator = match token.kind {
+            TokenKind::Minus => UnaryOperator::Negate,
+            TokenKind::Bang => UnaryOperator::Not,
+            _ => return self.primary(),
+        };
+        self.advance();
+        let operand = self.unary()?;
+        let span = token.span.to(operand.span);
+        Ok(Spanned::new(Expr::Unary { operator, operand: Box::new(operand) }, span))
+    }
+
+    /// An atom followed by any `.field` and `.method(arguments)` suffixes
+    fn primary(&mut self) -> ParseResult<Spanned<Expr>> {
+        let mut expression = self.atom()?;
+        while self.eat(&TokenKind::Dot) {
+            let member = self.ident(&["field or method name"])?;
+            let start = expression.span;
+            let receiver = Box::new(expression);
+            let node = if self.eat(&TokenKind::LParen) {
+                Expr::Method { receiver, method: member, arguments: self.arguments(false)? }
+            } else {
+                Expr::Field { receiver, field: member }
+            };
+            expression = Spanned::new(node, start.to(self.previous_span()));
+        }
+        Ok(expression)
+    }
+
+    fn atom(&mut self) -> ParseResult<Spanned<Expr>> {
+        let token = self.peek().clone();
+        match token.kind {
+            TokenKind::Number(value) => {
+                self.advance();
+                Ok(Spanned::new(Expr::Number(value), token.span))
+            }
+            TokenKind::Ident(name) => {
+                self.advance();
+                if !self.eat(&TokenKind::LParen) {
+                    return Ok(Spanned::new(Expr::Name(name), token.span));
+                }
+                let arguments = self.arguments(false)?;
+                let function = Spanned::new(name, token.span);
+                Ok(Spanned::new(Expr::Call { function, arguments }, token.span.to(self.previous_span())))
+            }
+            TokenKind::LParen => {
+                self.advance();
+                let inner = self.expression(0)?;
+                self.expect(&TokenKind::RParen)?;
+                Ok(Spanned::new(inner.node, token.span.to(self.previous_span())))
+            }
+            _ => Err(self.unexpected(&["number", "identifier", "`(`"])),
+        }
+    }
+
+    /// Comma-separated arguments up to and including `)`
+    ///
+    /// With `words`, an argument of several names in a row (`F major`) is
+    /// read as one name joined by spaces.
+    fn arguments(&mut self, words: bool) -> ParseResult<Vec<Spanned<Expr>>> {
+        let mut arguments = Vec::new();
+        if self.eat(&TokenKind::RParen) {
+            return Ok(arguments);
+        }
+        loop {
+            let is_word = |token: Option<&Token>| matches!(token.map(|t| &t.kind), Some(TokenKind::Ident(_)));
+            if words && is_word(self.tokens.get(self.position)) && is_word(self.tokens.get(self.position + 1)) {
+                let first = self.ident(&["argument"])?;
+                let (mut text, mut span) = (first.node, first.span);
+                while let TokenKind::Ident(word) = &self.peek().kind {
+                    text = format!("{} {}", text, word);
+                    span = span.to(self.advance().span);
+                }
+                arguments.push(Spanned::new(Expr::Name(text), span));
+            } else {
+                arguments.push(self.expression(0)?);
+            }
+            if self.eat(&TokenKind::RParen) {
+                return Ok(arguments);
+            }
+            if !self.eat(&TokenKind::Comma) {
+                return Err(self.unexpected(&["`,`", "`)`"]));
+            }
+        }
+    }
+
+    /// Comma-separated values up to and including `close`
+    fn values(&mut self, close: &TokenKind) -> ParseResult<Vec<Spanned<Value>>> {
+        let mut values = Vec::new();
+        if self.eat(close) {
+            return Ok(values);
+        }
+        loop {
+            values.push(self.value()?);
+            if self.eat(close) {
+                return Ok(values);
+            }
+            if !self.eat(&TokenKind::Comma) {
+                return Err(self.unexpected(&["`,`", &close.to_string()]));
+            }
+        }
+    }
+
+    /// `NAME =` at the start of a declaration
+    fn declared_name(&mut self) -> ParseResult<Ident> {
+        let name = self.ident(&["name"])?;
+        self.expect(&TokenKind::Equals)?;
+        Ok(name)
+    }
+
+    fn ident(&mut self, expected: &[&str]) -> ParseResult<Ident> {
+        match &self.peek().kind {
+            TokenKind::Ident(name) => {
+                let ident = Spanned::new(name.clone(), self.peek().span);
+                self.advance();
+                Ok(ident)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn number(&mut self) -> ParseResult<Spanned<f64>> {
+        match self.peek().kind {
+            TokenKind::Number(value) => Ok(Spanned::new(value, self.advance().span)),
+            _ => Err(self.unexpected(&["number"])),
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind) -> ParseResult<Span> {
+        if self.at(kind) {
+            Ok(self.advance().span)
+        } else {
+            Err(self.unexpected(&[kind.to_string().as_str()]))
+        }
+    }
+
+    fn end_of_statement(&mut self) -> ParseResult<()> {
+        match self.peek().kind {
+            TokenKind::Newline | TokenKind::Eof => Ok(()),
+            _ => Err(self.unexpected(&["end of line"])),
+        }
+    }
+
+    fn unexpected(&self, expected: &[&str]) -> Diagnostic {
+        let token = self.peek();
+        let found = match &token.kind {
+            TokenKind::Newline | TokenKind::Eof => token.kind.to_string(),
+            TokenKind::Number(_) => format!("number `{}`", &self.source[token.span.start..token.span.end]),
+            _ => format!("`{}`", &self.source[token.span.start..token.span.end]),
+        };
+        let error = ParseError::UnexpectedToken {
+            expected: expected.iter().map(|e| e.to_string()).collect(),
+            found,
+        };
+        // Point just past the previous token when the line ends early
+        let span = match token.kind {
+            TokenKind::Newline | TokenKind::Eof if self.position > 0 => {
+                let end = self.previous_span().end;
+                Span::new(end, end + 1)
+            }
+            _ => token.span,
+        };
+        Diagnostic::new(error, span, self.source)
+    }
+
+    /// Skip to the end of the current line, or past the closing brace when
+    /// inside a block
+    fn recover(&mut self) {
+        let mut depth = 0usize;
+        for token in &self.tokens[self.statement_start..self.position] {
+            match token.kind {
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        loop {
+            match self.peek().kind {
+                TokenKind::Eof => return,
+                TokenKind::Newline if depth == 0 => return,
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.at(&TokenKind::Newline) {
+            self.advance();
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while matches!(self.peek().kind, TokenKind::Newline | TokenKind::Comma) {
+            self.advance();
+        }
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        let matched = self.at(kind);
+        if matched {
+            self.advance();
+        }
+        matched
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let matched = matches!(&self.peek().kind, TokenKind::Ident(name) if name == keyword);
+        if matched {
+            self.advance();
+        }
+        matched
+    }
+
+    fn at(&self, kind: &TokenKind) -> bool {
+        std::mem::discriminant(&self.peek().kind) == std::mem::discriminant(kind)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position.min(self.tokens.len() - 1)]
+    }
+
+    fn advance(&mut self) -> &Token {
+        let index = self.position.min(self.tokens.len() - 1);
+        if self.position < self.tokens.len() - 1 {
+            self.position += 1;
+        }
+        &self.tokens[index]
+    }
+
+    fn previous_span(&self) -> Span {
+        self.tokens[self.position.saturating_sub(1)].span
+    }
+}
+
+/// The infix operator a token spells, if any
+fn binary_operator(kind: &TokenKind) -> Option<BinaryOperator> {
+    let operator = match kind {
+        TokenKind::OrOr => BinaryOperator::Or,
+        TokenKind::AndAnd => BinaryOperator::And,
+        TokenKind::EqEq => BinaryOperator::Eq,
+        TokenKind::NotEq => BinaryOperator::Ne,
+        TokenKind::Lt => BinaryOperator::Lt,
+        TokenKind::Le => BinaryOperator::Le,
+        TokenKind::Gt => BinaryOperator::Gt,
+        TokenKind::Ge => BinaryOperator::Ge,
+        TokenKind::Plus => BinaryOperator::Add,
+        TokenKind::Minus => BinaryOperator::Sub,
+        TokenKind::Star => BinaryOperator::Mul,
+        TokenKind::Slash => BinaryOperator::Div,
+        _ => return None,
+    };
+    Some(operator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statements_keep_spans() {
+        let source = "note C4 = 261.63\nchord C = C4 + E4 + G4";
+        let (program, diagnostics) = parse_program(source);
+        assert!(diagnostics.is_empty());
+        assert_eq!(program.statements.len(), 2);
+        assert_eq!(program.statements[0].span, Span::new(0, 16));
+        match &program.statements[1].kind {
+            StatementKind::Chord { notes, .. } => {
+                let names: Vec<&str> = notes.iter().map(|n| n.node.as_str()).collect();
+                assert_eq!(names, vec!["C4", "E4", "G4"]);
+                assert_eq!(&source[notes[2].span.start..notes[2].span.end], "G4");
+            }
+            other => panic!("Expected chord, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rhythm_durations_and_instrument_values() {
+        let source = "rhythm r = pickup q | q. e/3 e/2:3 | h\ninstrument Horn { range: [B1, F5], transposition: -7 }";
+        let (program, diagnostics) = parse_program(source);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        match &program.statements[0].kind {
+            StatementKind::Rhythm { pickup, measures, .. } => {
+                assert!(*pickup);
+                let texts: Vec<Vec<&str>> = measures.iter().map(|m| m.iter().map(|d| d.node.as_str()).collect()).collect();
+                assert_eq!(texts, vec![vec!["q"], vec!["q.", "e/3", "e/2:3"], vec!["h"]]);
+            }
+            other => panic!("Expected rhythm, got {:?}", other),
+        }
+        match &program.statements[1].kind {
+            StatementKind::Instrument { properties, .. } => {
+                assert_eq!(properties[1].value.node, Value::Number(-7.0));
+                assert!(matches!(&properties[0].value.node, Value::List(items) if items.len() == 2));
+            }
+            other => panic!("Expected instrument, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_melody_notes_ties_and_repeats() {
+        let source = "melody m = FA:q. DO:e~ DO:e/3 [LA:e rest:e] * 2 FA:h";
+        let (program, diagnostics) = parse_program(source);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let items = match &program.statements[0].kind {
+            StatementKind::Melody { items, .. } => items,
+            other => panic!("Expected melody, got {:?}", other),
+        };
+        assert_eq!(items.len(), 5);
+        assert!(matches!(&items[1].node, MelodyItem::Note { pitch, duration, tied: true }
+            if pitch.node == "DO" && duration.node == "e"));
+        assert!(matches!(&items[2].node, MelodyItem::Note { duration, tied: false, .. } if duration.node == "e/3"));
+        match &items[3].node {
+            MelodyItem::Repeat { items: repeated, count } => {
+                assert_eq!(count.node, 2.0);
+                assert!(matches!(&repeated[1].node, MelodyItem::Rest { duration } if duration.node == "e"));
+            }
+            other => panic!("Expected repeat, got {:?}", other),
+        }
+        assert_eq!(&source[items[3].span.start..items[3].span.end], "[LA:e rest:e] * 2");
+
+        let (_, diagnostics) = parse_program("melody a = FA q\nmelody b = [FA:q\nmelody c = [FA:q] 2\nmelody d =");
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line()).collect();
+        assert_eq!(lines, vec![1, 2, 3, 4]);
+
+        let nested = |depth: usize| format!("melody m = {}FA:q{}", "[".repeat(depth), "] * 2".repeat(depth));
+        assert!(parse_program(&nested(MAX_REPEAT_DEPTH)).1.is_empty());
+        let (_, diagnostics) = parse_program(&nested(100_000));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].error(), &ParseError::InvalidSyntax("repeats nested more than 8 deep".to_string()));
+    }
+
+    #[test]
+    fn test_progression_and_instantiation() {
+        let source = "progresion ii_V_I(tonalidad: Escala, n: Number) {\n  chord ii = tonalidad.triad(2)\n\n  constraint c = ii.root == tonalidad.grado(n)\n}\nii_V_I(F major, 2)";
+        let (program, diagnostics) = parse_program(source);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        match &program.statements[0].kind {
+            StatementKind::Template(template) => {
+                assert_eq!(template.name.node, "ii_V_I");
+                let types: Vec<&str> = template.parameters.iter().map(|p| p.ty.node.as_str()).collect();
+                assert_eq!(types, vec!["Escala", "Number"]);
+                assert_eq!(template.body.len(), 2);
+                assert_eq!(template.body[0].kind, LocalKind::Chord);
+                assert!(matches!(&template.body[1].value.node, Expr::Binary { right, .. }
+                    if matches!(&right.node, Expr::Method { method, arguments, .. } if method.node == "grado" && arguments.len() == 1)));
+            }
+            other => panic!("Expected progression, got {:?}", other),
+        }
+        match &program.statements[1].kind {
+            StatementKind::Instance { template, arguments } => {
+                assert_eq!(template.node, "ii_V_I");
+                assert_eq!(arguments[0].node, Expr::Name("F major".to_string()));
+                assert_eq!(&source[arguments[0].span.start..arguments[0].span.end], "F major");
+                assert_eq!(arguments[1].node, Expr::Number(2.0));
+            }
+            other => panic!("Expected instantiation, got {:?}", other),
+        }
+
+        let (_, diagnostics) = parse_program("progression p(k Key) {\n}\nprogression q() {\n  tempo t = 1\n}\np(C");
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line()).collect();
+        assert_eq!(lines, vec![1, 4, 6]);
+        assert_eq!(diagnostics[1].error(), &ParseError::InvalidSyntax("tempo inside a progression".to_string()));
+    }
+
+    #[test]
+    fn test_expression_precedence() {
+        let source = "constraint c = -a + b * 2 < cents(x, 1) || !(d == e) && f >= k.degree(3).x";
+        let (program, diagnostics) = parse_program(source);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let expression = match &program.statements[0].kind {
+            StatementKind::Constraint { expression, .. } => expression,
+            other => panic!("Expected constraint, got {:?}", other),
+        };
+        assert_eq!(&source[expression.span.start..expression.span.end], &source[15..]);
+
+        // Render the tree fully parenthesised
+        fn render(expr: &Spanned<Expr>) -> String {
+            match &expr.node {
+                Expr::Number(n) => n.to_string(),
+                Expr::Name(name) => name.clone(),
+                Expr::Call { function, arguments } => {
+                    let arguments: Vec<String> = arguments.iter().map(render).collect();
+                    format!("{}({})", function.node, arguments.join(", "))
+                }
+                Expr::Field { receiver, field } => format!("{}.{}", render(receiver), field.node),
+                Expr::Method { receiver, method, arguments } => {
+                    let arguments: Vec<String> = arguments.iter().map(render).collect();
+                    format!("{}.{}({})", render(receiver), method.node, arguments.join(", "))
+                }
+                Expr::Unary { operator: UnaryOperator::Negate, operand } => format!("-{}", render(operand)),
+                Expr::Unary { operator: UnaryOperator::Not, operand } => format!("!{}", render(operand)),
+                Expr::Binary { operator, left, right } => format!("({} {} {})", render(left), operator.symbol(), render(right)),
+            }
+        }
+        assert_eq!(render(expression), "(((-a + (b * 2)) < cents(x, 1)) || (!(d == e) && (f >= k.degree(3).x)))");
+
+        let (_, diagnostics) = parse_program("constraint c = 1 < x < 2\nconstraint d = (a + b\nconstraint e =");
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line()).collect();
+        assert_eq!(lines, vec![1, 2, 3]);
+        assert_eq!(diagnostics[0].error(), &ParseError::InvalidSyntax("comparisons cannot be chained".to_string()));
+    }
+
+    #[test]
+    fn test_recovery_reports_every_error() {
+        let source = "note C4 261.63\nmelodee x = 1\nnote E4 = 329.63\ninstrument P {\n  range: [A0 C8]\n}\ntempo";
+        let (program, diagnostics) = parse_program(source);
+        // The valid note between the errors still parses
+        assert_eq!(program.statements.len(), 1);
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line()).collect();
+        assert_eq!(lines, vec![1, 2, 5, 7]);
+        assert_eq!(
+            diagnostics[0].error(),
+            &ParseError::UnexpectedToken { expected: vec!["`=`".to_string()], found: "number `261.63`".to_string() }
+        );
+        assert_eq!(diagnostics[1].error(), &ParseError::UnknownStatement("melodee".to_string()));
+        assert_eq!(diagnostics[3].column(), 6);
+    }
+}
\ No newline at end of file
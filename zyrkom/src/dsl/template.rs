@@ -0,0 +1,277 @@
+//! Progression templates
+//!
+//! A `progression` declares typed parameters and a body of notes, intervals,
+//! chords and constraints written in terms of them:
+//!
+//! ```text
+//! progression ii_V_I(tonalidad: Key) {
+//!     chord ii = tonalidad.triad(2)
+//!     chord V = tonalidad.triad(5)
+//!     chord I = tonalidad.triad(1)
+//!     constraint roots = ii.root == tonalidad.degree(2) && V.root == tonalidad.degree(5)
+//! }
+//! ii_V_I(F major)
+//! ```
+//!
+//! Each instantiation binds the arguments, evaluates the body in order and
+//! expands it into ordinary elements named `progression.local`, so a compiled
+//! program only ever holds concrete pitches and checks.
+
+use crate::dsl::ast::{Expr, LocalKind, Spanned, Template};
+use crate::dsl::diagnostic::Diagnostic;
+use crate::dsl::expression::{evaluate, evaluate_in, Constant, Scope};
+use crate::dsl::lexer::Span;
+use crate::dsl::parser::{ParseError, ParsedElement};
+use crate::dsl::resolve::SymbolTable;
+use crate::musical::{Key, PitchReference};
+use crate::utils::frequency_to_note_name_at;
+
+/// Types a progression parameter may have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterType {
+    /// A key such as `F major`
+    Key,
+    /// A declared note
+    Note,
+    /// An interval
+    Interval,
+    /// A chord
+    Chord,
+    /// A number
+    Number,
+}
+
+impl ParameterType {
+    /// Every type, in the order they are listed in errors
+    pub const ALL: [ParameterType; 5] = [
+        ParameterType::Key,
+        ParameterType::Note,
+        ParameterType::Interval,
+        ParameterType::Chord,
+        ParameterType::Number,
+    ];
+
+    /// Parse a type name; `Scale` and the Spanish names are accepted
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Key" | "Scale" | "Escala" | "Tonalidad" => Some(ParameterType::Key),
+            "Note" | "Nota" => Some(ParameterType::Note),
+            "Interval" | "Intervalo" => Some(ParameterType::Interval),
+            "Chord" | "Acorde" => Some(ParameterType::Chord),
+            "Number" | "Numero" | "Número" => Some(ParameterType::Number),
+            _ => None,
+        }
+    }
+
+    /// Name of the type as written in English
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParameterType::Key => "Key",
+            ParameterType::Note => "Note",
+            ParameterType::Interval => "Interval",
+            ParameterType::Chord => "Chord",
+            ParameterType::Number => "Number",
+        }
+    }
+
+    /// Whether a value has this type
+    pub fn accepts(&self, value: &Constant) -> bool {
+        matches!(
+            (self, value),
+            (ParameterType::Key, Constant::Key(_))
+                | (ParameterType::Note, Constant::Note(_))
+                | (ParameterType::Interval, Constant::Interval(_))
+                | (ParameterType::Chord, Constant::Chord(_))
+                | (ParameterType::Number, Constant::Number(_))
+        )
+    }
+}
+
+/// Expand one instantiation of `template` into the elements its body declares
+///
+/// `span` is the instantiating statement; key degrees sound at `reference`.
+/// Errors in the body point into the template and name the instantiation.
+pub fn expand(
+    template: &Template,
+    arguments: &[Spanned<Expr>],
+    span: Span,
+    symbols: &SymbolTable,
+    reference: PitchReference,
+    source: &str,
+) -> Result<Vec<ParsedElement>, Diagnostic> {
+    let name = &template.name.node;
+    let error = |message: String, span: Span| Diagnostic::new(ParseError::TypeError(message), span, source);
+
+    if arguments.len() != template.parameters.len() {
+        let count = template.parameters.len();
+        let message = format!("{} takes {} argument{}, found {}", name, count, if count == 1 { "" } else { "s" }, arguments.len());
+        return Err(error(message, span));
+    }
+
+    let mut scope = Scope::new(reference);
+    for (parameter, argument) in template.parameters.iter().zip(arguments) {
+        // Unknown parameter types are reported with the template
+        let Some(ty) = ParameterType::from_name(&parameter.ty.node) else {
+            return Ok(Vec::new());
+        };
+        let value = match &argument.node {
+            Expr::Name(text) if ty == ParameterType::Key && symbols.get(text).is_none() => {
+                let key = Key::parse(text).map_err(|_| {
+                    error(format!("{} is not a key", text), argument.span).with_label("expected a key such as `F major`")
+                })?;
+                Constant::Key(key)
+            }
+            _ => evaluate(argument, symbols, source)?,
+        };
+        if !ty.accepts(&value) {
+            let message = format!(
+                "{} of {} must be a {}, found {}",
+                parameter.name.node,
+                name,
+                ty.name().to_lowercase(),
+                value.type_name()
+            );
+            return Err(error(message, argument.span));
+        }
+        scope.bind(&parameter.name.node, value);
+    }
+
+    let line = source[..span.start.min(source.len())].matches('\n').count() + 1;
+    let context = format!("in {} instantiated on line {}", name, line);
+    let mut elements = Vec::with_capacity(template.body.len());
+    for local in &template.body {
+        let value = evaluate_in(&local.value, symbols, &scope, source).map_err(|d| d.with_label(&context))?;
+        let qualified = format!("{}.{}", name, local.name.node);
+        let element = match (local.kind, &value) {
+            (LocalKind::Note, Constant::Note(pitch)) => ParsedElement::Note {
+                name: qualified,
+                frequency: pitch.frequency(),
+                reference: pitch.reference(),
+            },
+            (LocalKind::Interval, Constant::Interval(interval)) => ParsedElement::Interval {
+                name: qualified,
+                ratio: interval.ratio(),
+            },
+            (LocalKind::Chord, Constant::Chord(pitches)) => ParsedElement::Chord {
+                name: qualified,
+                notes: pitches.iter().map(|p| frequency_to_note_name_at(p.frequency(), p.reference())).collect(),
+                pitches: pitches.clone(),
+            },
+            (LocalKind::Constraint, Constant::Condition(_) | Constant::Chord(_) | Constant::Interval(_)) => {
+                ParsedElement::Constraint {
+                    name: qualified,
+                    expression: source[local.value.span.start..local.value.span.end].to_string(),
+                    value: value.clone(),
+                }
+            }
+            (kind, value) => {
+                let expected = match kind {
+                    LocalKind::Constraint => "condition, chord or interval",
+                    kind => kind.keyword(),
+                };
+                let message = format!("{} must be a {}, found {}", local.name.node, expected, value.type_name());
+                return Err(error(message, local.value.span).with_label(&context));
+            }
+        };
+        scope.bind(&local.name.node, value);
+        elements.push(element);
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::parser::ZyrkomParser;
+
+    const II_V_I: &str = "progression ii_V_I(tonalidad: Escala) {\n\
+                          chord acorde1 = tonalidad.triad(2)\n\
+                          chord acorde2 = tonalidad.triada(5)\n\
+                          chord acorde3 = tonalidad.triad(1)\n\
+                          constraint funcion = acorde1.fundamental == tonalidad.grado(2) && acorde3.root == tonalidad.tonic\n\
+                          constraint conduccion = distance(acorde1.root, acorde2.root) == 5 && in_scale(acorde2, tonalidad)\n\
+                          }\n";
+
+    fn expanded(call: &str) -> Vec<ParsedElement> {
+        ZyrkomParser::new().parse(&format!("{}{}", II_V_I, call)).unwrap()
+    }
+
+    #[test]
+    fn test_expands_into_concrete_chords_and_constraints() {
+        let elements = expanded("ii_V_I(F major)");
+        let names: Vec<&str> = elements.iter().map(ParsedElement::name).collect();
+        assert_eq!(
+            names,
+            vec!["ii_V_I.acorde1", "ii_V_I.acorde2", "ii_V_I.acorde3", "ii_V_I.funcion", "ii_V_I.conduccion"]
+        );
+
+        // Gm, C and F rooted on the degrees of F major from F4
+        let midi = |element: &ParsedElement| match element {
+            ParsedElement::Chord { pitches, .. } => pitches.iter().map(|p| p.midi_number()).collect::<Vec<_>>(),
+            other => panic!("Expected chord, got {:?}", other),
+        };
+        assert_eq!(midi(&elements[0]), vec![67, 70, 74]);
+        assert_eq!(midi(&elements[1]), vec![72, 76, 79]);
+        assert_eq!(midi(&elements[2]), vec![65, 69, 72]);
+        assert!(matches!(&elements[0], ParsedElement::Chord { notes, .. } if notes[1].starts_with("A#")));
+
+        for element in &elements[3..] {
+            match element {
+                ParsedElement::Constraint { value: Constant::Condition(condition), .. } => assert!(condition.holds()),
+                other => panic!("Expected condition, got {:?}", other),
+            }
+            assert!(element.to_constraints().unwrap().constraints.iter().all(|c| c.is_satisfied()));
+        }
+    }
+
+    #[test]
+    fn test_each_instantiation_uses_its_own_arguments() {
+        let elements = expanded("reference 415\nii_V_I(D minor)\nii_V_I(C)");
+        let roots: Vec<u8> = elements
+            .iter()
+            .filter_map(|element| match element {
+                ParsedElement::Chord { pitches, .. } => pitches.first().map(|p| p.midi_number()),
+                _ => None,
+            })
+            .collect();
+        // D minor: ii = E dim, V = A minor, i = D minor; then C major
+        assert_eq!(roots, vec![64, 69, 62, 62, 67, 60]);
+
+        let baroque = elements.iter().find_map(|element| match element {
+            ParsedElement::Chord { pitches, .. } => pitches.first().copied(),
+            _ => None,
+        });
+        assert_eq!(baroque.map(|p| p.reference()), Some(PitchReference::baroque()));
+    }
+
+    #[test]
+    fn test_instantiation_errors() {
+        let error = |call: &str| {
+            let errors = ZyrkomParser::new().parse(&format!("{}{}", II_V_I, call)).unwrap_err();
+            errors.first().unwrap().clone()
+        };
+
+        assert_eq!(error("ii_V_I()").error(), &ParseError::TypeError("ii_V_I takes 1 argument, found 0".to_string()));
+        assert_eq!(error("ii_V_I(H major)").error(), &ParseError::TypeError("H major is not a key".to_string()));
+        let wrong_type = error("ii_V_I(3)");
+        assert_eq!(wrong_type.error(), &ParseError::TypeError("tonalidad of ii_V_I must be a key, found number".to_string()));
+        assert_eq!((wrong_type.line(), wrong_type.column()), (8, 8));
+        assert_eq!(error("V_I(C)").error(), &ParseError::UndefinedReference("V_I".to_string()));
+
+        // Body errors point into the template and name the instantiation
+        let source = "progression p(k: Key, n: Nota) {\nchord c = k.degree(1)\n}\nnote A4 = 440\np(C, A4)";
+        let body = ZyrkomParser::new().parse(source).unwrap_err();
+        let body = body.first().unwrap();
+        assert_eq!(body.error(), &ParseError::TypeError("c must be a chord, found note".to_string()));
+        assert_eq!(body.line(), 2);
+        assert!(body.to_string().contains("in p instantiated on line 5"), "{}", body);
+
+        // Degrees are bounded before any scale arithmetic
+        let source = "progression p(k: Key, n: Number) {\nchord c = k.triad(n)\n}\np(C, 2147483647)";
+        let degree = ZyrkomParser::new().parse(source).unwrap_err();
+        assert_eq!(
+            degree.first().unwrap().error(),
+            &ParseError::TypeError("scale degrees are whole numbers from 1 to 36, found 2147483647".to_string())
+        );
+    }
+}
\ No newline at end of file
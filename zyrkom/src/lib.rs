@@ -28,8 +28,8 @@
 //! assert_eq!(fifth.ratio(), 1.5);
 //! 
 //! // Create a major chord using just intonation
-//! let root = MusicalNote::from_midi(60); // C4
-//! let chord = Chord::major_triad(root);
+//! let root = MusicalNote::from_midi(60).unwrap(); // C4
+//! let chord = Chord::major_triad(root).unwrap();
 //! 
 //! // Convert to ZK constraints (coming soon)
 //! // let constraints = chord.to_stark_constraints();
@@ -43,12 +43,14 @@ pub mod musical;
 pub mod zk;
 pub mod dsl;
 pub mod musical_dna;
+pub mod authorship;
 pub mod utils;
 
 pub use musical::{MusicalInterval, MusicalNote, Chord};
 pub use dsl::{ZyrkomParser, ParsedElement};
 pub use zk::{ZyrkomProver, ZyrkomVerifier, MusicalProof, ZyrkomComponent, ZyrkomProofJson, ConstraintType, MusicalConstraint, ConstraintSystem};
 pub use musical_dna::MusicalDna;
+pub use authorship::AuthorshipBundle;
 
 /// Common error types for Zyrkom operations
 #[derive(Debug, thiserror::Error)]
@@ -135,9 +137,13 @@ mod tests {
         println!("\n🇪🇸 Testing Spanish National Anthem - REAL MELODY");
         println!("🎼 Marcha Real - Exact note sequence from official score");
         
+        use crate::musical::{NoteValue, Tempo};
+        
         // Official specifications from Real Decreto 1560/1997
         let tempo_bpm = 76;
-        let beat_duration_ms = 60_000 / tempo_bpm; // ~789ms per beat
+        let tempo = Tempo::quarter(tempo_bpm as f64).expect("Official tempo is valid");
+        let beat_duration_ms = tempo.duration_ms(NoteValue::Quarter.duration()) as u64; // ~789ms per beat
+        let half_beat_ms = tempo.duration_ms(NoteValue::Eighth.duration()) as u64; // ~394ms per corchea
         
         println!("⏱️  Official tempo: {} BPM ({} ms per beat)", tempo_bpm, beat_duration_ms);
         println!("🎵 Real Spanish Anthem sequence provided by Zyra:");
@@ -147,14 +153,14 @@ mod tests {
         println!("   Phrase 4: FA SOL LA DO* SIb LA SOL FA DO*");
         
         // Note frequencies (4th octave as base):
-        let fa = MusicalNote::from_frequency(349.23);   // F4
-        let do_note = MusicalNote::from_frequency(261.63);   // C4  
-        let la = MusicalNote::from_frequency(440.00);   // A4
-        let do_high = MusicalNote::from_frequency(523.25); // C5 (DO*)
-        let sib = MusicalNote::from_frequency(466.16);  // Bb4 (SIb)
-        let sol = MusicalNote::from_frequency(392.00);  // G4
-        let mi = MusicalNote::from_frequency(329.63);   // E4
-        let re = MusicalNote::from_frequency(293.66);   // D4
+        let fa = MusicalNote::from_frequency(349.23).unwrap();   // F4
+        let do_note = MusicalNote::from_frequency(261.63).unwrap();   // C4  
+        let la = MusicalNote::from_frequency(440.00).unwrap();   // A4
+        let do_high = MusicalNote::from_frequency(523.25).unwrap(); // C5 (DO*)
+        let sib = MusicalNote::from_frequency(466.16).unwrap();  // Bb4 (SIb)
+        let sol = MusicalNote::from_frequency(392.00).unwrap();  // G4
+        let mi = MusicalNote::from_frequency(329.63).unwrap();   // E4
+        let re = MusicalNote::from_frequency(293.66).unwrap();   // D4
         
         // Phrase 1: FA DO LA FA DO* SIb LA SOL FA FA MI RE DO
         let phrase1 = vec![fa, do_note, la, fa, do_high, sib, la, sol, fa, fa, mi, re, do_note];
@@ -165,29 +171,29 @@ mod tests {
             beat_duration_ms,      // FA - negra (789ms)
             beat_duration_ms,      // DO - negra (789ms)  
             beat_duration_ms,      // LA - negra (789ms)
-            beat_duration_ms / 2,  // FA - corchea (394ms)
-            beat_duration_ms / 2,  // DO* - corchea (394ms)
-            beat_duration_ms / 2,  // SIb - corchea (394ms)
-            beat_duration_ms / 2,  // LA - corchea (394ms)
-            beat_duration_ms / 2,  // SOL - corchea (394ms)
-            beat_duration_ms / 2,  // FA - corchea (394ms)
-            beat_duration_ms / 2,  // FA - corchea (394ms)
-            beat_duration_ms / 2,  // MI - corchea (394ms)
-            beat_duration_ms / 2,  // RE - corchea (394ms)
-            beat_duration_ms / 2,  // DO - corchea (394ms)
+            half_beat_ms,          // FA - corchea (394ms)
+            half_beat_ms,          // DO* - corchea (394ms)
+            half_beat_ms,          // SIb - corchea (394ms)
+            half_beat_ms,          // LA - corchea (394ms)
+            half_beat_ms,          // SOL - corchea (394ms)
+            half_beat_ms,          // FA - corchea (394ms)
+            half_beat_ms,          // FA - corchea (394ms)
+            half_beat_ms,          // MI - corchea (394ms)
+            half_beat_ms,          // RE - corchea (394ms)
+            half_beat_ms,          // DO - corchea (394ms)
         ];
         
         // Phrase 2: FA SOL LA DO* SIb LA SOL FA DO*
         let phrase2 = vec![fa, sol, la, do_high, sib, la, sol, fa, do_high];
         let phrase2_durations = vec![
-            beat_duration_ms / 2,  // FA - corchea  
-            beat_duration_ms / 2,  // SOL - corchea
-            beat_duration_ms / 2,  // LA - corchea
-            beat_duration_ms / 2,  // DO* - corchea
-            beat_duration_ms / 2,  // SIb - corchea
-            beat_duration_ms / 2,  // LA - corchea
-            beat_duration_ms / 2,  // SOL - corchea
-            beat_duration_ms / 2,  // FA - corchea
+            half_beat_ms,          // FA - corchea  
+            half_beat_ms,          // SOL - corchea
+            half_beat_ms,          // LA - corchea
+            half_beat_ms,          // DO* - corchea
+            half_beat_ms,          // SIb - corchea
+            half_beat_ms,          // LA - corchea
+            half_beat_ms,          // SOL - corchea
+            half_beat_ms,          // FA - corchea
             beat_duration_ms,      // DO* - negra (final phrase)
         ];
         
@@ -244,14 +250,25 @@ mod tests {
             }
         }
         
-        // Generate ZK constraints for the complete real anthem
-        let mut all_notes = phrase1.clone();
-        all_notes.extend(phrase2.clone());
-        all_notes.extend(phrase3.clone());
-        all_notes.extend(phrase4.clone());
+        // Generate ZK constraints for the complete real anthem as a timed melody
+        use crate::musical::{Melody, RhythmicValue, TimeSignature};
+        let rhythmic_value = |duration: &u64| if *duration == beat_duration_ms {
+            RhythmicValue::new(NoteValue::Quarter)
+        } else {
+            RhythmicValue::new(NoteValue::Eighth)
+        };
+        let anthem: Vec<(MusicalNote, RhythmicValue)> = [
+            (&phrase1, &phrase1_durations),
+            (&phrase2, &phrase2_durations),
+            (&phrase3, &phrase3_durations),
+            (&phrase4, &phrase4_durations),
+        ]
+        .iter()
+        .flat_map(|(notes, durations)| notes.iter().copied().zip(durations.iter().map(rhythmic_value)))
+        .collect();
         
-        let chord = create_melody_chord(all_notes);
-        let constraints = chord.to_constraints().expect("Should generate constraints for real Spanish anthem");
+        let melody = Melody::from_notes(TimeSignature::common(), &anthem).unwrap();
+        let constraints = melody.to_constraints().expect("Should generate constraints for real Spanish anthem");
         
         println!("\n\n⚡ Generated {} ZK constraints for REAL Spanish anthem", constraints.constraints.len());
         println!("🏛️  Mathematical validation: Every note cryptographically verified");
@@ -308,24 +325,4 @@ mod tests {
             eprintln!("Audio playback error: {}", e);
         }
     }
-    
-    /// Helper function to create a chord from a sequence of notes (for melody analysis)
-    fn create_melody_chord(notes: Vec<MusicalNote>) -> Chord {
-        if notes.is_empty() {
-            // Default to C4 if no notes provided
-            let root = MusicalNote::from_frequency(261.63);
-            return Chord::new(root, &[]);
-        }
-        
-        let root = notes[0];
-        let mut intervals = Vec::new();
-        
-        // Calculate intervals from root to all other notes
-        for note in notes.iter().skip(1) {
-            let interval = root.interval_to(note);
-            intervals.push(interval);
-        }
-        
-        Chord::new(root, &intervals)
-    }
 } 
\ No newline at end of file
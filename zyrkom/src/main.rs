@@ -50,6 +50,30 @@ enum Commands {
         #[arg(short, long)]
         verbose: bool,
     },
+    /// Sign a ZK proof as its creator, producing an authorship bundle
+    Sign {
+        /// Proof file to sign (.zk extension)
+        #[arg(short, long)]
+        proof: PathBuf,
+        /// Creator secret key file (64 hex characters)
+        #[arg(short, long)]
+        key: PathBuf,
+        /// Output bundle file
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Generate a new secret key and write it to the key file, which must not exist
+        #[arg(long)]
+        new_key: bool,
+    },
+    /// Verify the creator signature on an authorship bundle
+    VerifyBundle {
+        /// Bundle file to verify
+        #[arg(short, long)]
+        bundle: PathBuf,
+        /// Show detailed verification info
+        #[arg(short, long)]
+        verbose: bool,
+    },
     /// Interactive DSL shell
     Shell,
 }
@@ -67,6 +91,12 @@ fn main() -> Result<()> {
         Commands::Verify { proof, source, verbose } => {
             handle_verify(proof, source, verbose)
         }
+        Commands::Sign { proof, key, output, new_key } => {
+            handle_sign(proof, key, output, new_key)
+        }
+        Commands::VerifyBundle { bundle, verbose } => {
+            handle_verify_bundle(bundle, verbose)
+        }
         Commands::Shell => {
             println!("🎼 Zyrkom Interactive Shell");
             println!("==========================");
@@ -75,6 +105,8 @@ fn main() -> Result<()> {
             println!("  📝 parse <file.zyrkom>                 - Parse and analyze musical DSL");
             println!("  🔮 prove <input.zyrkom> <output.zk>    - Generate ZK proof");
             println!("  🔍 verify <proof.zk> <source.zyrkom>   - Verify proof against original source");
+            println!("  ✍️  sign <proof.zk> <key> <bundle.zkb>  - Sign proof as its creator");
+            println!("  🔏 verify-bundle <bundle.zkb>          - Verify creator signature");
             println!("  🚪 exit                               - Exit shell");
             println!();
             println!("Examples:");
@@ -98,7 +130,7 @@ fn handle_parse(input: PathBuf, format: String) -> Result<()> {
         })?;
 
     let mut parser = ZyrkomParser::new();
-    let elements = parser.parse_multiple(&content)?;
+    let elements = parse_source(&mut parser, &input, &content)?;
 
     match format.as_str() {
         "constraints" => {
@@ -151,26 +183,17 @@ fn handle_prove(input: PathBuf, output: PathBuf, _gpu: bool, generate_json: bool
 
     println!("🔮 Generating ZK proof...");
     
-    let mut parser = ZyrkomParser::new();
-    let elements = parser.parse_multiple(&content)?;
-    
-    // Find first element that can generate constraints
-    let mut total_constraints = None;
-    for element in &elements {
-        let constraints = element.to_constraints()?;
-        if constraints.constraint_count() > 0 {
-            total_constraints = Some(constraints);
-            break;
-        }
+    // Every element of the composition goes into one statement
+    let (constraints, source_map) = compile_source(&input, &content)?.into_parts();
+    if constraints.constraint_count() == 0 {
+        return Err(zyrkom::ZyrkomError::ConstraintError {
+            context: "No valid constraints found in input".to_string(),
+        });
     }
     
-    let constraints = total_constraints.ok_or_else(|| zyrkom::ZyrkomError::ConstraintError {
-        context: "No valid constraints found in input".to_string(),
-    })?;
-    
     println!("  📊 {} constraints generated", constraints.constraint_count());
     
-    let prover = ZyrkomProver::new(constraints)?;
+    let prover = ZyrkomProver::new(constraints)?.with_source_map(source_map);
     
     // Measure proof generation time
     let start_time = std::time::Instant::now();
@@ -257,24 +280,13 @@ fn handle_verify(proof_path: PathBuf, source_path: PathBuf, verbose: bool) -> Re
 
     // Parse the original source file to get the TRUE constraint system
     println!("  📝 Parsing original source file...");
-    let mut parser = zyrkom::ZyrkomParser::new();
-    let elements = parser.parse_multiple(&source_content)?;
-    
     // Generate the TRUSTED constraint system from source (same as proving)
-    let mut trusted_constraint_system = None;
-    for element in &elements {
-        let constraints = element.to_constraints()?;
-        if constraints.constraint_count() > 0 {
-            trusted_constraint_system = Some(constraints);
-            break;
-        }
-    }
-    
-    let constraint_system = trusted_constraint_system.ok_or_else(|| {
-        zyrkom::ZyrkomError::ConstraintError {
+    let (constraint_system, source_map) = compile_source(&source_path, &source_content)?.into_parts();
+    if constraint_system.constraint_count() == 0 {
+        return Err(zyrkom::ZyrkomError::ConstraintError {
             context: "No valid constraints found in source file".to_string(),
-        }
-    })?;
+        });
+    }
 
     if verbose {
         println!("  ✅ Source parsed: {} constraints generated", constraint_system.constraint_count());
@@ -305,6 +317,9 @@ fn handle_verify(proof_path: PathBuf, source_path: PathBuf, verbose: bool) -> Re
         .enumerate() {
         if (proof_ratio - source_ratio).abs() > 0.001 {
             println!("  ❌ INTEGRITY VIOLATION: Musical ratio mismatch at index {}!", i);
+            if let Some(location) = source_map.location(i) {
+                println!("     Declared: {} of {}", location, source_path.display());
+            }
             println!("     Source: {:.6}", source_ratio);
             println!("     Proof claims: {:.6}", proof_ratio);
             return Err(zyrkom::ZyrkomError::ProofError {
@@ -316,7 +331,7 @@ fn handle_verify(proof_path: PathBuf, source_path: PathBuf, verbose: bool) -> Re
     println!("  ✅ Integrity checks PASSED - proof matches source");
 
     // Create verifier with TRUSTED constraint system from source
-    let verifier = ZyrkomVerifier::new(constraint_system)?;
+    let verifier = ZyrkomVerifier::new(constraint_system)?.with_source_map(source_map);
     let is_valid = verifier.verify(&proof)?;
     
     if is_valid {
@@ -333,6 +348,106 @@ fn handle_verify(proof_path: PathBuf, source_path: PathBuf, verbose: bool) -> Re
     Ok(())
 }
 
+fn handle_sign(proof_path: PathBuf, key_path: PathBuf, output: PathBuf, new_key: bool) -> Result<()> {
+    println!("✍️  Signing ZK proof...");
+
+    let signing_key = if new_key {
+        let key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        write_secret_key(&key_path, &key)
+            .map_err(|e| zyrkom::ZyrkomError::ProofError {
+                reason: match e.kind() {
+                    std::io::ErrorKind::AlreadyExists => {
+                        format!("Key file {} already exists; refusing to overwrite it", key_path.display())
+                    }
+                    _ => format!("Cannot write key file: {}", e),
+                },
+            })?;
+        println!("  🔑 New creator key saved to: {}", key_path.display());
+        key
+    } else {
+        let encoded = std::fs::read_to_string(&key_path)
+            .map_err(|e| zyrkom::ZyrkomError::ProofError {
+                reason: format!("Failed to read key file: {}", e),
+            })?;
+        let seed: [u8; 32] = hex::decode(encoded.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| zyrkom::ZyrkomError::ProofError {
+                reason: "Key file must contain 32 bytes as hex".to_string(),
+            })?;
+        ed25519_dalek::SigningKey::from_bytes(&seed)
+    };
+
+    let proof_bytes = std::fs::read(&proof_path)
+        .map_err(|e| zyrkom::ZyrkomError::ProofError {
+            reason: format!("Failed to read proof file: {}", e),
+        })?;
+    let proof: zyrkom::MusicalProof = bincode::deserialize(&proof_bytes)
+        .map_err(|e| zyrkom::ZyrkomError::ProofError {
+            reason: format!("Failed to parse proof file: {}", e),
+        })?;
+
+    let bundle = zyrkom::AuthorshipBundle::create(&proof, &signing_key)?;
+    std::fs::write(&output, bincode::serialize(&bundle)?)
+        .map_err(|e| zyrkom::ZyrkomError::ProofError {
+            reason: format!("Cannot write bundle file: {}", e),
+        })?;
+
+    println!("  👤 Creator: {}", hex::encode(signing_key.verifying_key().to_bytes()));
+    println!("  🧾 Statement digest: {}", hex::encode(bundle.statement_digest()));
+    println!("  💾 Bundle saved to: {}", output.display());
+    Ok(())
+}
+
+/// Write a new secret key, never replacing an existing file; on unix only
+/// the owner may read it
+fn write_secret_key(path: &std::path::Path, key: &ed25519_dalek::SigningKey) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(hex::encode(key.to_bytes()).as_bytes())
+}
+
+fn handle_verify_bundle(bundle_path: PathBuf, verbose: bool) -> Result<()> {
+    println!("🔏 Verifying authorship bundle...");
+
+    let bundle_bytes = std::fs::read(&bundle_path)
+        .map_err(|e| zyrkom::ZyrkomError::ProofError {
+            reason: format!("Failed to read bundle file: {}", e),
+        })?;
+    let bundle: zyrkom::AuthorshipBundle = bincode::deserialize(&bundle_bytes)
+        .map_err(|e| zyrkom::ZyrkomError::ProofError {
+            reason: format!("Failed to parse bundle file: {}", e),
+        })?;
+
+    if verbose {
+        let proof = bundle.proof()?;
+        println!("  📋 Bundled proof:");
+        println!("    🎵 Structure: {}", proof.metadata.structure_type);
+        println!("    🔢 Constraints: {}", proof.metadata.constraint_count);
+        println!("    📊 Public inputs: {:?}", proof.public_inputs);
+        println!("  🧾 Statement digest: {}", hex::encode(bundle.statement_digest()));
+    }
+
+    if !bundle.verify()? {
+        println!("  ❌ Creator signature is INVALID");
+        return Err(zyrkom::ZyrkomError::ProofError {
+            reason: "Authorship bundle signature verification failed".to_string(),
+        });
+    }
+
+    println!("  ✅ Creator signature is valid");
+    println!("  👤 Creator: {}", hex::encode(bundle.creator_public_key()?.to_bytes()));
+    println!("  ℹ️  Run `zyrkom verify` with the source file to check the STARK proof itself");
+    Ok(())
+}
+
 fn handle_shell() -> Result<()> {
     println!("🎼 Zyrkom Interactive Shell");
     println!("Enter musical DSL statements (type 'exit' to quit):");
@@ -396,5 +511,29 @@ fn get_element_type(element: &zyrkom::ParsedElement) -> &'static str {
         zyrkom::ParsedElement::Chord { .. } => "chord",
         zyrkom::ParsedElement::Note { .. } => "note",
         zyrkom::ParsedElement::Constraint { .. } => "constraint",
+        zyrkom::ParsedElement::TimeSignature { .. } => "time_signature",
+        zyrkom::ParsedElement::Tempo { .. } => "tempo",
+        zyrkom::ParsedElement::Rhythm { .. } => "rhythm",
+        zyrkom::ParsedElement::Melody { .. } => "melody",
+        zyrkom::ParsedElement::Reference { .. } => "reference",
+        zyrkom::ParsedElement::Key { .. } => "key",
+        zyrkom::ParsedElement::Instrument { .. } => "instrument",
     }
 }
+
+/// Parse a source file, printing every located error before failing
+fn parse_source(parser: &mut ZyrkomParser, path: &std::path::Path, content: &str) -> Result<Vec<zyrkom::ParsedElement>> {
+    parser.parse_multiple(content).map_err(|diagnostics| {
+        eprintln!("{}", diagnostics.render(&path.display().to_string()));
+        diagnostics.into()
+    })
+}
+
+/// Compile a source file into one constraint system, printing every located
+/// error before failing
+fn compile_source(path: &std::path::Path, content: &str) -> Result<zyrkom::dsl::CompiledProgram> {
+    zyrkom::dsl::Compiler::new().compile(content).map_err(|diagnostics| {
+        eprintln!("{}", diagnostics.render(&path.display().to_string()));
+        diagnostics.into()
+    })
+}
@@ -0,0 +1,438 @@
+//! Chord vocabulary: qualities, inversions, voicings and recognition
+//!
+//! Chords built from a quality use 5-limit just ratios for each chord tone,
+//! like `Chord::major_triad`. Recognition works the other way round: it
+//! reduces any set of notes to pitch classes and finds the root and quality
+//! whose chord tones match exactly, preferring the bass as root.
+
+use crate::musical::{constants, Chord, MusicalInterval, MusicalNote};
+use crate::{Result, ZyrkomError};
+use std::collections::BTreeSet;
+
+/// The quality of a chord: which intervals sit above its root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChordQuality {
+    /// Major triad: 1 3 5
+    Major,
+    /// Minor triad: 1 b3 5
+    Minor,
+    /// Diminished triad: 1 b3 b5
+    Diminished,
+    /// Augmented triad: 1 3 #5
+    Augmented,
+    /// Suspended second: 1 2 5
+    SuspendedSecond,
+    /// Suspended fourth: 1 4 5
+    SuspendedFourth,
+    /// Major sixth: 1 3 5 6
+    MajorSixth,
+    /// Minor sixth: 1 b3 5 6
+    MinorSixth,
+    /// Dominant seventh: 1 3 5 b7
+    DominantSeventh,
+    /// Major seventh: 1 3 5 7
+    MajorSeventh,
+    /// Minor seventh: 1 b3 5 b7
+    MinorSeventh,
+    /// Minor-major seventh: 1 b3 5 7
+    MinorMajorSeventh,
+    /// Half-diminished seventh: 1 b3 b5 b7
+    HalfDiminishedSeventh,
+    /// Diminished seventh: 1 b3 b5 bb7
+    DiminishedSeventh,
+    /// Augmented seventh: 1 3 #5 b7
+    AugmentedSeventh,
+    /// Dominant seventh with suspended fourth: 1 4 5 b7
+    DominantSeventhSuspended,
+    /// Dominant ninth: 1 3 5 b7 9
+    DominantNinth,
+    /// Major ninth: 1 3 5 7 9
+    MajorNinth,
+    /// Minor ninth: 1 b3 5 b7 9
+    MinorNinth,
+    /// Dominant seventh flat nine: 1 3 5 b7 b9
+    DominantSeventhFlatNine,
+    /// Dominant seventh sharp nine: 1 3 5 b7 #9
+    DominantSeventhSharpNine,
+    /// Dominant seventh flat five: 1 3 b5 b7
+    DominantSeventhFlatFive,
+    /// Altered dominant: 1 3 #5 b7 b9 #9
+    Altered,
+}
+
+impl ChordQuality {
+    /// Every quality, in recognition order
+    pub const ALL: [ChordQuality; 23] = [
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Diminished,
+        ChordQuality::Augmented,
+        ChordQuality::SuspendedSecond,
+        ChordQuality::SuspendedFourth,
+        ChordQuality::MajorSixth,
+        ChordQuality::MinorSixth,
+        ChordQuality::DominantSeventh,
+        ChordQuality::MajorSeventh,
+        ChordQuality::MinorSeventh,
+        ChordQuality::MinorMajorSeventh,
+        ChordQuality::HalfDiminishedSeventh,
+        ChordQuality::DiminishedSeventh,
+        ChordQuality::AugmentedSeventh,
+        ChordQuality::DominantSeventhSuspended,
+        ChordQuality::DominantNinth,
+        ChordQuality::MajorNinth,
+        ChordQuality::MinorNinth,
+        ChordQuality::DominantSeventhFlatNine,
+        ChordQuality::DominantSeventhSharpNine,
+        ChordQuality::DominantSeventhFlatFive,
+        ChordQuality::Altered,
+    ];
+
+    /// Semitones of each chord tone above the root, in close position
+    pub fn intervals(&self) -> &'static [u8] {
+        match self {
+            ChordQuality::Major => &[0, 4, 7],
+            ChordQuality::Minor => &[0, 3, 7],
+            ChordQuality::Diminished => &[0, 3, 6],
+            ChordQuality::Augmented => &[0, 4, 8],
+            ChordQuality::SuspendedSecond => &[0, 2, 7],
+            ChordQuality::SuspendedFourth => &[0, 5, 7],
+            ChordQuality::MajorSixth => &[0, 4, 7, 9],
+            ChordQuality::MinorSixth => &[0, 3, 7, 9],
+            ChordQuality::DominantSeventh => &[0, 4, 7, 10],
+            ChordQuality::MajorSeventh => &[0, 4, 7, 11],
+            ChordQuality::MinorSeventh => &[0, 3, 7, 10],
+            ChordQuality::MinorMajorSeventh => &[0, 3, 7, 11],
+            ChordQuality::HalfDiminishedSeventh => &[0, 3, 6, 10],
+            ChordQuality::DiminishedSeventh => &[0, 3, 6, 9],
+            ChordQuality::AugmentedSeventh => &[0, 4, 8, 10],
+            ChordQuality::DominantSeventhSuspended => &[0, 5, 7, 10],
+            ChordQuality::DominantNinth => &[0, 4, 7, 10, 14],
+            ChordQuality::MajorNinth => &[0, 4, 7, 11, 14],
+            ChordQuality::MinorNinth => &[0, 3, 7, 10, 14],
+            ChordQuality::DominantSeventhFlatNine => &[0, 4, 7, 10, 13],
+            ChordQuality::DominantSeventhSharpNine => &[0, 4, 7, 10, 15],
+            ChordQuality::DominantSeventhFlatFive => &[0, 4, 6, 10],
+            ChordQuality::Altered => &[0, 4, 8, 10, 13, 15],
+        }
+    }
+
+    /// Chord symbol suffix (e.g. "m7", "7b9"; empty for a major triad)
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ChordQuality::Major => "",
+            ChordQuality::Minor => "m",
+            ChordQuality::Diminished => "dim",
+            ChordQuality::Augmented => "aug",
+            ChordQuality::SuspendedSecond => "sus2",
+            ChordQuality::SuspendedFourth => "sus4",
+            ChordQuality::MajorSixth => "6",
+            ChordQuality::MinorSixth => "m6",
+            ChordQuality::DominantSeventh => "7",
+            ChordQuality::MajorSeventh => "maj7",
+            ChordQuality::MinorSeventh => "m7",
+            ChordQuality::MinorMajorSeventh => "m(maj7)",
+            ChordQuality::HalfDiminishedSeventh => "m7b5",
+            ChordQuality::DiminishedSeventh => "dim7",
+            ChordQuality::AugmentedSeventh => "aug7",
+            ChordQuality::DominantSeventhSuspended => "7sus4",
+            ChordQuality::DominantNinth => "9",
+            ChordQuality::MajorNinth => "maj9",
+            ChordQuality::MinorNinth => "m9",
+            ChordQuality::DominantSeventhFlatNine => "7b9",
+            ChordQuality::DominantSeventhSharpNine => "7#9",
+            ChordQuality::DominantSeventhFlatFive => "7b5",
+            ChordQuality::Altered => "7alt",
+        }
+    }
+
+    /// Stable numeric identifier committed to in proofs
+    pub fn id(&self) -> u32 {
+        Self::ALL.iter().position(|q| q == self).map_or(0, |i| i as u32 + 1)
+    }
+
+    /// Chord tones reduced to pitch classes above the root
+    pub fn pitch_classes(&self) -> BTreeSet<u8> {
+        self.intervals().iter().map(|i| i % 12).collect()
+    }
+}
+
+/// How a chord's notes are spread across registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Voicing {
+    /// All notes within an octave above the bass
+    Close,
+    /// Close position with every other upper voice raised an octave
+    Open,
+    /// Close position with the second voice from the top dropped an octave
+    Drop2,
+    /// Close position with the third voice from the top dropped an octave
+    Drop3,
+}
+
+/// The result of recognising a chord from its notes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChordRecognition {
+    /// Lowest sounding note on the recognised root
+    pub root: MusicalNote,
+    /// Recognised quality
+    pub quality: ChordQuality,
+    /// Which chord tone is in the bass (0 = root position, 1 = first inversion, ...)
+    pub inversion: usize,
+}
+
+impl Chord {
+    /// Create a chord of a given quality on a root, in close root position
+    pub fn with_quality(root: MusicalNote, quality: ChordQuality) -> Result<Self> {
+        let intervals = quality.intervals()[1..]
+            .iter()
+            .map(|&semitones| {
+                let ratio = constants::JUST_RATIOS[semitones as usize % 12] * 2.0_f64.powi(semitones as i32 / 12);
+                MusicalInterval::new(ratio, MusicalInterval::ratio_to_cents(ratio)).ok_or_else(|| {
+                    ZyrkomError::PhysicsError {
+                        details: format!("Invalid chord interval of {} semitones", semitones),
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::new(root, &intervals)
+    }
+
+    /// Minor triad (10:12:15)
+    pub fn minor_triad(root: MusicalNote) -> Result<Self> {
+        Self::with_quality(root, ChordQuality::Minor)
+    }
+
+    /// Diminished triad
+    pub fn diminished_triad(root: MusicalNote) -> Result<Self> {
+        Self::with_quality(root, ChordQuality::Diminished)
+    }
+
+    /// Augmented triad
+    pub fn augmented_triad(root: MusicalNote) -> Result<Self> {
+        Self::with_quality(root, ChordQuality::Augmented)
+    }
+
+    /// Dominant seventh chord
+    pub fn dominant_seventh(root: MusicalNote) -> Result<Self> {
+        Self::with_quality(root, ChordQuality::DominantSeventh)
+    }
+
+    /// The n-th inversion: the lowest note is raised by octaves n times
+    pub fn inversion(&self, n: usize) -> Result<Self> {
+        if n >= self.note_count() {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("A {}-note chord has no inversion {}", self.note_count(), n),
+            });
+        }
+
+        let mut notes = sorted_by_pitch(self.notes());
+        for _ in 0..n {
+            let bass = notes.remove(0);
+            let mut raised = shift_octaves(&bass, 1)?;
+            while notes.first().is_some_and(|next| raised.frequency() <= next.frequency()) {
+                raised = shift_octaves(&raised, 1)?;
+            }
+            notes.push(raised);
+        }
+
+        Self::from_voicing(self.root(), notes)
+    }
+
+    /// Re-voice the chord, keeping its bass note
+    pub fn voicing(&self, voicing: Voicing) -> Result<Self> {
+        let sorted = sorted_by_pitch(self.notes());
+        let bass = sorted[0];
+
+        let mut close = vec![bass];
+        for note in &sorted[1..] {
+            let octaves = ((bass.exact_midi() - note.exact_midi()) / 12.0).floor() as i32 + 1;
+            let mut moved = shift_octaves(note, octaves)?;
+            if moved.exact_midi() - bass.exact_midi() >= 12.0 {
+                moved = shift_octaves(&moved, -1)?;
+            }
+            close.push(moved);
+        }
+        close = sorted_by_pitch(&close);
+
+        let drop = |notes: &mut Vec<MusicalNote>, from_top: usize| -> Result<()> {
+            if notes.len() < from_top + 1 {
+                return Err(ZyrkomError::PhysicsError {
+                    details: format!("{:?} needs at least {} notes", voicing, from_top + 1),
+                });
+            }
+            let index = notes.len() - from_top;
+            notes[index] = shift_octaves(&notes[index], -1)?;
+            Ok(())
+        };
+
+        let notes = match voicing {
+            Voicing::Close => close,
+            Voicing::Open => close
+                .iter()
+                .enumerate()
+                .map(|(i, note)| if i % 2 == 1 { shift_octaves(note, 1) } else { Ok(*note) })
+                .collect::<Result<Vec<_>>>()?,
+            Voicing::Drop2 => {
+                drop(&mut close, 2)?;
+                close
+            }
+            Voicing::Drop3 => {
+                drop(&mut close, 3)?;
+                close
+            }
+        };
+
+        Self::from_voicing(self.root(), sorted_by_pitch(&notes))
+    }
+
+    /// Recognise this chord's root, quality and inversion
+    pub fn recognize(&self) -> Option<ChordRecognition> {
+        Self::recognize_notes(self.notes())
+    }
+
+    /// Recognise root, quality and inversion from any set of notes
+    ///
+    /// Notes are reduced to pitch classes, so doublings and octave placement
+    /// do not matter. A root on the bass is preferred, so C E G A is read as
+    /// C6 rather than Am7/C.
+    pub fn recognize_notes(notes: &[MusicalNote]) -> Option<ChordRecognition> {
+        let semitone = |note: &MusicalNote| note.exact_midi().round() as i32;
+        let pitch_class = |note: &MusicalNote| semitone(note).rem_euclid(12);
+
+        let bass = *notes.iter().min_by(|a, b| a.frequency().total_cmp(&b.frequency()))?;
+        let bass_class = pitch_class(&bass);
+        let classes: BTreeSet<i32> = notes.iter().map(pitch_class).collect();
+
+        let candidates = std::iter::once(bass_class).chain(
+            (1..12).map(|offset| (bass_class + offset) % 12).filter(|pc| classes.contains(pc)),
+        );
+
+        for root_class in candidates {
+            let relative: BTreeSet<u8> = classes.iter().map(|pc| (pc - root_class).rem_euclid(12) as u8).collect();
+            let Some(quality) = ChordQuality::ALL.into_iter().find(|q| q.pitch_classes() == relative) else {
+                continue;
+            };
+
+            let bass_interval = (bass_class - root_class).rem_euclid(12) as u8;
+            let inversion = quality.intervals().iter().position(|i| i % 12 == bass_interval)?;
+            let root = notes
+                .iter()
+                .filter(|note| pitch_class(note) == root_class)
+                .min_by(|a, b| a.frequency().total_cmp(&b.frequency()))
+                .copied()?;
+
+            return Some(ChordRecognition { root, quality, inversion });
+        }
+
+        None
+    }
+}
+
+/// Move a note by whole octaves, keeping its microtonal offset
+fn shift_octaves(note: &MusicalNote, octaves: i32) -> Result<MusicalNote> {
+    MusicalNote::from_frequency_at(note.frequency() * 2.0_f64.powi(octaves), note.reference())
+}
+
+fn sorted_by_pitch(notes: &[MusicalNote]) -> Vec<MusicalNote> {
+    let mut sorted = notes.to_vec();
+    sorted.sort_by(|a, b| a.frequency().total_cmp(&b.frequency()));
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn midi(notes: &[u8]) -> Vec<MusicalNote> {
+        notes.iter().map(|&m| MusicalNote::from_midi(m).unwrap()).collect()
+    }
+
+    fn midi_numbers(chord: &Chord) -> Vec<u8> {
+        chord.notes().iter().map(|n| n.midi_number()).collect()
+    }
+
+    #[test]
+    fn test_quality_constructors() {
+        let c4 = MusicalNote::from_midi(60).unwrap();
+        assert_eq!(midi_numbers(&Chord::minor_triad(c4).unwrap()), vec![60, 63, 67]);
+        assert_eq!(midi_numbers(&Chord::diminished_triad(c4).unwrap()), vec![60, 63, 66]);
+        assert_eq!(midi_numbers(&Chord::augmented_triad(c4).unwrap()), vec![60, 64, 68]);
+        assert_eq!(
+            midi_numbers(&Chord::with_quality(c4, ChordQuality::DominantNinth).unwrap()),
+            vec![60, 64, 67, 70, 74]
+        );
+
+        // Minor triad is 10:12:15
+        let minor = Chord::minor_triad(c4).unwrap();
+        let fifth = minor.notes()[1].interval_to(&minor.notes()[2]);
+        assert!((fifth.ratio() - 1.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_recognition_of_every_quality() {
+        for quality in ChordQuality::ALL {
+            let chord = Chord::with_quality(MusicalNote::from_midi(62).unwrap(), quality).unwrap();
+            let recognized = chord.recognize().unwrap();
+            assert_eq!(recognized.quality, quality, "{}", quality.symbol());
+            assert_eq!(recognized.root.midi_number(), 62);
+            assert_eq!(recognized.inversion, 0);
+        }
+    }
+
+    #[test]
+    fn test_recognition_of_inversions_and_spacing() {
+        // E3 C4 G4 C5: C major, first inversion, doubled root
+        let recognized = Chord::recognize_notes(&midi(&[52, 60, 67, 72])).unwrap();
+        assert_eq!(recognized.quality, ChordQuality::Major);
+        assert_eq!(recognized.root.midi_number(), 60);
+        assert_eq!(recognized.inversion, 1);
+
+        // F3 G4 B4 D5: G7 in third inversion
+        let recognized = Chord::recognize_notes(&midi(&[53, 67, 71, 74])).unwrap();
+        assert_eq!(recognized.quality, ChordQuality::DominantSeventh);
+        assert_eq!(recognized.root.midi_number(), 67);
+        assert_eq!(recognized.inversion, 3);
+
+        // Bass preference: C E G A is C6, A C E G is Am7
+        assert_eq!(Chord::recognize_notes(&midi(&[60, 64, 67, 69])).unwrap().quality, ChordQuality::MajorSixth);
+        assert_eq!(Chord::recognize_notes(&midi(&[57, 60, 64, 67])).unwrap().quality, ChordQuality::MinorSeventh);
+
+        // A cluster has no quality
+        assert!(Chord::recognize_notes(&midi(&[60, 61, 62])).is_none());
+    }
+
+    #[test]
+    fn test_inversions() {
+        let c_major = Chord::major_triad(MusicalNote::from_midi(60).unwrap()).unwrap();
+        assert_eq!(midi_numbers(&c_major.inversion(1).unwrap()), vec![64, 67, 72]);
+        assert_eq!(midi_numbers(&c_major.inversion(2).unwrap()), vec![67, 72, 76]);
+        assert!(c_major.inversion(3).is_err());
+
+        let second = c_major.inversion(2).unwrap();
+        assert_eq!(second.root().midi_number(), 60);
+        assert_eq!(second.recognize().unwrap().inversion, 2);
+
+        // Raising the root of a ninth chord leaves the third in the bass
+        let ninth = Chord::with_quality(MusicalNote::from_midi(60).unwrap(), ChordQuality::DominantNinth).unwrap();
+        assert_eq!(ninth.inversion(1).unwrap().bass().midi_number(), 64);
+    }
+
+    #[test]
+    fn test_voicings() {
+        let open_c = Chord::from_voicing(MusicalNote::from_midi(48).unwrap(), midi(&[48, 67, 76])).unwrap();
+        assert_eq!(midi_numbers(&open_c.voicing(Voicing::Close).unwrap()), vec![48, 52, 55]);
+        assert_eq!(midi_numbers(&open_c.voicing(Voicing::Open).unwrap()), vec![48, 55, 64]);
+
+        let cmaj7 = Chord::with_quality(MusicalNote::from_midi(60).unwrap(), ChordQuality::MajorSeventh).unwrap();
+        assert_eq!(midi_numbers(&cmaj7.voicing(Voicing::Drop2).unwrap()), vec![55, 60, 64, 71]);
+        assert_eq!(midi_numbers(&cmaj7.voicing(Voicing::Drop3).unwrap()), vec![52, 60, 67, 71]);
+        assert!(Chord::major_triad(MusicalNote::from_midi(60).unwrap()).unwrap().voicing(Voicing::Drop3).is_err());
+
+        // Voicing never changes the recognised chord
+        let drop2 = cmaj7.voicing(Voicing::Drop2).unwrap().recognize().unwrap();
+        assert_eq!(drop2.quality, ChordQuality::MajorSeventh);
+        assert_eq!(drop2.inversion, 2);
+    }
+}
\ No newline at end of file
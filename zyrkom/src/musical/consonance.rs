@@ -0,0 +1,348 @@
+//! Consonance metrics
+//!
+//! Frequency ratios are read as the simplest just ratio within a tolerance
+//! and measured with Tenney height or Euler's gradus suavitatis. Harmonic
+//! entropy and Plomp–Levelt roughness (in Sethares' parameterisation) work
+//! on the raw interval instead. Constraint generation uses a fixed 5-limit
+//! set: a ratio is consonant when, reduced into the octave, its simplest just
+//! reading within `DEFAULT_TOLERANCE_CENTS` is one of `CONSONANT_RATIOS`
+//! (1:1, 6:5, 5:4, 4:3, 3:2, 8:5, 5:3 and 2:1). Compound intervals such as
+//! 12:5 are consonant through their octave reduction; 7:4 never is.
+
+use crate::musical::MusicalInterval;
+use crate::{Result, ZyrkomError};
+
+/// Tolerance for reading a ratio as a just ratio; wide enough for 12-TET
+/// fifths, fourths and major thirds
+pub const DEFAULT_TOLERANCE_CENTS: f64 = 15.0;
+
+/// The consonant just ratios within an octave, in ascending order
+pub const CONSONANT_RATIOS: [(u64, u64); 8] = [(1, 1), (6, 5), (5, 4), (4, 3), (3, 2), (8, 5), (5, 3), (2, 1)];
+
+/// Upper bound on Stern–Brocot steps when searching for a simple ratio
+const MAX_SEARCH_STEPS: usize = 100_000;
+
+/// Sethares' fit of the Plomp–Levelt curve
+const ROUGHNESS_MAX_POINT: f64 = 0.24;
+const ROUGHNESS_S1: f64 = 0.0207;
+const ROUGHNESS_S2: f64 = 18.96;
+const ROUGHNESS_B1: f64 = 3.51;
+const ROUGHNESS_B2: f64 = 5.75;
+
+/// Amplitude falloff between consecutive partials of `Spectrum::harmonic`
+const HARMONIC_FALLOFF: f64 = 0.88;
+
+/// Tenney height log2(n·d) of a just ratio, reduced to lowest terms
+pub fn tenney_height(numerator: u64, denominator: u64) -> f64 {
+    let (n, d) = reduce(numerator, denominator);
+    ((n * d) as f64).log2()
+}
+
+/// Euler's gradus suavitatis of a just ratio: 1 + Σ e·(p - 1) over the prime
+/// factorisation p^e of n·d
+pub fn euler_gradus(numerator: u64, denominator: u64) -> u32 {
+    let (n, d) = reduce(numerator, denominator);
+    let mut remaining = n * d;
+    let mut gradus = 1;
+    let mut prime = 2;
+    while remaining > 1 {
+        while remaining % prime == 0 {
+            gradus += prime as u32 - 1;
+            remaining /= prime;
+        }
+        prime += 1;
+    }
+    gradus
+}
+
+/// The simplest just ratio within a tolerance of a frequency ratio
+///
+/// Found by Stern–Brocot search, so both numerator and denominator are
+/// minimal among all fractions in the window.
+pub fn simplest_ratio(ratio: f64, tolerance_cents: f64) -> Option<(u64, u64)> {
+    if !(ratio > 0.0 && ratio.is_finite() && tolerance_cents > 0.0) {
+        return None;
+    }
+    let low = ratio * MusicalInterval::cents_to_ratio(-tolerance_cents);
+    let high = ratio * MusicalInterval::cents_to_ratio(tolerance_cents);
+
+    let (mut left, mut right) = ((0u64, 1u64), (1u64, 0u64));
+    for _ in 0..MAX_SEARCH_STEPS {
+        let mediant = (left.0 + right.0, left.1 + right.1);
+        let value = mediant.0 as f64 / mediant.1 as f64;
+        if value < low {
+            left = mediant;
+        } else if value > high {
+            right = mediant;
+        } else {
+            return Some(mediant);
+        }
+    }
+    None
+}
+
+/// Harmonic complexity of a frequency ratio: the Tenney height of its
+/// simplest just reading, or infinity if none is found
+pub fn complexity(ratio: f64) -> f64 {
+    simplest_ratio(ratio, DEFAULT_TOLERANCE_CENTS)
+        .map_or(f64::INFINITY, |(n, d)| tenney_height(n, d))
+}
+
+/// Transpose a frequency ratio by octaves into [1, 2)
+pub fn octave_reduce(ratio: f64) -> f64 {
+    if !(ratio > 0.0 && ratio.is_finite()) {
+        return ratio;
+    }
+    ratio / 2f64.powf(ratio.log2().floor())
+}
+
+/// Whether a frequency ratio reads as one of `CONSONANT_RATIOS` once reduced
+/// into the octave
+pub fn is_consonant(ratio: f64) -> bool {
+    simplest_ratio(octave_reduce(ratio), DEFAULT_TOLERANCE_CENTS)
+        .is_some_and(|reading| CONSONANT_RATIOS.contains(&reading))
+}
+
+/// Distance from a frequency ratio, reduced into the octave, to the nearest
+/// of `CONSONANT_RATIOS`
+pub fn harmonic_distance(ratio: f64) -> f64 {
+    let reduced = octave_reduce(ratio);
+    CONSONANT_RATIOS
+        .iter()
+        .map(|&(n, d)| (reduced - n as f64 / d as f64).abs())
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Harmonic entropy (Erlich), with Tenney-weighted candidate ratios
+///
+/// A heard interval is blurred by a Gaussian of `spread_cents`; each candidate
+/// ratio n/d with n·d up to `max_product` is weighted by that Gaussian and by
+/// 1/√(n·d). The entropy of the resulting distribution is low where one simple
+/// ratio dominates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HarmonicEntropy {
+    /// Standard deviation of pitch perception in cents
+    spread_cents: f64,
+    /// Largest numerator × denominator considered
+    max_product: u64,
+}
+
+impl HarmonicEntropy {
+    /// Create a model with a perceptual spread and candidate bound
+    pub fn new(spread_cents: f64, max_product: u64) -> Result<Self> {
+        if !(spread_cents > 0.0 && spread_cents.is_finite()) || max_product == 0 {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!(
+                    "Invalid harmonic entropy model: spread {} cents, max product {}",
+                    spread_cents, max_product
+                ),
+            });
+        }
+        Ok(Self {
+            spread_cents,
+            max_product,
+        })
+    }
+
+    /// Entropy in nats of an interval given in cents
+    pub fn entropy(&self, cents: f64) -> f64 {
+        let window = 5.0 * self.spread_cents;
+        let mut weights = Vec::new();
+
+        for d in 1..=self.max_product {
+            let lowest = (d as f64 * MusicalInterval::cents_to_ratio(cents - window)).ceil().max(1.0) as u64;
+            let highest = (d as f64 * MusicalInterval::cents_to_ratio(cents + window)).floor() as u64;
+            for n in lowest..=highest.min(self.max_product / d) {
+                if gcd(n, d) != 1 {
+                    continue;
+                }
+                let offset = MusicalInterval::ratio_to_cents(n as f64 / d as f64) - cents;
+                let gaussian = (-offset * offset / (2.0 * self.spread_cents * self.spread_cents)).exp();
+                weights.push(gaussian / ((n * d) as f64).sqrt());
+            }
+        }
+
+        let total: f64 = weights.iter().sum();
+        if total == 0.0 {
+            return 0.0;
+        }
+        weights
+            .iter()
+            .map(|w| w / total)
+            .filter(|&p| p > 0.0)
+            .map(|p| -p * p.ln())
+            .sum()
+    }
+}
+
+impl Default for HarmonicEntropy {
+    /// Erlich's usual parameters: 17 cents spread, n·d up to 10 000
+    fn default() -> Self {
+        Self {
+            spread_cents: 17.0,
+            max_product: 10_000,
+        }
+    }
+}
+
+/// An overtone spectrum: partials as (frequency ratio to the fundamental, amplitude)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    /// Partials, fundamental first
+    partials: Vec<(f64, f64)>,
+}
+
+impl Spectrum {
+    /// Create a spectrum from (ratio, amplitude) partials
+    pub fn new(partials: Vec<(f64, f64)>) -> Result<Self> {
+        if partials.is_empty()
+            || partials
+                .iter()
+                .any(|&(ratio, amplitude)| !(ratio > 0.0 && ratio.is_finite() && amplitude >= 0.0))
+        {
+            return Err(ZyrkomError::PhysicsError {
+                details: "Spectrum needs partials with positive ratios and non-negative amplitudes".to_string(),
+            });
+        }
+        Ok(Self { partials })
+    }
+
+    /// A pure sine tone
+    pub fn sine() -> Self {
+        Self {
+            partials: vec![(1.0, 1.0)],
+        }
+    }
+
+    /// The first `count` harmonics with amplitudes falling by 0.88 per partial
+    pub fn harmonic(count: usize) -> Self {
+        Self {
+            partials: (0..count.max(1))
+                .map(|k| ((k + 1) as f64, HARMONIC_FALLOFF.powi(k as i32)))
+                .collect(),
+        }
+    }
+
+    /// The spectrum of an instrument timbre, including its inharmonicity
+    pub fn from_timbre(timbre: &crate::musical::Timbre) -> Self {
+        timbre.spectrum()
+    }
+
+    /// Get the partials
+    pub fn partials(&self) -> &[(f64, f64)] {
+        &self.partials
+    }
+
+    /// Sensory roughness of two tones with this spectrum (Plomp–Levelt,
+    /// Sethares' fit), summed over every pair of partials
+    pub fn roughness(&self, lower_hz: f64, upper_hz: f64) -> f64 {
+        let partials: Vec<(f64, f64)> = [lower_hz, upper_hz]
+            .iter()
+            .flat_map(|&fundamental| self.partials.iter().map(move |&(r, a)| (fundamental * r, a)))
+            .collect();
+
+        let mut total = 0.0;
+        for (i, &(f1, a1)) in partials.iter().enumerate() {
+            for &(f2, a2) in &partials[i + 1..] {
+                let scale = ROUGHNESS_MAX_POINT / (ROUGHNESS_S1 * f1.min(f2) + ROUGHNESS_S2);
+                let difference = (f2 - f1).abs();
+                total += a1.min(a2)
+                    * ((-ROUGHNESS_B1 * scale * difference).exp() - (-ROUGHNESS_B2 * scale * difference).exp());
+            }
+        }
+        total
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn reduce(numerator: u64, denominator: u64) -> (u64, u64) {
+    let divisor = gcd(numerator, denominator).max(1);
+    (numerator / divisor, denominator / divisor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tenney_height_and_euler_gradus() {
+        assert_eq!(tenney_height(2, 1), 1.0);
+        assert!((tenney_height(6, 4) - 6f64.log2()).abs() < 1e-12);
+        assert_eq!(euler_gradus(1, 1), 1);
+        assert_eq!(euler_gradus(2, 1), 2);
+        assert_eq!(euler_gradus(3, 2), 4);
+        assert_eq!(euler_gradus(5, 4), 7);
+        assert_eq!(euler_gradus(45, 32), 14);
+    }
+
+    #[test]
+    fn test_simplest_ratio() {
+        let tempered_fifth = MusicalInterval::cents_to_ratio(700.0);
+        let tempered_third = MusicalInterval::cents_to_ratio(400.0);
+        assert_eq!(simplest_ratio(tempered_fifth, DEFAULT_TOLERANCE_CENTS), Some((3, 2)));
+        assert_eq!(simplest_ratio(tempered_third, DEFAULT_TOLERANCE_CENTS), Some((5, 4)));
+        assert_ne!(simplest_ratio(tempered_third, 1.0), Some((5, 4)));
+        assert_eq!(simplest_ratio(2.0 / 3.0, 1.0), Some((2, 3)));
+        assert_eq!(simplest_ratio(-1.0, 1.0), None);
+    }
+
+    #[test]
+    fn test_consonance_classification() {
+        for ratio in [1.0, 2.0, 1.5, 4.0 / 3.0, 1.25, 6.0 / 5.0, 5.0 / 3.0, 3.0] {
+            assert!(is_consonant(ratio), "{}", ratio);
+        }
+        for ratio in [16.0 / 15.0, 9.0 / 8.0, 45.0 / 32.0, 15.0 / 8.0, 2f64.sqrt()] {
+            assert!(!is_consonant(ratio), "{}", ratio);
+        }
+        assert!(complexity(1.5) < complexity(1.25));
+        assert!(harmonic_distance(1.505) < 0.01);
+    }
+
+    #[test]
+    fn test_consonance_is_five_limit_after_octave_reduction() {
+        // Minor sixth, minor tenth and inverted fifth reduce into the set
+        for ratio in [8.0 / 5.0, 12.0 / 5.0, 2.0 / 3.0, 4.0] {
+            assert!(is_consonant(ratio), "{}", ratio);
+        }
+        // Septimal and higher harmonics are not consonant however simple
+        for ratio in [7.0 / 4.0, 7.0 / 3.0, 7.0 / 2.0, 9.0 / 2.0, 11.0 / 2.0, 13.0 / 2.0] {
+            assert!(!is_consonant(ratio), "{}", ratio);
+        }
+        for ratio in [1.75, 5.5, 6.5] {
+            assert!(harmonic_distance(ratio) > 0.01, "{}", ratio);
+        }
+        assert!(harmonic_distance(3.01) < 0.01);
+        assert_eq!(octave_reduce(12.0 / 5.0), 1.2);
+    }
+
+    #[test]
+    fn test_harmonic_entropy() {
+        let model = HarmonicEntropy::default();
+        assert!(model.entropy(701.955) < model.entropy(650.0));
+        assert!(model.entropy(1200.0) < model.entropy(1150.0));
+        assert!(model.entropy(386.3) < model.entropy(450.0));
+        assert!(HarmonicEntropy::new(0.0, 100).is_err());
+    }
+
+    #[test]
+    fn test_roughness() {
+        let spectrum = Spectrum::harmonic(6);
+        let c4 = 261.63;
+        let fifth = spectrum.roughness(c4, c4 * 1.5);
+        let minor_second = spectrum.roughness(c4, c4 * 16.0 / 15.0);
+        let tritone = spectrum.roughness(c4, c4 * 45.0 / 32.0);
+        assert!(fifth < tritone);
+        assert!(tritone < minor_second);
+
+        // Pure sines an octave apart are beyond the critical band
+        assert!(Spectrum::sine().roughness(c4, 2.0 * c4) < 1e-3);
+        assert!(Spectrum::new(vec![(1.0, -1.0)]).is_err());
+    }
+}
\ No newline at end of file
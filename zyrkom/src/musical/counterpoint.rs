@@ -0,0 +1,305 @@
+//! Species counterpoint rule checking (after Fux, Gradus ad Parnassum)
+//!
+//! Voices are given top to bottom as note sequences. Two voices are aligned
+//! on a common grid when one voice's length is an integer multiple of the
+//! other's: equal lengths are first species, two notes against one second
+//! species, four against one third species. Positions in every report are
+//! grid indices of that pair (the index into the faster voice).
+
+use crate::musical::MusicalNote;
+use crate::{Result, ZyrkomError};
+
+/// Semitones in an octave, for interval class arithmetic
+const OCTAVE_SEMITONES: i64 = 12;
+
+/// Largest melodic motion still considered a step (a whole tone)
+const MAX_STEP_SEMITONES: i64 = 2;
+
+/// Smallest melodic leap that must be resolved (a perfect fourth)
+const MIN_RESOLVED_LEAP_SEMITONES: i64 = 5;
+
+/// A voice-leading rule of species counterpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CounterpointRule {
+    /// Two voices move in similar motion from one perfect fifth to another
+    ParallelFifths,
+    /// Two voices move in similar motion from one octave (or unison) to another
+    ParallelOctaves,
+    /// An upper voice sounds below a lower voice
+    VoiceCrossing,
+    /// Dissonances appear only on weak beats as passing or neighbour tones
+    DissonanceTreatment,
+    /// A leap of a fourth or more is followed by a step in the opposite direction
+    LeapResolution,
+}
+
+/// The outcome of checking one rule at one position
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleCheck {
+    /// Rule that was checked
+    pub rule: CounterpointRule,
+    /// Grid position of the check (index into the faster voice of the pair)
+    pub position: usize,
+    /// Voices involved (the same index twice for melodic rules)
+    pub voices: (usize, usize),
+    /// Whether the rule holds at this position
+    pub holds: bool,
+    /// Non-negative margin when the rule is a numeric bound (e.g. semitones
+    /// between voices for crossing), negative when violated
+    pub margin: Option<i64>,
+}
+
+/// Rule checks for a complete multi-voice passage
+#[derive(Debug, Clone)]
+pub struct CounterpointAnalysis {
+    /// Voices top to bottom
+    voices: Vec<Vec<MusicalNote>>,
+    /// Every rule check performed, in order
+    checks: Vec<RuleCheck>,
+}
+
+impl CounterpointAnalysis {
+    /// Analyse voices (ordered top to bottom) against the species rules
+    pub fn analyze(voices: &[Vec<MusicalNote>]) -> Result<Self> {
+        if voices.len() < 2 {
+            return Err(ZyrkomError::ConstraintError {
+                context: "Counterpoint needs at least two voices".to_string(),
+            });
+        }
+
+        let mut checks = Vec::new();
+        for upper in 0..voices.len() {
+            for lower in (upper + 1)..voices.len() {
+                let is_bass = lower == voices.len() - 1;
+                checks.extend(Self::check_pair(voices, upper, lower, is_bass)?);
+            }
+        }
+        for (index, voice) in voices.iter().enumerate() {
+            checks.extend(Self::check_leaps(voice, index));
+        }
+
+        Ok(Self {
+            voices: voices.to_vec(),
+            checks,
+        })
+    }
+
+    /// Get the analysed voices
+    pub fn voices(&self) -> &[Vec<MusicalNote>] {
+        &self.voices
+    }
+
+    /// Get every rule check performed
+    pub fn checks(&self) -> &[RuleCheck] {
+        &self.checks
+    }
+
+    /// Get the checks that failed
+    pub fn violations(&self) -> Vec<&RuleCheck> {
+        self.checks.iter().filter(|check| !check.holds).collect()
+    }
+
+    /// Whether the passage satisfies every rule
+    pub fn is_compliant(&self) -> bool {
+        self.checks.iter().all(|check| check.holds)
+    }
+
+    /// Vertical and contrapuntal-motion checks for one pair of voices
+    fn check_pair(voices: &[Vec<MusicalNote>], upper: usize, lower: usize, is_bass: bool) -> Result<Vec<RuleCheck>> {
+        let (up, low) = (&voices[upper], &voices[lower]);
+        let grid = up.len().max(low.len());
+        let slow = up.len().min(low.len());
+
+        if slow == 0 || grid % slow != 0 {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!(
+                    "Voices {} and {} cannot be aligned: {} against {} notes",
+                    upper, lower, up.len(), low.len()
+                ),
+            });
+        }
+
+        let notes_per_beat = grid / slow;
+        let (up_factor, low_factor) = (grid / up.len(), grid / low.len());
+        let at = |t: usize| (midi(&up[t / up_factor]), midi(&low[t / low_factor]));
+        let fast = if up.len() >= low.len() { up } else { low };
+
+        let mut checks = Vec::new();
+        for t in 0..grid {
+            let (u, l) = at(t);
+            let vertical = u - l;
+
+            checks.push(RuleCheck {
+                rule: CounterpointRule::VoiceCrossing,
+                position: t,
+                voices: (upper, lower),
+                holds: vertical >= 0,
+                margin: Some(vertical),
+            });
+
+            let strong_beat = t % notes_per_beat == 0;
+            let treated = !is_dissonant(vertical, is_bass)
+                || (!strong_beat && t > 0 && t + 1 < grid && is_step_figure(fast, t));
+            checks.push(RuleCheck {
+                rule: CounterpointRule::DissonanceTreatment,
+                position: t,
+                voices: (upper, lower),
+                holds: treated,
+                margin: None,
+            });
+
+            if t > 0 {
+                let (pu, pl) = at(t - 1);
+                let similar_motion = (u - pu).signum() == (l - pl).signum() && u != pu && l != pl;
+                let perfect = |class: i64| {
+                    interval_class(vertical) == class && interval_class(pu - pl) == class
+                };
+
+                for (rule, class) in [
+                    (CounterpointRule::ParallelFifths, 7),
+                    (CounterpointRule::ParallelOctaves, 0),
+                ] {
+                    checks.push(RuleCheck {
+                        rule,
+                        position: t,
+                        voices: (upper, lower),
+                        holds: !(similar_motion && perfect(class)),
+                        margin: None,
+                    });
+                }
+            }
+        }
+
+        Ok(checks)
+    }
+
+    /// Melodic leap resolution checks for one voice
+    fn check_leaps(voice: &[MusicalNote], index: usize) -> Vec<RuleCheck> {
+        (1..voice.len().saturating_sub(1))
+            .filter_map(|i| {
+                let leap = midi(&voice[i]) - midi(&voice[i - 1]);
+                if leap.abs() < MIN_RESOLVED_LEAP_SEMITONES {
+                    return None;
+                }
+
+                let next = midi(&voice[i + 1]) - midi(&voice[i]);
+                let resolved = (1..=MAX_STEP_SEMITONES).contains(&next.abs())
+                    && next.signum() == -leap.signum();
+
+                Some(RuleCheck {
+                    rule: CounterpointRule::LeapResolution,
+                    position: i,
+                    voices: (index, index),
+                    holds: resolved,
+                    margin: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// MIDI number as a signed integer for interval arithmetic
+fn midi(note: &MusicalNote) -> i64 {
+    note.midi_number() as i64
+}
+
+/// Interval class within the octave (0 = unison/octave, 7 = fifth)
+fn interval_class(semitones: i64) -> i64 {
+    semitones.abs().rem_euclid(OCTAVE_SEMITONES)
+}
+
+/// Seconds, sevenths and the tritone are dissonant; the perfect fourth is
+/// dissonant only when sounding above the bass
+fn is_dissonant(semitones: i64, is_bass: bool) -> bool {
+    match interval_class(semitones) {
+        1 | 2 | 6 | 10 | 11 => true,
+        5 => is_bass,
+        _ => false,
+    }
+}
+
+/// Whether the note at `t` is approached and left by step (a passing or
+/// neighbour tone)
+fn is_step_figure(voice: &[MusicalNote], t: usize) -> bool {
+    let is_step = |a: &MusicalNote, b: &MusicalNote| {
+        (1..=MAX_STEP_SEMITONES).contains(&(midi(b) - midi(a)).abs())
+    };
+    is_step(&voice[t - 1], &voice[t]) && is_step(&voice[t], &voice[t + 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(midi_numbers: &[u8]) -> Vec<MusicalNote> {
+        midi_numbers.iter().map(|&m| MusicalNote::from_midi(m).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_fux_first_species_example_is_compliant() {
+        // Cantus firmus in D dorian (Fux, fig. 5) with a counterpoint above
+        let counterpoint = line(&[69, 69, 67, 69, 71, 72, 72, 71, 74, 73, 74]);
+        let cantus = line(&[62, 65, 64, 62, 67, 65, 69, 67, 65, 64, 62]);
+
+        let analysis = CounterpointAnalysis::analyze(&[counterpoint, cantus]).unwrap();
+        assert!(analysis.is_compliant(), "{:?}", analysis.violations());
+    }
+
+    #[test]
+    fn test_parallel_fifths_and_octaves() {
+        // C-G to D-A (fifths), then D-D to E-E (octaves)
+        let upper = line(&[67, 69, 74, 76]);
+        let lower = line(&[60, 62, 62, 64]);
+
+        let analysis = CounterpointAnalysis::analyze(&[upper, lower]).unwrap();
+        let rules: Vec<_> = analysis.violations().iter().map(|v| (v.rule, v.position)).collect();
+        assert!(rules.contains(&(CounterpointRule::ParallelFifths, 1)));
+        assert!(rules.contains(&(CounterpointRule::ParallelOctaves, 3)));
+    }
+
+    #[test]
+    fn test_voice_crossing_reports_margin() {
+        let upper = line(&[64, 60]);
+        let lower = line(&[60, 64]);
+
+        let analysis = CounterpointAnalysis::analyze(&[upper, lower]).unwrap();
+        let crossing: Vec<_> = analysis.violations().into_iter()
+            .filter(|v| v.rule == CounterpointRule::VoiceCrossing)
+            .collect();
+        assert_eq!(crossing.len(), 1);
+        assert_eq!(crossing[0].position, 1);
+        assert_eq!(crossing[0].margin, Some(-4));
+    }
+
+    #[test]
+    fn test_second_species_passing_tone() {
+        // C5 D5 | E5 G5 over C4 | E4: D5 is a passing dissonance
+        let upper = line(&[72, 74, 76, 79]);
+        let lower = line(&[60, 64]);
+        let analysis = CounterpointAnalysis::analyze(&[upper, lower]).unwrap();
+        assert!(analysis.violations().iter().all(|v| v.rule != CounterpointRule::DissonanceTreatment));
+
+        // The same dissonance on a strong beat is not allowed
+        let upper = line(&[74, 72, 76, 79]);
+        let lower = line(&[60, 64]);
+        let analysis = CounterpointAnalysis::analyze(&[upper, lower]).unwrap();
+        assert!(analysis.violations().iter().any(|v| v.rule == CounterpointRule::DissonanceTreatment && v.position == 0));
+    }
+
+    #[test]
+    fn test_leap_resolution() {
+        // Leap up a sixth then step down: resolved. Leap up a fifth then continue up: not.
+        let resolved = CounterpointAnalysis::check_leaps(&line(&[60, 69, 67]), 0);
+        assert!(resolved.iter().all(|c| c.holds));
+
+        let unresolved = CounterpointAnalysis::check_leaps(&line(&[60, 67, 69]), 0);
+        assert_eq!(unresolved.len(), 1);
+        assert!(!unresolved[0].holds);
+    }
+
+    #[test]
+    fn test_unaligned_voices_are_rejected() {
+        assert!(CounterpointAnalysis::analyze(&[line(&[60, 62, 64]), line(&[48, 50])]).is_err());
+        assert!(CounterpointAnalysis::analyze(&[line(&[60])]).is_err());
+    }
+}
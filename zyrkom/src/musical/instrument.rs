@@ -0,0 +1,540 @@
+//! Instruments and playability
+//!
+//! An instrument has a sounding range, a written transposition (a B♭
+//! clarinet sounds a major second below the written note), a limit on how
+//! many notes it can sound at once and, for keyboards, how far each hand can
+//! stretch. Parts are given at sounding pitch; every note that falls outside
+//! what the instrument can play is reported with the reason.
+
+use std::fmt;
+
+use crate::musical::{Melody, MusicalNote, NoteDuration, Score, SpelledPitch, Timbre, Voice};
+use crate::{Result, ZyrkomError};
+
+/// Widest interval one hand can hold on a keyboard, in semitones (a major ninth)
+pub const PIANO_HAND_STRETCH: u32 = 14;
+
+/// Polyphony of instruments declared without a limit
+pub const MAX_POLYPHONY: usize = 128;
+
+/// An instrument's playable range and idiomatic limits
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instrument {
+    name: String,
+    lowest: MusicalNote,
+    highest: MusicalNote,
+    transposition: i32,
+    polyphony: usize,
+    hand_span: Option<(usize, u32)>,
+    timbre: Timbre,
+}
+
+impl Instrument {
+    /// Create an instrument sounding from `lowest` to `highest`, non-transposing
+    /// and fully polyphonic
+    pub fn new(name: &str, lowest: MusicalNote, highest: MusicalNote) -> Result<Self> {
+        if lowest.midi_number() > highest.midi_number() {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Instrument {} has its lowest note above its highest", name),
+            });
+        }
+        Ok(Self {
+            name: name.to_string(),
+            lowest,
+            highest,
+            transposition: 0,
+            polyphony: MAX_POLYPHONY,
+            hand_span: None,
+            timbre: Timbre::default(),
+        })
+    }
+
+    /// Create an instrument from spelled range limits such as "A0" and "C8"
+    pub fn from_range_names(name: &str, lowest: &str, highest: &str) -> Result<Self> {
+        let to_note = |spelled: &str| -> Result<MusicalNote> {
+            let midi = SpelledPitch::parse(spelled)?.midi_number();
+            u8::try_from(midi)
+                .ok()
+                .and_then(|m| MusicalNote::from_midi(m).ok())
+                .ok_or_else(|| ZyrkomError::PhysicsError {
+                    details: format!("{} is outside the MIDI range", spelled),
+                })
+        };
+        Self::new(name, to_note(lowest)?, to_note(highest)?)
+    }
+
+    /// Change the sounding range, keeping every other limit
+    pub fn with_range(self, lowest: MusicalNote, highest: MusicalNote) -> Result<Self> {
+        let checked = Self::new(&self.name, lowest, highest)?;
+        Ok(Self {
+            lowest: checked.lowest,
+            highest: checked.highest,
+            ..self
+        })
+    }
+
+    /// Set the written transposition: semitones from written to sounding pitch
+    /// (-2 for a B♭ clarinet, -7 for a horn in F)
+    pub fn with_transposition(mut self, semitones: i32) -> Self {
+        self.transposition = semitones;
+        self
+    }
+
+    /// Set how many notes may sound at once
+    pub fn with_polyphony(mut self, polyphony: usize) -> Result<Self> {
+        if polyphony == 0 || polyphony > MAX_POLYPHONY {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Polyphony must be between 1 and {}, got {}", MAX_POLYPHONY, polyphony),
+            });
+        }
+        self.polyphony = polyphony;
+        Ok(self)
+    }
+
+    /// Require simultaneous notes to fit under `hands` hands, each spanning at
+    /// most `stretch` semitones
+    pub fn with_hand_span(mut self, hands: usize, stretch: u32) -> Result<Self> {
+        if hands == 0 {
+            return Err(ZyrkomError::PhysicsError {
+                details: "A hand span needs at least one hand".to_string(),
+            });
+        }
+        self.hand_span = Some((hands, stretch));
+        Ok(self)
+    }
+
+    /// Set the timbre used for rendering and roughness
+    pub fn with_timbre(mut self, timbre: Timbre) -> Self {
+        self.timbre = timbre;
+        self
+    }
+
+    /// Concert grand: A0–C8, two hands of a ninth, ten fingers
+    pub fn piano() -> Self {
+        Self::preset_unchecked("piano", 21, 108, 0, 10, Some((2, PIANO_HAND_STRETCH)), Timbre::piano())
+    }
+
+    /// B♭ clarinet: sounds D3–B♭6, a major second below written pitch
+    pub fn clarinet() -> Self {
+        Self::preset_unchecked("clarinet", 50, 94, -2, 1, None, Timbre::clarinet())
+    }
+
+    /// Horn in F: sounds B1–F5, a perfect fifth below written pitch
+    pub fn horn() -> Self {
+        Self::preset_unchecked("horn", 35, 77, -7, 1, None, Timbre::default())
+    }
+
+    /// Flute: C4–D7
+    pub fn flute() -> Self {
+        Self::preset_unchecked("flute", 60, 98, 0, 1, None, Timbre::flute())
+    }
+
+    /// Violin: G3–A7, up to quadruple stops
+    pub fn violin() -> Self {
+        Self::preset_unchecked("violin", 55, 105, 0, 4, None, Timbre::violin())
+    }
+
+    /// Guitar: sounds E2–B5, an octave below written pitch, six strings
+    pub fn guitar() -> Self {
+        Self::preset_unchecked("guitar", 40, 83, -12, 6, None, Timbre::guitar())
+    }
+
+    /// Look up an instrument preset by name (case-insensitive, English or Spanish)
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "piano" => Some(Self::piano()),
+            "clarinet" | "clarinete" => Some(Self::clarinet()),
+            "horn" | "trompa" => Some(Self::horn()),
+            "flute" | "flauta" => Some(Self::flute()),
+            "violin" | "violín" => Some(Self::violin()),
+            "guitar" | "guitarra" => Some(Self::guitar()),
+            _ => None,
+        }
+    }
+
+    fn preset_unchecked(
+        name: &str,
+        lowest: u8,
+        highest: u8,
+        transposition: i32,
+        polyphony: usize,
+        hand_span: Option<(usize, u32)>,
+        timbre: Timbre,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            lowest: MusicalNote::from_midi(lowest).expect("preset ranges are MIDI numbers"),
+            highest: MusicalNote::from_midi(highest).expect("preset ranges are MIDI numbers"),
+            transposition,
+            polyphony,
+            hand_span,
+            timbre,
+        }
+    }
+
+    /// Get the instrument name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the lowest sounding note
+    pub fn lowest(&self) -> MusicalNote {
+        self.lowest
+    }
+
+    /// Get the highest sounding note
+    pub fn highest(&self) -> MusicalNote {
+        self.highest
+    }
+
+    /// Get the written transposition in semitones (written to sounding)
+    pub fn transposition(&self) -> i32 {
+        self.transposition
+    }
+
+    /// Get the number of notes that may sound at once
+    pub fn polyphony(&self) -> usize {
+        self.polyphony
+    }
+
+    /// Get the hand count and per-hand stretch in semitones, if limited
+    pub fn hand_span(&self) -> Option<(usize, u32)> {
+        self.hand_span
+    }
+
+    /// Get the timbre
+    pub fn timbre(&self) -> &Timbre {
+        &self.timbre
+    }
+
+    /// Whether a sounding note lies within the range
+    pub fn in_range(&self, note: &MusicalNote) -> bool {
+        (self.lowest.midi_number()..=self.highest.midi_number()).contains(&note.midi_number())
+    }
+
+    /// Sounding pitch of a part written for this instrument
+    pub fn to_sounding(&self, written: &Melody) -> Result<Melody> {
+        written.transpose(self.transposition)
+    }
+
+    /// Written part for this instrument of a melody at sounding pitch
+    pub fn to_written(&self, sounding: &Melody) -> Result<Melody> {
+        sounding.transpose(-self.transposition)
+    }
+
+    /// Fewest hands that can hold these simultaneous notes, each hand spanning
+    /// at most the instrument's stretch (1 when hands are not limited)
+    ///
+    /// Greedy from the bottom: each hand takes every note within reach of its
+    /// lowest note, which is optimal for covering points with intervals.
+    pub fn hands_needed(&self, notes: &[MusicalNote]) -> usize {
+        let Some((_, stretch)) = self.hand_span else {
+            return notes.len().min(1);
+        };
+        let mut midi: Vec<u32> = notes.iter().map(|n| n.midi_number() as u32).collect();
+        midi.sort_unstable();
+
+        let mut hands = 0;
+        let mut reach = None;
+        for m in midi {
+            if reach.is_none_or(|top| m > top) {
+                hands += 1;
+                reach = Some(m + stretch);
+            }
+        }
+        hands
+    }
+
+    /// Violations of a single melody played on this instrument
+    pub fn validate_melody(&self, melody: &Melody) -> Vec<NoteViolation> {
+        self.validate_voices(&[Voice::new(&self.name, melody.clone())])
+    }
+
+    /// Violations of every voice of a score played together on this instrument
+    pub fn validate_score(&self, score: &Score) -> Vec<NoteViolation> {
+        self.validate_voices(score.voices())
+    }
+
+    fn validate_voices(&self, voices: &[Voice]) -> Vec<NoteViolation> {
+        let mut violations = Vec::new();
+        for voice in voices {
+            for (index, event) in voice.melody().events().iter().enumerate() {
+                let pitch = event.pitch();
+                let violation = if pitch.midi_number() < self.lowest.midi_number() {
+                    Some(Violation::BelowRange)
+                } else if pitch.midi_number() > self.highest.midi_number() {
+                    Some(Violation::AboveRange)
+                } else {
+                    None
+                };
+                if let Some(violation) = violation {
+                    violations.push(NoteViolation::new(voice, index, violation));
+                }
+            }
+        }
+
+        // Polyphony and stretch are judged where notes start, against
+        // everything sounding at that moment
+        for onset in onsets(voices) {
+            let sounding = sounding_at(voices, onset);
+            let notes: Vec<MusicalNote> = sounding.iter().map(|&(v, i)| voices[v].melody().events()[i].pitch()).collect();
+            let hands = self.hands_needed(&notes);
+            for &(v, i) in &sounding {
+                if voices[v].melody().events()[i].onset() != onset {
+                    continue;
+                }
+                if notes.len() > self.polyphony {
+                    violations.push(NoteViolation::new(&voices[v], i, Violation::Polyphony { sounding: notes.len() }));
+                }
+                if let Some((available, _)) = self.hand_span.filter(|&(available, _)| hands > available) {
+                    violations.push(NoteViolation::new(&voices[v], i, Violation::Stretch { hands_needed: hands, available }));
+                }
+            }
+        }
+        violations
+    }
+}
+
+impl fmt::Display for Instrument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}, {}]",
+            self.name,
+            crate::utils::frequency_to_note_name(self.lowest.frequency()),
+            crate::utils::frequency_to_note_name(self.highest.frequency())
+        )
+    }
+}
+
+/// Why a note cannot be played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// The note is below the instrument's lowest note
+    BelowRange,
+    /// The note is above the instrument's highest note
+    AboveRange,
+    /// More notes sound at this onset than the instrument can play
+    Polyphony {
+        /// Notes sounding at the onset
+        sounding: usize,
+    },
+    /// The notes sounding at this onset need more hands than there are
+    Stretch {
+        /// Hands the chord needs
+        hands_needed: usize,
+        /// Hands the instrument has
+        available: usize,
+    },
+}
+
+/// A note that cannot be played, located by voice and event index
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteViolation {
+    /// Voice the note belongs to
+    pub voice: String,
+    /// Index of the note among the voice's events
+    pub index: usize,
+    /// Sounding pitch of the note
+    pub pitch: MusicalNote,
+    /// Onset of the note in whole notes
+    pub onset: NoteDuration,
+    /// What is wrong
+    pub violation: Violation,
+}
+
+impl NoteViolation {
+    fn new(voice: &Voice, index: usize, violation: Violation) -> Self {
+        let event = &voice.melody().events()[index];
+        Self {
+            voice: voice.name().to_string(),
+            index,
+            pitch: event.pitch(),
+            onset: event.onset(),
+            violation,
+        }
+    }
+}
+
+impl fmt::Display for NoteViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let note = crate::utils::frequency_to_note_name(self.pitch.frequency());
+        write!(f, "{} note {} ({} at {}): ", self.voice, self.index + 1, note, self.onset)?;
+        match self.violation {
+            Violation::BelowRange => write!(f, "below range"),
+            Violation::AboveRange => write!(f, "above range"),
+            Violation::Polyphony { sounding } => write!(f, "{} notes sounding at once", sounding),
+            Violation::Stretch { hands_needed, available } => {
+                write!(f, "needs {} hands, only {} available", hands_needed, available)
+            }
+        }
+    }
+}
+
+/// A part claimed to be playable on an instrument
+#[derive(Debug, Clone)]
+pub struct InstrumentPart {
+    instrument: Instrument,
+    score: Score,
+}
+
+impl InstrumentPart {
+    /// Claim that every voice of a score can be played together on an instrument
+    pub fn new(instrument: Instrument, score: Score) -> Self {
+        Self { instrument, score }
+    }
+
+    /// Claim that a melody at sounding pitch can be played on an instrument
+    pub fn melody(instrument: Instrument, melody: Melody) -> Result<Self> {
+        let mut score = Score::new(melody.time_signature());
+        score.add_voice(Voice::new(instrument.name(), melody))?;
+        Ok(Self::new(instrument, score))
+    }
+
+    /// Get the instrument
+    pub fn instrument(&self) -> &Instrument {
+        &self.instrument
+    }
+
+    /// Get the part
+    pub fn score(&self) -> &Score {
+        &self.score
+    }
+
+    /// Every note that cannot be played
+    pub fn violations(&self) -> Vec<NoteViolation> {
+        self.instrument.validate_score(&self.score)
+    }
+
+    /// Whether the whole part can be played
+    pub fn is_playable(&self) -> bool {
+        self.violations().is_empty()
+    }
+
+    /// Every onset in the part with the notes sounding at it
+    pub fn simultaneities(&self) -> Vec<(NoteDuration, Vec<MusicalNote>)> {
+        let voices = self.score.voices();
+        onsets(voices)
+            .into_iter()
+            .map(|onset| {
+                let notes = sounding_at(voices, onset)
+                    .into_iter()
+                    .map(|(v, i)| voices[v].melody().events()[i].pitch())
+                    .collect();
+                (onset, notes)
+            })
+            .collect()
+    }
+}
+
+/// Distinct note onsets across all voices, in order
+fn onsets(voices: &[Voice]) -> Vec<NoteDuration> {
+    let mut onsets: Vec<NoteDuration> = voices
+        .iter()
+        .flat_map(|voice| voice.melody().events().iter().map(|event| event.onset()))
+        .collect();
+    onsets.sort();
+    onsets.dedup();
+    onsets
+}
+
+/// (voice, event) indices of the notes sounding at an instant
+fn sounding_at(voices: &[Voice], instant: NoteDuration) -> Vec<(usize, usize)> {
+    voices
+        .iter()
+        .enumerate()
+        .flat_map(|(v, voice)| {
+            voice
+                .melody()
+                .events()
+                .iter()
+                .enumerate()
+                // An end past u64 lies beyond any representable instant
+                .filter(move |(_, event)| event.onset() <= instant && event.end().map_or(true, |end| instant < end))
+                .map(move |(i, _)| (v, i))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::musical::{NoteValue, RhythmicValue, TimeSignature};
+
+    fn melody(notes: &[u8]) -> Melody {
+        let q = RhythmicValue::new(NoteValue::Quarter);
+        let notes: Vec<(MusicalNote, RhythmicValue)> = notes.iter().map(|&m| (MusicalNote::from_midi(m).unwrap(), q)).collect();
+        Melody::from_notes(TimeSignature::common(), &notes).unwrap()
+    }
+
+    fn score(voices: &[(&str, &[u8])]) -> Score {
+        let mut score = Score::new(TimeSignature::common());
+        for (name, notes) in voices {
+            score.add_voice(Voice::new(name, melody(notes))).unwrap();
+        }
+        score
+    }
+
+    #[test]
+    fn test_range_violations_per_note() {
+        let flute = Instrument::flute();
+        let violations = flute.validate_melody(&melody(&[59, 60, 98, 99]));
+        assert_eq!(violations.len(), 2);
+        assert_eq!((violations[0].index, violations[0].violation), (0, Violation::BelowRange));
+        assert_eq!((violations[1].index, violations[1].violation), (3, Violation::AboveRange));
+        assert_eq!(violations[1].onset, NoteDuration::new(3, 4).unwrap());
+        assert!(violations[0].to_string().starts_with("flute note 1"));
+        assert!(flute.validate_melody(&melody(&[60, 72, 84])).is_empty());
+    }
+
+    #[test]
+    fn test_range_from_names() {
+        let piano = Instrument::from_range_names("Piano", "A0", "C8").unwrap();
+        assert_eq!(piano.lowest().midi_number(), 21);
+        assert_eq!(piano.highest().midi_number(), 108);
+        assert_eq!(piano.to_string(), "Piano [A0, C8]");
+        assert!(Instrument::from_range_names("Piano", "C8", "A0").is_err());
+        assert!(Instrument::from_range_names("Piano", "X2", "C8").is_err());
+    }
+
+    #[test]
+    fn test_written_transposition() {
+        let clarinet = Instrument::clarinet();
+        // Written C5 on a B♭ clarinet sounds B♭4
+        let sounding = clarinet.to_sounding(&melody(&[72])).unwrap();
+        assert_eq!(sounding.pitches()[0].midi_number(), 70);
+        assert_eq!(clarinet.to_written(&sounding).unwrap().pitches()[0].midi_number(), 72);
+        assert_eq!(Instrument::horn().transposition(), -7);
+        assert!(Instrument::preset("Trompa").is_some());
+    }
+
+    #[test]
+    fn test_polyphony_limit() {
+        let flute = Instrument::flute();
+        let violations = flute.validate_score(&score(&[("upper", &[72, 74]), ("lower", &[67, 69])]));
+        // Both voices start together at each onset, so every note is flagged
+        assert_eq!(violations.len(), 4);
+        assert!(violations.iter().all(|v| v.violation == Violation::Polyphony { sounding: 2 }));
+        assert!(Instrument::violin().validate_score(&score(&[("upper", &[72]), ("lower", &[67])])).is_empty());
+        assert!(Instrument::flute().with_polyphony(0).is_err());
+    }
+
+    #[test]
+    fn test_piano_hand_stretch() {
+        let piano = Instrument::piano();
+        let notes = |midi: &[u8]| midi.iter().map(|&m| MusicalNote::from_midi(m).unwrap()).collect::<Vec<_>>();
+        assert_eq!(piano.hands_needed(&notes(&[48, 55, 64, 72, 76])), 2);
+        assert_eq!(piano.hands_needed(&notes(&[36, 60, 84])), 3);
+
+        let wide = score(&[("bass", &[36]), ("tenor", &[60]), ("soprano", &[84])]);
+        let part = InstrumentPart::new(piano.clone(), wide);
+        let violations = part.violations();
+        assert_eq!(violations.len(), 3);
+        assert_eq!(violations[0].violation, Violation::Stretch { hands_needed: 3, available: 2 });
+        assert!(!part.is_playable());
+
+        let close = InstrumentPart::new(piano, score(&[("left", &[36]), ("right", &[60])]));
+        assert!(close.is_playable());
+        assert_eq!(close.simultaneities().len(), 1);
+    }
+}
\ No newline at end of file
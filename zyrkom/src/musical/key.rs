@@ -0,0 +1,679 @@
+//! Keys: detection from pitch content and Roman-numeral analysis
+//!
+//! Key finding follows Krumhansl–Schmuckler: the pitch-class distribution of
+//! a passage is correlated against a major and a minor key profile rotated to
+//! all twelve tonics, and the best-correlated key wins. The margin over the
+//! runner-up is reported as a confidence. Chords are labelled relative to a
+//! key as diatonic, secondary dominants or leading-tone chords, or chords
+//! borrowed from the parallel mode.
+
+use crate::musical::scale::{Scale, ScaleKind, OCTAVE_SEMITONES};
+use crate::musical::{Chord, ChordQuality, MusicalNote, PitchReference, SpelledPitch};
+use crate::{Result, ZyrkomError};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Tonic names for major keys, spelled the way key signatures are
+const MAJOR_TONIC_NAMES: [&str; 12] = ["C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B"];
+
+/// Tonic names for minor keys, spelled the way key signatures are
+const MINOR_TONIC_NAMES: [&str; 12] = ["C", "C#", "D", "Eb", "E", "F", "F#", "G", "G#", "A", "Bb", "B"];
+
+/// Key mode: the tonic triad is major or minor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// Major key
+    Major,
+    /// Minor key
+    Minor,
+}
+
+impl Mode {
+    /// Lower-case name ("major", "minor")
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Major => "major",
+            Mode::Minor => "minor",
+        }
+    }
+
+    /// Parse a mode name ("major"/"ionian"/"mayor", "minor"/"aeolian"/"menor")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "mayor" => return Some(Mode::Major),
+            "menor" => return Some(Mode::Minor),
+            _ => {}
+        }
+        match ScaleKind::from_name(name)? {
+            ScaleKind::Major => Some(Mode::Major),
+            ScaleKind::NaturalMinor => Some(Mode::Minor),
+            _ => None,
+        }
+    }
+
+    /// Stable numeric identifier committed to in proofs
+    pub fn id(&self) -> u32 {
+        match self {
+            Mode::Major => 0,
+            Mode::Minor => 1,
+        }
+    }
+
+    /// Mode for an identifier produced by `id`
+    pub fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(Mode::Major),
+            1 => Some(Mode::Minor),
+            _ => None,
+        }
+    }
+
+    /// The opposite mode on the same tonic
+    pub fn parallel(&self) -> Self {
+        match self {
+            Mode::Major => Mode::Minor,
+            Mode::Minor => Mode::Major,
+        }
+    }
+
+    /// Scale collections treated as diatonic in this mode
+    ///
+    /// Minor keys also accept the raised sixth and seventh degrees, so the
+    /// major dominant and the leading-tone chord count as diatonic.
+    fn collections(&self) -> &'static [ScaleKind] {
+        match self {
+            Mode::Major => &[ScaleKind::Major],
+            Mode::Minor => &[ScaleKind::NaturalMinor, ScaleKind::HarmonicMinor, ScaleKind::MelodicMinor],
+        }
+    }
+}
+
+/// A key: tonic pitch class and mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    /// Pitch class of the tonic (0 = C)
+    tonic: u8,
+    /// Major or minor
+    mode: Mode,
+}
+
+impl Key {
+    /// Create a key from a tonic pitch class (0 = C) and mode
+    pub fn new(tonic: u8, mode: Mode) -> Result<Self> {
+        if tonic >= OCTAVE_SEMITONES {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Key tonic must be a pitch class below 12, got {}", tonic),
+            });
+        }
+        Ok(Self { tonic, mode })
+    }
+
+    /// Parse "F major", "Bb minor", "FA mayor"; the mode defaults to major
+    pub fn parse(text: &str) -> Result<Self> {
+        let invalid = || ZyrkomError::ParseError {
+            message: format!("Invalid key: {}", text),
+            line: 0,
+        };
+
+        let mut words = text.split_whitespace();
+        let tonic = SpelledPitch::parse(words.next().ok_or_else(invalid)?)?;
+        let mode = match words.next() {
+            Some(name) => Mode::from_name(name).ok_or_else(invalid)?,
+            None => Mode::Major,
+        };
+        if words.next().is_some() {
+            return Err(invalid());
+        }
+
+        Self::new(tonic.pitch_class(), mode)
+    }
+
+    /// Get the tonic pitch class (0 = C)
+    pub fn tonic(&self) -> u8 {
+        self.tonic
+    }
+
+    /// Get the mode
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Same tonic, opposite mode (C major ↔ C minor)
+    pub fn parallel(&self) -> Self {
+        Self { tonic: self.tonic, mode: self.mode.parallel() }
+    }
+
+    /// Same key signature, opposite mode (C major ↔ A minor)
+    pub fn relative(&self) -> Self {
+        let tonic = match self.mode {
+            Mode::Major => (self.tonic + 9) % OCTAVE_SEMITONES,
+            Mode::Minor => (self.tonic + 3) % OCTAVE_SEMITONES,
+        };
+        Self { tonic, mode: self.mode.parallel() }
+    }
+
+    /// The key's scale with its tonic in octave 4
+    pub fn scale(&self, reference: PitchReference) -> Scale {
+        let tonic = MusicalNote::from_midi_at(60 + self.tonic, reference).expect("octave 4 is within the MIDI range");
+        match self.mode {
+            Mode::Major => Scale::major(tonic),
+            Mode::Minor => Scale::minor(tonic),
+        }
+    }
+
+    /// Pitch classes of the key's scale, starting on the tonic
+    pub fn pitch_classes(&self) -> Vec<u8> {
+        self.scale_kind().steps()
+            .iter()
+            .scan(self.tonic, |pc, &step| {
+                let current = *pc;
+                *pc = (*pc + step) % OCTAVE_SEMITONES;
+                Some(current)
+            })
+            .collect()
+    }
+
+    /// Roman-numeral label of a chord in this key, if its quality is recognised
+    ///
+    /// Diatonic chords are tried first, then secondary dominants and
+    /// leading-tone chords of a non-tonic degree, then chords borrowed from
+    /// the parallel mode. Anything else is labelled chromatically by its root.
+    pub fn roman_numeral(&self, chord: &Chord) -> Option<RomanNumeral> {
+        let recognition = chord.recognize()?;
+        let root = (pitch_class(&recognition.root) + OCTAVE_SEMITONES - self.tonic) % OCTAVE_SEMITONES;
+        let classes: BTreeSet<u8> = recognition.quality.pitch_classes()
+            .iter()
+            .map(|pc| (pc + root) % OCTAVE_SEMITONES)
+            .collect();
+
+        let label = |offsets: &[u8], borrowed: bool| {
+            let (degree, alteration) = spell_degree(offsets, root);
+            RomanNumeral {
+                degree,
+                alteration,
+                quality: recognition.quality,
+                inversion: recognition.inversion,
+                secondary: None,
+                borrowed,
+            }
+        };
+
+        if let Some(offsets) = containing_collection(self.mode, &classes) {
+            return Some(label(&offsets, false));
+        }
+
+        if let Some(target) = self.tonicized_degree(recognition.quality, root) {
+            // Applied chords are numbered from the degree they tonicize
+            return Some(RomanNumeral {
+                degree: if is_dominant(recognition.quality) { 5 } else { 7 },
+                alteration: 0,
+                quality: recognition.quality,
+                inversion: recognition.inversion,
+                secondary: Some(Box::new(target)),
+                borrowed: false,
+            });
+        }
+
+        let home = offsets_of(self.scale_kind());
+        if containing_collection(self.mode.parallel(), &classes).is_some() {
+            return Some(label(&home, true));
+        }
+
+        Some(label(&home, false))
+    }
+
+    /// Roman-numeral labels for a chord progression
+    pub fn roman_numerals(&self, chords: &[Chord]) -> Vec<Option<RomanNumeral>> {
+        chords.iter().map(|chord| self.roman_numeral(chord)).collect()
+    }
+
+    /// The diatonic triad a dominant-function chord on `root` resolves to
+    fn tonicized_degree(&self, quality: ChordQuality, root: u8) -> Option<RomanNumeral> {
+        let target = if is_dominant(quality) {
+            (root + 5) % OCTAVE_SEMITONES
+        } else if is_leading_tone(quality) {
+            (root + 1) % OCTAVE_SEMITONES
+        } else {
+            return None;
+        };
+
+        let offsets = offsets_of(self.scale_kind());
+        let degree = offsets.iter().position(|&offset| offset == target)? + 1;
+        if degree == 1 {
+            return None;
+        }
+
+        let triad = self.diatonic_triad(degree);
+        if !matches!(triad, ChordQuality::Major | ChordQuality::Minor) {
+            return None;
+        }
+
+        Some(RomanNumeral {
+            degree: degree as u8,
+            alteration: 0,
+            quality: triad,
+            inversion: 0,
+            secondary: None,
+            borrowed: false,
+        })
+    }
+
+    /// Quality of the triad stacked in thirds on a 1-based degree
+    ///
+    /// In minor the dominant uses the raised leading tone.
+    fn diatonic_triad(&self, degree: usize) -> ChordQuality {
+        let kind = match (self.mode, degree) {
+            (Mode::Minor, 5) => ScaleKind::HarmonicMinor,
+            _ => self.scale_kind(),
+        };
+        let offsets = offsets_of(kind);
+        let at = |index: usize| offsets[(degree - 1 + index) % offsets.len()];
+        let third = (at(2) + OCTAVE_SEMITONES - at(0)) % OCTAVE_SEMITONES;
+        let fifth = (at(4) + OCTAVE_SEMITONES - at(0)) % OCTAVE_SEMITONES;
+
+        match (third, fifth) {
+            (4, 7) => ChordQuality::Major,
+            (3, 7) => ChordQuality::Minor,
+            (3, 6) => ChordQuality::Diminished,
+            _ => ChordQuality::Augmented,
+        }
+    }
+
+    fn scale_kind(&self) -> ScaleKind {
+        match self.mode {
+            Mode::Major => ScaleKind::Major,
+            Mode::Minor => ScaleKind::NaturalMinor,
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = match self.mode {
+            Mode::Major => &MAJOR_TONIC_NAMES,
+            Mode::Minor => &MINOR_TONIC_NAMES,
+        };
+        write!(f, "{} {}", names[self.tonic as usize], self.mode.name())
+    }
+}
+
+/// Published key profiles used to score each candidate key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyProfile {
+    /// Krumhansl & Kessler probe-tone ratings (1982)
+    KrumhanslSchmuckler,
+    /// Temperley's corpus-derived profiles (2007)
+    Temperley,
+}
+
+impl KeyProfile {
+    /// Weights for each pitch class above a major tonic
+    pub fn major(&self) -> [f64; 12] {
+        match self {
+            KeyProfile::KrumhanslSchmuckler => {
+                [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88]
+            }
+            KeyProfile::Temperley => {
+                [0.748, 0.060, 0.488, 0.082, 0.670, 0.460, 0.096, 0.715, 0.104, 0.366, 0.057, 0.400]
+            }
+        }
+    }
+
+    /// Weights for each pitch class above a minor tonic
+    pub fn minor(&self) -> [f64; 12] {
+        match self {
+            KeyProfile::KrumhanslSchmuckler => {
+                [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17]
+            }
+            KeyProfile::Temperley => {
+                [0.712, 0.084, 0.474, 0.618, 0.049, 0.460, 0.105, 0.747, 0.404, 0.067, 0.133, 0.330]
+            }
+        }
+    }
+
+    /// All 24 keys ranked by correlation with a pitch-class histogram
+    ///
+    /// Empty if the histogram is flat, since no key is preferred then.
+    pub fn rank(&self, histogram: &[f64; 12]) -> Vec<(Key, f64)> {
+        let mut ranked: Vec<(Key, f64)> = [Mode::Major, Mode::Minor]
+            .into_iter()
+            .flat_map(|mode| (0..OCTAVE_SEMITONES).map(move |tonic| Key { tonic, mode }))
+            .filter_map(|key| {
+                let profile = match key.mode {
+                    Mode::Major => self.major(),
+                    Mode::Minor => self.minor(),
+                };
+                let rotated: Vec<f64> = (0..12)
+                    .map(|pc| profile[(pc + 12 - key.tonic as usize) % 12])
+                    .collect();
+                pearson(histogram, &rotated).map(|correlation| (key, correlation))
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+    }
+
+    /// Most likely key for a pitch-class histogram
+    pub fn estimate(&self, histogram: &[f64; 12]) -> Option<KeyEstimate> {
+        let ranked = self.rank(histogram);
+        let (key, correlation) = *ranked.first()?;
+        let runner_up = ranked.get(1).map_or(-1.0, |&(_, c)| c);
+        let confidence = if correlation > 0.0 {
+            ((correlation - runner_up) / correlation).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        Some(KeyEstimate { key, correlation, confidence })
+    }
+}
+
+/// Result of key finding
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyEstimate {
+    /// The best-correlated key
+    pub key: Key,
+    /// Pearson correlation between the passage and the key's profile
+    pub correlation: f64,
+    /// Margin over the runner-up key as a fraction of the best correlation (0–1)
+    pub confidence: f64,
+}
+
+/// Pitch-class histogram of notes, each weighted (e.g. by duration)
+pub fn pitch_class_histogram(notes: &[(MusicalNote, f64)]) -> [f64; 12] {
+    let mut histogram = [0.0; 12];
+    for (note, weight) in notes {
+        histogram[pitch_class(note) as usize] += weight;
+    }
+    histogram
+}
+
+/// Most likely key of a melody, counting every note once
+pub fn detect_key(notes: &[MusicalNote], profile: KeyProfile) -> Option<KeyEstimate> {
+    let weighted: Vec<(MusicalNote, f64)> = notes.iter().map(|&note| (note, 1.0)).collect();
+    profile.estimate(&pitch_class_histogram(&weighted))
+}
+
+/// Most likely key of a chord sequence, counting every chord tone once
+pub fn detect_key_of_chords(chords: &[Chord], profile: KeyProfile) -> Option<KeyEstimate> {
+    let notes: Vec<MusicalNote> = chords.iter().flat_map(|chord| chord.notes().iter().copied()).collect();
+    detect_key(&notes, profile)
+}
+
+/// A chord's function in a key, such as "V65/V", "bVI" or "vii°7"
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomanNumeral {
+    /// Scale degree of the root (1–7)
+    degree: u8,
+    /// Chromatic alteration of the root degree (-1 = flat, +1 = sharp)
+    alteration: i8,
+    /// Recognised chord quality
+    quality: ChordQuality,
+    /// Which chord tone is in the bass (0 = root position)
+    inversion: usize,
+    /// For applied chords, the diatonic triad being tonicized
+    secondary: Option<Box<RomanNumeral>>,
+    /// Whether the chord is borrowed from the parallel mode
+    borrowed: bool,
+}
+
+impl RomanNumeral {
+    /// Get the scale degree of the root (relative to the tonicized degree for applied chords)
+    pub fn degree(&self) -> u8 {
+        self.degree
+    }
+
+    /// Get the chromatic alteration of the root degree
+    pub fn alteration(&self) -> i8 {
+        self.alteration
+    }
+
+    /// Get the chord quality
+    pub fn quality(&self) -> ChordQuality {
+        self.quality
+    }
+
+    /// Get the inversion (0 = root position)
+    pub fn inversion(&self) -> usize {
+        self.inversion
+    }
+
+    /// The tonicized degree, for secondary dominants and leading-tone chords
+    pub fn secondary(&self) -> Option<&RomanNumeral> {
+        self.secondary.as_deref()
+    }
+
+    /// Whether the chord is borrowed from the parallel mode
+    pub fn is_borrowed(&self) -> bool {
+        self.borrowed
+    }
+}
+
+impl fmt::Display for RomanNumeral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+        let accidental = match self.alteration {
+            a if a < 0 => "b",
+            a if a > 0 => "#",
+            _ => "",
+        };
+        let numeral = NUMERALS[(self.degree as usize - 1) % 7];
+        let classes = self.quality.pitch_classes();
+        let numeral = if classes.contains(&3) && !classes.contains(&4) {
+            numeral.to_lowercase()
+        } else {
+            numeral.to_string()
+        };
+
+        write!(f, "{}{}{}", accidental, numeral, figures(self.quality, self.inversion))?;
+        if let Some(target) = &self.secondary {
+            write!(f, "/{}", target)?;
+        }
+        Ok(())
+    }
+}
+
+/// Quality marker plus figured-bass inversion figures
+///
+/// Sixth, extended and altered chords show a chord-symbol suffix instead.
+fn figures(quality: ChordQuality, inversion: usize) -> String {
+    const TRIAD: [&str; 3] = ["", "6", "64"];
+    const SEVENTH: [&str; 4] = ["7", "65", "43", "42"];
+
+    let (marker, figures): (&str, &[&str]) = match quality {
+        ChordQuality::Major | ChordQuality::Minor => ("", &TRIAD),
+        ChordQuality::Diminished => ("°", &TRIAD),
+        ChordQuality::Augmented => ("+", &TRIAD),
+        ChordQuality::DominantSeventh | ChordQuality::MinorSeventh => ("", &SEVENTH),
+        ChordQuality::MajorSeventh | ChordQuality::MinorMajorSeventh => ("maj", &SEVENTH),
+        ChordQuality::HalfDiminishedSeventh => ("ø", &SEVENTH),
+        ChordQuality::DiminishedSeventh => ("°", &SEVENTH),
+        ChordQuality::AugmentedSeventh => ("+", &SEVENTH),
+        ChordQuality::MajorSixth | ChordQuality::MinorSixth => return "add6".to_string(),
+        other => return other.symbol().trim_start_matches('m').to_string(),
+    };
+
+    format!("{}{}", marker, figures.get(inversion).copied().unwrap_or(""))
+}
+
+/// Chords that act as a dominant (V, V7, V9, V7b9)
+fn is_dominant(quality: ChordQuality) -> bool {
+    matches!(
+        quality,
+        ChordQuality::Major
+            | ChordQuality::DominantSeventh
+            | ChordQuality::DominantNinth
+            | ChordQuality::DominantSeventhFlatNine
+    )
+}
+
+/// Chords that act as a leading-tone chord (vii°, viiø7, vii°7)
+fn is_leading_tone(quality: ChordQuality) -> bool {
+    matches!(
+        quality,
+        ChordQuality::Diminished | ChordQuality::HalfDiminishedSeventh | ChordQuality::DiminishedSeventh
+    )
+}
+
+/// Semitone offsets of a named scale's degrees above its tonic
+fn offsets_of(kind: ScaleKind) -> Vec<u8> {
+    kind.steps()
+        .iter()
+        .scan(0, |offset, &step| {
+            let current = *offset;
+            *offset += step;
+            Some(current)
+        })
+        .collect()
+}
+
+/// Offsets of the first of the mode's collections holding every pitch class
+fn containing_collection(mode: Mode, classes: &BTreeSet<u8>) -> Option<Vec<u8>> {
+    mode.collections()
+        .iter()
+        .map(|&kind| offsets_of(kind))
+        .find(|offsets| classes.iter().all(|pc| offsets.contains(pc)))
+}
+
+/// 1-based degree and alteration naming a semitone offset above the tonic
+///
+/// Offsets outside the scale are spelled as a lowered degree where possible
+/// (bVI, bVII), otherwise as a raised one.
+fn spell_degree(offsets: &[u8], offset: u8) -> (u8, i8) {
+    let find = |target: u8| offsets.iter().position(|&o| o == target % OCTAVE_SEMITONES);
+    if let Some(index) = find(offset) {
+        (index as u8 + 1, 0)
+    } else if let Some(index) = find(offset + 1) {
+        (index as u8 + 1, -1)
+    } else {
+        let index = find(offset + OCTAVE_SEMITONES - 1).unwrap_or(0);
+        (index as u8 + 1, 1)
+    }
+}
+
+fn pitch_class(note: &MusicalNote) -> u8 {
+    (note.exact_midi().round() as i32).rem_euclid(OCTAVE_SEMITONES as i32) as u8
+}
+
+/// Pearson correlation, or `None` if either series is constant
+fn pearson(x: &[f64; 12], y: &[f64]) -> Option<f64> {
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let (mx, my) = (mean(x), mean(y));
+
+    let covariance: f64 = x.iter().zip(y).map(|(a, b)| (a - mx) * (b - my)).sum();
+    let spread_x: f64 = x.iter().map(|a| (a - mx).powi(2)).sum::<f64>().sqrt();
+    let spread_y: f64 = y.iter().map(|b| (b - my).powi(2)).sum::<f64>().sqrt();
+
+    if spread_x == 0.0 || spread_y == 0.0 {
+        return None;
+    }
+    Some(covariance / (spread_x * spread_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes(midi: &[u8]) -> Vec<MusicalNote> {
+        midi.iter().map(|&m| MusicalNote::from_midi(m).unwrap()).collect()
+    }
+
+    fn chord(midi: &[u8]) -> Chord {
+        let notes = notes(midi);
+        Chord::from_voicing(notes[0], notes).unwrap()
+    }
+
+    fn label(key: &Key, midi: &[u8]) -> String {
+        key.roman_numeral(&chord(midi)).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse_and_display_keys() {
+        let f_major = Key::parse("F major").unwrap();
+        assert_eq!(f_major, Key::new(5, Mode::Major).unwrap());
+        assert_eq!(f_major.to_string(), "F major");
+        assert_eq!(Key::parse("FA mayor").unwrap(), f_major);
+        assert_eq!(Key::parse("Bb").unwrap().to_string(), "Bb major");
+        assert_eq!(Key::parse("c# minor").unwrap().to_string(), "C# minor");
+        assert_eq!(f_major.relative().to_string(), "D minor");
+        assert_eq!(f_major.parallel().to_string(), "F minor");
+        assert_eq!(f_major.pitch_classes(), vec![5, 7, 9, 10, 0, 2, 4]);
+        assert!(Key::parse("F dorian").is_err());
+        assert!(Key::parse("").is_err());
+        assert!(Key::new(12, Mode::Major).is_err());
+    }
+
+    #[test]
+    fn test_detect_key_of_melody() {
+        // Opening of the Marcha Real: FA DO LA FA DO* SIb LA SOL FA FA MI RE DO
+        let melody = notes(&[65, 60, 69, 65, 72, 70, 69, 67, 65, 65, 64, 62, 60]);
+
+        for profile in [KeyProfile::KrumhanslSchmuckler, KeyProfile::Temperley] {
+            let estimate = detect_key(&melody, profile).unwrap();
+            assert_eq!(estimate.key, Key::new(5, Mode::Major).unwrap());
+            assert!(estimate.correlation > 0.7);
+            assert!(estimate.confidence > 0.0 && estimate.confidence <= 1.0);
+        }
+
+        // A minor-third-heavy line with a raised leading tone reads as minor
+        let a_minor = notes(&[57, 60, 64, 62, 60, 59, 57, 56, 57]);
+        let estimate = detect_key(&a_minor, KeyProfile::KrumhanslSchmuckler).unwrap();
+        assert_eq!(estimate.key.to_string(), "A minor");
+
+        assert!(detect_key(&[], KeyProfile::Temperley).is_none());
+    }
+
+    #[test]
+    fn test_detect_key_of_chords() {
+        // ii - V7 - I in Bb
+        let progression = [chord(&[60, 63, 67]), chord(&[65, 69, 72, 75]), chord(&[58, 62, 65])];
+        let estimate = detect_key_of_chords(&progression, KeyProfile::Temperley).unwrap();
+        assert_eq!(estimate.key.to_string(), "Bb major");
+    }
+
+    #[test]
+    fn test_diatonic_roman_numerals() {
+        let c_major = Key::new(0, Mode::Major).unwrap();
+        assert_eq!(label(&c_major, &[60, 64, 67]), "I");
+        assert_eq!(label(&c_major, &[62, 65, 69]), "ii");
+        assert_eq!(label(&c_major, &[64, 67, 72]), "I6");
+        assert_eq!(label(&c_major, &[67, 71, 74, 77]), "V7");
+        assert_eq!(label(&c_major, &[65, 67, 71, 74]), "V42");
+        assert_eq!(label(&c_major, &[71, 74, 77]), "vii°");
+        assert_eq!(label(&c_major, &[65, 69, 72, 76]), "IVmaj7");
+
+        let a_minor = Key::new(9, Mode::Minor).unwrap();
+        assert_eq!(label(&a_minor, &[64, 68, 71]), "V");
+        assert_eq!(label(&a_minor, &[68, 71, 74, 77]), "vii°7");
+        assert_eq!(label(&a_minor, &[64, 67, 71]), "v");
+        assert_eq!(label(&a_minor, &[60, 64, 67]), "III");
+    }
+
+    #[test]
+    fn test_secondary_and_borrowed_chords() {
+        let c_major = Key::new(0, Mode::Major).unwrap();
+
+        let v_of_v = c_major.roman_numeral(&chord(&[62, 66, 69, 72])).unwrap();
+        assert_eq!(v_of_v.to_string(), "V7/V");
+        assert_eq!(v_of_v.secondary().unwrap().degree(), 5);
+        assert!(!v_of_v.is_borrowed());
+
+        assert_eq!(label(&c_major, &[66, 69, 72, 74]), "V65/V");
+        assert_eq!(label(&c_major, &[64, 68, 71]), "V/vi");
+        assert_eq!(label(&c_major, &[60, 64, 67, 70]), "V7/IV");
+        assert_eq!(label(&c_major, &[61, 64, 67, 70]), "vii°7/ii");
+
+        let flat_six = c_major.roman_numeral(&chord(&[56, 60, 63])).unwrap();
+        assert_eq!(flat_six.to_string(), "bVI");
+        assert!(flat_six.is_borrowed());
+        assert_eq!(label(&c_major, &[65, 68, 72]), "iv");
+        assert_eq!(label(&c_major, &[58, 62, 65]), "bVII");
+
+        // Neapolitan: neither applied nor borrowed from the parallel minor
+        let neapolitan = c_major.roman_numeral(&chord(&[61, 65, 68])).unwrap();
+        assert_eq!(neapolitan.to_string(), "bII");
+        assert!(!neapolitan.is_borrowed());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,549 @@
+//! Melodies and scores: notes placed in time
+//!
+//! A `NoteEvent` is a pitch with an onset, a duration and a velocity. Onsets
+//! and durations are exact fractions of a whole note counted from the start
+//! of the passage, so slicing at a barline never drifts. A `Melody` is one
+//! line of non-overlapping events under a time signature, with silence
+//! between events read as rests; a `Score` gathers named `Voice`s that share
+//! a meter and tempo.
+
+use crate::musical::{MusicalNote, NoteDuration, RhythmicValue, Tempo, TimeSignature};
+use crate::musical::rhythm::checked_lcm;
+use crate::{Result, ZyrkomError};
+use std::ops::Range;
+
+/// MIDI velocity used when none is given (mezzo-forte)
+pub const DEFAULT_VELOCITY: u8 = 80;
+
+/// Highest MIDI velocity
+const MAX_VELOCITY: u8 = 127;
+
+/// A sounding note: pitch, onset, duration and velocity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteEvent {
+    /// Pitch of the note
+    pitch: MusicalNote,
+    /// Start time from the beginning of the passage
+    onset: NoteDuration,
+    /// How long the note sounds
+    duration: NoteDuration,
+    /// MIDI velocity (1–127)
+    velocity: u8,
+}
+
+impl NoteEvent {
+    /// Create a note event; the duration must be positive and the velocity 1–127
+    pub fn new(pitch: MusicalNote, onset: NoteDuration, duration: NoteDuration, velocity: u8) -> Result<Self> {
+        if duration == NoteDuration::zero() {
+            return Err(ZyrkomError::ConstraintError {
+                context: "Note event duration must be positive".to_string(),
+            });
+        }
+        if velocity == 0 || velocity > MAX_VELOCITY {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!("Velocity must be between 1 and {}, got {}", MAX_VELOCITY, velocity),
+            });
+        }
+
+        Ok(Self { pitch, onset, duration, velocity })
+    }
+
+    /// Get the pitch
+    pub fn pitch(&self) -> MusicalNote {
+        self.pitch
+    }
+
+    /// Get the onset from the start of the passage
+    pub fn onset(&self) -> NoteDuration {
+        self.onset
+    }
+
+    /// Get the sounding duration
+    pub fn duration(&self) -> NoteDuration {
+        self.duration
+    }
+
+    /// Get the MIDI velocity
+    pub fn velocity(&self) -> u8 {
+        self.velocity
+    }
+
+    /// Time at which the note stops sounding
+    pub fn end(&self) -> Result<NoteDuration> {
+        self.onset.checked_add(&self.duration)
+    }
+}
+
+/// A single melodic line under one time signature
+#[derive(Debug, Clone, PartialEq)]
+pub struct Melody {
+    /// Meter the line is measured in
+    time_signature: TimeSignature,
+    /// Events ordered by onset, never overlapping
+    events: Vec<NoteEvent>,
+    /// Total length, including any trailing rest
+    length: NoteDuration,
+}
+
+impl Melody {
+    /// Create an empty melody
+    pub fn new(time_signature: TimeSignature) -> Self {
+        Self {
+            time_signature,
+            events: Vec::new(),
+            length: NoteDuration::zero(),
+        }
+    }
+
+    /// Create a melody from consecutive notes and their rhythmic values
+    pub fn from_notes(time_signature: TimeSignature, notes: &[(MusicalNote, RhythmicValue)]) -> Result<Self> {
+        let mut melody = Self::new(time_signature);
+        for &(pitch, value) in notes {
+            melody.push_note(pitch, value)?;
+        }
+        Ok(melody)
+    }
+
+    /// Append a note at the end of the line
+    pub fn push_note(&mut self, pitch: MusicalNote, value: RhythmicValue) -> Result<()> {
+        let duration = value.duration();
+        let length = self.length.checked_add(&duration)?;
+        self.events.push(NoteEvent {
+            pitch,
+            onset: self.length,
+            duration,
+            velocity: DEFAULT_VELOCITY,
+        });
+        self.length = length;
+        Ok(())
+    }
+
+    /// Append a rest at the end of the line
+    pub fn push_rest(&mut self, value: RhythmicValue) -> Result<()> {
+        self.length = self.length.checked_add(&value.duration())?;
+        Ok(())
+    }
+
+    /// Add an event that starts no earlier than the line currently ends
+    pub fn push_event(&mut self, event: NoteEvent) -> Result<()> {
+        if let Some(last) = self.events.last() {
+            let last_end = last.end()?;
+            if event.onset < last_end {
+                return Err(ZyrkomError::ConstraintError {
+                    context: format!("Note at {} overlaps the previous note ending at {}", event.onset, last_end),
+                });
+            }
+        }
+
+        self.length = self.length.max(event.end()?);
+        self.events.push(event);
+        Ok(())
+    }
+
+    /// Get the time signature
+    pub fn time_signature(&self) -> TimeSignature {
+        self.time_signature
+    }
+
+    /// Get the events in time order
+    pub fn events(&self) -> &[NoteEvent] {
+        &self.events
+    }
+
+    /// Pitches in time order
+    pub fn pitches(&self) -> Vec<MusicalNote> {
+        self.events.iter().map(|event| event.pitch).collect()
+    }
+
+    /// Total length, including any trailing rest
+    pub fn duration(&self) -> NoteDuration {
+        self.length
+    }
+
+    /// Number of measures the melody spans (a partial last measure counts)
+    pub fn measure_count(&self) -> usize {
+        let measure = self.time_signature.measure_duration();
+        let spanned = self.length.numerator() as u128 * measure.denominator() as u128;
+        let per_measure = self.length.denominator() as u128 * measure.numerator() as u128;
+        spanned.div_ceil(per_measure) as usize
+    }
+
+    /// Smallest tick resolution (ticks per whole note) on which every onset,
+    /// every duration and the measure length land exactly
+    pub fn ticks_per_whole(&self) -> Result<u64> {
+        self.events
+            .iter()
+            .flat_map(|event| [event.onset.denominator(), event.duration.denominator()])
+            .try_fold(self.time_signature.measure_duration().denominator(), checked_lcm)
+    }
+
+    /// Length of the melody in milliseconds at a tempo
+    pub fn duration_ms(&self, tempo: &Tempo) -> f64 {
+        tempo.duration_ms(self.length)
+    }
+
+    /// Transpose every note by a number of semitones, keeping microtonal offsets
+    pub fn transpose(&self, semitones: i32) -> Result<Self> {
+        self.map_pitches(|pitch| shift(pitch, semitones as f64))
+    }
+
+    /// Mirror every pitch around an axis (melodic inversion)
+    pub fn invert(&self, axis: &MusicalNote) -> Result<Self> {
+        self.map_pitches(|pitch| shift(pitch, 2.0 * (axis.exact_midi() - pitch.exact_midi())))
+    }
+
+    /// Play the line backwards, keeping its rests in mirrored positions
+    pub fn retrograde(&self) -> Result<Self> {
+        let events = self
+            .events
+            .iter()
+            .rev()
+            .map(|event| {
+                Ok(NoteEvent {
+                    onset: self.length.saturating_sub(&event.end()?)?,
+                    ..*event
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { events, ..self.clone() })
+    }
+
+    /// Multiply every onset and duration by a factor (augmentation)
+    pub fn augment(&self, factor: u64) -> Result<Self> {
+        self.scale_time(factor, 1)
+    }
+
+    /// Divide every onset and duration by a factor (diminution)
+    pub fn diminish(&self, factor: u64) -> Result<Self> {
+        self.scale_time(1, factor)
+    }
+
+    /// The part of the melody inside a range of measures (0-based)
+    ///
+    /// Onsets are re-measured from the start of the first measure; notes
+    /// crossing either edge are cut at the barline.
+    pub fn measures(&self, range: Range<usize>) -> Result<Self> {
+        let measure = self.time_signature.measure_duration();
+        let start = measure.scale(range.start as u64, 1)?;
+        let end = measure.scale(range.end as u64, 1)?.min(self.length);
+
+        let mut events = Vec::new();
+        for event in &self.events {
+            let event_end = event.end()?;
+            if event.onset >= end || event_end <= start {
+                continue;
+            }
+            let onset = event.onset.max(start);
+            events.push(NoteEvent {
+                onset: onset.saturating_sub(&start)?,
+                duration: event_end.min(end).saturating_sub(&onset)?,
+                ..*event
+            });
+        }
+
+        Ok(Self {
+            time_signature: self.time_signature,
+            events,
+            length: end.saturating_sub(&start)?,
+        })
+    }
+
+    /// A single measure (0-based)
+    pub fn measure(&self, index: usize) -> Result<Self> {
+        self.measures(index..index + 1)
+    }
+
+    fn map_pitches(&self, map: impl Fn(&MusicalNote) -> Result<MusicalNote>) -> Result<Self> {
+        let events = self
+            .events
+            .iter()
+            .map(|event| Ok(NoteEvent { pitch: map(&event.pitch)?, ..*event }))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { events, ..self.clone() })
+    }
+
+    fn scale_time(&self, numerator: u64, denominator: u64) -> Result<Self> {
+        if numerator == 0 || denominator == 0 {
+            return Err(ZyrkomError::ConstraintError {
+                context: "Augmentation and diminution factors must be positive".to_string(),
+            });
+        }
+
+        let events = self
+            .events
+            .iter()
+            .map(|event| {
+                Ok(NoteEvent {
+                    onset: event.onset.scale(numerator, denominator)?,
+                    duration: event.duration.scale(numerator, denominator)?,
+                    ..*event
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            time_signature: self.time_signature,
+            events,
+            length: self.length.scale(numerator, denominator)?,
+        })
+    }
+}
+
+/// A named part of a score
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voice {
+    /// Part name (e.g. "soprano", "piano right hand")
+    name: String,
+    /// The line this part plays
+    melody: Melody,
+}
+
+impl Voice {
+    /// Create a named voice
+    pub fn new(name: &str, melody: Melody) -> Self {
+        Self {
+            name: name.to_string(),
+            melody,
+        }
+    }
+
+    /// Get the part name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the line
+    pub fn melody(&self) -> &Melody {
+        &self.melody
+    }
+}
+
+/// Several voices sharing a time signature and tempo
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    /// Meter shared by every voice
+    time_signature: TimeSignature,
+    /// Performance tempo, if declared
+    tempo: Option<Tempo>,
+    /// Parts in score order (top to bottom)
+    voices: Vec<Voice>,
+}
+
+impl Score {
+    /// Create an empty score
+    pub fn new(time_signature: TimeSignature) -> Self {
+        Self {
+            time_signature,
+            tempo: None,
+            voices: Vec::new(),
+        }
+    }
+
+    /// Attach a performance tempo
+    pub fn with_tempo(mut self, tempo: Tempo) -> Self {
+        self.tempo = Some(tempo);
+        self
+    }
+
+    /// Add a voice; it must use the score's time signature and a new name
+    pub fn add_voice(&mut self, voice: Voice) -> Result<()> {
+        if voice.melody.time_signature != self.time_signature {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!(
+                    "Voice '{}' is in {} but the score is in {}",
+                    voice.name, voice.melody.time_signature, self.time_signature
+                ),
+            });
+        }
+        if self.voice(&voice.name).is_some() {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!("Score already has a voice named '{}'", voice.name),
+            });
+        }
+
+        self.voices.push(voice);
+        Ok(())
+    }
+
+    /// Get the time signature
+    pub fn time_signature(&self) -> TimeSignature {
+        self.time_signature
+    }
+
+    /// Get the declared tempo
+    pub fn tempo(&self) -> Option<Tempo> {
+        self.tempo
+    }
+
+    /// Get the voices in score order
+    pub fn voices(&self) -> &[Voice] {
+        &self.voices
+    }
+
+    /// Find a voice by name
+    pub fn voice(&self, name: &str) -> Option<&Voice> {
+        self.voices.iter().find(|voice| voice.name == name)
+    }
+
+    /// Length of the longest voice
+    pub fn duration(&self) -> NoteDuration {
+        self.voices
+            .iter()
+            .map(|voice| voice.melody.duration())
+            .max()
+            .unwrap_or_else(NoteDuration::zero)
+    }
+
+    /// Transpose every voice by a number of semitones
+    pub fn transpose(&self, semitones: i32) -> Result<Self> {
+        self.map_voices(|melody| melody.transpose(semitones))
+    }
+
+    /// The part of every voice inside a range of measures (0-based)
+    pub fn measures(&self, range: Range<usize>) -> Result<Self> {
+        self.map_voices(|melody| melody.measures(range.clone()))
+    }
+
+    fn map_voices(&self, map: impl Fn(&Melody) -> Result<Melody>) -> Result<Self> {
+        let voices = self
+            .voices
+            .iter()
+            .map(|voice| Ok(Voice { name: voice.name.clone(), melody: map(&voice.melody)? }))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { voices, ..self.clone() })
+    }
+}
+
+/// Move a note by a (possibly fractional) number of semitones
+fn shift(note: &MusicalNote, semitones: f64) -> Result<MusicalNote> {
+    let target = note.exact_midi() + semitones;
+    let midi = target.round();
+    let midi_number = u8::try_from(midi as i64).map_err(|_| ZyrkomError::PhysicsError {
+        details: format!("Transposed pitch {:.2} is outside the MIDI range", target),
+    })?;
+    MusicalNote::from_midi_cents_at(midi_number, (target - midi) * 100.0, note.reference())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::musical::NoteValue;
+
+    fn q() -> RhythmicValue {
+        RhythmicValue::new(NoteValue::Quarter)
+    }
+
+    fn e() -> RhythmicValue {
+        RhythmicValue::new(NoteValue::Eighth)
+    }
+
+    fn melody(notes: &[(u8, RhythmicValue)]) -> Melody {
+        let notes: Vec<(MusicalNote, RhythmicValue)> =
+            notes.iter().map(|&(m, v)| (MusicalNote::from_midi(m).unwrap(), v)).collect();
+        Melody::from_notes(TimeSignature::common(), &notes).unwrap()
+    }
+
+    fn midi(melody: &Melody) -> Vec<u8> {
+        melody.pitches().iter().map(|n| n.midi_number()).collect()
+    }
+
+    fn whole(numerator: u64, denominator: u64) -> NoteDuration {
+        NoteDuration::new(numerator, denominator).unwrap()
+    }
+
+    #[test]
+    fn test_events_and_timeline() {
+        let mut line = melody(&[(65, q()), (60, q())]);
+        line.push_rest(q()).unwrap();
+        line.push_note(MusicalNote::from_midi(69).unwrap(), q()).unwrap();
+
+        assert_eq!(line.events()[2].onset(), whole(3, 4));
+        assert_eq!(line.duration(), whole(1, 1));
+        assert_eq!(line.measure_count(), 1);
+        assert_eq!(line.ticks_per_whole().unwrap(), 4);
+
+        let overlapping = NoteEvent::new(MusicalNote::from_midi(67).unwrap(), whole(7, 8), whole(1, 4), 90).unwrap();
+        assert!(line.push_event(overlapping).is_err());
+        let later = NoteEvent::new(MusicalNote::from_midi(67).unwrap(), whole(5, 4), whole(1, 4), 90).unwrap();
+        line.push_event(later).unwrap();
+        assert_eq!(line.duration(), whole(3, 2));
+        assert_eq!(line.measure_count(), 2);
+
+        assert!(NoteEvent::new(MusicalNote::from_midi(60).unwrap(), whole(0, 1), whole(0, 1), 80).is_err());
+        assert!(NoteEvent::new(MusicalNote::from_midi(60).unwrap(), whole(0, 1), whole(1, 4), 0).is_err());
+    }
+
+    #[test]
+    fn test_pitch_transformations() {
+        let line = melody(&[(60, q()), (64, q()), (67, q()), (65, q())]);
+
+        assert_eq!(midi(&line.transpose(5).unwrap()), vec![65, 69, 72, 70]);
+        assert_eq!(midi(&line.invert(&MusicalNote::from_midi(60).unwrap()).unwrap()), vec![60, 56, 53, 55]);
+        assert!(line.transpose(100).is_err());
+
+        let quarter_tone = MusicalNote::from_midi_cents_at(60, 50.0, Default::default()).unwrap();
+        let shifted = melody(&[(60, q())]).invert(&quarter_tone).unwrap();
+        assert_eq!(shifted.pitches()[0].exact_midi(), 61.0);
+    }
+
+    #[test]
+    fn test_retrograde_keeps_rests_mirrored() {
+        let mut line = melody(&[(60, q()), (62, e())]);
+        line.push_rest(e()).unwrap();
+        line.push_note(MusicalNote::from_midi(64).unwrap(), RhythmicValue::new(NoteValue::Half)).unwrap();
+
+        let reversed = line.retrograde().unwrap();
+        assert_eq!(midi(&reversed), vec![64, 62, 60]);
+        let onsets: Vec<NoteDuration> = reversed.events().iter().map(|e| e.onset()).collect();
+        assert_eq!(onsets, vec![whole(0, 1), whole(5, 8), whole(3, 4)]);
+        assert_eq!(reversed.retrograde().unwrap(), line);
+    }
+
+    #[test]
+    fn test_augmentation_and_diminution() {
+        let line = melody(&[(60, q()), (62, e()), (64, e())]);
+
+        let augmented = line.augment(2).unwrap();
+        assert_eq!(augmented.events()[1].onset(), whole(1, 2));
+        assert_eq!(augmented.events()[2].duration(), whole(1, 4));
+        assert_eq!(augmented.duration(), whole(1, 1));
+        assert_eq!(augmented.diminish(2).unwrap(), line);
+        assert!(line.diminish(0).is_err());
+    }
+
+    #[test]
+    fn test_slice_by_measure() {
+        // A half note tied across the first barline
+        let line = melody(&[(60, q()), (62, q()), (64, q()), (65, RhythmicValue::new(NoteValue::Half)), (67, q())]);
+        assert_eq!(line.measure_count(), 2);
+
+        let second = line.measure(1).unwrap();
+        assert_eq!(midi(&second), vec![65, 67]);
+        assert_eq!(second.events()[0].onset(), whole(0, 1));
+        assert_eq!(second.events()[0].duration(), whole(1, 4));
+        assert_eq!(second.duration(), whole(1, 2));
+
+        let first = line.measure(0).unwrap();
+        assert_eq!(first.events()[3].duration(), whole(1, 4));
+        assert_eq!(line.measures(0..2).unwrap(), line);
+    }
+
+    #[test]
+    fn test_score_voices() {
+        let mut score = Score::new(TimeSignature::common());
+        score.add_voice(Voice::new("soprano", melody(&[(72, q()), (71, q())]))).unwrap();
+        score.add_voice(Voice::new("bass", melody(&[(48, RhythmicValue::new(NoteValue::Whole))]))).unwrap();
+        assert!(score.add_voice(Voice::new("bass", melody(&[(50, q())]))).is_err());
+
+        let waltz = Melody::new(TimeSignature::new(3, 4).unwrap());
+        assert!(score.add_voice(Voice::new("alto", waltz)).is_err());
+
+        assert_eq!(score.duration(), whole(1, 1));
+        let up = score.transpose(2).unwrap();
+        assert_eq!(midi(up.voice("soprano").unwrap().melody()), vec![74, 73]);
+        assert_eq!(score.measures(0..1).unwrap().voices().len(), 2);
+    }
+}
\ No newline at end of file
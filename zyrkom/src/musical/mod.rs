@@ -6,10 +6,141 @@
 
 /// Physics constants and musical structures implementation
 pub mod physics;
+/// Note values, tuplets, time signatures and tempo
+pub mod rhythm;
+/// Species counterpoint rule checking
+pub mod counterpoint;
+/// Voice assignment and motion between consecutive chords
+pub mod voice_leading;
+/// Tuning systems and temperaments
+pub mod tuning;
+/// Scala `.scl` and `.kbm` tuning files
+pub mod scala;
+/// Spelled pitches and intervals (letter, accidental, octave)
+pub mod spelling;
+/// Scales built from a tonic and a step pattern
+pub mod scale;
+/// Chord qualities, inversions, voicings and recognition
+pub mod chords;
+/// Pitch-class sets, set classes and Forte numbers
+pub mod pitch_class;
+/// Consonance and roughness metrics
+pub mod consonance;
+/// Key finding and Roman-numeral analysis
+pub mod key;
+/// Note events in time, melodies, voices and scores
+pub mod melody;
+/// Neo-Riemannian P/L/R operations and the Tonnetz
+pub mod neo_riemannian;
+/// Partials, inharmonicity and instrument timbre presets
+pub mod timbre;
+/// Instrument ranges, transpositions and playability
+pub mod instrument;
 
 pub use physics::{
     MusicalInterval,
     MusicalNote, 
     Chord,
     constants,
-}; 
\ No newline at end of file
+};
+
+pub use rhythm::{
+    NoteDuration,
+    NoteValue,
+    Tuplet,
+    RhythmicValue,
+    TimeSignature,
+    Tempo,
+    TempoBounds,
+    MeasureSequence,
+};
+
+pub use counterpoint::{
+    CounterpointAnalysis,
+    CounterpointRule,
+    RuleCheck,
+};
+
+pub use voice_leading::VoiceLeading;
+
+pub use tuning::{
+    TuningSystem,
+    PitchReference,
+    EqualTemperament,
+    TwelveNoteTuning,
+    TwelveNoteKind,
+};
+
+pub use scala::{
+    ScalaPitch,
+    ScalaScale,
+    KeyboardMapping,
+    ScalaTuning,
+};
+
+pub use spelling::{
+    Letter,
+    Accidental,
+    SpelledPitch,
+    IntervalQuality,
+    SpelledInterval,
+};
+
+pub use scale::{
+    Scale,
+    ScaleKind,
+};
+
+pub use chords::{
+    ChordQuality,
+    ChordRecognition,
+    Voicing,
+};
+
+pub use pitch_class::{
+    PitchClassSet,
+    ForteNumber,
+    SetClassMembership,
+};
+
+pub use consonance::{
+    HarmonicEntropy,
+    Spectrum,
+};
+
+pub use key::{
+    Key,
+    KeyEstimate,
+    KeyProfile,
+    Mode,
+    RomanNumeral,
+    detect_key,
+    detect_key_of_chords,
+    pitch_class_histogram,
+};
+
+pub use melody::{
+    NoteEvent,
+    Melody,
+    Voice,
+    Score,
+};
+
+pub use neo_riemannian::{
+    Triad,
+    PlrOperation,
+    PlrWord,
+    PlrDerivation,
+};
+
+pub use timbre::{
+    Rolloff,
+    Timbre,
+};
+
+pub use instrument::{
+    Instrument,
+    InstrumentPart,
+    NoteViolation,
+    Violation,
+};
\ No newline at end of file
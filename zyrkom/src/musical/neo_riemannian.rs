@@ -0,0 +1,465 @@
+//! Neo-Riemannian transformations on major and minor triads
+//!
+//! The three operations each keep two tones of a triad and move the third
+//! by a semitone or a whole tone, flipping the triad's mode:
+//!
+//! - P (parallel): C major ↔ C minor, the third moves by a semitone
+//! - L (leading-tone exchange): C major ↔ E minor, root ↔ fifth by a semitone
+//! - R (relative): C major ↔ A minor, fifth ↔ root by a whole tone
+//!
+//! Each is its own inverse. On the Tonnetz, pitch classes sit on a lattice of
+//! fifths (x) and major thirds (y), triads are its triangles, and P, L and R
+//! flip a triangle across one of its edges.
+
+use crate::musical::key::Mode;
+use crate::musical::scale::OCTAVE_SEMITONES;
+use crate::musical::{Chord, ChordQuality, Key, MusicalInterval, MusicalNote, PitchReference};
+use crate::{Result, ZyrkomError};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// A major or minor triad reduced to pitch classes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Triad {
+    /// Pitch class of the root (0 = C)
+    root: u8,
+    /// Major or minor
+    mode: Mode,
+}
+
+impl Triad {
+    /// Create a triad from a root pitch class (0 = C) and mode
+    pub fn new(root: u8, mode: Mode) -> Result<Self> {
+        if root >= OCTAVE_SEMITONES {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Triad root must be a pitch class below 12, got {}", root),
+            });
+        }
+        Ok(Self { root, mode })
+    }
+
+    /// Reduce a chord to its triad; it must be recognised as major or minor
+    pub fn from_chord(chord: &Chord) -> Result<Self> {
+        let recognition = chord
+            .recognize()
+            .filter(|r| matches!(r.quality, ChordQuality::Major | ChordQuality::Minor))
+            .ok_or_else(|| ZyrkomError::PhysicsError {
+                details: "Neo-Riemannian operations need a major or minor triad".to_string(),
+            })?;
+        let mode = match recognition.quality {
+            ChordQuality::Major => Mode::Major,
+            _ => Mode::Minor,
+        };
+
+        Ok(Self { root: pitch_class(&recognition.root), mode })
+    }
+
+    /// Get the root pitch class
+    pub fn root(&self) -> u8 {
+        self.root
+    }
+
+    /// Get the mode
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Root, third and fifth as pitch classes
+    pub fn pitch_classes(&self) -> [u8; 3] {
+        let third = match self.mode {
+            Mode::Major => 4,
+            Mode::Minor => 3,
+        };
+        [self.root, (self.root + third) % OCTAVE_SEMITONES, (self.root + 7) % OCTAVE_SEMITONES]
+    }
+
+    /// Stable numeric identifier committed to in proofs (1–24)
+    pub fn id(&self) -> u32 {
+        self.root as u32 * 2 + self.mode.id() + 1
+    }
+
+    /// Root-position chord with its root in octave 4, tuned like `Chord::with_quality`
+    pub fn to_chord(&self, reference: PitchReference) -> Result<Chord> {
+        let quality = match self.mode {
+            Mode::Major => ChordQuality::Major,
+            Mode::Minor => ChordQuality::Minor,
+        };
+        Chord::with_quality(MusicalNote::from_midi_at(60 + self.root, reference)?, quality)
+    }
+
+    /// Apply one operation
+    pub fn apply(&self, operation: PlrOperation) -> Self {
+        let root = match (operation, self.mode) {
+            (PlrOperation::P, _) => self.root,
+            (PlrOperation::L, Mode::Major) => self.root + 4,
+            (PlrOperation::L, Mode::Minor) => self.root + 8,
+            (PlrOperation::R, Mode::Major) => self.root + 9,
+            (PlrOperation::R, Mode::Minor) => self.root + 3,
+        };
+        Self {
+            root: root % OCTAVE_SEMITONES,
+            mode: self.mode.parallel(),
+        }
+    }
+
+    /// Apply a word of operations left to right
+    pub fn apply_word(&self, word: &PlrWord) -> Self {
+        word.operations().iter().fold(*self, |triad, &op| triad.apply(op))
+    }
+
+    /// Vertices of the triad's Tonnetz triangle: root, third, fifth
+    ///
+    /// The root sits at its canonical coordinates; the fifth is one step
+    /// along the fifths axis and the third one step along the thirds axis
+    /// (up from the root for major, down from the fifth for minor).
+    pub fn tonnetz_triangle(&self) -> [(i32, i32); 3] {
+        let (x, y) = tonnetz_coordinates(self.root);
+        let third = match self.mode {
+            Mode::Major => (x, y + 1),
+            Mode::Minor => (x + 1, y - 1),
+        };
+        [(x, y), third, (x + 1, y)]
+    }
+
+    /// Shortest word of operations leading from this triad to another
+    ///
+    /// Ties are broken by trying P, then L, then R at each step.
+    pub fn shortest_path(&self, target: &Triad) -> PlrWord {
+        let mut previous: [Option<(Triad, PlrOperation)>; 24] = [None; 24];
+        let mut visited = [false; 24];
+        let index = |triad: &Triad| triad.id() as usize - 1;
+
+        let mut queue = VecDeque::from([*self]);
+        visited[index(self)] = true;
+        while let Some(current) = queue.pop_front() {
+            if current == *target {
+                break;
+            }
+            for operation in PlrOperation::ALL {
+                let next = current.apply(operation);
+                if !visited[index(&next)] {
+                    visited[index(&next)] = true;
+                    previous[index(&next)] = Some((current, operation));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut operations = Vec::new();
+        let mut current = *target;
+        while let Some((before, operation)) = previous[index(&current)] {
+            operations.push(operation);
+            current = before;
+        }
+        operations.reverse();
+        PlrWord::new(operations)
+    }
+
+    /// Number of operations on the shortest path to another triad
+    pub fn distance(&self, target: &Triad) -> usize {
+        self.shortest_path(target).len()
+    }
+}
+
+impl fmt::Display for Triad {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let key = Key::new(self.root, self.mode).map_err(|_| fmt::Error)?;
+        write!(f, "{}", key)
+    }
+}
+
+/// One of the three neo-Riemannian operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlrOperation {
+    /// Parallel: same root, opposite mode
+    P,
+    /// Leading-tone exchange: C major ↔ E minor
+    L,
+    /// Relative: C major ↔ A minor
+    R,
+}
+
+impl PlrOperation {
+    /// All operations, in tie-breaking order
+    pub const ALL: [PlrOperation; 3] = [PlrOperation::P, PlrOperation::L, PlrOperation::R];
+
+    /// Stable numeric identifier committed to in proofs
+    pub fn id(&self) -> u32 {
+        match self {
+            PlrOperation::P => 1,
+            PlrOperation::L => 2,
+            PlrOperation::R => 3,
+        }
+    }
+
+    /// Letter naming the operation
+    pub fn symbol(&self) -> char {
+        match self {
+            PlrOperation::P => 'P',
+            PlrOperation::L => 'L',
+            PlrOperation::R => 'R',
+        }
+    }
+}
+
+/// A composition of operations, applied left to right ("PL" = P then L)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PlrWord {
+    /// Operations in the order they are applied
+    operations: Vec<PlrOperation>,
+}
+
+impl PlrWord {
+    /// Create a word from operations in application order
+    pub fn new(operations: Vec<PlrOperation>) -> Self {
+        Self { operations }
+    }
+
+    /// Parse a word such as "PLR", "LP" or "R L" (case-insensitive)
+    pub fn parse(text: &str) -> Result<Self> {
+        let operations = text
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '.')
+            .map(|c| match c.to_ascii_uppercase() {
+                'P' => Ok(PlrOperation::P),
+                'L' => Ok(PlrOperation::L),
+                'R' => Ok(PlrOperation::R),
+                other => Err(ZyrkomError::ParseError {
+                    message: format!("Unknown neo-Riemannian operation '{}' in '{}'", other, text),
+                    line: 0,
+                }),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { operations })
+    }
+
+    /// Get the operations in application order
+    pub fn operations(&self) -> &[PlrOperation] {
+        &self.operations
+    }
+
+    /// Number of operations
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Whether the word is empty (the identity)
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Every triad visited from a starting triad, including the start
+    pub fn progression(&self, start: Triad) -> Vec<Triad> {
+        std::iter::once(start)
+            .chain(self.operations.iter().scan(start, |triad, &op| {
+                *triad = triad.apply(op);
+                Some(*triad)
+            }))
+            .collect()
+    }
+}
+
+impl fmt::Display for PlrWord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.operations.iter().try_for_each(|op| write!(f, "{}", op.symbol()))
+    }
+}
+
+impl Chord {
+    /// Apply a neo-Riemannian operation with parsimonious voice leading
+    ///
+    /// Only the voices holding the changing tone move, by a semitone or a
+    /// whole tone, so the voicing, doublings and tuning offsets are kept.
+    pub fn plr(&self, operation: PlrOperation) -> Result<Chord> {
+        let from = Triad::from_chord(self)?;
+        let to = from.apply(operation);
+        let (old, new) = (from.pitch_classes(), to.pitch_classes());
+
+        let leaving = *old.iter().find(|pc| !new.contains(pc)).unwrap_or(&old[0]);
+        let arriving = *new.iter().find(|pc| !old.contains(pc)).unwrap_or(&new[0]);
+        let semitones = match (arriving + OCTAVE_SEMITONES - leaving) % OCTAVE_SEMITONES {
+            step if step > 6 => step as i32 - OCTAVE_SEMITONES as i32,
+            step => step as i32,
+        };
+        let motion = MusicalInterval::from_cents(semitones as f64 * 100.0);
+
+        let notes = self
+            .notes()
+            .iter()
+            .map(|note| if pitch_class(note) == leaving { note.transpose(&motion) } else { Ok(*note) })
+            .collect::<Result<Vec<_>>>()?;
+        let root = notes
+            .iter()
+            .filter(|note| pitch_class(note) == to.root)
+            .min_by(|a, b| a.frequency().total_cmp(&b.frequency()))
+            .copied()
+            .unwrap_or(notes[0]);
+
+        Chord::from_voicing(root, notes)
+    }
+
+    /// Every chord reached by applying a word, including this one
+    pub fn plr_progression(&self, word: &PlrWord) -> Result<Vec<Chord>> {
+        let mut chords = vec![self.clone()];
+        for &operation in word.operations() {
+            let next = chords[chords.len() - 1].plr(operation)?;
+            chords.push(next);
+        }
+        Ok(chords)
+    }
+}
+
+/// Claim that a chord progression is generated by a word of operations
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlrDerivation {
+    /// The declared word
+    word: PlrWord,
+    /// Triads of the progression, one more than the word's length
+    triads: Vec<Triad>,
+}
+
+impl PlrDerivation {
+    /// Pair a progression with the word claimed to generate it
+    pub fn new(word: PlrWord, chords: &[Chord]) -> Result<Self> {
+        if chords.len() != word.len() + 1 {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!(
+                    "A word of {} operations spans {} chords, got {}",
+                    word.len(),
+                    word.len() + 1,
+                    chords.len()
+                ),
+            });
+        }
+
+        let triads = chords.iter().map(Triad::from_chord).collect::<Result<Vec<_>>>()?;
+        Ok(Self { word, triads })
+    }
+
+    /// Get the declared word
+    pub fn word(&self) -> &PlrWord {
+        &self.word
+    }
+
+    /// Get the progression's triads
+    pub fn triads(&self) -> &[Triad] {
+        &self.triads
+    }
+
+    /// Whether every step applies the declared operation
+    pub fn holds(&self) -> bool {
+        self.word.progression(self.triads[0]) == self.triads
+    }
+}
+
+/// Canonical Tonnetz coordinates of a pitch class: `pc = 7x + 4y (mod 12)`
+/// with x in 0..4 along the fifths axis and y in 0..3 along the thirds axis
+pub fn tonnetz_coordinates(pitch_class: u8) -> (i32, i32) {
+    (0..4)
+        .flat_map(|x| (0..3).map(move |y| (x, y)))
+        .find(|&(x, y)| (7 * x + 4 * y) % OCTAVE_SEMITONES as i32 == (pitch_class % OCTAVE_SEMITONES) as i32)
+        .unwrap_or((0, 0))
+}
+
+fn pitch_class(note: &MusicalNote) -> u8 {
+    (note.exact_midi().round() as i32).rem_euclid(OCTAVE_SEMITONES as i32) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triad(name: &str) -> Triad {
+        let key = Key::parse(name).unwrap();
+        Triad::new(key.tonic(), key.mode()).unwrap()
+    }
+
+    fn midi(chord: &Chord) -> Vec<u8> {
+        chord.notes().iter().map(|n| n.midi_number()).collect()
+    }
+
+    #[test]
+    fn test_operations_on_triads() {
+        let c = triad("C major");
+        assert_eq!(c.apply(PlrOperation::P), triad("C minor"));
+        assert_eq!(c.apply(PlrOperation::L), triad("E minor"));
+        assert_eq!(c.apply(PlrOperation::R), triad("A minor"));
+        assert_eq!(triad("C minor").apply(PlrOperation::L), triad("Ab major"));
+        assert_eq!(triad("C minor").apply(PlrOperation::R), triad("Eb major"));
+
+        for t in [c, triad("F# minor")] {
+            for op in PlrOperation::ALL {
+                assert_eq!(t.apply(op).apply(op), t);
+            }
+        }
+
+        // PL cycles through the hexatonic system in six steps
+        let hexatonic = PlrWord::parse("PLPLPL").unwrap();
+        assert_eq!(c.apply_word(&hexatonic), c);
+        assert_eq!(hexatonic.progression(c).len(), 7);
+    }
+
+    #[test]
+    fn test_parse_words() {
+        let word = PlrWord::parse("p l-R").unwrap();
+        assert_eq!(word.operations(), &[PlrOperation::P, PlrOperation::L, PlrOperation::R]);
+        assert_eq!(word.to_string(), "PLR");
+        assert!(PlrWord::parse("PX").is_err());
+        assert!(PlrWord::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tonnetz_coordinates() {
+        assert_eq!(tonnetz_coordinates(0), (0, 0));
+        assert_eq!(tonnetz_coordinates(7), (1, 0));
+        assert_eq!(tonnetz_coordinates(4), (0, 1));
+
+        for t in [triad("C major"), triad("C minor"), triad("B minor")] {
+            let classes: Vec<u8> = t
+                .tonnetz_triangle()
+                .iter()
+                .map(|&(x, y)| (7 * x + 4 * y).rem_euclid(12) as u8)
+                .collect();
+            assert_eq!(classes, t.pitch_classes().to_vec());
+        }
+    }
+
+    #[test]
+    fn test_shortest_paths() {
+        let c = triad("C major");
+        assert_eq!(c.shortest_path(&c).len(), 0);
+        assert_eq!(c.shortest_path(&triad("E major")).to_string(), "LP");
+        assert_eq!(c.distance(&triad("Ab major")), 2);
+
+        for target in (0..12).flat_map(|root| [Mode::Major, Mode::Minor].map(|m| Triad::new(root, m).unwrap())) {
+            let path = c.shortest_path(&target);
+            assert_eq!(c.apply_word(&path), target);
+            assert!(path.len() <= 5);
+        }
+    }
+
+    #[test]
+    fn test_parsimonious_voice_leading_on_chords() {
+        let c_major = Chord::from_voicing(MusicalNote::from_midi(48).unwrap(), [48, 64, 67, 72].map(|m| MusicalNote::from_midi(m).unwrap()).to_vec()).unwrap();
+
+        assert_eq!(midi(&c_major.plr(PlrOperation::P).unwrap()), vec![48, 63, 67, 72]);
+        assert_eq!(midi(&c_major.plr(PlrOperation::L).unwrap()), vec![47, 64, 67, 71]);
+        assert_eq!(midi(&c_major.plr(PlrOperation::R).unwrap()), vec![48, 64, 69, 72]);
+
+        let progression = c_major.plr_progression(&PlrWord::parse("RL").unwrap()).unwrap();
+        assert_eq!(Triad::from_chord(&progression[2]).unwrap(), triad("F major"));
+        assert!(Chord::dominant_seventh(MusicalNote::from_midi(60).unwrap()).unwrap().plr(PlrOperation::P).is_err());
+    }
+
+    #[test]
+    fn test_derivation_claims() {
+        let start = triad("C major").to_chord(PitchReference::standard()).unwrap();
+        let word = PlrWord::parse("PLR").unwrap();
+        let chords = start.plr_progression(&word).unwrap();
+
+        assert!(PlrDerivation::new(word.clone(), &chords).unwrap().holds());
+        assert!(!PlrDerivation::new(PlrWord::parse("PRL").unwrap(), &chords).unwrap().holds());
+        assert!(PlrDerivation::new(word, &chords[..2]).is_err());
+    }
+}
\ No newline at end of file
@@ -6,6 +6,9 @@
 
 // Note: PI import removed as not currently used
 
+use crate::musical::tuning::{PitchReference, TuningSystem};
+use crate::{Result, ZyrkomError};
+
 /// Perfect mathematical ratios derived from harmonic series
 /// These are NOT human conventions - they are physical laws
 pub mod constants {
@@ -32,6 +35,15 @@ pub mod constants {
     
     /// Cents per octave (logarithmic scale)
     pub const CENTS_PER_OCTAVE: f64 = 1200.0;
+    
+    /// Highest MIDI note number (G9, about 12.5 kHz at A4 = 440 Hz)
+    pub const MAX_MIDI_NUMBER: u8 = 127;
+
+    /// 5-limit just ratio for each semitone above a tonic (or chord root)
+    pub const JUST_RATIOS: [f64; 12] = [
+        1.0, 16.0 / 15.0, 9.0 / 8.0, 6.0 / 5.0, 5.0 / 4.0, 4.0 / 3.0,
+        45.0 / 32.0, 3.0 / 2.0, 8.0 / 5.0, 5.0 / 3.0, 9.0 / 5.0, 15.0 / 8.0,
+    ];
 }
 
 /// Type-safe musical interval representation
@@ -132,6 +144,14 @@ impl MusicalInterval {
         2.0_f64.powf(cents / constants::CENTS_PER_OCTAVE)
     }
     
+    /// Create an interval from its size in cents (negative sizes descend)
+    pub fn from_cents(cents: f64) -> Self {
+        Self {
+            ratio: Self::cents_to_ratio(cents),
+            cents,
+        }
+    }
+    
     /// Combine two intervals by multiplying their ratios
     /// This represents stacking intervals on top of each other
     pub fn combine(&self, other: &Self) -> Self {
@@ -158,42 +178,115 @@ impl MusicalInterval {
 pub struct MusicalNote {
     /// Fundamental frequency in Hz
     frequency: f64,
-    /// MIDI note number (A4 = 69)
+    /// Nearest MIDI note number (A4 = 69)
     midi_number: u8,
+    /// Offset from the nearest MIDI pitch in cents (-50..=50)
+    cents: f64,
+    /// Concert pitch the MIDI number is measured against
+    reference: PitchReference,
 }
 
 impl MusicalNote {
-    /// Create a note from MIDI number
-    pub fn from_midi(midi_number: u8) -> Self {
-        let frequency = Self::midi_to_frequency(midi_number);
-        Self {
+    /// Create a note from MIDI number at standard pitch (A4 = 440 Hz)
+    pub fn from_midi(midi_number: u8) -> Result<Self> {
+        Self::from_midi_at(midi_number, PitchReference::standard())
+    }
+    
+    /// Create a note from MIDI number at a given concert pitch
+    ///
+    /// Numbers above `MAX_MIDI_NUMBER` (127) are rejected.
+    pub fn from_midi_at(midi_number: u8, reference: PitchReference) -> Result<Self> {
+        if midi_number > constants::MAX_MIDI_NUMBER {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!(
+                    "MIDI number {} is outside the MIDI range (0-{})",
+                    midi_number,
+                    constants::MAX_MIDI_NUMBER
+                ),
+            });
+        }
+
+        let frequency = Self::midi_to_frequency(midi_number, reference);
+        Ok(Self {
             frequency,
             midi_number,
-        }
+            cents: 0.0,
+            reference,
+        })
     }
     
-    /// Create a note from frequency
-    pub fn from_frequency(frequency: f64) -> Self {
-        let midi_number = Self::frequency_to_midi(frequency);
-        Self {
-            frequency,
-            midi_number,
+    /// Create a note from a MIDI number plus a cents offset at a given concert pitch
+    pub fn from_midi_cents_at(midi_number: u8, cents: f64, reference: PitchReference) -> Result<Self> {
+        let frequency = Self::midi_to_frequency(midi_number, reference) * 2.0_f64.powf(cents / 1200.0);
+        Self::from_frequency_at(frequency, reference)
+    }
+    
+    /// Create a note from frequency at standard pitch (A4 = 440 Hz)
+    pub fn from_frequency(frequency: f64) -> Result<Self> {
+        Self::from_frequency_at(frequency, PitchReference::standard())
+    }
+    
+    /// Create a note from frequency, naming it against a given concert pitch
+    ///
+    /// The nearest MIDI pitch must lie in 0..=127 (about 8 Hz to 12.5 kHz at
+    /// A4 = 440 Hz); the remaining deviation is kept as a cents offset.
+    pub fn from_frequency_at(frequency: f64, reference: PitchReference) -> Result<Self> {
+        if !frequency.is_finite() || frequency <= 0.0 {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Frequency must be positive and finite, got {}", frequency),
+            });
+        }
+        
+        let exact_midi = Self::frequency_to_midi(frequency, reference);
+        let nearest = exact_midi.round();
+        if !(0.0..=constants::MAX_MIDI_NUMBER as f64).contains(&nearest) {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!(
+                    "Frequency {:.3} Hz is outside the MIDI range ({:.2}-{:.2} Hz at {})",
+                    frequency,
+                    Self::midi_to_frequency(0, reference),
+                    Self::midi_to_frequency(constants::MAX_MIDI_NUMBER, reference),
+                    reference,
+                ),
+            });
         }
+        
+        Ok(Self {
+            frequency,
+            midi_number: nearest as u8,
+            cents: (exact_midi - nearest) * 100.0,
+            reference,
+        })
+    }
+    
+    /// Create a note from a pitch step of a tuning system
+    pub fn from_step(step: i32, tuning: &dyn TuningSystem) -> Result<Self> {
+        Self::from_frequency_at(tuning.frequency(step), tuning.reference())
+    }
+    
+    /// Get the nearest pitch step of this note in a tuning system
+    pub fn step_in(&self, tuning: &dyn TuningSystem) -> i32 {
+        tuning.nearest_step(self.frequency)
     }
     
     /// Convert MIDI number to frequency using equal temperament
-    /// f = 440 * 2^((midi - 69) / 12)
-    fn midi_to_frequency(midi_number: u8) -> f64 {
+    /// f = A4 * 2^((midi - 69) / 12)
+    fn midi_to_frequency(midi_number: u8, reference: PitchReference) -> f64 {
         let semitones_from_a4 = (midi_number as f64) - 69.0;
-        constants::CONCERT_PITCH_A4 * 2.0_f64.powf(semitones_from_a4 / 12.0)
+        reference.a4_frequency() * 2.0_f64.powf(semitones_from_a4 / 12.0)
     }
     
-    /// Convert frequency to MIDI number
-    /// midi = 69 + 12 * log2(f / 440)
-    fn frequency_to_midi(frequency: f64) -> u8 {
-        let ratio_to_a4 = frequency / constants::CONCERT_PITCH_A4;
+    /// Convert frequency to a fractional MIDI number
+    /// midi = 69 + 12 * log2(f / A4)
+    fn frequency_to_midi(frequency: f64, reference: PitchReference) -> f64 {
+        let ratio_to_a4 = frequency / reference.a4_frequency();
         let semitones_from_a4 = 12.0 * ratio_to_a4.log2();
-        (69.0 + semitones_from_a4).round() as u8
+        69.0 + semitones_from_a4
+    }
+    
+    /// Get the concert pitch this note is named against
+    pub fn reference(&self) -> PitchReference {
+        self.reference
     }
     
     /// Get the frequency of this note
@@ -201,11 +294,21 @@ impl MusicalNote {
         self.frequency
     }
     
-    /// Get the MIDI number of this note
+    /// Get the nearest MIDI number of this note
     pub fn midi_number(&self) -> u8 {
         self.midi_number
     }
     
+    /// Get the offset from the nearest MIDI pitch in cents
+    pub fn cents(&self) -> f64 {
+        self.cents
+    }
+    
+    /// Get the fractional MIDI number (e.g. 60.5 for a quarter tone above C4)
+    pub fn exact_midi(&self) -> f64 {
+        self.midi_number as f64 + self.cents / 100.0
+    }
+    
     /// Calculate the interval between two notes
     pub fn interval_to(&self, other: &Self) -> MusicalInterval {
         let ratio = other.frequency / self.frequency;
@@ -217,15 +320,15 @@ impl MusicalNote {
         }
     }
     
-    /// Transpose this note by an interval
-    pub fn transpose(&self, interval: &MusicalInterval) -> Self {
+    /// Transpose this note by an interval, keeping any microtonal offset
+    pub fn transpose(&self, interval: &MusicalInterval) -> Result<Self> {
         let new_frequency = self.frequency * interval.ratio();
-        Self::from_frequency(new_frequency)
+        Self::from_frequency_at(new_frequency, self.reference)
     }
     
     /// Play this note for a specified duration (audio feature)
     #[cfg(feature = "test-audio")]
-    pub fn play_for_duration(&self, duration: std::time::Duration) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn play_for_duration(&self, duration: std::time::Duration) -> std::result::Result<(), Box<dyn std::error::Error>> {
         use crate::utils::audio;
         audio::play_frequency(self.frequency, duration.as_millis() as u64)
     }
@@ -242,19 +345,32 @@ pub struct Chord {
 
 impl Chord {
     /// Create a new chord from a root note and intervals
-    pub fn new(root: MusicalNote, intervals: &[MusicalInterval]) -> Self {
+    pub fn new(root: MusicalNote, intervals: &[MusicalInterval]) -> Result<Self> {
         let mut notes = vec![root];
         
         for interval in intervals {
-            let note = root.transpose(interval);
+            let note = root.transpose(interval)?;
             notes.push(note);
         }
         
-        Self { notes, root }
+        Ok(Self { notes, root })
+    }
+    
+    /// Create a chord from notes in voicing order around a harmonic root
+    ///
+    /// The root need not be the lowest note (e.g. an inverted chord).
+    pub fn from_voicing(root: MusicalNote, notes: Vec<MusicalNote>) -> Result<Self> {
+        if notes.is_empty() {
+            return Err(ZyrkomError::PhysicsError {
+                details: "A chord needs at least one note".to_string(),
+            });
+        }
+        
+        Ok(Self { notes, root })
     }
     
     /// Create a major triad using just intonation (5:4:6 ratios)
-    pub fn major_triad(root: MusicalNote) -> Self {
+    pub fn major_triad(root: MusicalNote) -> Result<Self> {
         let intervals = [
             MusicalInterval::major_third(),   // 5:4 ratio
             MusicalInterval::perfect_fifth(), // 3:2 ratio
@@ -263,6 +379,28 @@ impl Chord {
         Self::new(root, &intervals)
     }
     
+    /// Create a chord from pitch steps of a tuning system, first step as root
+    pub fn from_steps(steps: &[i32], tuning: &dyn TuningSystem) -> Result<Self> {
+        let first = steps.first().ok_or_else(|| ZyrkomError::PhysicsError {
+            details: "A chord needs at least one step".to_string(),
+        })?;
+        let root = MusicalNote::from_step(*first, tuning)?;
+        let intervals = steps[1..]
+            .iter()
+            .map(|&step| Ok(root.interval_to(&MusicalNote::from_step(step, tuning)?)))
+            .collect::<Result<Vec<MusicalInterval>>>()?;
+        
+        Self::new(root, &intervals)
+    }
+    
+    /// Create a major triad from the tuning's steps nearest to 5:4 and 3:2
+    pub fn major_triad_in(root_step: i32, tuning: &dyn TuningSystem) -> Result<Self> {
+        let third = tuning.steps_for_cents(MusicalInterval::major_third().cents());
+        let fifth = tuning.steps_for_cents(MusicalInterval::perfect_fifth().cents());
+        
+        Self::from_steps(&[root_step, root_step + third, root_step + fifth], tuning)
+    }
+    
     /// Get all notes in the chord
     pub fn notes(&self) -> &[MusicalNote] {
         &self.notes
@@ -273,6 +411,15 @@ impl Chord {
         self.root
     }
     
+    /// Get the lowest sounding note
+    pub fn bass(&self) -> MusicalNote {
+        self.notes
+            .iter()
+            .copied()
+            .min_by(|a, b| a.frequency().total_cmp(&b.frequency()))
+            .unwrap_or(self.root)
+    }
+    
     /// Get the number of notes in the chord
     pub fn note_count(&self) -> usize {
         self.notes.len()
@@ -290,8 +437,8 @@ mod tests {
         assert!((fifth.cents() - 701.955).abs() < 0.1); // Use the exact value
         
         // Test frequency calculation
-        let c4 = MusicalNote::from_frequency(261.63);
-        let g4 = c4.transpose(&fifth);
+        let c4 = MusicalNote::from_frequency(261.63).unwrap();
+        let g4 = c4.transpose(&fifth).unwrap();
         assert!((g4.frequency() - 392.445).abs() < 0.1);
     }
 
@@ -308,8 +455,8 @@ mod tests {
         assert_eq!(octave.ratio(), 2.0);
         assert_eq!(octave.cents(), 1200.0);
         
-        let a4 = MusicalNote::from_frequency(440.0);
-        let a5 = a4.transpose(&octave);
+        let a4 = MusicalNote::from_frequency(440.0).unwrap();
+        let a5 = a4.transpose(&octave).unwrap();
         assert_eq!(a5.frequency(), 880.0);
     }
 
@@ -327,8 +474,8 @@ mod tests {
 
     #[test]
     fn test_major_triad_physics() {
-        let c4 = MusicalNote::from_frequency(261.63);
-        let chord = Chord::major_triad(c4);
+        let c4 = MusicalNote::from_frequency(261.63).unwrap();
+        let chord = Chord::major_triad(c4).unwrap();
         
         assert_eq!(chord.notes().len(), 3);
         
@@ -344,6 +491,68 @@ mod tests {
         assert!((fifth_ratio - 1.5).abs() < 0.01);
     }
     
+    #[test]
+    fn test_notes_at_baroque_pitch() {
+        let a4 = MusicalNote::from_midi_at(69, PitchReference::baroque()).unwrap();
+        assert!((a4.frequency() - 415.0).abs() < 1e-9);
+        
+        // 440 Hz is a semitone above A4 at baroque pitch
+        let b_flat = MusicalNote::from_frequency_at(440.0, PitchReference::baroque()).unwrap();
+        assert_eq!(b_flat.midi_number(), 70);
+        assert_eq!(a4.transpose(&MusicalInterval::octave()).unwrap().midi_number(), 81);
+    }
+    
+    #[test]
+    fn test_microtonal_notes() {
+        // A quarter tone above A4
+        let quarter_sharp = MusicalNote::from_midi_cents_at(69, 50.0, PitchReference::standard()).unwrap();
+        assert!((quarter_sharp.exact_midi() - 69.5).abs() < 1e-9);
+        
+        // 30 cents flat of C4
+        let flat_c = MusicalNote::from_frequency(261.6256 * 2.0_f64.powf(-30.0 / 1200.0)).unwrap();
+        assert_eq!(flat_c.midi_number(), 60);
+        assert!((flat_c.cents() + 30.0).abs() < 0.01);
+        
+        // Transposition keeps the offset
+        let g = flat_c.transpose(&MusicalInterval::new(MusicalInterval::cents_to_ratio(700.0), 700.0).unwrap()).unwrap();
+        assert_eq!(g.midi_number(), 67);
+        assert!((g.cents() + 30.0).abs() < 0.01);
+    }
+    
+    #[test]
+    fn test_out_of_range_frequencies() {
+        assert!(MusicalNote::from_frequency(7.5).is_err());
+        assert!(MusicalNote::from_frequency(8.3).is_ok());
+        assert!(MusicalNote::from_frequency(13_000.0).is_err());
+        assert!(MusicalNote::from_frequency(0.0).is_err());
+        assert!(MusicalNote::from_frequency(f64::NAN).is_err());
+
+        // MIDI numbers past G9 are rejected like frequencies past it
+        assert!(MusicalNote::from_midi(127).is_ok());
+        assert!(MusicalNote::from_midi(128).is_err());
+        assert!(MusicalNote::from_midi_at(200, PitchReference::baroque()).is_err());
+        
+        // Transposing past the top of the range fails instead of wrapping
+        let c8 = MusicalNote::from_midi(108).unwrap();
+        assert!(c8.transpose(&MusicalInterval::octave()).is_ok());
+        assert!(matches!(
+            c8.transpose(&MusicalInterval::new(4.0, 2400.0).unwrap()),
+            Err(ZyrkomError::PhysicsError { .. })
+        ));
+    }
+    
+    #[test]
+    fn test_major_triad_in_meantone() {
+        use crate::musical::tuning::TwelveNoteTuning;
+        
+        // Quarter-comma meantone on C has a pure major third C-E
+        let meantone = TwelveNoteTuning::quarter_comma_meantone(0).unwrap();
+        let chord = Chord::major_triad_in(60, &meantone).unwrap();
+        let third = chord.notes()[0].interval_to(&chord.notes()[1]);
+        assert!((third.ratio() - 1.25).abs() < 1e-9);
+        assert_eq!(chord.notes()[2].step_in(&meantone), 67);
+    }
+    
     #[test]
     #[cfg(feature = "test-audio")]
     fn test_perfect_fifth_audio() {
@@ -385,8 +594,8 @@ mod tests {
     #[cfg(feature = "test-audio")]
     fn test_major_triad_audio() {
         println!("\n🎼 Audio test: C Major Triad");
-        let c4 = MusicalNote::from_frequency(261.63);
-        let chord = Chord::major_triad(c4);
+        let c4 = MusicalNote::from_frequency(261.63).unwrap();
+        let chord = Chord::major_triad(c4).unwrap();
         
         let frequencies: Vec<f64> = chord.notes().iter().map(|n| n.frequency()).collect();
         
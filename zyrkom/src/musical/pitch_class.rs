@@ -0,0 +1,519 @@
+//! Pitch-class sets for post-tonal analysis
+//!
+//! A `PitchClassSet` keeps the twelve pitch classes as a bitmask. Normal form
+//! follows Rahn's algorithm (smallest span, then intervals compared from the
+//! right); prime form is the more compact of the normal forms of the set and
+//! its inversion, transposed to 0. Forte numbers are looked up by set class,
+//! so the catalogue below may list any member of each class.
+
+use crate::musical::MusicalNote;
+use crate::{Result, ZyrkomError};
+use std::fmt;
+
+/// Number of pitch classes
+const PITCH_CLASSES: u8 = 12;
+
+/// Forte's catalogue of set classes with three to six members; classes with
+/// seven to nine members are named after their complements
+const FORTE_CATALOGUE: &[(&str, &[u8])] = &[
+    ("3-1", &[0, 1, 2]),
+    ("3-2", &[0, 1, 3]),
+    ("3-3", &[0, 1, 4]),
+    ("3-4", &[0, 1, 5]),
+    ("3-5", &[0, 1, 6]),
+    ("3-6", &[0, 2, 4]),
+    ("3-7", &[0, 2, 5]),
+    ("3-8", &[0, 2, 6]),
+    ("3-9", &[0, 2, 7]),
+    ("3-10", &[0, 3, 6]),
+    ("3-11", &[0, 3, 7]),
+    ("3-12", &[0, 4, 8]),
+    ("4-1", &[0, 1, 2, 3]),
+    ("4-2", &[0, 1, 2, 4]),
+    ("4-3", &[0, 1, 3, 4]),
+    ("4-4", &[0, 1, 2, 5]),
+    ("4-5", &[0, 1, 2, 6]),
+    ("4-6", &[0, 1, 2, 7]),
+    ("4-7", &[0, 1, 4, 5]),
+    ("4-8", &[0, 1, 5, 6]),
+    ("4-9", &[0, 1, 6, 7]),
+    ("4-10", &[0, 2, 3, 5]),
+    ("4-11", &[0, 1, 3, 5]),
+    ("4-12", &[0, 2, 3, 6]),
+    ("4-13", &[0, 1, 3, 6]),
+    ("4-14", &[0, 2, 3, 7]),
+    ("4-Z15", &[0, 1, 4, 6]),
+    ("4-16", &[0, 1, 5, 7]),
+    ("4-17", &[0, 3, 4, 7]),
+    ("4-18", &[0, 1, 4, 7]),
+    ("4-19", &[0, 1, 4, 8]),
+    ("4-20", &[0, 1, 5, 8]),
+    ("4-21", &[0, 2, 4, 6]),
+    ("4-22", &[0, 2, 4, 7]),
+    ("4-23", &[0, 2, 5, 7]),
+    ("4-24", &[0, 2, 4, 8]),
+    ("4-25", &[0, 2, 6, 8]),
+    ("4-26", &[0, 3, 5, 8]),
+    ("4-27", &[0, 2, 5, 8]),
+    ("4-28", &[0, 3, 6, 9]),
+    ("4-Z29", &[0, 1, 3, 7]),
+    ("5-1", &[0, 1, 2, 3, 4]),
+    ("5-2", &[0, 1, 2, 3, 5]),
+    ("5-3", &[0, 1, 2, 4, 5]),
+    ("5-4", &[0, 1, 2, 3, 6]),
+    ("5-5", &[0, 1, 2, 3, 7]),
+    ("5-6", &[0, 1, 2, 5, 6]),
+    ("5-7", &[0, 1, 2, 6, 7]),
+    ("5-8", &[0, 2, 3, 4, 6]),
+    ("5-9", &[0, 1, 2, 4, 6]),
+    ("5-10", &[0, 1, 3, 4, 6]),
+    ("5-11", &[0, 2, 3, 4, 7]),
+    ("5-Z12", &[0, 1, 3, 5, 6]),
+    ("5-13", &[0, 1, 2, 4, 8]),
+    ("5-14", &[0, 1, 2, 5, 7]),
+    ("5-15", &[0, 1, 2, 6, 8]),
+    ("5-16", &[0, 1, 3, 4, 7]),
+    ("5-Z17", &[0, 1, 3, 4, 8]),
+    ("5-Z18", &[0, 1, 4, 5, 7]),
+    ("5-19", &[0, 1, 3, 6, 7]),
+    ("5-20", &[0, 1, 5, 6, 8]),
+    ("5-21", &[0, 1, 4, 5, 8]),
+    ("5-22", &[0, 1, 4, 7, 8]),
+    ("5-23", &[0, 2, 3, 5, 7]),
+    ("5-24", &[0, 1, 3, 5, 7]),
+    ("5-25", &[0, 2, 3, 5, 8]),
+    ("5-26", &[0, 2, 4, 5, 8]),
+    ("5-27", &[0, 1, 3, 5, 8]),
+    ("5-28", &[0, 2, 3, 6, 8]),
+    ("5-29", &[0, 1, 3, 6, 8]),
+    ("5-30", &[0, 1, 4, 6, 8]),
+    ("5-31", &[0, 1, 3, 6, 9]),
+    ("5-32", &[0, 1, 4, 6, 9]),
+    ("5-33", &[0, 2, 4, 6, 8]),
+    ("5-34", &[0, 2, 4, 6, 9]),
+    ("5-35", &[0, 2, 4, 7, 9]),
+    ("5-Z36", &[0, 1, 2, 4, 7]),
+    ("5-Z37", &[0, 3, 4, 5, 8]),
+    ("5-Z38", &[0, 1, 2, 5, 8]),
+    ("6-1", &[0, 1, 2, 3, 4, 5]),
+    ("6-2", &[0, 1, 2, 3, 4, 6]),
+    ("6-Z3", &[0, 1, 2, 3, 5, 6]),
+    ("6-Z4", &[0, 1, 2, 4, 5, 6]),
+    ("6-5", &[0, 1, 2, 3, 6, 7]),
+    ("6-Z6", &[0, 1, 2, 5, 6, 7]),
+    ("6-7", &[0, 1, 2, 6, 7, 8]),
+    ("6-8", &[0, 2, 3, 4, 5, 7]),
+    ("6-9", &[0, 1, 2, 3, 5, 7]),
+    ("6-Z10", &[0, 1, 3, 4, 5, 7]),
+    ("6-Z11", &[0, 1, 2, 4, 5, 7]),
+    ("6-Z12", &[0, 1, 2, 4, 6, 7]),
+    ("6-Z13", &[0, 1, 3, 4, 6, 7]),
+    ("6-14", &[0, 1, 3, 4, 5, 8]),
+    ("6-15", &[0, 1, 2, 4, 5, 8]),
+    ("6-16", &[0, 1, 4, 5, 6, 8]),
+    ("6-Z17", &[0, 1, 2, 4, 7, 8]),
+    ("6-18", &[0, 1, 2, 5, 7, 8]),
+    ("6-Z19", &[0, 1, 3, 4, 7, 8]),
+    ("6-20", &[0, 1, 4, 5, 8, 9]),
+    ("6-21", &[0, 2, 3, 4, 6, 8]),
+    ("6-22", &[0, 1, 2, 4, 6, 8]),
+    ("6-Z23", &[0, 2, 3, 5, 6, 8]),
+    ("6-Z24", &[0, 1, 3, 4, 6, 8]),
+    ("6-Z25", &[0, 1, 3, 5, 6, 8]),
+    ("6-Z26", &[0, 1, 3, 5, 7, 8]),
+    ("6-27", &[0, 1, 3, 4, 6, 9]),
+    ("6-Z28", &[0, 1, 3, 5, 6, 9]),
+    ("6-Z29", &[0, 1, 3, 6, 8, 9]),
+    ("6-30", &[0, 1, 3, 6, 7, 9]),
+    ("6-31", &[0, 1, 3, 5, 8, 9]),
+    ("6-32", &[0, 2, 4, 5, 7, 9]),
+    ("6-33", &[0, 2, 3, 5, 7, 9]),
+    ("6-34", &[0, 1, 3, 5, 7, 9]),
+    ("6-35", &[0, 2, 4, 6, 8, 10]),
+    ("6-Z36", &[0, 1, 2, 3, 4, 7]),
+    ("6-Z37", &[0, 1, 2, 3, 4, 8]),
+    ("6-Z38", &[0, 1, 2, 3, 7, 8]),
+    ("6-Z39", &[0, 2, 3, 4, 5, 8]),
+    ("6-Z40", &[0, 1, 2, 3, 5, 8]),
+    ("6-Z41", &[0, 1, 2, 3, 6, 8]),
+    ("6-Z42", &[0, 1, 2, 3, 6, 9]),
+    ("6-Z43", &[0, 1, 2, 5, 6, 8]),
+    ("6-Z44", &[0, 1, 2, 5, 6, 9]),
+    ("6-Z45", &[0, 2, 3, 4, 6, 9]),
+    ("6-Z46", &[0, 1, 2, 4, 6, 9]),
+    ("6-Z47", &[0, 1, 2, 4, 7, 9]),
+    ("6-Z48", &[0, 1, 2, 5, 7, 9]),
+    ("6-Z49", &[0, 1, 3, 4, 7, 9]),
+    ("6-Z50", &[0, 1, 4, 6, 7, 9]),
+];
+
+/// A set of pitch classes (C = 0 ... B = 11)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PitchClassSet {
+    /// Bit `pc` is set when pitch class `pc` is a member
+    mask: u16,
+}
+
+impl PitchClassSet {
+    /// Create a set from pitch classes, reduced modulo 12
+    pub fn new(pitch_classes: &[u8]) -> Self {
+        let mask = pitch_classes.iter().fold(0, |mask, &pc| mask | 1 << (pc % PITCH_CLASSES));
+        Self { mask }
+    }
+
+    /// The pitch classes of a collection of notes
+    pub fn from_notes(notes: &[MusicalNote]) -> Self {
+        let classes: Vec<u8> = notes
+            .iter()
+            .map(|note| (note.exact_midi().round() as i32).rem_euclid(PITCH_CLASSES as i32) as u8)
+            .collect();
+        Self::new(&classes)
+    }
+
+    /// Members in ascending order
+    pub fn pitch_classes(&self) -> Vec<u8> {
+        (0..PITCH_CLASSES).filter(|&pc| self.contains(pc)).collect()
+    }
+
+    /// Number of members
+    pub fn cardinality(&self) -> usize {
+        self.mask.count_ones() as usize
+    }
+
+    /// Whether a pitch class is a member
+    pub fn contains(&self, pitch_class: u8) -> bool {
+        self.mask & (1 << (pitch_class % PITCH_CLASSES)) != 0
+    }
+
+    /// Transposition T<sub>n</sub>: add n to every member
+    pub fn transpose(&self, n: u8) -> Self {
+        Self::new(&self.pitch_classes().iter().map(|pc| pc + n % PITCH_CLASSES).collect::<Vec<_>>())
+    }
+
+    /// Inversion T<sub>n</sub>I: map every member x to n - x
+    pub fn invert(&self, n: u8) -> Self {
+        let classes: Vec<u8> = self
+            .pitch_classes()
+            .iter()
+            .map(|&pc| (n % PITCH_CLASSES + PITCH_CLASSES - pc) % PITCH_CLASSES)
+            .collect();
+        Self::new(&classes)
+    }
+
+    /// The pitch classes not in this set
+    pub fn complement(&self) -> Self {
+        Self {
+            mask: !self.mask & ((1 << PITCH_CLASSES) - 1),
+        }
+    }
+
+    /// Normal form: the most compact rotation of the members
+    pub fn normal_form(&self) -> Vec<u8> {
+        let members = self.pitch_classes();
+        (0..members.len())
+            .map(|start| {
+                let mut rotation = members.clone();
+                rotation.rotate_left(start);
+                rotation
+            })
+            .min_by_key(|rotation| (compactness(rotation), rotation[0]))
+            .unwrap_or_default()
+    }
+
+    /// Prime form: the most compact normal form of the set or its inversion,
+    /// transposed to start on 0
+    pub fn prime_form(&self) -> Vec<u8> {
+        let zeroed = |form: Vec<u8>| -> Vec<u8> {
+            let first = form.first().copied().unwrap_or(0);
+            form.iter().map(|pc| (pc + PITCH_CLASSES - first) % PITCH_CLASSES).collect()
+        };
+        let original = zeroed(self.normal_form());
+        let inverted = zeroed(self.invert(0).normal_form());
+
+        if compactness(&inverted) < compactness(&original) {
+            inverted
+        } else {
+            original
+        }
+    }
+
+    /// Interval-class vector: counts of interval classes 1 to 6
+    pub fn interval_class_vector(&self) -> [u8; 6] {
+        let members = self.pitch_classes();
+        let mut vector = [0; 6];
+        for (i, &a) in members.iter().enumerate() {
+            for &b in &members[i + 1..] {
+                let interval = b - a;
+                let class = interval.min(PITCH_CLASSES - interval);
+                vector[class as usize - 1] += 1;
+            }
+        }
+        vector
+    }
+
+    /// Whether some transposition T<sub>n</sub> maps this set onto the other
+    pub fn is_transpositionally_equivalent(&self, other: &PitchClassSet) -> bool {
+        (0..PITCH_CLASSES).any(|n| self.transpose(n) == *other)
+    }
+
+    /// Whether the sets belong to the same set class (T/I equivalence)
+    pub fn is_equivalent(&self, other: &PitchClassSet) -> bool {
+        self.class_key() == other.class_key()
+    }
+
+    /// Whether the sets share an interval-class vector without being
+    /// T/I-equivalent
+    pub fn is_z_related(&self, other: &PitchClassSet) -> bool {
+        self.interval_class_vector() == other.interval_class_vector() && !self.is_equivalent(other)
+    }
+
+    /// Forte number of this set's class, for sets of three to nine members
+    pub fn forte_number(&self) -> Option<ForteNumber> {
+        let cardinality = self.cardinality();
+        if !(3..=9).contains(&cardinality) {
+            return None;
+        }
+        if cardinality > 6 {
+            let complement = self.complement().forte_number()?;
+            return Some(ForteNumber {
+                cardinality: cardinality as u8,
+                ..complement
+            });
+        }
+
+        let key = self.class_key();
+        FORTE_CATALOGUE
+            .iter()
+            .find(|(_, members)| PitchClassSet::new(members).class_key() == key)
+            .and_then(|(name, _)| ForteNumber::parse_name(name))
+    }
+
+    /// Smallest bitmask among all transpositions and inversions, identifying
+    /// the set class
+    pub fn class_key(&self) -> u16 {
+        (0..PITCH_CLASSES)
+            .flat_map(|n| [self.transpose(n).mask, self.invert(n).mask])
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+impl fmt::Display for PitchClassSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let members: String = self
+            .pitch_classes()
+            .iter()
+            .map(|&pc| match pc {
+                10 => 'T',
+                11 => 'E',
+                _ => char::from(b'0' + pc),
+            })
+            .collect();
+        write!(f, "[{}]", members)
+    }
+}
+
+/// A set class name in Forte's catalogue, e.g. 3-11 or 6-Z17
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ForteNumber {
+    /// Number of pitch classes
+    cardinality: u8,
+    /// Position in Forte's list for this cardinality
+    ordinal: u8,
+    /// Whether the class has a Z-related partner
+    z: bool,
+}
+
+impl ForteNumber {
+    /// Parse a Forte number such as "3-11" or "6-Z17" (the Z is optional)
+    pub fn parse(name: &str) -> Result<Self> {
+        let parsed = Self::parse_name(name.trim()).ok_or_else(|| ZyrkomError::ParseError {
+            message: format!("Invalid Forte number '{}'", name),
+            line: 0,
+        })?;
+        // Re-derive from the catalogue so the Z flag is always correct
+        parsed.prime_form().forte_number().filter(|found| {
+            found.cardinality == parsed.cardinality && found.ordinal == parsed.ordinal
+        }).ok_or_else(|| ZyrkomError::ParseError {
+            message: format!("No set class {} in Forte's catalogue", name),
+            line: 0,
+        })
+    }
+
+    /// Get the cardinality
+    pub fn cardinality(&self) -> u8 {
+        self.cardinality
+    }
+
+    /// Get the ordinal within the cardinality
+    pub fn ordinal(&self) -> u8 {
+        self.ordinal
+    }
+
+    /// Whether the class is Z-related to another
+    pub fn is_z(&self) -> bool {
+        self.z
+    }
+
+    /// Prime form of the named class (empty if the name is not catalogued)
+    pub fn prime_form(&self) -> PitchClassSet {
+        let (lookup, complement) = if self.cardinality > 6 {
+            (PITCH_CLASSES - self.cardinality, true)
+        } else {
+            (self.cardinality, false)
+        };
+
+        let members = FORTE_CATALOGUE
+            .iter()
+            .filter_map(|(name, members)| Some((Self::parse_name(name)?, members)))
+            .find(|(number, _)| number.cardinality == lookup && number.ordinal == self.ordinal)
+            .map(|(_, members)| PitchClassSet::new(members));
+
+        match members {
+            Some(set) if complement => PitchClassSet::new(&set.complement().prime_form()),
+            Some(set) => PitchClassSet::new(&set.prime_form()),
+            None => PitchClassSet::default(),
+        }
+    }
+
+    fn parse_name(name: &str) -> Option<Self> {
+        let (cardinality, rest) = name.split_once('-')?;
+        let (z, ordinal) = match rest.strip_prefix(['Z', 'z']) {
+            Some(ordinal) => (true, ordinal),
+            None => (false, rest),
+        };
+        Some(Self {
+            cardinality: cardinality.parse().ok()?,
+            ordinal: ordinal.parse().ok()?,
+            z,
+        })
+    }
+}
+
+impl fmt::Display for ForteNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let z = if self.z { "Z" } else { "" };
+        write!(f, "{}-{}{}", self.cardinality, z, self.ordinal)
+    }
+}
+
+/// Claim that a pitch-class set belongs to a set class
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SetClassMembership {
+    /// The set under analysis
+    set: PitchClassSet,
+    /// The claimed set class
+    class: ForteNumber,
+}
+
+impl SetClassMembership {
+    /// Claim that a set belongs to a class
+    pub fn new(set: PitchClassSet, class: ForteNumber) -> Self {
+        Self { set, class }
+    }
+
+    /// Claim that a collection of notes belongs to a class named like "3-11"
+    pub fn for_notes(notes: &[MusicalNote], class: &str) -> Result<Self> {
+        Ok(Self::new(PitchClassSet::from_notes(notes), ForteNumber::parse(class)?))
+    }
+
+    /// Get the set
+    pub fn set(&self) -> PitchClassSet {
+        self.set
+    }
+
+    /// Get the claimed class
+    pub fn class(&self) -> ForteNumber {
+        self.class
+    }
+
+    /// Whether the claim is true
+    pub fn holds(&self) -> bool {
+        self.set.is_equivalent(&self.class.prime_form())
+    }
+}
+
+/// Intervals from the first member to the last, then to the second-to-last,
+/// and so on; smaller compares as more compact
+fn compactness(form: &[u8]) -> Vec<u8> {
+    let first = form.first().copied().unwrap_or(0);
+    form.iter().skip(1).rev().map(|pc| (pc + PITCH_CLASSES - first) % PITCH_CLASSES).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_and_prime_form() {
+        // E G C: normal form [0,4,7], prime form of the major triad is [0,3,7]
+        let c_major = PitchClassSet::new(&[4, 7, 0]);
+        assert_eq!(c_major.normal_form(), vec![0, 4, 7]);
+        assert_eq!(c_major.prime_form(), vec![0, 3, 7]);
+
+        let set = PitchClassSet::new(&[11, 2, 8, 5]);
+        assert_eq!(set.normal_form(), vec![2, 5, 8, 11]);
+        assert_eq!(set.prime_form(), vec![0, 3, 6, 9]);
+
+        assert_eq!(PitchClassSet::new(&[9, 1, 10, 2]).normal_form(), vec![9, 10, 1, 2]);
+        assert_eq!(PitchClassSet::new(&[1, 5, 6, 7]).prime_form(), vec![0, 1, 2, 6]);
+    }
+
+    #[test]
+    fn test_forte_numbers_cover_every_set_class() {
+        use std::collections::BTreeSet;
+
+        for cardinality in 3..=9 {
+            let classes: BTreeSet<u16> = (0u16..1 << 12)
+                .filter(|mask| mask.count_ones() == cardinality)
+                .map(|mask| PitchClassSet { mask }.class_key())
+                .collect();
+            let names: BTreeSet<String> = classes
+                .iter()
+                .map(|&mask| PitchClassSet { mask }.forte_number().unwrap().to_string())
+                .collect();
+            assert_eq!(names.len(), classes.len());
+        }
+
+        assert_eq!(PitchClassSet::new(&[0, 4, 7]).forte_number().unwrap().to_string(), "3-11");
+        assert_eq!(PitchClassSet::new(&[0, 1, 4, 6]).forte_number().unwrap().to_string(), "4-Z15");
+        assert_eq!(PitchClassSet::new(&[0, 2, 4, 5, 7, 9, 11]).forte_number().unwrap().to_string(), "7-35");
+    }
+
+    #[test]
+    fn test_interval_class_vectors_and_z_relation() {
+        assert_eq!(PitchClassSet::new(&[0, 3, 7]).interval_class_vector(), [0, 0, 1, 1, 1, 0]);
+        assert_eq!(PitchClassSet::new(&[0, 2, 4, 6, 8, 10]).interval_class_vector(), [0, 6, 0, 6, 0, 3]);
+
+        let all_interval_a = PitchClassSet::new(&[0, 1, 4, 6]);
+        let all_interval_b = PitchClassSet::new(&[0, 1, 3, 7]);
+        assert!(all_interval_a.is_z_related(&all_interval_b));
+        assert!(!all_interval_a.is_z_related(&all_interval_a.transpose(5)));
+        assert!(ForteNumber::parse("4-29").unwrap().is_z());
+    }
+
+    #[test]
+    fn test_transposition_inversion_and_complement() {
+        let c_major = PitchClassSet::new(&[0, 4, 7]);
+        let a_minor = PitchClassSet::new(&[9, 0, 4]);
+
+        assert!(c_major.is_transpositionally_equivalent(&c_major.transpose(7)));
+        assert!(!c_major.is_transpositionally_equivalent(&a_minor));
+        assert!(c_major.is_equivalent(&a_minor));
+        assert_eq!(c_major.invert(4), PitchClassSet::new(&[4, 0, 9]));
+
+        let complement = c_major.complement();
+        assert_eq!(complement.cardinality(), 9);
+        assert_eq!(complement.forte_number().unwrap().to_string(), "9-11");
+        assert_eq!(ForteNumber::parse("9-11").unwrap().prime_form(), PitchClassSet::new(&complement.prime_form()));
+    }
+
+    #[test]
+    fn test_set_class_membership() {
+        let notes: Vec<MusicalNote> = [57, 60, 64].iter().map(|&m| MusicalNote::from_midi(m).unwrap()).collect();
+        assert!(SetClassMembership::for_notes(&notes, "3-11").unwrap().holds());
+        assert!(!SetClassMembership::for_notes(&notes, "3-12").unwrap().holds());
+        assert!(ForteNumber::parse("3-13").is_err());
+        assert!(ForteNumber::parse("triad").is_err());
+    }
+}
\ No newline at end of file
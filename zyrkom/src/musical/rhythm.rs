@@ -0,0 +1,583 @@
+//! Rhythm and Meter for Zyrkom
+//!
+//! Durations are exact rationals measured in whole notes, so a measure either
+//! sums to its time signature or it does not - there is no floating point
+//! tolerance involved. Milliseconds only appear once a tempo is applied.
+
+use crate::utils::math::gcd;
+use crate::{Result, ZyrkomError};
+
+/// Most augmentation dots a note value may carry
+pub const MAX_DOTS: u8 = 3;
+
+/// Largest note count on either side of a tuplet ratio
+pub const MAX_TUPLET_SIZE: u32 = 64;
+
+/// Exact rhythmic duration expressed as a fraction of a whole note
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct NoteDuration {
+    /// Numerator of the fraction (always reduced)
+    numerator: u64,
+    /// Denominator of the fraction (always reduced, never zero)
+    denominator: u64,
+}
+
+impl NoteDuration {
+    /// Create a duration of `numerator / denominator` whole notes
+    pub fn new(numerator: u64, denominator: u64) -> Result<Self> {
+        if denominator == 0 {
+            return Err(ZyrkomError::ConstraintError {
+                context: "Duration denominator cannot be zero".to_string(),
+            });
+        }
+
+        Ok(Self::reduced(numerator, denominator))
+    }
+
+    /// A zero-length duration
+    pub const fn zero() -> Self {
+        Self { numerator: 0, denominator: 1 }
+    }
+
+    /// Reduce a fraction computed in 128 bits, failing if it does not fit back into u64
+    fn reduced_wide(numerator: u128, denominator: u128) -> Result<Self> {
+        if numerator == 0 {
+            return Ok(Self::zero());
+        }
+        let divisor = gcd_wide(numerator, denominator);
+        match (u64::try_from(numerator / divisor), u64::try_from(denominator / divisor)) {
+            (Ok(numerator), Ok(denominator)) => Ok(Self { numerator, denominator }),
+            _ => Err(ZyrkomError::ConstraintError {
+                context: format!("Duration overflow: {}/{} whole notes", numerator, denominator),
+            }),
+        }
+    }
+
+    fn reduced(numerator: u64, denominator: u64) -> Self {
+        if numerator == 0 {
+            return Self::zero();
+        }
+        let divisor = gcd(numerator, denominator);
+        Self {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    /// Get the numerator of the reduced fraction
+    pub fn numerator(&self) -> u64 {
+        self.numerator
+    }
+
+    /// Get the denominator of the reduced fraction
+    pub fn denominator(&self) -> u64 {
+        self.denominator
+    }
+
+    /// Duration as a floating point number of whole notes
+    pub fn as_whole_notes(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// Scale this duration by the fraction `numerator / denominator`
+    pub fn scale(&self, numerator: u64, denominator: u64) -> Result<Self> {
+        if denominator == 0 {
+            return Err(ZyrkomError::ConstraintError {
+                context: "Duration denominator cannot be zero".to_string(),
+            });
+        }
+        Self::reduced_wide(
+            self.numerator as u128 * numerator as u128,
+            self.denominator as u128 * denominator as u128,
+        )
+    }
+
+    /// Number of ticks this duration spans at a resolution of `ticks_per_whole`.
+    /// Returns None when the duration does not fall on a whole tick or the
+    /// tick count does not fit in a u64.
+    pub fn to_ticks(&self, ticks_per_whole: u64) -> Option<u64> {
+        let scaled = self.numerator as u128 * ticks_per_whole as u128;
+        if scaled.is_multiple_of(self.denominator as u128) {
+            u64::try_from(scaled / self.denominator as u128).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Exact sum of two durations, failing if the result overflows
+    pub fn checked_add(&self, other: &Self) -> Result<Self> {
+        Self::reduced_wide(
+            self.numerator as u128 * other.denominator as u128 + other.numerator as u128 * self.denominator as u128,
+            self.denominator as u128 * other.denominator as u128,
+        )
+    }
+
+    /// Sum a sequence of durations exactly
+    pub fn sum<'a>(durations: impl IntoIterator<Item = &'a NoteDuration>) -> Result<Self> {
+        durations.into_iter().try_fold(Self::zero(), |acc, d| acc.checked_add(d))
+    }
+
+    /// Difference of two durations, or zero if `other` is longer
+    pub fn saturating_sub(&self, other: &Self) -> Result<Self> {
+        let lhs = self.numerator as u128 * other.denominator as u128;
+        let rhs = other.numerator as u128 * self.denominator as u128;
+        Self::reduced_wide(lhs.saturating_sub(rhs), self.denominator as u128 * other.denominator as u128)
+    }
+}
+
+fn gcd_wide(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd_wide(b, a % b)
+    }
+}
+
+/// Least common multiple of two tick resolutions, failing if it overflows
+pub(crate) fn checked_lcm(a: u64, b: u64) -> Result<u64> {
+    (a / gcd(a, b)).checked_mul(b).ok_or_else(|| ZyrkomError::ConstraintError {
+        context: format!("Tick resolution overflow: lcm({}, {})", a, b),
+    })
+}
+
+impl PartialOrd for NoteDuration {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NoteDuration {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let lhs = self.numerator as u128 * other.denominator as u128;
+        let rhs = other.numerator as u128 * self.denominator as u128;
+        lhs.cmp(&rhs)
+    }
+}
+
+impl std::fmt::Display for NoteDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// Standard note values (undotted, outside any tuplet)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum NoteValue {
+    /// Whole note (redonda)
+    Whole,
+    /// Half note (blanca)
+    Half,
+    /// Quarter note (negra)
+    Quarter,
+    /// Eighth note (corchea)
+    Eighth,
+    /// Sixteenth note (semicorchea)
+    Sixteenth,
+    /// Thirty-second note (fusa)
+    ThirtySecond,
+    /// Sixty-fourth note (semifusa)
+    SixtyFourth,
+}
+
+impl NoteValue {
+    /// How many of this value fit in a whole note
+    pub fn divisions(&self) -> u64 {
+        match self {
+            NoteValue::Whole => 1,
+            NoteValue::Half => 2,
+            NoteValue::Quarter => 4,
+            NoteValue::Eighth => 8,
+            NoteValue::Sixteenth => 16,
+            NoteValue::ThirtySecond => 32,
+            NoteValue::SixtyFourth => 64,
+        }
+    }
+
+    /// Look up a note value from its divisions of the whole note (1, 2, 4, ...)
+    pub fn from_divisions(divisions: u64) -> Option<Self> {
+        match divisions {
+            1 => Some(NoteValue::Whole),
+            2 => Some(NoteValue::Half),
+            4 => Some(NoteValue::Quarter),
+            8 => Some(NoteValue::Eighth),
+            16 => Some(NoteValue::Sixteenth),
+            32 => Some(NoteValue::ThirtySecond),
+            64 => Some(NoteValue::SixtyFourth),
+            _ => None,
+        }
+    }
+
+    /// Exact duration of this note value
+    pub fn duration(&self) -> NoteDuration {
+        NoteDuration::reduced(1, self.divisions())
+    }
+}
+
+/// Tuplet ratio: `actual` notes played in the time of `normal` notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Tuplet {
+    /// Number of notes actually played (3 for a triplet)
+    pub actual: u32,
+    /// Number of notes whose time is occupied (2 for a triplet)
+    pub normal: u32,
+}
+
+impl Tuplet {
+    /// Create a tuplet, rejecting degenerate ratios and sides above `MAX_TUPLET_SIZE`
+    pub fn new(actual: u32, normal: u32) -> Result<Self> {
+        if actual == 0 || normal == 0 || actual > MAX_TUPLET_SIZE || normal > MAX_TUPLET_SIZE {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!("Invalid tuplet {}:{}", actual, normal),
+            });
+        }
+        Ok(Self { actual, normal })
+    }
+
+    /// Standard triplet (3 in the time of 2)
+    pub const fn triplet() -> Self {
+        Self { actual: 3, normal: 2 }
+    }
+}
+
+/// A note value with optional augmentation dots and tuplet grouping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct RhythmicValue {
+    /// Base note value
+    pub value: NoteValue,
+    /// Number of augmentation dots
+    pub dots: u8,
+    /// Tuplet the note belongs to, if any
+    pub tuplet: Option<Tuplet>,
+}
+
+impl RhythmicValue {
+    /// Plain note value without dots or tuplet
+    pub const fn new(value: NoteValue) -> Self {
+        Self { value, dots: 0, tuplet: None }
+    }
+
+    /// Note value with augmentation dots (capped at `MAX_DOTS`)
+    pub const fn dotted(value: NoteValue, dots: u8) -> Self {
+        let dots = if dots > MAX_DOTS { MAX_DOTS } else { dots };
+        Self { value, dots, tuplet: None }
+    }
+
+    /// Note value inside a tuplet
+    pub const fn in_tuplet(value: NoteValue, tuplet: Tuplet) -> Self {
+        Self { value, dots: 0, tuplet: Some(tuplet) }
+    }
+
+    /// Exact duration including dots and tuplet scaling
+    ///
+    /// Dots beyond `MAX_DOTS` are ignored, which keeps the result well inside
+    /// u64 for any tuplet ratio.
+    pub fn duration(&self) -> NoteDuration {
+        // Each dot adds half of the previous addition: d * (2 - 1/2^dots)
+        let dot_factor = 1u64 << self.dots.min(MAX_DOTS);
+        let mut numerator = 2 * dot_factor - 1;
+        let mut denominator = self.value.divisions() * dot_factor;
+
+        if let Some(tuplet) = self.tuplet {
+            numerator *= tuplet.normal as u64;
+            denominator *= tuplet.actual as u64;
+        }
+
+        NoteDuration::reduced(numerator, denominator)
+    }
+}
+
+/// Time signature such as 4/4, 3/4 or 6/8
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct TimeSignature {
+    /// Beats per measure
+    numerator: u32,
+    /// Note value receiving one beat (must be a power of two)
+    denominator: u32,
+}
+
+impl TimeSignature {
+    /// Create a time signature, validating that the denominator is a note value
+    pub fn new(numerator: u32, denominator: u32) -> Result<Self> {
+        if numerator == 0 || NoteValue::from_divisions(denominator as u64).is_none() {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!("Invalid time signature {}/{}", numerator, denominator),
+            });
+        }
+        Ok(Self { numerator, denominator })
+    }
+
+    /// Common time (4/4)
+    pub const fn common() -> Self {
+        Self { numerator: 4, denominator: 4 }
+    }
+
+    /// Get the number of beats per measure
+    pub fn numerator(&self) -> u32 {
+        self.numerator
+    }
+
+    /// Get the beat note value denominator
+    pub fn denominator(&self) -> u32 {
+        self.denominator
+    }
+
+    /// Exact duration of one full measure
+    pub fn measure_duration(&self) -> NoteDuration {
+        NoteDuration::reduced(self.numerator as u64, self.denominator as u64)
+    }
+}
+
+impl std::fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// Tempo in beats per minute for a given beat duration
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Tempo {
+    /// Beats per minute
+    bpm: f64,
+    /// Duration of one beat
+    beat: NoteDuration,
+}
+
+impl Tempo {
+    /// Create a tempo, rejecting non-positive or non-finite BPM
+    pub fn new(bpm: f64, beat: NoteDuration) -> Result<Self> {
+        if !bpm.is_finite() || bpm <= 0.0 || beat.numerator() == 0 {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!("Invalid tempo: {} BPM", bpm),
+            });
+        }
+        Ok(Self { bpm, beat })
+    }
+
+    /// Tempo counted in quarter notes (the usual metronome marking)
+    pub fn quarter(bpm: f64) -> Result<Self> {
+        Self::new(bpm, NoteValue::Quarter.duration())
+    }
+
+    /// Get beats per minute
+    pub fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    /// Get the beat duration
+    pub fn beat(&self) -> NoteDuration {
+        self.beat
+    }
+
+    /// Wall-clock length of a duration at this tempo, in milliseconds
+    pub fn duration_ms(&self, duration: NoteDuration) -> f64 {
+        let beats = duration.as_whole_notes() / self.beat.as_whole_notes();
+        beats * 60_000.0 / self.bpm
+    }
+}
+
+/// Inclusive BPM range a tempo must fall within
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TempoBounds {
+    /// Slowest permitted tempo
+    pub min_bpm: f64,
+    /// Fastest permitted tempo
+    pub max_bpm: f64,
+}
+
+impl TempoBounds {
+    /// Create tempo bounds, rejecting inverted ranges
+    pub fn new(min_bpm: f64, max_bpm: f64) -> Result<Self> {
+        if !(min_bpm > 0.0 && min_bpm <= max_bpm && max_bpm.is_finite()) {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!("Invalid tempo bounds {}..{}", min_bpm, max_bpm),
+            });
+        }
+        Ok(Self { min_bpm, max_bpm })
+    }
+
+    /// Check whether a tempo lies within these bounds
+    pub fn contains(&self, tempo: &Tempo) -> bool {
+        tempo.bpm() >= self.min_bpm && tempo.bpm() <= self.max_bpm
+    }
+}
+
+/// A metered passage: measures of rhythmic values under one time signature
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasureSequence {
+    /// Time signature every measure is checked against
+    time_signature: TimeSignature,
+    /// Measures in order
+    measures: Vec<Vec<RhythmicValue>>,
+    /// Whether the first measure is a pickup (anacrusis)
+    anacrusis: bool,
+    /// Performance tempo, if declared
+    tempo: Option<Tempo>,
+    /// Permitted tempo range, if declared
+    tempo_bounds: Option<TempoBounds>,
+}
+
+impl MeasureSequence {
+    /// Create an empty passage in the given time signature
+    pub fn new(time_signature: TimeSignature) -> Self {
+        Self {
+            time_signature,
+            measures: Vec::new(),
+            anacrusis: false,
+            tempo: None,
+            tempo_bounds: None,
+        }
+    }
+
+    /// Mark the first measure as a pickup
+    pub fn with_anacrusis(mut self) -> Self {
+        self.anacrusis = true;
+        self
+    }
+
+    /// Attach a performance tempo
+    pub fn with_tempo(mut self, tempo: Tempo) -> Self {
+        self.tempo = Some(tempo);
+        self
+    }
+
+    /// Attach a permitted tempo range
+    pub fn with_tempo_bounds(mut self, bounds: TempoBounds) -> Self {
+        self.tempo_bounds = Some(bounds);
+        self
+    }
+
+    /// Append a measure
+    pub fn push_measure(&mut self, measure: Vec<RhythmicValue>) {
+        self.measures.push(measure);
+    }
+
+    /// Get the time signature
+    pub fn time_signature(&self) -> TimeSignature {
+        self.time_signature
+    }
+
+    /// Get all measures
+    pub fn measures(&self) -> &[Vec<RhythmicValue>] {
+        &self.measures
+    }
+
+    /// Whether the first measure is a pickup
+    pub fn has_anacrusis(&self) -> bool {
+        self.anacrusis
+    }
+
+    /// Get the declared tempo
+    pub fn tempo(&self) -> Option<Tempo> {
+        self.tempo
+    }
+
+    /// Get the declared tempo range
+    pub fn tempo_bounds(&self) -> Option<TempoBounds> {
+        self.tempo_bounds
+    }
+
+    /// Exact duration of each measure
+    pub fn measure_durations(&self) -> Result<Vec<NoteDuration>> {
+        self.measures
+            .iter()
+            .map(|measure| {
+                measure
+                    .iter()
+                    .try_fold(NoteDuration::zero(), |acc, v| acc.checked_add(&v.duration()))
+            })
+            .collect()
+    }
+
+    /// Smallest tick resolution (ticks per whole note) on which every measure,
+    /// every note and the time signature land exactly
+    pub fn ticks_per_whole(&self) -> Result<u64> {
+        self.measures
+            .iter()
+            .flatten()
+            .map(|v| v.duration().denominator())
+            .try_fold(self.time_signature.measure_duration().denominator(), checked_lcm)
+    }
+
+    /// Total length of the passage in milliseconds at the declared tempo
+    pub fn total_duration_ms(&self) -> Option<f64> {
+        let tempo = self.tempo?;
+        let total = NoteDuration::sum(self.measure_durations().ok()?.iter()).ok()?;
+        Some(tempo.duration_ms(total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dotted_and_tuplet_durations() {
+        let dotted_quarter = RhythmicValue::dotted(NoteValue::Quarter, 1);
+        assert_eq!(dotted_quarter.duration(), NoteDuration::new(3, 8).unwrap());
+
+        let double_dotted_half = RhythmicValue::dotted(NoteValue::Half, 2);
+        assert_eq!(double_dotted_half.duration(), NoteDuration::new(7, 8).unwrap());
+
+        let triplet_eighth = RhythmicValue::in_tuplet(NoteValue::Eighth, Tuplet::triplet());
+        assert_eq!(triplet_eighth.duration(), NoteDuration::new(1, 12).unwrap());
+    }
+
+    #[test]
+    fn test_triplets_fill_a_beat_exactly() {
+        let triplet = RhythmicValue::in_tuplet(NoteValue::Eighth, Tuplet::triplet());
+        let beat: Vec<NoteDuration> = (0..3).map(|_| triplet.duration()).collect();
+        assert_eq!(NoteDuration::sum(beat.iter()).unwrap(), NoteValue::Quarter.duration());
+    }
+
+    #[test]
+    fn test_time_signature_validation() {
+        assert!(TimeSignature::new(3, 4).is_ok());
+        assert!(TimeSignature::new(6, 8).is_ok());
+        assert!(TimeSignature::new(4, 3).is_err());
+        assert!(TimeSignature::new(0, 4).is_err());
+        assert_eq!(TimeSignature::new(6, 8).unwrap().measure_duration(), NoteDuration::new(3, 4).unwrap());
+    }
+
+    #[test]
+    fn test_marcha_real_tempo() {
+        // Real Decreto 1560/1997: negra = 76
+        let tempo = Tempo::quarter(76.0).unwrap();
+        let negra = tempo.duration_ms(NoteValue::Quarter.duration());
+        let corchea = tempo.duration_ms(NoteValue::Eighth.duration());
+
+        assert!((negra - 789.47).abs() < 0.01);
+        assert!((corchea - negra / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_measure_sequence_ticks() {
+        let mut passage = MeasureSequence::new(TimeSignature::common());
+        passage.push_measure(vec![RhythmicValue::new(NoteValue::Half); 2]);
+        passage.push_measure(vec![RhythmicValue::in_tuplet(NoteValue::Quarter, Tuplet::triplet()); 6]);
+
+        let ticks = passage.ticks_per_whole().unwrap();
+        for duration in passage.measure_durations().unwrap() {
+            assert_eq!(duration.to_ticks(ticks), Some(ticks));
+        }
+    }
+
+    #[test]
+    fn test_duration_arithmetic_reports_overflow() {
+        // Dots are capped, so even an absurd count keeps a finite duration
+        assert_eq!(RhythmicValue::dotted(NoteValue::Quarter, 64).duration(), NoteDuration::new(15, 32).unwrap());
+        assert!(Tuplet::new(4294967291, 4).is_err());
+
+        let mut passage = MeasureSequence::new(TimeSignature::common());
+        let primes = [61, 59, 53, 47, 43, 41, 37, 31, 29, 23, 19, 17, 13];
+        passage.push_measure(
+            primes.iter().map(|&p| RhythmicValue::in_tuplet(NoteValue::Eighth, Tuplet::new(p, 1).unwrap())).collect(),
+        );
+        assert!(matches!(passage.ticks_per_whole(), Err(ZyrkomError::ConstraintError { .. })));
+        assert!(matches!(passage.measure_durations(), Err(ZyrkomError::ConstraintError { .. })));
+
+        let huge = NoteDuration::new(u64::MAX, 1).unwrap();
+        assert!(huge.checked_add(&huge).is_err());
+        assert!(huge.scale(2, 1).is_err());
+        assert_eq!(huge.to_ticks(2), None);
+    }
+}
\ No newline at end of file
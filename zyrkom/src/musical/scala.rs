@@ -0,0 +1,574 @@
+//! Scala tuning files: `.scl` scales and `.kbm` keyboard mappings
+//!
+//! Follows the format described at <https://www.huygens-fokker.org/scala/scl_format.html>.
+//! Lines starting with `!` are comments. Pitch lines containing a `.` are
+//! cents; all others are ratios (`3/2`) or integers (`2`). Only the first
+//! token of a pitch line is significant. The last pitch is the period of
+//! the scale, usually but not always the octave.
+
+use crate::musical::tuning::{PitchReference, TuningSystem, REFERENCE_STEP};
+use crate::musical::{constants, MusicalInterval};
+use crate::{Result, ZyrkomError};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Largest keyboard mapping accepted: one entry per MIDI key
+const MAX_MAP_SIZE: u32 = 128;
+
+/// One pitch of a Scala scale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScalaPitch {
+    /// Exact ratio `numerator/denominator`
+    Ratio(u64, u64),
+    /// Size in cents
+    Cents(f64),
+}
+
+impl ScalaPitch {
+    /// Size of the pitch above 1/1 in cents
+    pub fn cents(&self) -> f64 {
+        match *self {
+            ScalaPitch::Ratio(numerator, denominator) => {
+                MusicalInterval::ratio_to_cents(numerator as f64 / denominator as f64)
+            }
+            ScalaPitch::Cents(cents) => cents,
+        }
+    }
+
+    /// Parse the significant token of a pitch line
+    fn parse(token: &str, line: usize) -> Result<Self> {
+        let error = |message: String| ZyrkomError::ParseError { message, line };
+
+        if token.contains('.') {
+            let cents: f64 = token
+                .parse()
+                .map_err(|_| error(format!("Invalid cents value: '{}'", token)))?;
+            if !cents.is_finite() {
+                return Err(error(format!("Invalid cents value: '{}'", token)));
+            }
+            return Ok(ScalaPitch::Cents(cents));
+        }
+
+        let (numerator, denominator) = token.split_once('/').unwrap_or((token, "1"));
+        let parse_term = |term: &str| -> Result<u64> {
+            term.parse::<u64>()
+                .ok()
+                .filter(|&value| value > 0)
+                .ok_or_else(|| error(format!("Invalid ratio: '{}'", token)))
+        };
+
+        Ok(ScalaPitch::Ratio(parse_term(numerator)?, parse_term(denominator)?))
+    }
+}
+
+impl fmt::Display for ScalaPitch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ScalaPitch::Ratio(numerator, denominator) => write!(f, "{}/{}", numerator, denominator),
+            ScalaPitch::Cents(cents) => {
+                // Shortest round-tripping form, which must contain a '.' to read back as cents
+                let text = cents.to_string();
+                if text.contains('.') {
+                    write!(f, "{}", text)
+                } else {
+                    write!(f, "{}.0", text)
+                }
+            }
+        }
+    }
+}
+
+/// A Scala `.scl` scale
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalaScale {
+    /// One-line description
+    description: String,
+    /// Pitches above 1/1, the last being the period
+    pitches: Vec<ScalaPitch>,
+}
+
+impl ScalaScale {
+    /// Create a scale from a description and pitches, of which there must be
+    /// at least one to define a period
+    pub fn new(description: &str, pitches: Vec<ScalaPitch>) -> Result<Self> {
+        if pitches.is_empty() {
+            return Err(ZyrkomError::PhysicsError {
+                details: "Scala scale has no pitches".to_string(),
+            });
+        }
+        Ok(Self {
+            description: description.to_string(),
+            pitches,
+        })
+    }
+
+    /// Parse the contents of a `.scl` file
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut lines = content_lines(input);
+
+        let (_, description) = lines.next().ok_or_else(|| ZyrkomError::ParseError {
+            message: "Missing scale description".to_string(),
+            line: 0,
+        })?;
+
+        let (count_line, count_text) = next_value(&mut lines, "note count")?;
+        let count: usize = first_token(count_text)
+            .parse()
+            .map_err(|_| ZyrkomError::ParseError {
+                message: format!("Invalid note count: '{}'", count_text.trim()),
+                line: count_line,
+            })?;
+        // Legal in Scala, but a scale without a period has no degrees to play
+        if count == 0 {
+            return Err(ZyrkomError::ParseError {
+                message: "Scale has no pitches".to_string(),
+                line: count_line,
+            });
+        }
+
+        // The count is untrusted; pitches only grow with the lines actually present
+        let mut pitches = Vec::new();
+        for (line, text) in lines.filter(|(_, text)| !text.trim().is_empty()) {
+            if pitches.len() == count {
+                return Err(ZyrkomError::ParseError {
+                    message: format!("More pitches than the declared count of {}", count),
+                    line,
+                });
+            }
+            pitches.push(ScalaPitch::parse(first_token(text), line)?);
+        }
+
+        if pitches.len() != count {
+            return Err(ZyrkomError::ParseError {
+                message: format!("Expected {} pitches, found {}", count, pitches.len()),
+                line: count_line,
+            });
+        }
+
+        Ok(Self {
+            description: description.trim().to_string(),
+            pitches,
+        })
+    }
+
+    /// Write the scale in `.scl` format
+    pub fn to_scl(&self) -> String {
+        let mut output = format!("!\n{}\n {}\n!\n", self.description, self.pitches.len());
+        for pitch in &self.pitches {
+            output.push_str(&format!(" {}\n", pitch));
+        }
+        output
+    }
+
+    /// Get the description
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Get the pitches above 1/1
+    pub fn pitches(&self) -> &[ScalaPitch] {
+        &self.pitches
+    }
+
+    /// Size of the period (the last pitch) in cents
+    pub fn period_cents(&self) -> f64 {
+        self.pitches.last().map_or(constants::CENTS_PER_OCTAVE, ScalaPitch::cents)
+    }
+
+    /// Cents of a scale degree, repeating at the period
+    pub fn degree_cents(&self, degree: i64) -> f64 {
+        let size = self.pitches.len() as i64;
+        let within = degree.rem_euclid(size) as usize;
+        let base = if within == 0 { 0.0 } else { self.pitches[within - 1].cents() };
+        degree.div_euclid(size) as f64 * self.period_cents() + base
+    }
+}
+
+/// A Scala `.kbm` keyboard mapping
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardMapping {
+    /// First MIDI key to retune
+    pub first_key: u8,
+    /// Last MIDI key to retune
+    pub last_key: u8,
+    /// Key where the first mapping entry (degree 0) sounds
+    pub middle_key: u8,
+    /// Key whose frequency is given
+    pub reference_key: u8,
+    /// Frequency of the reference key in Hz
+    pub reference_frequency: f64,
+    /// Scale degree treated as the formal octave
+    pub octave_degree: u32,
+    /// Scale degree of each key in the repeating pattern (`None` = unmapped);
+    /// empty for a linear mapping
+    pub mapping: Vec<Option<u32>>,
+}
+
+impl KeyboardMapping {
+    /// Linear mapping: consecutive keys play consecutive degrees
+    pub fn linear(middle_key: u8, reference_key: u8, reference_frequency: f64, octave_degree: u32) -> Self {
+        Self {
+            first_key: 0,
+            last_key: 127,
+            middle_key,
+            reference_key,
+            reference_frequency,
+            octave_degree,
+            mapping: Vec::new(),
+        }
+    }
+
+    /// Parse the contents of a `.kbm` file
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut lines = content_lines(input).filter(|(_, text)| !text.trim().is_empty());
+
+        let (map_size_line, map_size) = next_integer(&mut lines, "map size")?;
+        if map_size > MAX_MAP_SIZE {
+            return Err(ZyrkomError::ParseError {
+                message: format!("Map size {} exceeds the limit of {}", map_size, MAX_MAP_SIZE),
+                line: map_size_line,
+            });
+        }
+        let first_key = next_key(&mut lines, "first key")?;
+        let last_key = next_key(&mut lines, "last key")?;
+        let middle_key = next_key(&mut lines, "middle key")?;
+        let reference_key = next_key(&mut lines, "reference key")?;
+
+        let (frequency_line, frequency_text) = next_value(&mut lines, "reference frequency")?;
+        let reference_frequency: f64 = first_token(frequency_text)
+            .parse()
+            .ok()
+            .filter(|f: &f64| f.is_finite() && *f > 0.0)
+            .ok_or_else(|| ZyrkomError::ParseError {
+                message: format!("Invalid reference frequency: '{}'", frequency_text.trim()),
+                line: frequency_line,
+            })?;
+
+        let (_, octave_degree) = next_integer(&mut lines, "octave degree")?;
+
+        let mut mapping = Vec::with_capacity(map_size as usize);
+        for (line, text) in lines {
+            if mapping.len() == map_size as usize {
+                return Err(ZyrkomError::ParseError {
+                    message: format!("More mapping entries than the map size of {}", map_size),
+                    line,
+                });
+            }
+            let entry = match first_token(text) {
+                "x" | "X" => None,
+                token => Some(token.parse().map_err(|_| ZyrkomError::ParseError {
+                    message: format!("Invalid mapping entry: '{}'", token),
+                    line,
+                })?),
+            };
+            mapping.push(entry);
+        }
+        // Missing trailing entries are unmapped
+        mapping.resize(map_size as usize, None);
+
+        Ok(Self {
+            first_key,
+            last_key,
+            middle_key,
+            reference_key,
+            reference_frequency,
+            octave_degree,
+            mapping,
+        })
+    }
+
+    /// Write the mapping in `.kbm` format
+    pub fn to_kbm(&self) -> String {
+        let mut output = format!(
+            "! Size of map:\n{}\n! First MIDI note number to retune:\n{}\n\
+             ! Last MIDI note number to retune:\n{}\n! Middle note:\n{}\n\
+             ! Reference note:\n{}\n! Reference frequency:\n{}\n\
+             ! Scale degree to consider as formal octave:\n{}\n! Mapping:\n",
+            self.mapping.len(),
+            self.first_key,
+            self.last_key,
+            self.middle_key,
+            self.reference_key,
+            ScalaPitch::Cents(self.reference_frequency),
+            self.octave_degree,
+        );
+        for entry in &self.mapping {
+            match entry {
+                Some(degree) => output.push_str(&format!("{}\n", degree)),
+                None => output.push_str("x\n"),
+            }
+        }
+        output
+    }
+
+    /// Scale degree played by a key, or `None` if the key is unmapped
+    pub fn degree_of(&self, key: i32) -> Option<i64> {
+        let offset = (key - self.middle_key as i32) as i64;
+        if self.mapping.is_empty() {
+            return Some(offset);
+        }
+
+        let size = self.mapping.len() as i64;
+        let entry = self.mapping[offset.rem_euclid(size) as usize]?;
+        Some(entry as i64 + offset.div_euclid(size) * self.octave_degree as i64)
+    }
+}
+
+/// A Scala scale played through a keyboard mapping
+///
+/// Steps are MIDI keys. Every key of the mapping must be mapped, since the
+/// tuning has to give each step a frequency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalaTuning {
+    /// The scale
+    scale: ScalaScale,
+    /// How keys select scale degrees
+    mapping: KeyboardMapping,
+}
+
+impl ScalaTuning {
+    /// Combine a scale with a keyboard mapping
+    pub fn new(scale: ScalaScale, mapping: KeyboardMapping) -> Result<Self> {
+        if scale.period_cents() <= 0.0 {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Scala scale period of {} cents is not positive", scale.period_cents()),
+            });
+        }
+        if mapping.mapping.iter().any(Option::is_none) {
+            return Err(ZyrkomError::PhysicsError {
+                details: "Keyboard mapping has unmapped keys".to_string(),
+            });
+        }
+        Ok(Self { scale, mapping })
+    }
+
+    /// Scale on a linear keyboard with degree 0 on middle C and key 69 at 440 Hz
+    pub fn with_standard_mapping(scale: ScalaScale) -> Result<Self> {
+        let octave_degree = scale.pitches().len() as u32;
+        let mapping = KeyboardMapping::linear(60, 69, constants::CONCERT_PITCH_A4, octave_degree);
+        Self::new(scale, mapping)
+    }
+
+    /// Get the scale
+    pub fn scale(&self) -> &ScalaScale {
+        &self.scale
+    }
+
+    /// Get the keyboard mapping
+    pub fn mapping(&self) -> &KeyboardMapping {
+        &self.mapping
+    }
+
+    /// Cents of a key relative to the middle key
+    fn key_cents(&self, key: i32) -> f64 {
+        let degree = self.mapping.degree_of(key).expect("all keys are mapped");
+        self.scale.degree_cents(degree)
+    }
+}
+
+impl TuningSystem for ScalaTuning {
+    fn name(&self) -> String {
+        if self.scale.description().is_empty() {
+            "Scala scale".to_string()
+        } else {
+            self.scale.description().to_string()
+        }
+    }
+
+    fn tuning_id(&self) -> u32 {
+        // Digest of the canonical files, kept clear of the built-in identifiers
+        let mut hasher = Sha256::new();
+        hasher.update(self.scale.to_scl());
+        hasher.update(self.mapping.to_kbm());
+        let digest = hasher.finalize();
+        0x4000_0000 | (u32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]]) & 0x3FFF_FFFF)
+    }
+
+    fn divisions(&self) -> u32 {
+        self.scale.pitches().len() as u32
+    }
+
+    fn degree_cents(&self, degree: u32) -> f64 {
+        self.scale.degree_cents(degree as i64)
+    }
+
+    fn tonic(&self) -> i32 {
+        self.mapping.middle_key as i32
+    }
+
+    fn reference(&self) -> PitchReference {
+        // The mapping may fix any key; report the pitch it implies for A4,
+        // or standard pitch if that lies outside the concert pitch range
+        PitchReference::new(self.frequency(REFERENCE_STEP)).unwrap_or_default()
+    }
+
+    fn step_cents(&self, step: i32) -> f64 {
+        self.key_cents(step)
+    }
+
+    fn frequency(&self, step: i32) -> f64 {
+        let cents = self.key_cents(step) - self.key_cents(self.mapping.reference_key as i32);
+        self.mapping.reference_frequency * MusicalInterval::cents_to_ratio(cents)
+    }
+
+    fn nearest_step(&self, frequency: f64) -> i32 {
+        // Steps are not evenly spaced in general, so search a period either side
+        let keys_per_period = self.mapping.mapping.len().max(self.scale.pitches().len()) as i32;
+        let period_cents = self.scale.period_cents() * keys_per_period as f64 / self.divisions() as f64;
+        let cents = MusicalInterval::ratio_to_cents(frequency / self.mapping.reference_frequency);
+        let estimate = self.mapping.reference_key as i32
+            + (cents / period_cents * keys_per_period as f64).round() as i32;
+
+        (estimate - keys_per_period..=estimate + keys_per_period)
+            .min_by(|&a, &b| {
+                let distance = |s: i32| MusicalInterval::ratio_to_cents(frequency / self.frequency(s)).abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .unwrap_or(estimate)
+    }
+}
+
+/// Non-comment lines with their 1-based line numbers
+fn content_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, text)| (index + 1, text))
+        .filter(|(_, text)| !text.starts_with('!'))
+}
+
+/// Next line of a header, or an error naming the missing field
+fn next_value<'a>(lines: &mut impl Iterator<Item = (usize, &'a str)>, what: &str) -> Result<(usize, &'a str)> {
+    lines.next().ok_or_else(|| ZyrkomError::ParseError {
+        message: format!("Missing {}", what),
+        line: 0,
+    })
+}
+
+/// First whitespace-separated token of a line
+fn first_token(text: &str) -> &str {
+    text.split_whitespace().next().unwrap_or("")
+}
+
+/// Next line parsed as a non-negative integer
+fn next_integer<'a>(lines: &mut impl Iterator<Item = (usize, &'a str)>, what: &str) -> Result<(usize, u32)> {
+    let (line, text) = next_value(lines, what)?;
+    let value = first_token(text).parse().map_err(|_| ZyrkomError::ParseError {
+        message: format!("Invalid {}: '{}'", what, text.trim()),
+        line,
+    })?;
+    Ok((line, value))
+}
+
+/// Next line parsed as a MIDI key number
+fn next_key<'a>(lines: &mut impl Iterator<Item = (usize, &'a str)>, what: &str) -> Result<u8> {
+    let (line, value) = next_integer(lines, what)?;
+    u8::try_from(value).ok().filter(|&key| key <= 127).ok_or_else(|| ZyrkomError::ParseError {
+        message: format!("{} {} is not a MIDI key", what, value),
+        line,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_SCALES: [&str; 5] = [
+        include_str!("../../scales/12-tet.scl"),
+        include_str!("../../scales/ji-12.scl"),
+        include_str!("../../scales/meanquar.scl"),
+        include_str!("../../scales/bohlen-pierce.scl"),
+        include_str!("../../scales/slendro.scl"),
+    ];
+
+    const SAMPLE_MAPPINGS: [&str; 2] = [
+        include_str!("../../scales/a440.kbm"),
+        include_str!("../../scales/slendro-white-keys.kbm"),
+    ];
+
+    #[test]
+    fn test_sample_files_round_trip() {
+        for source in SAMPLE_SCALES {
+            let scale = ScalaScale::parse(source).unwrap();
+            assert_eq!(ScalaScale::parse(&scale.to_scl()).unwrap(), scale);
+        }
+        for source in SAMPLE_MAPPINGS {
+            let mapping = KeyboardMapping::parse(source).unwrap();
+            assert_eq!(KeyboardMapping::parse(&mapping.to_kbm()).unwrap(), mapping);
+        }
+    }
+
+    #[test]
+    fn test_ratio_and_cents_lines() {
+        let ji = ScalaScale::parse(SAMPLE_SCALES[1]).unwrap();
+        assert_eq!(ji.pitches()[3], ScalaPitch::Ratio(5, 4));
+        assert_eq!(ji.description(), "5-limit just intonation, 12 tones");
+
+        // Trailing text after the value is ignored
+        let slendro = ScalaScale::parse(SAMPLE_SCALES[4]).unwrap();
+        assert_eq!(slendro.pitches()[0], ScalaPitch::Cents(231.0));
+        assert!((slendro.period_cents() - 1206.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tuning_from_scala_files() {
+        let scale = ScalaScale::parse(SAMPLE_SCALES[0]).unwrap();
+        let mapping = KeyboardMapping::parse(SAMPLE_MAPPINGS[0]).unwrap();
+        let tuning = ScalaTuning::new(scale, mapping).unwrap();
+        assert!((tuning.frequency(69) - 440.0).abs() < 1e-9);
+        assert!((tuning.frequency(60) - 261.6256).abs() < 1e-3);
+        assert_eq!(tuning.nearest_step(262.0), 60);
+
+        // Bohlen-Pierce repeats at the tritave, not the octave
+        let bp = ScalaTuning::with_standard_mapping(ScalaScale::parse(SAMPLE_SCALES[3]).unwrap()).unwrap();
+        let tritave = bp.frequency(60 + 13) / bp.frequency(60);
+        assert!((tritave - 3.0).abs() < 1e-9);
+
+        // Unmapped keys cannot form a tuning
+        let slendro = ScalaScale::parse(SAMPLE_SCALES[4]).unwrap();
+        assert!(ScalaTuning::new(slendro, KeyboardMapping::parse(SAMPLE_MAPPINGS[1]).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_errors_report_line_numbers() {
+        let bad_ratio = "! comment\nbroken\n 2\n 3/2\n 2/0\n";
+        match ScalaScale::parse(bad_ratio) {
+            Err(ZyrkomError::ParseError { line, .. }) => assert_eq!(line, 5),
+            other => panic!("expected parse error, got {:?}", other),
+        }
+
+        let short = "short\n 3\n 3/2\n 2/1\n";
+        match ScalaScale::parse(short) {
+            Err(ZyrkomError::ParseError { line, message }) => {
+                assert_eq!(line, 2);
+                assert!(message.contains("Expected 3 pitches"));
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+
+        // A 0-pitch scale has no period to repeat degrees at
+        match ScalaScale::parse("empty\n 0\n") {
+            Err(ZyrkomError::ParseError { line, message }) => {
+                assert_eq!(line, 2);
+                assert!(message.contains("no pitches"));
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+        assert!(ScalaScale::new("empty", Vec::new()).is_err());
+
+        let huge_map = "! size\n4294967295\n0\n127\n60\n69\n440.0\n12\n";
+        match KeyboardMapping::parse(huge_map) {
+            Err(ZyrkomError::ParseError { line, message }) => {
+                assert_eq!(line, 2);
+                assert!(message.contains("exceeds the limit"));
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+
+        let bad_key = "12\n0\n127\n60\n200\n440.0\n12\n";
+        match KeyboardMapping::parse(bad_key) {
+            Err(ZyrkomError::ParseError { line, .. }) => assert_eq!(line, 5),
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,358 @@
+//! Scales: a tonic plus an octave-filling pattern of semitone steps
+//!
+//! Named patterns cover the major and minor scales, the church modes,
+//! pentatonic, whole-tone and octatonic collections; any other pattern of
+//! positive steps summing to an octave can be used as a custom scale. Degrees
+//! are built on the tonic's exact pitch, so a microtonally offset tonic keeps
+//! its offset on every degree.
+
+use crate::musical::MusicalNote;
+use crate::{Result, ZyrkomError};
+
+/// Semitones in the octave a scale pattern must fill
+pub const OCTAVE_SEMITONES: u8 = 12;
+
+/// Largest offset, in cents, at which a note still counts as a scale member
+const MEMBERSHIP_TOLERANCE_CENTS: f64 = 1.0;
+
+/// Named scale patterns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScaleKind {
+    /// Major scale (Ionian): W W H W W W H
+    Major,
+    /// Natural minor scale (Aeolian): W H W W H W W
+    NaturalMinor,
+    /// Harmonic minor: natural minor with a raised seventh
+    HarmonicMinor,
+    /// Melodic minor in its ascending form: raised sixth and seventh
+    MelodicMinor,
+    /// Dorian mode: W H W W W H W
+    Dorian,
+    /// Phrygian mode: H W W W H W W
+    Phrygian,
+    /// Lydian mode: W W W H W W H
+    Lydian,
+    /// Mixolydian mode: W W H W W H W
+    Mixolydian,
+    /// Locrian mode: H W W H W W W
+    Locrian,
+    /// Major pentatonic: W W m3 W m3
+    MajorPentatonic,
+    /// Minor pentatonic: m3 W W m3 W
+    MinorPentatonic,
+    /// Whole-tone scale: six whole steps
+    WholeTone,
+    /// Octatonic scale starting with a half step
+    OctatonicHalfWhole,
+    /// Octatonic scale starting with a whole step
+    OctatonicWholeHalf,
+}
+
+impl ScaleKind {
+    /// Step pattern in semitones, from the tonic up to its octave
+    pub fn steps(&self) -> &'static [u8] {
+        match self {
+            ScaleKind::Major => &[2, 2, 1, 2, 2, 2, 1],
+            ScaleKind::NaturalMinor => &[2, 1, 2, 2, 1, 2, 2],
+            ScaleKind::HarmonicMinor => &[2, 1, 2, 2, 1, 3, 1],
+            ScaleKind::MelodicMinor => &[2, 1, 2, 2, 2, 2, 1],
+            ScaleKind::Dorian => &[2, 1, 2, 2, 2, 1, 2],
+            ScaleKind::Phrygian => &[1, 2, 2, 2, 1, 2, 2],
+            ScaleKind::Lydian => &[2, 2, 2, 1, 2, 2, 1],
+            ScaleKind::Mixolydian => &[2, 2, 1, 2, 2, 1, 2],
+            ScaleKind::Locrian => &[1, 2, 2, 1, 2, 2, 2],
+            ScaleKind::MajorPentatonic => &[2, 2, 3, 2, 3],
+            ScaleKind::MinorPentatonic => &[3, 2, 2, 3, 2],
+            ScaleKind::WholeTone => &[2, 2, 2, 2, 2, 2],
+            ScaleKind::OctatonicHalfWhole => &[1, 2, 1, 2, 1, 2, 1, 2],
+            ScaleKind::OctatonicWholeHalf => &[2, 1, 2, 1, 2, 1, 2, 1],
+        }
+    }
+
+    /// Human-readable name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScaleKind::Major => "major",
+            ScaleKind::NaturalMinor => "minor",
+            ScaleKind::HarmonicMinor => "harmonic minor",
+            ScaleKind::MelodicMinor => "melodic minor",
+            ScaleKind::Dorian => "dorian",
+            ScaleKind::Phrygian => "phrygian",
+            ScaleKind::Lydian => "lydian",
+            ScaleKind::Mixolydian => "mixolydian",
+            ScaleKind::Locrian => "locrian",
+            ScaleKind::MajorPentatonic => "major pentatonic",
+            ScaleKind::MinorPentatonic => "minor pentatonic",
+            ScaleKind::WholeTone => "whole tone",
+            ScaleKind::OctatonicHalfWhole => "octatonic half-whole",
+            ScaleKind::OctatonicWholeHalf => "octatonic whole-half",
+        }
+    }
+
+    /// Look up a pattern by name ("major", "aeolian", "harmonic minor", ...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized = name.trim().to_lowercase().replace(['_', '-'], " ");
+        let kind = match normalized.as_str() {
+            "major" | "ionian" => ScaleKind::Major,
+            "minor" | "natural minor" | "aeolian" => ScaleKind::NaturalMinor,
+            "harmonic minor" => ScaleKind::HarmonicMinor,
+            "melodic minor" => ScaleKind::MelodicMinor,
+            "dorian" => ScaleKind::Dorian,
+            "phrygian" => ScaleKind::Phrygian,
+            "lydian" => ScaleKind::Lydian,
+            "mixolydian" => ScaleKind::Mixolydian,
+            "locrian" => ScaleKind::Locrian,
+            "pentatonic" | "major pentatonic" => ScaleKind::MajorPentatonic,
+            "minor pentatonic" => ScaleKind::MinorPentatonic,
+            "whole tone" => ScaleKind::WholeTone,
+            "octatonic" | "octatonic half whole" | "diminished" => ScaleKind::OctatonicHalfWhole,
+            "octatonic whole half" => ScaleKind::OctatonicWholeHalf,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+/// A scale: tonic note plus a step pattern spanning one octave
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scale {
+    /// First degree of the scale
+    tonic: MusicalNote,
+    /// Semitone steps between consecutive degrees, summing to an octave
+    steps: Vec<u8>,
+    /// Named pattern, if the scale was built from one
+    kind: Option<ScaleKind>,
+}
+
+impl Scale {
+    /// Create a scale from a named pattern
+    pub fn new(tonic: MusicalNote, kind: ScaleKind) -> Self {
+        Self {
+            tonic,
+            steps: kind.steps().to_vec(),
+            kind: Some(kind),
+        }
+    }
+
+    /// Create a scale from a user-defined step pattern
+    pub fn custom(tonic: MusicalNote, steps: &[u8]) -> Result<Self> {
+        if steps.is_empty() || steps.contains(&0) {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Scale steps must be positive, got {:?}", steps),
+            });
+        }
+        let span: u32 = steps.iter().map(|&s| s as u32).sum();
+        if span != OCTAVE_SEMITONES as u32 {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Scale steps {:?} span {} semitones, not an octave", steps, span),
+            });
+        }
+
+        Ok(Self {
+            tonic,
+            steps: steps.to_vec(),
+            kind: None,
+        })
+    }
+
+    /// Major scale on a tonic
+    pub fn major(tonic: MusicalNote) -> Self {
+        Self::new(tonic, ScaleKind::Major)
+    }
+
+    /// Natural minor scale on a tonic
+    pub fn minor(tonic: MusicalNote) -> Self {
+        Self::new(tonic, ScaleKind::NaturalMinor)
+    }
+
+    /// Get the tonic
+    pub fn tonic(&self) -> MusicalNote {
+        self.tonic
+    }
+
+    /// Get the step pattern in semitones
+    pub fn steps(&self) -> &[u8] {
+        &self.steps
+    }
+
+    /// Get the named pattern, if any
+    pub fn kind(&self) -> Option<ScaleKind> {
+        self.kind
+    }
+
+    /// Number of distinct degrees per octave
+    pub fn degree_count(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Semitone offsets of each degree above the tonic, starting at 0
+    pub fn offsets(&self) -> Vec<u8> {
+        self.steps
+            .iter()
+            .scan(0, |offset, &step| {
+                let current = *offset;
+                *offset += step;
+                Some(current)
+            })
+            .collect()
+    }
+
+    /// Semitones from the tonic to a 1-based degree; degrees beyond the
+    /// pattern continue into higher octaves and degrees below 1 into lower ones
+    pub fn degree_semitones(&self, degree: i32) -> Result<i32> {
+        let count = self.degree_count() as i32;
+        let offsets = self.offsets();
+        degree
+            .checked_sub(1)
+            .and_then(|index| {
+                let octave = index.div_euclid(count).checked_mul(OCTAVE_SEMITONES as i32)?;
+                octave.checked_add(offsets[index.rem_euclid(count) as usize] as i32)
+            })
+            .ok_or_else(|| Self::outside_midi_range(degree))
+    }
+
+    /// Note at a 1-based degree (1 = tonic, 8 = octave in a heptatonic scale)
+    pub fn degree(&self, degree: i32) -> Result<MusicalNote> {
+        let midi = (self.tonic.midi_number() as i32)
+            .checked_add(self.degree_semitones(degree)?)
+            .and_then(|midi| u8::try_from(midi).ok())
+            .ok_or_else(|| Self::outside_midi_range(degree))?;
+        MusicalNote::from_midi_cents_at(midi, self.tonic.cents(), self.tonic.reference())
+    }
+
+    fn outside_midi_range(degree: i32) -> ZyrkomError {
+        ZyrkomError::PhysicsError {
+            details: format!("Scale degree {} is outside the MIDI range", degree),
+        }
+    }
+
+    /// 1-based degree of a note's pitch class, if it belongs to the scale
+    pub fn degree_of(&self, note: &MusicalNote) -> Option<usize> {
+        let distance = note.exact_midi() - self.tonic.exact_midi();
+        let semitones = distance.round();
+        if (distance - semitones).abs() * 100.0 > MEMBERSHIP_TOLERANCE_CENTS {
+            return None;
+        }
+
+        let pitch_class = (semitones as i32).rem_euclid(OCTAVE_SEMITONES as i32) as u8;
+        self.offsets().iter().position(|&offset| offset == pitch_class).map(|i| i + 1)
+    }
+
+    /// Whether a note's pitch class belongs to the scale
+    pub fn contains(&self, note: &MusicalNote) -> bool {
+        self.degree_of(note).is_some()
+    }
+
+    /// The mode starting on a 1-based degree (e.g. degree 2 of major is dorian)
+    pub fn mode(&self, degree: usize) -> Result<Self> {
+        if degree == 0 || degree > self.degree_count() {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Scale has no degree {}", degree),
+            });
+        }
+
+        let mut steps = self.steps.clone();
+        steps.rotate_left(degree - 1);
+        let mut mode = Self::custom(self.degree(degree as i32)?, &steps)?;
+        mode.kind = [
+            ScaleKind::Major,
+            ScaleKind::Dorian,
+            ScaleKind::Phrygian,
+            ScaleKind::Lydian,
+            ScaleKind::Mixolydian,
+            ScaleKind::NaturalMinor,
+            ScaleKind::Locrian,
+        ]
+        .into_iter()
+        .find(|kind| kind.steps() == steps.as_slice());
+        Ok(mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::musical::PitchReference;
+
+    #[test]
+    fn test_named_patterns_fill_an_octave() {
+        let all = [
+            ScaleKind::Major,
+            ScaleKind::NaturalMinor,
+            ScaleKind::HarmonicMinor,
+            ScaleKind::MelodicMinor,
+            ScaleKind::Dorian,
+            ScaleKind::Phrygian,
+            ScaleKind::Lydian,
+            ScaleKind::Mixolydian,
+            ScaleKind::Locrian,
+            ScaleKind::MajorPentatonic,
+            ScaleKind::MinorPentatonic,
+            ScaleKind::WholeTone,
+            ScaleKind::OctatonicHalfWhole,
+            ScaleKind::OctatonicWholeHalf,
+        ];
+        for kind in all {
+            let span: u8 = kind.steps().iter().sum();
+            assert_eq!(span, OCTAVE_SEMITONES, "{}", kind.name());
+            assert_eq!(ScaleKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(ScaleKind::from_name("Aeolian"), Some(ScaleKind::NaturalMinor));
+    }
+
+    #[test]
+    fn test_degree_lookup() {
+        let f_major = Scale::major(MusicalNote::from_midi(65).unwrap());
+        assert_eq!(f_major.degree(4).unwrap().midi_number(), 70); // Bb4
+        assert_eq!(f_major.degree(8).unwrap().midi_number(), 77); // F5
+        assert_eq!(f_major.degree(0).unwrap().midi_number(), 64); // E4, leading tone below
+
+        let a_harmonic = Scale::new(MusicalNote::from_midi(57).unwrap(), ScaleKind::HarmonicMinor);
+        assert_eq!(a_harmonic.degree(7).unwrap().midi_number(), 68); // G#4
+
+        assert!(f_major.degree(i32::MAX).is_err());
+        assert!(f_major.degree(i32::MIN).is_err());
+        assert!(f_major.degree_semitones(i32::MIN).is_err());
+    }
+
+    #[test]
+    fn test_membership() {
+        let c_major = Scale::major(MusicalNote::from_midi(60).unwrap());
+        assert!(c_major.contains(&MusicalNote::from_midi(71).unwrap()));
+        assert!(c_major.contains(&MusicalNote::from_midi(48).unwrap()));
+        assert!(!c_major.contains(&MusicalNote::from_midi(61).unwrap()));
+        assert_eq!(c_major.degree_of(&MusicalNote::from_midi(67).unwrap()), Some(5));
+
+        let whole_tone = Scale::new(MusicalNote::from_midi(60).unwrap(), ScaleKind::WholeTone);
+        assert!(whole_tone.contains(&MusicalNote::from_midi(66).unwrap()));
+        assert!(!whole_tone.contains(&MusicalNote::from_midi(67).unwrap()));
+    }
+
+    #[test]
+    fn test_microtonal_tonic_carries_offset() {
+        let tonic = MusicalNote::from_midi_cents_at(60, -14.0, PitchReference::standard()).unwrap();
+        let scale = Scale::minor(tonic);
+        let fifth = scale.degree(5).unwrap();
+        assert_eq!(fifth.midi_number(), 67);
+        assert!((fifth.cents() + 14.0).abs() < 1e-6);
+
+        // A tempered G is 14 cents away from this scale's fifth
+        assert!(scale.contains(&fifth));
+        assert!(!scale.contains(&MusicalNote::from_midi(67).unwrap()));
+    }
+
+    #[test]
+    fn test_custom_scales_and_modes() {
+        let c = MusicalNote::from_midi(60).unwrap();
+        assert!(Scale::custom(c, &[2, 2, 2]).is_err());
+        assert!(Scale::custom(c, &[0, 12]).is_err());
+
+        let hirajoshi = Scale::custom(c, &[2, 1, 4, 1, 4]).unwrap();
+        assert_eq!(hirajoshi.offsets(), vec![0, 2, 3, 7, 8]);
+        assert_eq!(hirajoshi.kind(), None);
+
+        let d_dorian = Scale::major(c).mode(2).unwrap();
+        assert_eq!(d_dorian.kind(), Some(ScaleKind::Dorian));
+        assert_eq!(d_dorian.tonic().midi_number(), 62);
+    }
+}
\ No newline at end of file
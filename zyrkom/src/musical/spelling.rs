@@ -0,0 +1,473 @@
+//! Spelled pitches: letter name, accidental and octave
+//!
+//! Unlike a MIDI number, a spelling tells C#4 from Db4 and an augmented
+//! fourth from a diminished fifth. Names can be parsed in English ("Bb4"),
+//! Latin solfège ("SIb4", "DO#3") or German ("H", "Es", "Fis4"). The octave
+//! is optional and defaults to 4; octaves follow scientific pitch notation.
+
+use crate::musical::{MusicalNote, PitchReference};
+use crate::{Result, ZyrkomError};
+use std::fmt;
+
+/// Octave assumed when a name has none
+const DEFAULT_OCTAVE: i8 = 4;
+
+/// Semitones above C of each natural letter
+const NATURAL_SEMITONES: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+/// Solfège syllables in letter order (SOL before SO so the longer match wins)
+const SOLFEGE_SYLLABLES: [(&str, Letter); 8] = [
+    ("DO", Letter::C),
+    ("RE", Letter::D),
+    ("MI", Letter::E),
+    ("FA", Letter::F),
+    ("SOL", Letter::G),
+    ("LA", Letter::A),
+    ("SI", Letter::B),
+    ("TI", Letter::B),
+];
+
+/// A natural note letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Letter {
+    /// C (DO)
+    C,
+    /// D (RE)
+    D,
+    /// E (MI)
+    E,
+    /// F (FA)
+    F,
+    /// G (SOL)
+    G,
+    /// A (LA)
+    A,
+    /// B (SI; H in German)
+    B,
+}
+
+impl Letter {
+    /// All letters from C upwards
+    pub const ALL: [Letter; 7] = [Letter::C, Letter::D, Letter::E, Letter::F, Letter::G, Letter::A, Letter::B];
+
+    /// Position from C (C = 0, B = 6)
+    pub fn index(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Semitones of the natural note above C
+    pub fn natural_semitones(&self) -> i32 {
+        NATURAL_SEMITONES[self.index() as usize]
+    }
+
+    fn from_english(c: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|letter| format!("{:?}", letter).starts_with(c.to_ascii_uppercase()))
+    }
+}
+
+/// A chromatic alteration of a letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Accidental {
+    /// Two semitones down
+    DoubleFlat,
+    /// One semitone down
+    Flat,
+    /// No alteration
+    Natural,
+    /// One semitone up
+    Sharp,
+    /// Two semitones up
+    DoubleSharp,
+}
+
+impl Accidental {
+    /// Alteration in semitones
+    pub fn semitones(&self) -> i32 {
+        match self {
+            Accidental::DoubleFlat => -2,
+            Accidental::Flat => -1,
+            Accidental::Natural => 0,
+            Accidental::Sharp => 1,
+            Accidental::DoubleSharp => 2,
+        }
+    }
+
+    fn from_semitones(semitones: i32) -> Option<Self> {
+        match semitones {
+            -2 => Some(Accidental::DoubleFlat),
+            -1 => Some(Accidental::Flat),
+            0 => Some(Accidental::Natural),
+            1 => Some(Accidental::Sharp),
+            2 => Some(Accidental::DoubleSharp),
+            _ => None,
+        }
+    }
+
+    /// Parse English/solfège accidental symbols: `#`, `b`, `♯`, `♭`, `x`, doubled
+    fn parse_symbols(symbols: &str) -> Option<Self> {
+        let semitones = symbols.chars().try_fold(0, |total, c| match c {
+            '#' | '♯' => Some(total + 1),
+            'x' => Some(total + 2),
+            'b' | 'B' | '♭' => Some(total - 1),
+            '♮' => Some(total),
+            _ => None,
+        })?;
+        Self::from_semitones(semitones)
+    }
+}
+
+impl fmt::Display for Accidental {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Accidental::DoubleFlat => "bb",
+            Accidental::Flat => "b",
+            Accidental::Natural => "",
+            Accidental::Sharp => "#",
+            Accidental::DoubleSharp => "##",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// A pitch with its spelling: letter, accidental and octave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpelledPitch {
+    /// Note letter
+    letter: Letter,
+    /// Alteration of the letter
+    accidental: Accidental,
+    /// Octave in scientific pitch notation (C4 = middle C)
+    octave: i8,
+}
+
+impl SpelledPitch {
+    /// Create a spelled pitch
+    pub fn new(letter: Letter, accidental: Accidental, octave: i8) -> Self {
+        Self { letter, accidental, octave }
+    }
+
+    /// Parse a name in English or solfège, detected from its first letters
+    pub fn parse(name: &str) -> Result<Self> {
+        let upper = name.to_ascii_uppercase();
+        if SOLFEGE_SYLLABLES.iter().any(|(syllable, _)| upper.starts_with(syllable)) {
+            Self::parse_solfege(name)
+        } else {
+            Self::parse_english(name)
+        }
+    }
+
+    /// Parse an English name such as "Bb4", "F#", "C##-1"
+    pub fn parse_english(name: &str) -> Result<Self> {
+        let mut chars = name.chars();
+        let letter = chars
+            .next()
+            .and_then(Letter::from_english)
+            .ok_or_else(|| invalid(name, "unknown note letter"))?;
+        Self::with_suffix(name, letter, chars.as_str(), Accidental::parse_symbols)
+    }
+
+    /// Parse a Latin solfège name such as "SIb4", "DO#3" or "SOL"
+    pub fn parse_solfege(name: &str) -> Result<Self> {
+        let upper = name.to_ascii_uppercase();
+        let (syllable, letter) = SOLFEGE_SYLLABLES
+            .iter()
+            .filter(|(syllable, _)| upper.starts_with(syllable))
+            .max_by_key(|(syllable, _)| syllable.len())
+            .ok_or_else(|| invalid(name, "unknown solfège syllable"))?;
+        Self::with_suffix(name, *letter, &name[syllable.len()..], Accidental::parse_symbols)
+    }
+
+    /// Parse a German name such as "H", "B" (B flat), "Es", "Fis4", "Ceses"
+    pub fn parse_german(name: &str) -> Result<Self> {
+        let lower = name.to_ascii_lowercase();
+        let (letter_part, octave_part) = split_octave(&lower);
+
+        let (letter, accidental) = match letter_part {
+            "b" => (Letter::B, Accidental::Flat),
+            "heses" => (Letter::B, Accidental::DoubleFlat),
+            "as" => (Letter::A, Accidental::Flat),
+            "asas" => (Letter::A, Accidental::DoubleFlat),
+            "es" => (Letter::E, Accidental::Flat),
+            "eses" => (Letter::E, Accidental::DoubleFlat),
+            _ => {
+                let mut chars = letter_part.chars();
+                let letter = match chars.next() {
+                    Some('h') => Letter::B,
+                    Some(c) if c != 'b' => Letter::from_english(c).ok_or_else(|| invalid(name, "unknown note letter"))?,
+                    _ => return Err(invalid(name, "unknown note letter")),
+                };
+                let accidental = match chars.as_str() {
+                    "" => Accidental::Natural,
+                    "is" => Accidental::Sharp,
+                    "isis" => Accidental::DoubleSharp,
+                    "es" => Accidental::Flat,
+                    "eses" => Accidental::DoubleFlat,
+                    _ => return Err(invalid(name, "unknown German suffix")),
+                };
+                (letter, accidental)
+            }
+        };
+
+        Ok(Self::new(letter, accidental, parse_octave(name, octave_part)?))
+    }
+
+    /// Get the letter
+    pub fn letter(&self) -> Letter {
+        self.letter
+    }
+
+    /// Get the accidental
+    pub fn accidental(&self) -> Accidental {
+        self.accidental
+    }
+
+    /// Get the octave
+    pub fn octave(&self) -> i8 {
+        self.octave
+    }
+
+    /// MIDI number (may lie outside 0..=127 for extreme spellings)
+    pub fn midi_number(&self) -> i32 {
+        (self.octave as i32 + 1) * 12 + self.letter.natural_semitones() + self.accidental.semitones()
+    }
+
+    /// Pitch class (C = 0 ... B = 11)
+    pub fn pitch_class(&self) -> u8 {
+        self.midi_number().rem_euclid(12) as u8
+    }
+
+    /// Whether two spellings sound the same pitch (e.g. C#4 and Db4)
+    pub fn is_enharmonic(&self, other: &SpelledPitch) -> bool {
+        self.midi_number() == other.midi_number()
+    }
+
+    /// Sounding note at a given concert pitch
+    pub fn to_note(&self, reference: PitchReference) -> Result<MusicalNote> {
+        let midi = u8::try_from(self.midi_number())
+            .ok()
+            .filter(|&m| m <= 127)
+            .ok_or_else(|| ZyrkomError::PhysicsError {
+                details: format!("{} is outside the MIDI range", self),
+            })?;
+        MusicalNote::from_midi_at(midi, reference)
+    }
+
+    /// Spelled interval from this pitch to another
+    pub fn interval_to(&self, other: &SpelledPitch) -> SpelledInterval {
+        let diatonic = |p: &SpelledPitch| p.octave as i32 * 7 + p.letter.index();
+        let steps = diatonic(other) - diatonic(self);
+        let semitones = other.midi_number() - self.midi_number();
+
+        let descending = steps < 0 || (steps == 0 && semitones < 0);
+        let (steps, semitones) = if descending { (-steps, -semitones) } else { (steps, semitones) };
+
+        let simple = steps.rem_euclid(7);
+        let reference = steps.div_euclid(7) * 12 + NATURAL_SEMITONES[simple as usize];
+        let difference = semitones - reference;
+
+        let quality = if matches!(simple, 0 | 3 | 4) {
+            match difference {
+                0 => IntervalQuality::Perfect,
+                d if d > 0 => IntervalQuality::Augmented(d as u8),
+                d => IntervalQuality::Diminished((-d) as u8),
+            }
+        } else {
+            match difference {
+                0 => IntervalQuality::Major,
+                -1 => IntervalQuality::Minor,
+                d if d > 0 => IntervalQuality::Augmented(d as u8),
+                d => IntervalQuality::Diminished((-d - 1) as u8),
+            }
+        };
+
+        SpelledInterval {
+            number: steps as u32 + 1,
+            quality,
+            descending,
+        }
+    }
+
+    fn with_suffix(
+        name: &str,
+        letter: Letter,
+        suffix: &str,
+        accidental: impl Fn(&str) -> Option<Accidental>,
+    ) -> Result<Self> {
+        let (symbols, octave) = split_octave(suffix);
+        let accidental = accidental(symbols).ok_or_else(|| invalid(name, "unknown accidental"))?;
+        Ok(Self::new(letter, accidental, parse_octave(name, octave)?))
+    }
+}
+
+impl fmt::Display for SpelledPitch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}{}{}", self.letter, self.accidental, self.octave)
+    }
+}
+
+/// Quality of a spelled interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntervalQuality {
+    /// Diminished by the given number of semitones (1 = diminished)
+    Diminished(u8),
+    /// Minor (seconds, thirds, sixths, sevenths)
+    Minor,
+    /// Perfect (unisons, fourths, fifths, octaves)
+    Perfect,
+    /// Major (seconds, thirds, sixths, sevenths)
+    Major,
+    /// Augmented by the given number of semitones (1 = augmented)
+    Augmented(u8),
+}
+
+/// An interval with its spelling, e.g. augmented fourth vs diminished fifth
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpelledInterval {
+    /// Interval number: 1 = unison, 2 = second, ... 8 = octave
+    number: u32,
+    /// Quality
+    quality: IntervalQuality,
+    /// Whether the second pitch is below the first
+    descending: bool,
+}
+
+impl SpelledInterval {
+    /// Get the interval number (1 = unison, 8 = octave)
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Get the quality
+    pub fn quality(&self) -> IntervalQuality {
+        self.quality
+    }
+
+    /// Whether the interval descends
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    /// Size in semitones (always non-negative)
+    pub fn semitones(&self) -> i32 {
+        let steps = self.number as i32 - 1;
+        let simple = steps.rem_euclid(7);
+        let reference = steps.div_euclid(7) * 12 + NATURAL_SEMITONES[simple as usize];
+        match self.quality {
+            IntervalQuality::Perfect | IntervalQuality::Major => reference,
+            IntervalQuality::Minor => reference - 1,
+            IntervalQuality::Augmented(n) => reference + n as i32,
+            IntervalQuality::Diminished(n) if matches!(simple, 0 | 3 | 4) => reference - n as i32,
+            IntervalQuality::Diminished(n) => reference - 1 - n as i32,
+        }
+    }
+}
+
+impl fmt::Display for SpelledInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quality = match self.quality {
+            IntervalQuality::Diminished(n) => "d".repeat(n as usize),
+            IntervalQuality::Minor => "m".to_string(),
+            IntervalQuality::Perfect => "P".to_string(),
+            IntervalQuality::Major => "M".to_string(),
+            IntervalQuality::Augmented(n) => "A".repeat(n as usize),
+        };
+        let direction = if self.descending { "-" } else { "" };
+        write!(f, "{}{}{}", direction, quality, self.number)
+    }
+}
+
+/// Split a trailing (possibly negative) octave number from a name
+fn split_octave(text: &str) -> (&str, &str) {
+    let digits = text.trim_end_matches(|c: char| c.is_ascii_digit());
+    let split = if digits.len() < text.len() && digits.ends_with('-') {
+        digits.len() - 1
+    } else {
+        digits.len()
+    };
+    text.split_at(split)
+}
+
+fn parse_octave(name: &str, octave: &str) -> Result<i8> {
+    if octave.is_empty() {
+        return Ok(DEFAULT_OCTAVE);
+    }
+    octave
+        .parse::<i8>()
+        .ok()
+        .filter(|o| (-1..=9).contains(o))
+        .ok_or_else(|| invalid(name, "octave out of range"))
+}
+
+fn invalid(name: &str, reason: &str) -> ZyrkomError {
+    ZyrkomError::ParseError {
+        message: format!("Invalid pitch name '{}': {}", name, reason),
+        line: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_english_names() {
+        let b_flat = SpelledPitch::parse("Bb4").unwrap();
+        assert_eq!(b_flat, SpelledPitch::new(Letter::B, Accidental::Flat, 4));
+        assert_eq!(b_flat.midi_number(), 70);
+        assert_eq!(b_flat.to_string(), "Bb4");
+
+        assert_eq!(SpelledPitch::parse("C##-1").unwrap().midi_number(), 2);
+        assert_eq!(SpelledPitch::parse("F♯").unwrap().octave(), 4);
+        assert!(SpelledPitch::parse("X4").is_err());
+        assert!(SpelledPitch::parse("C#12").is_err());
+    }
+
+    #[test]
+    fn test_solfege_names() {
+        assert_eq!(SpelledPitch::parse("SIb4").unwrap(), SpelledPitch::parse("Bb4").unwrap());
+        assert_eq!(SpelledPitch::parse("DO#3").unwrap().midi_number(), 49);
+        assert_eq!(SpelledPitch::parse("SOL").unwrap().letter(), Letter::G);
+        // Anthem-style uppercase flat
+        assert_eq!(SpelledPitch::parse("SIB").unwrap().accidental(), Accidental::Flat);
+    }
+
+    #[test]
+    fn test_german_names() {
+        assert_eq!(SpelledPitch::parse_german("H").unwrap(), SpelledPitch::new(Letter::B, Accidental::Natural, 4));
+        assert_eq!(SpelledPitch::parse_german("B").unwrap(), SpelledPitch::new(Letter::B, Accidental::Flat, 4));
+        assert_eq!(SpelledPitch::parse_german("Es").unwrap(), SpelledPitch::new(Letter::E, Accidental::Flat, 4));
+        assert_eq!(SpelledPitch::parse_german("As3").unwrap(), SpelledPitch::new(Letter::A, Accidental::Flat, 3));
+        assert_eq!(SpelledPitch::parse_german("Fis5").unwrap().midi_number(), 78);
+        assert_eq!(SpelledPitch::parse_german("Ceses").unwrap().accidental(), Accidental::DoubleFlat);
+        assert!(SpelledPitch::parse_german("Bis").is_err());
+    }
+
+    #[test]
+    fn test_enharmonic_equivalence() {
+        let c_sharp = SpelledPitch::parse("C#4").unwrap();
+        let d_flat = SpelledPitch::parse("Db4").unwrap();
+        assert!(c_sharp.is_enharmonic(&d_flat));
+        assert_ne!(c_sharp, d_flat);
+
+        // B#3 is middle C
+        assert!(SpelledPitch::parse("B#3").unwrap().is_enharmonic(&SpelledPitch::parse("C4").unwrap()));
+        let reference = PitchReference::standard();
+        assert_eq!(c_sharp.to_note(reference).unwrap(), d_flat.to_note(reference).unwrap());
+    }
+
+    #[test]
+    fn test_interval_spelling() {
+        let c = SpelledPitch::parse("C4").unwrap();
+        let augmented_fourth = c.interval_to(&SpelledPitch::parse("F#4").unwrap());
+        let diminished_fifth = c.interval_to(&SpelledPitch::parse("Gb4").unwrap());
+
+        assert_eq!(augmented_fourth.to_string(), "A4");
+        assert_eq!(diminished_fifth.to_string(), "d5");
+        assert_eq!(augmented_fourth.semitones(), diminished_fifth.semitones());
+
+        assert_eq!(c.interval_to(&SpelledPitch::parse("Eb4").unwrap()).to_string(), "m3");
+        assert_eq!(c.interval_to(&SpelledPitch::parse("E5").unwrap()).to_string(), "M10");
+        assert_eq!(SpelledPitch::parse("B3").unwrap().interval_to(&SpelledPitch::parse("Cb4").unwrap()).to_string(), "d2");
+        assert_eq!(c.interval_to(&SpelledPitch::parse("G3").unwrap()).to_string(), "-P4");
+    }
+}
\ No newline at end of file
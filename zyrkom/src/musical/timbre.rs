@@ -0,0 +1,367 @@
+//! Timbre: partials, amplitudes and inharmonicity
+//!
+//! A timbre describes the first N partials of a tone relative to its
+//! fundamental. Partial k of an ideal string sits at exactly k times the
+//! fundamental; a stiff string (piano, guitar) is stretched by its
+//! inharmonicity coefficient B, so partial k sits at k·√((1 + B·k²)/(1 + B))
+//! times the sounding fundamental (Fletcher's formula, normalised to the
+//! first partial). Timbres produce a `Spectrum` for the consonance metrics
+//! and can render samples for audio playback.
+
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::musical::{MusicalInterval, Spectrum};
+use crate::{Result, ZyrkomError};
+
+/// Number of partials in `overtones()` when none is given (PRD `overtones(16)`)
+pub const DEFAULT_OVERTONE_COUNT: usize = 16;
+
+/// Largest inharmonicity coefficient accepted; real strings stay below 0.01
+pub const MAX_INHARMONICITY: f64 = 0.1;
+
+/// Upper bound on partials, to keep spectra and renders bounded
+pub const MAX_PARTIALS: usize = 128;
+
+/// Amplitude falloff of `overtones()`, matching `Spectrum::harmonic`
+const OVERTONE_FALLOFF: f64 = 0.88;
+
+/// How partial amplitudes fall with partial number
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rolloff {
+    /// Amplitude g^(k-1) for partial k
+    Geometric(f64),
+    /// Amplitude 1/k^p for partial k (p = 1 is a sawtooth)
+    Power(f64),
+}
+
+impl Rolloff {
+    /// Amplitude of harmonic number `k` (1 is the fundamental)
+    pub fn amplitude(&self, k: usize) -> f64 {
+        match *self {
+            Rolloff::Geometric(g) => g.powi(k as i32 - 1),
+            Rolloff::Power(p) => (k as f64).powf(-p),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match *self {
+            Rolloff::Geometric(g) => g > 0.0 && g <= 1.0,
+            Rolloff::Power(p) => p >= 0.0 && p.is_finite(),
+        }
+    }
+}
+
+/// The partial structure of a tone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timbre {
+    name: String,
+    partial_count: usize,
+    rolloff: Rolloff,
+    inharmonicity: f64,
+    odd_only: bool,
+}
+
+impl Timbre {
+    /// Create a harmonic timbre with `partial_count` partials
+    pub fn new(name: &str, partial_count: usize, rolloff: Rolloff) -> Result<Self> {
+        if partial_count == 0 || partial_count > MAX_PARTIALS {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Timbre needs between 1 and {} partials, got {}", MAX_PARTIALS, partial_count),
+            });
+        }
+        if !rolloff.is_valid() {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Invalid amplitude rolloff {:?}", rolloff),
+            });
+        }
+        Ok(Self {
+            name: name.to_string(),
+            partial_count,
+            rolloff,
+            inharmonicity: 0.0,
+            odd_only: false,
+        })
+    }
+
+    /// The first `count` harmonics with the default falloff (DSL `overtones(n)`)
+    pub fn overtones(count: usize) -> Result<Self> {
+        Self::new("overtones", count, Rolloff::Geometric(OVERTONE_FALLOFF))
+    }
+
+    /// A pure sine tone
+    pub fn sine() -> Self {
+        Self::preset_unchecked("sine", 1, Rolloff::Geometric(1.0), 0.0, false)
+    }
+
+    /// Set the stiff-string inharmonicity coefficient B
+    pub fn with_inharmonicity(mut self, inharmonicity: f64) -> Result<Self> {
+        if !(0.0..=MAX_INHARMONICITY).contains(&inharmonicity) {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!(
+                    "Inharmonicity must be between 0 and {}, got {}",
+                    MAX_INHARMONICITY, inharmonicity
+                ),
+            });
+        }
+        self.inharmonicity = inharmonicity;
+        Ok(self)
+    }
+
+    /// Change the number of partials, keeping rolloff and inharmonicity
+    pub fn with_partial_count(mut self, partial_count: usize) -> Result<Self> {
+        let checked = Self::new(&self.name, partial_count, self.rolloff)?;
+        self.partial_count = checked.partial_count;
+        Ok(self)
+    }
+
+    /// Keep only odd harmonics (closed pipes, clarinet)
+    pub fn odd_harmonics_only(mut self) -> Self {
+        self.odd_only = true;
+        self
+    }
+
+    /// Acoustic piano: sawtooth-like spectrum, B ≈ 0.0004 in the middle register
+    pub fn piano() -> Self {
+        Self::preset_unchecked("piano", 16, Rolloff::Power(1.0), 0.0004, false)
+    }
+
+    /// Steel-string guitar: slightly brighter falloff, low inharmonicity
+    pub fn guitar() -> Self {
+        Self::preset_unchecked("guitar", 12, Rolloff::Power(1.2), 0.00005, false)
+    }
+
+    /// Bowed violin: full sawtooth series
+    pub fn violin() -> Self {
+        Self::preset_unchecked("violin", 16, Rolloff::Power(1.0), 0.0, false)
+    }
+
+    /// Clarinet: odd harmonics of a closed cylindrical bore
+    pub fn clarinet() -> Self {
+        Self::preset_unchecked("clarinet", 8, Rolloff::Power(1.0), 0.0, true)
+    }
+
+    /// Flute: few partials, fast falloff
+    pub fn flute() -> Self {
+        Self::preset_unchecked("flute", 6, Rolloff::Geometric(0.5), 0.0, false)
+    }
+
+    /// Pipe organ principal stop
+    pub fn organ() -> Self {
+        Self::preset_unchecked("organ", 8, Rolloff::Geometric(0.7), 0.0, false)
+    }
+
+    /// Names accepted by `preset`
+    pub fn preset_names() -> &'static [&'static str] {
+        &["sine", "piano", "guitar", "violin", "clarinet", "flute", "organ"]
+    }
+
+    /// Look up an instrument preset by name (case-insensitive, English or Spanish)
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "sine" | "seno" => Some(Self::sine()),
+            "piano" => Some(Self::piano()),
+            "guitar" | "guitarra" => Some(Self::guitar()),
+            "violin" | "violín" => Some(Self::violin()),
+            "clarinet" | "clarinete" => Some(Self::clarinet()),
+            "flute" | "flauta" => Some(Self::flute()),
+            "organ" | "órgano" | "organo" => Some(Self::organ()),
+            _ => None,
+        }
+    }
+
+    fn preset_unchecked(name: &str, partial_count: usize, rolloff: Rolloff, inharmonicity: f64, odd_only: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            partial_count,
+            rolloff,
+            inharmonicity,
+            odd_only,
+        }
+    }
+
+    /// Get the timbre name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the number of partials
+    pub fn partial_count(&self) -> usize {
+        self.partial_count
+    }
+
+    /// Get the amplitude rolloff
+    pub fn rolloff(&self) -> Rolloff {
+        self.rolloff
+    }
+
+    /// Get the inharmonicity coefficient B
+    pub fn inharmonicity(&self) -> f64 {
+        self.inharmonicity
+    }
+
+    /// Whether only odd harmonics sound
+    pub fn is_odd_only(&self) -> bool {
+        self.odd_only
+    }
+
+    /// Harmonic numbers of the partials, in order
+    pub fn harmonic_numbers(&self) -> Vec<usize> {
+        let step = if self.odd_only { 2 } else { 1 };
+        (0..self.partial_count).map(|i| 1 + i * step).collect()
+    }
+
+    /// Frequency ratio of harmonic `k` to the sounding fundamental
+    pub fn partial_ratio(&self, k: usize) -> f64 {
+        let b = self.inharmonicity;
+        let k = k as f64;
+        k * ((1.0 + b * k * k) / (1.0 + b)).sqrt()
+    }
+
+    /// How far harmonic `k` is stretched above k times the fundamental
+    pub fn stretch(&self, k: usize) -> MusicalInterval {
+        MusicalInterval::from_cents(1200.0 * (self.partial_ratio(k) / k as f64).log2())
+    }
+
+    /// (ratio, amplitude) partials relative to the fundamental
+    pub fn spectrum(&self) -> Spectrum {
+        let partials = self
+            .harmonic_numbers()
+            .into_iter()
+            .map(|k| (self.partial_ratio(k), self.rolloff.amplitude(k)))
+            .collect();
+        Spectrum::new(partials).expect("validated timbres have positive partials")
+    }
+
+    /// (frequency, amplitude) partials of a tone at `fundamental_hz`
+    pub fn partials_at(&self, fundamental_hz: f64) -> Vec<(f64, f64)> {
+        self.spectrum()
+            .partials()
+            .iter()
+            .map(|&(ratio, amplitude)| (fundamental_hz * ratio, amplitude))
+            .collect()
+    }
+
+    /// Sensory roughness of two tones played with this timbre
+    pub fn roughness(&self, lower_hz: f64, upper_hz: f64) -> f64 {
+        self.spectrum().roughness(lower_hz, upper_hz)
+    }
+
+    /// Render `duration_secs` of a tone as mono samples in [-1, 1]
+    ///
+    /// Partials at or above the Nyquist frequency are dropped and the rest
+    /// are normalised by their total amplitude so the sum cannot clip.
+    pub fn render(&self, fundamental_hz: f64, duration_secs: f64, sample_rate: u32) -> Vec<f32> {
+        let nyquist = sample_rate as f64 / 2.0;
+        let audible: Vec<(f64, f64)> = self
+            .partials_at(fundamental_hz)
+            .into_iter()
+            .filter(|&(frequency, _)| frequency < nyquist)
+            .collect();
+        let total: f64 = audible.iter().map(|&(_, amplitude)| amplitude).sum();
+        let sample_count = (duration_secs.max(0.0) * sample_rate as f64) as usize;
+        if total <= 0.0 {
+            return vec![0.0; sample_count];
+        }
+
+        (0..sample_count)
+            .map(|n| {
+                let t = n as f64 / sample_rate as f64;
+                let sum: f64 = audible
+                    .iter()
+                    .map(|&(frequency, amplitude)| amplitude * (2.0 * PI * frequency * t).sin())
+                    .sum();
+                (sum / total) as f32
+            })
+            .collect()
+    }
+}
+
+impl Default for Timbre {
+    fn default() -> Self {
+        Self::preset_unchecked(
+            "overtones",
+            DEFAULT_OVERTONE_COUNT,
+            Rolloff::Geometric(OVERTONE_FALLOFF),
+            0.0,
+            false,
+        )
+    }
+}
+
+impl fmt::Display for Timbre {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} partials", self.name, self.partial_count)?;
+        if self.odd_only {
+            write!(f, ", odd harmonics")?;
+        }
+        if self.inharmonicity > 0.0 {
+            write!(f, ", B = {}", self.inharmonicity)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overtones_match_harmonic_spectrum() {
+        let timbre = Timbre::overtones(DEFAULT_OVERTONE_COUNT).unwrap();
+        assert_eq!(timbre.spectrum(), Spectrum::harmonic(DEFAULT_OVERTONE_COUNT));
+        assert_eq!(timbre, Timbre::default());
+        assert!(Timbre::overtones(0).is_err());
+        assert!(Timbre::overtones(MAX_PARTIALS + 1).is_err());
+    }
+
+    #[test]
+    fn test_piano_partials_are_stretched() {
+        let piano = Timbre::piano();
+        assert!((piano.partial_ratio(1) - 1.0).abs() < 1e-12);
+        // B = 0.0004 puts the 16th partial roughly 84 cents sharp
+        let stretch = piano.stretch(16).cents();
+        assert!((stretch - 84.0).abs() < 1.0, "stretch was {}", stretch);
+        assert!(Timbre::violin().stretch(16).cents().abs() < 1e-9);
+        assert!(Timbre::overtones(4).unwrap().with_inharmonicity(0.5).is_err());
+    }
+
+    #[test]
+    fn test_clarinet_has_odd_harmonics() {
+        let clarinet = Timbre::clarinet();
+        assert_eq!(clarinet.harmonic_numbers(), vec![1, 3, 5, 7, 9, 11, 13, 15]);
+        let ratios: Vec<f64> = clarinet.spectrum().partials().iter().map(|&(r, _)| r).collect();
+        assert_eq!(ratios[1], 3.0);
+        assert!((clarinet.spectrum().partials()[1].1 - 1.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_presets_by_name() {
+        for name in Timbre::preset_names() {
+            assert_eq!(Timbre::preset(name).unwrap().name(), *name);
+        }
+        assert_eq!(Timbre::preset("Guitarra"), Some(Timbre::guitar()));
+        assert!(Timbre::preset("theremin").is_none());
+        assert_eq!(Timbre::piano().to_string(), "piano (16 partials, B = 0.0004)");
+    }
+
+    #[test]
+    fn test_inharmonicity_roughens_octaves() {
+        let c4 = 261.63;
+        let harmonic = Timbre::overtones(8).unwrap();
+        let stiff = harmonic.clone().with_inharmonicity(0.002).unwrap();
+        assert!(stiff.roughness(c4, 2.0 * c4) > harmonic.roughness(c4, 2.0 * c4));
+        assert!(Timbre::sine().roughness(c4, 2.0 * c4) < harmonic.roughness(c4, 2.0 * c4));
+    }
+
+    #[test]
+    fn test_render_stays_in_range() {
+        let samples = Timbre::piano().render(440.0, 0.1, 44_100);
+        assert_eq!(samples.len(), 4_410);
+        assert!(samples.iter().all(|s| s.abs() <= 1.0));
+        assert!(samples.iter().any(|s| s.abs() > 0.1));
+        // Partials above Nyquist are dropped rather than aliased
+        let high = Timbre::violin().render(4_000.0, 0.01, 8_000);
+        assert!(high.iter().all(|s| s.abs() < 1e-3));
+    }
+}
\ No newline at end of file
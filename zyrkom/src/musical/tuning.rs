@@ -0,0 +1,425 @@
+//! Tuning systems: mapping pitch identities to frequencies
+//!
+//! A pitch identity is an integer step. Step 69 always sounds at the
+//! reference pitch (A4), so in any twelve-note tuning steps coincide with
+//! MIDI note numbers; in an n-EDO each step is one division of the octave.
+//! Non-equal tunings are built on a tonic pitch class (0 = C).
+
+use crate::musical::{constants, MusicalInterval};
+use crate::{Result, ZyrkomError};
+use std::fmt;
+
+/// Step that sounds at the reference frequency (A4 in MIDI numbering)
+pub const REFERENCE_STEP: i32 = 69;
+
+/// The syntonic comma (81:80) in cents
+const SYNTONIC_COMMA_CENTS: f64 = 21.506_289_596_714_994;
+
+/// Lowest accepted concert pitch in Hz
+const MIN_CONCERT_PITCH: f64 = 300.0;
+
+/// Highest accepted concert pitch in Hz
+const MAX_CONCERT_PITCH: f64 = 600.0;
+
+/// Concert pitch: the frequency the reference step (A4) sounds at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitchReference {
+    /// Frequency of A4 in Hz
+    a4_frequency: f64,
+}
+
+impl PitchReference {
+    /// Create a reference from the frequency of A4
+    pub fn new(a4_frequency: f64) -> Result<Self> {
+        if !(MIN_CONCERT_PITCH..=MAX_CONCERT_PITCH).contains(&a4_frequency) {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Concert pitch A4 = {} Hz is out of range", a4_frequency),
+            });
+        }
+        Ok(Self { a4_frequency })
+    }
+
+    /// Modern standard pitch, A4 = 440 Hz (ISO 16)
+    pub const fn standard() -> Self {
+        Self { a4_frequency: constants::CONCERT_PITCH_A4 }
+    }
+
+    /// Baroque pitch, A4 = 415 Hz
+    pub const fn baroque() -> Self {
+        Self { a4_frequency: 415.0 }
+    }
+
+    /// "Verdi" pitch, A4 = 432 Hz
+    pub const fn verdi() -> Self {
+        Self { a4_frequency: 432.0 }
+    }
+
+    /// Common orchestral pitch, A4 = 442 Hz
+    pub const fn orchestral() -> Self {
+        Self { a4_frequency: 442.0 }
+    }
+
+    /// Restore a reference recorded in a proof statement
+    pub fn from_millihertz(millihertz: u32) -> Result<Self> {
+        Self::new(millihertz as f64 / 1000.0)
+    }
+
+    /// Get the frequency of A4 in Hz
+    pub fn a4_frequency(&self) -> f64 {
+        self.a4_frequency
+    }
+
+    /// Frequency of A4 in thousandths of a Hz, as recorded in proofs
+    pub fn millihertz(&self) -> u32 {
+        (self.a4_frequency * 1000.0).round() as u32
+    }
+}
+
+impl Default for PitchReference {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl fmt::Display for PitchReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "A4 = {} Hz", self.a4_frequency)
+    }
+}
+
+/// A system assigning a frequency to every pitch step
+pub trait TuningSystem: fmt::Debug {
+    /// Human-readable name, e.g. "12-TET" or "5-limit just intonation on C"
+    fn name(&self) -> String;
+
+    /// Stable identifier committed to in proofs
+    fn tuning_id(&self) -> u32;
+
+    /// Number of steps per octave
+    fn divisions(&self) -> u32;
+
+    /// Cents above the tonic of scale degree `degree` (`0 <= degree < divisions`)
+    fn degree_cents(&self, degree: u32) -> f64;
+
+    /// Pitch class of degree 0
+    fn tonic(&self) -> i32 {
+        0
+    }
+
+    /// Concert pitch the reference step sounds at
+    fn reference(&self) -> PitchReference {
+        PitchReference::standard()
+    }
+
+    /// Cents of a step above the tonic in octave zero
+    fn step_cents(&self, step: i32) -> f64 {
+        let divisions = self.divisions() as i32;
+        let relative = step - self.tonic();
+        relative.div_euclid(divisions) as f64 * constants::CENTS_PER_OCTAVE
+            + self.degree_cents(relative.rem_euclid(divisions) as u32)
+    }
+
+    /// Frequency of a step in Hz
+    fn frequency(&self, step: i32) -> f64 {
+        let cents_from_reference = self.step_cents(step) - self.step_cents(REFERENCE_STEP);
+        self.reference().a4_frequency() * MusicalInterval::cents_to_ratio(cents_from_reference)
+    }
+
+    /// Step whose frequency is closest to the given frequency
+    fn nearest_step(&self, frequency: f64) -> i32 {
+        let step_size = constants::CENTS_PER_OCTAVE / self.divisions() as f64;
+        let cents = MusicalInterval::ratio_to_cents(frequency / self.reference().a4_frequency());
+        let estimate = REFERENCE_STEP + (cents / step_size).round() as i32;
+
+        // Unequal steps can shift the nearest pitch by one either way
+        (estimate - 1..=estimate + 1)
+            .min_by(|&a, &b| {
+                let distance = |s: i32| MusicalInterval::ratio_to_cents(frequency / self.frequency(s)).abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .unwrap_or(estimate)
+    }
+
+    /// Signed cents deviation of a frequency from the nearest step
+    fn deviation_cents(&self, frequency: f64) -> f64 {
+        let step = self.nearest_step(frequency);
+        MusicalInterval::ratio_to_cents(frequency / self.frequency(step))
+    }
+
+    /// Signed cents deviation of an interval from the closest interval
+    /// available between any two steps of the tuning
+    fn interval_deviation_cents(&self, cents: f64) -> f64 {
+        let divisions = self.divisions() as i32;
+        let step_size = constants::CENTS_PER_OCTAVE / divisions as f64;
+        let span = (cents / step_size).round() as i32;
+
+        (0..divisions)
+            .flat_map(|start| (span - 1..=span + 1).map(move |k| (start, k)))
+            .map(|(start, k)| {
+                let step = self.tonic() + start;
+                cents - (self.step_cents(step + k) - self.step_cents(step))
+            })
+            .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap_or(0.0)
+    }
+
+    /// Number of steps closest to an interval of the given size
+    fn steps_for_cents(&self, cents: f64) -> i32 {
+        (cents * self.divisions() as f64 / constants::CENTS_PER_OCTAVE).round() as i32
+    }
+}
+
+/// Equal division of the octave into n steps (12-TET is `n = 12`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqualTemperament {
+    /// Steps per octave
+    divisions: u32,
+    /// Concert pitch
+    reference: PitchReference,
+}
+
+impl EqualTemperament {
+    /// Create an n-EDO tuning
+    pub fn new(divisions: u32) -> Result<Self> {
+        if divisions == 0 || divisions > 1200 {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Invalid number of octave divisions: {}", divisions),
+            });
+        }
+        Ok(Self {
+            divisions,
+            reference: PitchReference::standard(),
+        })
+    }
+
+    /// Standard twelve-tone equal temperament
+    pub const fn twelve_tet() -> Self {
+        Self {
+            divisions: 12,
+            reference: PitchReference::standard(),
+        }
+    }
+
+    /// The same temperament at another concert pitch
+    pub fn with_reference(self, reference: PitchReference) -> Self {
+        Self { reference, ..self }
+    }
+}
+
+impl TuningSystem for EqualTemperament {
+    fn name(&self) -> String {
+        if self.divisions == 12 {
+            "12-TET".to_string()
+        } else {
+            format!("{}-EDO", self.divisions)
+        }
+    }
+
+    fn tuning_id(&self) -> u32 {
+        1000 + self.divisions
+    }
+
+    fn divisions(&self) -> u32 {
+        self.divisions
+    }
+
+    fn degree_cents(&self, degree: u32) -> f64 {
+        degree as f64 * constants::CENTS_PER_OCTAVE / self.divisions as f64
+    }
+
+    fn tonic(&self) -> i32 {
+        REFERENCE_STEP
+    }
+
+    fn reference(&self) -> PitchReference {
+        self.reference
+    }
+}
+
+/// Twelve-note tunings defined by a table of degree cents above a tonic
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwelveNoteTuning {
+    /// Which historical tuning this is
+    kind: TwelveNoteKind,
+    /// Tonic pitch class (0 = C)
+    tonic: u8,
+    /// Cents above the tonic of each degree
+    degrees: [f64; 12],
+    /// Concert pitch
+    reference: PitchReference,
+}
+
+/// The built-in twelve-note tunings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwelveNoteKind {
+    /// 5-limit just intonation
+    JustIntonation,
+    /// Pure 3:2 fifths, wolf between F# and Db
+    Pythagorean,
+    /// Fifths narrowed by a quarter of the syntonic comma (pure major thirds)
+    QuarterCommaMeantone,
+    /// Andreas Werckmeister's well temperament III (1691)
+    WerckmeisterIII,
+}
+
+impl TwelveNoteTuning {
+    /// 5-limit just intonation on a tonic pitch class
+    pub fn just_intonation(tonic: u8) -> Result<Self> {
+        let cents = constants::JUST_RATIOS.map(MusicalInterval::ratio_to_cents);
+        Self::from_table(TwelveNoteKind::JustIntonation, tonic, cents)
+    }
+
+    /// Pythagorean tuning on a tonic pitch class (fifths from Db to F#)
+    pub fn pythagorean(tonic: u8) -> Result<Self> {
+        let fifth = MusicalInterval::ratio_to_cents(constants::PERFECT_FIFTH_RATIO);
+        Self::from_table(TwelveNoteKind::Pythagorean, tonic, Self::chain_of_fifths(fifth, -5))
+    }
+
+    /// Quarter-comma meantone on a tonic pitch class (fifths from Eb to G#)
+    pub fn quarter_comma_meantone(tonic: u8) -> Result<Self> {
+        let fifth = MusicalInterval::ratio_to_cents(constants::PERFECT_FIFTH_RATIO) - SYNTONIC_COMMA_CENTS / 4.0;
+        Self::from_table(TwelveNoteKind::QuarterCommaMeantone, tonic, Self::chain_of_fifths(fifth, -3))
+    }
+
+    /// Werckmeister III, a well temperament defined relative to C
+    pub fn werckmeister_iii() -> Self {
+        let degrees = [
+            0.0, 90.225, 192.180, 294.135, 390.225, 498.045,
+            588.270, 696.090, 792.180, 888.270, 996.090, 1092.180,
+        ];
+        Self {
+            kind: TwelveNoteKind::WerckmeisterIII,
+            tonic: 0,
+            degrees,
+            reference: PitchReference::standard(),
+        }
+    }
+
+    /// The same tuning at another concert pitch
+    pub fn with_reference(self, reference: PitchReference) -> Self {
+        Self { reference, ..self }
+    }
+
+    /// Get which built-in tuning this is
+    pub fn kind(&self) -> TwelveNoteKind {
+        self.kind
+    }
+
+    fn from_table(kind: TwelveNoteKind, tonic: u8, degrees: [f64; 12]) -> Result<Self> {
+        if tonic >= 12 {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Invalid tonic pitch class: {}", tonic),
+            });
+        }
+        Ok(Self {
+            kind,
+            tonic,
+            degrees,
+            reference: PitchReference::standard(),
+        })
+    }
+
+    /// Twelve degrees generated by a chain of fifths starting `first` fifths
+    /// below the tonic
+    fn chain_of_fifths(fifth_cents: f64, first: i32) -> [f64; 12] {
+        let mut degrees = [0.0; 12];
+        for k in first..first + 12 {
+            let degree = (7 * k).rem_euclid(12) as usize;
+            degrees[degree] = (k as f64 * fifth_cents).rem_euclid(constants::CENTS_PER_OCTAVE);
+        }
+        degrees
+    }
+}
+
+impl TuningSystem for TwelveNoteTuning {
+    fn name(&self) -> String {
+        const PITCH_CLASSES: [&str; 12] = ["C", "C#", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B"];
+        let tonic = PITCH_CLASSES[self.tonic as usize];
+        match self.kind {
+            TwelveNoteKind::JustIntonation => format!("5-limit just intonation on {}", tonic),
+            TwelveNoteKind::Pythagorean => format!("Pythagorean on {}", tonic),
+            TwelveNoteKind::QuarterCommaMeantone => format!("Quarter-comma meantone on {}", tonic),
+            TwelveNoteKind::WerckmeisterIII => "Werckmeister III".to_string(),
+        }
+    }
+
+    fn tuning_id(&self) -> u32 {
+        let family = match self.kind {
+            TwelveNoteKind::JustIntonation => 2000,
+            TwelveNoteKind::Pythagorean => 3000,
+            TwelveNoteKind::QuarterCommaMeantone => 4000,
+            TwelveNoteKind::WerckmeisterIII => 5000,
+        };
+        family + self.tonic as u32
+    }
+
+    fn divisions(&self) -> u32 {
+        12
+    }
+
+    fn degree_cents(&self, degree: u32) -> f64 {
+        self.degrees[degree as usize]
+    }
+
+    fn tonic(&self) -> i32 {
+        self.tonic as i32
+    }
+
+    fn reference(&self) -> PitchReference {
+        self.reference
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_twelve_tet_matches_midi() {
+        let tet = EqualTemperament::twelve_tet();
+        assert!((tet.frequency(69) - 440.0).abs() < 1e-9);
+        assert!((tet.frequency(60) - 261.6256).abs() < 1e-3);
+        assert_eq!(tet.nearest_step(261.63), 60);
+        assert_eq!(tet.name(), "12-TET");
+    }
+
+    #[test]
+    fn test_just_intonation_on_a_has_pure_third() {
+        let just = TwelveNoteTuning::just_intonation(9).unwrap();
+        // A4 is the tonic, so C#5 is a pure 5:4 above it
+        assert!((just.frequency(69) - 440.0).abs() < 1e-9);
+        assert!((just.frequency(73) - 550.0).abs() < 1e-9);
+        assert!(just.interval_deviation_cents(MusicalInterval::ratio_to_cents(1.25)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pythagorean_and_meantone_fifths() {
+        let pythagorean = TwelveNoteTuning::pythagorean(0).unwrap();
+        let fifth = pythagorean.step_cents(67) - pythagorean.step_cents(60);
+        assert!((fifth - 701.955).abs() < 1e-3);
+
+        // Meantone thirds are pure: C to E is exactly 5:4
+        let meantone = TwelveNoteTuning::quarter_comma_meantone(0).unwrap();
+        let third = meantone.step_cents(64) - meantone.step_cents(60);
+        assert!((third - 386.314).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_edo_and_werckmeister() {
+        let edo19 = EqualTemperament::new(19).unwrap();
+        assert_eq!(edo19.name(), "19-EDO");
+        assert!((edo19.frequency(69 + 19) - 880.0).abs() < 1e-9);
+        assert_eq!(edo19.steps_for_cents(MusicalInterval::perfect_fifth().cents()), 11);
+        assert!(EqualTemperament::new(0).is_err());
+
+        let baroque = EqualTemperament::twelve_tet().with_reference(PitchReference::baroque());
+        assert!((baroque.frequency(69) - 415.0).abs() < 1e-9);
+        assert_eq!(baroque.nearest_step(415.0), 69);
+        assert!(PitchReference::new(44.0).is_err());
+        assert_eq!(PitchReference::from_millihertz(442_000).unwrap(), PitchReference::orchestral());
+
+        let werckmeister = TwelveNoteTuning::werckmeister_iii();
+        assert!(werckmeister.deviation_cents(werckmeister.frequency(66)).abs() < 1e-9);
+        assert_ne!(werckmeister.tuning_id(), EqualTemperament::twelve_tet().tuning_id());
+    }
+}
\ No newline at end of file
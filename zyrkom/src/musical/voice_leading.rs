@@ -0,0 +1,128 @@
+//! Voice leading between consecutive chords
+//!
+//! Voices are matched by sorting both chords by pitch and pairing them in
+//! order. For points on a line this assignment minimises both the total and
+//! the largest semitone displacement, and it never crosses voices.
+
+use crate::musical::{Chord, MusicalNote};
+use crate::{Result, ZyrkomError};
+
+/// The motion of every voice from one chord to the next
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoiceLeading {
+    /// Pairs of (note index in the first chord, note index in the second chord),
+    /// ordered from the lowest voice to the highest
+    assignment: Vec<(usize, usize)>,
+    /// Signed semitone motion of each voice, in the same order
+    motions: Vec<i32>,
+}
+
+impl VoiceLeading {
+    /// Get the voice assignment as (from, to) note indices, lowest voice first
+    pub fn assignment(&self) -> &[(usize, usize)] {
+        &self.assignment
+    }
+
+    /// Get the signed semitone motion of each voice, lowest voice first
+    pub fn motions(&self) -> &[i32] {
+        &self.motions
+    }
+
+    /// Sum of absolute semitone motion across all voices
+    pub fn total_displacement(&self) -> u32 {
+        self.motions.iter().map(|m| m.unsigned_abs()).sum()
+    }
+
+    /// Largest absolute semitone motion of any single voice
+    pub fn max_motion(&self) -> u32 {
+        self.motions.iter().map(|m| m.unsigned_abs()).max().unwrap_or(0)
+    }
+}
+
+impl Chord {
+    /// Find the optimal voice assignment from this chord to the next
+    pub fn voice_leading_to(&self, next: &Chord) -> Result<VoiceLeading> {
+        if self.note_count() != next.note_count() {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!(
+                    "Voice leading needs equal voice counts: {} against {}",
+                    self.note_count(),
+                    next.note_count()
+                ),
+            });
+        }
+
+        let from = sorted_voices(self.notes());
+        let to = sorted_voices(next.notes());
+
+        let assignment: Vec<(usize, usize)> = from.iter().zip(&to).map(|(&a, &b)| (a, b)).collect();
+        let motions = assignment
+            .iter()
+            .map(|&(a, b)| next.notes()[b].midi_number() as i32 - self.notes()[a].midi_number() as i32)
+            .collect();
+
+        Ok(VoiceLeading { assignment, motions })
+    }
+
+    /// Voice leadings for every consecutive pair in a progression
+    pub fn progression_voice_leading(progression: &[Chord]) -> Result<Vec<VoiceLeading>> {
+        progression
+            .windows(2)
+            .map(|pair| pair[0].voice_leading_to(&pair[1]))
+            .collect()
+    }
+}
+
+/// Note indices ordered from lowest to highest pitch
+fn sorted_voices(notes: &[MusicalNote]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..notes.len()).collect();
+    indices.sort_by(|&a, &b| notes[a].frequency().total_cmp(&notes[b].frequency()));
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::musical::MusicalInterval;
+
+    fn chord(midi_numbers: &[u8]) -> Chord {
+        let root = MusicalNote::from_midi(midi_numbers[0]).unwrap();
+        let intervals: Vec<MusicalInterval> = midi_numbers[1..]
+            .iter()
+            .map(|&m| root.interval_to(&MusicalNote::from_midi(m).unwrap()))
+            .collect();
+        Chord::new(root, &intervals).unwrap()
+    }
+
+    #[test]
+    fn test_c_major_to_f_major_second_inversion() {
+        // C4 E4 G4 -> C4 F4 A4: common tone held, upper voices step up
+        let leading = chord(&[60, 64, 67]).voice_leading_to(&chord(&[65, 69, 60])).unwrap();
+
+        assert_eq!(leading.motions(), &[0, 1, 2]);
+        assert_eq!(leading.assignment(), &[(0, 2), (1, 0), (2, 1)]);
+        assert_eq!(leading.total_displacement(), 3);
+        assert_eq!(leading.max_motion(), 2);
+    }
+
+    #[test]
+    fn test_progression_voice_leading() {
+        // I - IV64 - I - V6 - I in close position
+        let progression = [
+            chord(&[60, 64, 67]),
+            chord(&[60, 65, 69]),
+            chord(&[60, 64, 67]),
+            chord(&[59, 62, 67]),
+            chord(&[60, 64, 67]),
+        ];
+        let leadings = Chord::progression_voice_leading(&progression).unwrap();
+
+        assert_eq!(leadings.len(), 4);
+        assert!(leadings.iter().all(|l| l.max_motion() <= 2));
+    }
+
+    #[test]
+    fn test_mismatched_voice_counts() {
+        assert!(chord(&[60, 64, 67]).voice_leading_to(&chord(&[60, 64])).is_err());
+    }
+}
\ No newline at end of file
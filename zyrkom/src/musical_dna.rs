@@ -1,7 +1,9 @@
 //! Musical DNA - Unique musical fingerprint generation
 
 use crate::musical::MusicalInterval;
-use crate::zk::{ConstraintSystem, ZyrkomProver, MusicalConstraint};
+use crate::zk::{ConstraintSystem, ZyrkomProver, MusicalConstraint, MusicalProof};
+use crate::authorship::AuthorshipBundle;
+use ed25519_dalek::SigningKey;
 use sha2::{Sha256, Digest};
 use serde::{Serialize, Deserialize};
 use bincode;
@@ -116,6 +118,17 @@ impl MusicalDna {
     
     /// Generate ZK proof of Musical DNA ownership
     pub fn generate_ownership_proof(&self) -> Result<Vec<u8>, crate::ZyrkomError> {
+        // Serialize the proof to bytes
+        Ok(bincode::serialize(&self.ownership_proof()?)?)
+    }
+
+    /// Generate the ownership proof and sign it with the owner's key
+    pub fn sign_ownership_proof(&self, signing_key: &SigningKey) -> Result<AuthorshipBundle, crate::ZyrkomError> {
+        AuthorshipBundle::create(&self.ownership_proof()?, signing_key)
+    }
+
+    /// Build and prove the ownership constraint system
+    fn ownership_proof(&self) -> Result<MusicalProof, crate::ZyrkomError> {
         let mut constraints = ConstraintSystem::new();
         
         // Add interval preference constraints
@@ -146,10 +159,7 @@ impl MusicalDna {
         
         // Generate the proof
         let prover = ZyrkomProver::new(constraints)?;
-        let proof = prover.prove()?;
-        
-        // Serialize the proof to bytes
-        Ok(bincode::serialize(&proof)?)
+        prover.prove()
     }
     
     /// Compare two Musical DNAs and calculate similarity
@@ -299,4 +309,20 @@ mod tests {
         assert!(similarity_same > 0.99); // Should be identical
         assert!(similarity_different < similarity_same);
     }
+
+    #[test]
+    fn test_signed_ownership_proof() {
+        let dna = MusicalDna::generate(&["Song A".to_string()], &[], &["Rock".to_string()]);
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+
+        let bundle = dna.sign_ownership_proof(&key).unwrap();
+        assert!(bundle.verify().unwrap());
+        assert!(bundle.is_signed_by(&key.verifying_key()));
+
+        // Flipping a bit of the signature, the last field serialized, breaks it
+        let mut bytes = bincode::serialize(&bundle).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        let tampered: AuthorshipBundle = bincode::deserialize(&bytes).unwrap();
+        assert!(!tampered.verify().unwrap());
+    }
 }
\ No newline at end of file
@@ -3,18 +3,25 @@
 //! This module contains helper functions and common utilities
 //! used throughout the Zyrkom codebase.
 
-/// Convert frequency to musical note name
+use crate::musical::PitchReference;
+
+/// Convert frequency to musical note name at standard pitch (A4 = 440 Hz)
 pub fn frequency_to_note_name(frequency: f64) -> String {
+    frequency_to_note_name_at(frequency, PitchReference::standard())
+}
+
+/// Convert frequency to musical note name at a given concert pitch
+pub fn frequency_to_note_name_at(frequency: f64, reference: PitchReference) -> String {
     if frequency <= 0.0 {
         return "Invalid".to_string();
     }
     
-    // A4 = 440Hz as reference (MIDI note 69)
-    const A4_FREQUENCY: f64 = 440.0;
+    // A4 as reference (MIDI note 69)
+    let a4_frequency = reference.a4_frequency();
     const A4_MIDI: f64 = 69.0;
     
     // Convert frequency to MIDI note number using logarithmic scale
-    let midi_note = A4_MIDI + 12.0 * (frequency / A4_FREQUENCY).log2();
+    let midi_note = A4_MIDI + 12.0 * (frequency / a4_frequency).log2();
     let midi_rounded = midi_note.round() as i32;
     
     // Note names within an octave
@@ -32,7 +39,7 @@ pub fn frequency_to_note_name(frequency: f64) -> String {
     let note_name = NOTE_NAMES[note_index];
     
     // Calculate cents deviation from exact semitone
-    let exact_frequency = A4_FREQUENCY * 2_f64.powf((midi_rounded as f64 - A4_MIDI) / 12.0);
+    let exact_frequency = a4_frequency * 2_f64.powf((midi_rounded as f64 - A4_MIDI) / 12.0);
     let cents_deviation = 1200.0 * (frequency / exact_frequency).log2();
     
     if cents_deviation.abs() < 5.0 {
@@ -115,35 +122,56 @@ pub mod audio {
         Ok(())
     }
     
-    /// Play a chord (multiple frequencies simultaneously)
+    /// Play a chord (multiple frequencies simultaneously) as pure sines
     pub fn play_chord(frequencies: &[f64], duration_ms: u64) -> Result<(), Box<dyn std::error::Error>> {
-        use rodio::{OutputStream, Sink};
-        
         if frequencies.is_empty() {
             return Ok(());
         }
         
-        let (_stream, stream_handle) = OutputStream::try_default()?;
-        let sink = Sink::try_new(&stream_handle)?;
-        
-        println!("🎵 Playing chord with {} notes for {}ms...", frequencies.len(), duration_ms);
         for (i, &freq) in frequencies.iter().enumerate() {
             let note_name = crate::utils::frequency_to_note_name(freq);
             println!("   Note {}: {} ({:.2}Hz)", i + 1, note_name, freq);
         }
         
-        // Create mixed sine waves for all frequencies
-        let sample_rate = 44100;
-        let duration = Duration::from_millis(duration_ms);
-        let mixed_wave = ChordWave::new(frequencies.to_vec(), sample_rate, duration);
-        
-        sink.append(mixed_wave);
-        sink.sleep_until_end();
+        play_with_timbre(frequencies, &crate::musical::Timbre::sine(), duration_ms)?;
         
         println!("✅ Chord played successfully!");
         Ok(())
     }
-    
+
+    /// Play one or more frequencies with an instrument timbre instead of pure sines
+    pub fn play_with_timbre(
+        frequencies: &[f64],
+        timbre: &crate::musical::Timbre,
+        duration_ms: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use rodio::{buffer::SamplesBuffer, OutputStream, Sink};
+
+        if frequencies.is_empty() {
+            return Ok(());
+        }
+
+        let (_stream, stream_handle) = OutputStream::try_default()?;
+        let sink = Sink::try_new(&stream_handle)?;
+
+        println!("🎵 Playing {} note(s) as {} for {}ms...", frequencies.len(), timbre, duration_ms);
+
+        // Render each tone with its partials and mix at 30% volume
+        let sample_rate = 44100;
+        let duration_secs = duration_ms as f64 / 1000.0;
+        let mut mixed = vec![0.0f32; (duration_secs * sample_rate as f64) as usize];
+        for &frequency in frequencies {
+            for (sample, value) in mixed.iter_mut().zip(timbre.render(frequency, duration_secs, sample_rate)) {
+                *sample += value * 0.3 / frequencies.len() as f32;
+            }
+        }
+
+        sink.append(SamplesBuffer::new(1, sample_rate, mixed));
+        sink.sleep_until_end();
+
+        Ok(())
+    }
+
     /// Simple sine wave generator
     struct SineWave {
         frequency: f32,
@@ -197,65 +225,6 @@ pub mod audio {
             Some(Duration::from_secs_f64(self.total_samples as f64 / self.sample_rate as f64))
         }
     }
-    
-    /// Chord wave generator (mix multiple frequencies)
-    struct ChordWave {
-        frequencies: Vec<f32>,
-        sample_rate: u32,
-        samples_generated: usize,
-        total_samples: usize,
-    }
-    
-    impl ChordWave {
-        fn new(frequencies: Vec<f64>, sample_rate: u32, duration: Duration) -> Self {
-            let total_samples = (sample_rate as f64 * duration.as_secs_f64()) as usize;
-            Self {
-                frequencies: frequencies.into_iter().map(|f| f as f32).collect(),
-                sample_rate,
-                samples_generated: 0,
-                total_samples,
-            }
-        }
-    }
-    
-    impl Iterator for ChordWave {
-        type Item = f32;
-        
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.samples_generated >= self.total_samples {
-                return None;
-            }
-            
-            let t = self.samples_generated as f32 / self.sample_rate as f32;
-            
-            // Mix all frequencies together
-            let mixed_sample: f32 = self.frequencies
-                .iter()
-                .map(|&freq| (2.0 * PI * freq * t).sin())
-                .sum::<f32>() / self.frequencies.len() as f32; // Average to prevent clipping
-            
-            self.samples_generated += 1;
-            Some(mixed_sample * 0.3) // 30% volume
-        }
-    }
-    
-    impl Source for ChordWave {
-        fn current_frame_len(&self) -> Option<usize> {
-            None
-        }
-        
-        fn channels(&self) -> u16 {
-            1
-        }
-        
-        fn sample_rate(&self) -> u32 {
-            self.sample_rate
-        }
-        
-        fn total_duration(&self) -> Option<Duration> {
-            Some(Duration::from_secs_f64(self.total_samples as f64 / self.sample_rate as f64))
-        }
-    }
 }
 
 #[cfg(test)]
@@ -299,6 +268,12 @@ mod tests {
         assert_eq!(frequency_to_note_name(880.0), "A5");
     }
 
+    #[test]
+    fn test_frequency_to_note_name_at_baroque_pitch() {
+        assert_eq!(frequency_to_note_name_at(415.0, PitchReference::baroque()), "A4");
+        assert_eq!(frequency_to_note_name_at(440.0, PitchReference::baroque()), "A#4");
+    }
+
     #[test]
     fn test_math_utilities() {
         // Test GCD
@@ -358,4 +333,19 @@ mod tests {
         
         println!("✅ C major chord audio test completed!");
     }
+    
+    #[test]
+    #[cfg(feature = "test-audio")]
+    fn test_audio_chord_with_timbre() {
+        println!("\n🎼 Testing C major chord on a piano timbre...");
+        
+        let c_major = vec![261.63, 329.63, 392.00];
+        
+        if let Err(e) = audio::play_with_timbre(&c_major, &crate::musical::Timbre::piano(), 800) {
+            println!("⚠️ Audio test skipped (no audio device): {}", e);
+            return;
+        }
+        
+        println!("✅ Timbre chord audio test completed!");
+    }
 } 
\ No newline at end of file
@@ -3,7 +3,9 @@
 /// Converts immutable musical relationships into mathematical constraints
 /// for Circle STARK proofs using M31 field arithmetic.
 
-use crate::musical::{MusicalInterval, Chord};
+use crate::musical::{consonance, MusicalInterval, MusicalNote, Chord, ChordQuality, MeasureSequence, Tempo, TempoBounds};
+use crate::musical::counterpoint::{CounterpointAnalysis, CounterpointRule};
+use crate::musical::{InstrumentPart, Key, Melody, Mode, NoteDuration, PitchReference, PlrDerivation, Scale, Score, SetClassMembership, TuningSystem, VoiceLeading};
 use crate::{Result, ZyrkomError};
 use stwo::core::fields::m31::M31;
 
@@ -11,6 +13,24 @@ use stwo::core::fields::m31::M31;
 /// Using 2^20 to maintain precision while staying within M31 range
 const M31_SCALE_FACTOR: f64 = 1_048_576.0; // 2^20
 
+/// Bit width of range-checked values: a slack is valid iff it lies in [0, 2^30)
+const RANGE_CHECK_BITS: u32 = 30;
+
+/// Fixed point scale for tempo values (thousandths of a BPM)
+const TEMPO_FIXED_POINT_SCALE: f64 = 1000.0;
+
+/// Fixed point scale for cents deviations (thousandths of a cent)
+pub const CENTS_FIXED_POINT_SCALE: f64 = 1000.0;
+
+/// Tolerance applied when a tuning consistency constraint is built from a bare ratio
+pub const DEFAULT_TUNING_TOLERANCE_CENTS: f64 = 5.0;
+
+/// Public parameter name under which the concert pitch is recorded (millihertz)
+pub const PITCH_REFERENCE_PARAMETER: &str = "reference_millihertz";
+
+/// Public parameter names under which a key is recorded (tonic pitch class, mode id)
+pub const KEY_PARAMETERS: [&str; 2] = ["key_tonic", "key_mode"];
+
 /// A constraint derived from musical physics laws
 #[derive(Debug, Clone, PartialEq)]
 pub struct MusicalConstraint {
@@ -35,8 +55,105 @@ pub enum ConstraintType {
     Consonance,
     /// Ensures chord intervals sum correctly
     IntervalSum,
-    /// Validates tuning system consistency
+    /// Validates tuning consistency: a cents deviation lies within a tolerance
     TuningConsistency,
+    /// Ensures a measure's durations sum exactly to its time signature
+    MeasureDuration,
+    /// Ensures a pickup measure is shorter than a full measure and is
+    /// completed by the final measure
+    Anacrusis,
+    /// Ensures the tempo lies within declared BPM bounds
+    TempoBounds,
+    /// Forbids parallel fifths and octaves between two voices
+    ParallelMotion,
+    /// Ensures an upper voice never sounds below a lower voice
+    VoiceCrossing,
+    /// Ensures dissonances are prepared and resolved by step on weak beats
+    DissonanceTreatment,
+    /// Ensures melodic leaps are resolved by contrary step
+    LeapResolution,
+    /// Bounds the semitone motion of each voice between consecutive chords
+    VoiceLeadingDistance,
+    /// Ensures the step between consecutive scale degrees matches the pattern
+    ScaleStep,
+    /// Ensures a chord contains each tone of its recognised quality
+    ChordTone,
+    /// Ensures a pitch-class set belongs to a declared set class
+    SetClass,
+    /// Ensures a note starts no earlier than the previous note in its line ends
+    NoteTiming,
+    /// Ensures consecutive triads are related by a declared neo-Riemannian operation
+    Transformation,
+    /// Ensures a part fits an instrument's range, polyphony and hand stretch
+    Playability,
+    /// Ensures a comparison declared in a DSL constraint expression holds
+    Comparison,
+}
+
+impl ConstraintType {
+    /// Stable snake_case name used in JSON output
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConstraintType::HarmonicRatio => "harmonic_ratio",
+            ConstraintType::OctaveEquivalence => "octave_equivalence",
+            ConstraintType::Consonance => "consonance",
+            ConstraintType::IntervalSum => "interval_sum",
+            ConstraintType::TuningConsistency => "tuning_consistency",
+            ConstraintType::MeasureDuration => "measure_duration",
+            ConstraintType::Anacrusis => "anacrusis",
+            ConstraintType::TempoBounds => "tempo_bounds",
+            ConstraintType::ParallelMotion => "parallel_motion",
+            ConstraintType::VoiceCrossing => "voice_crossing",
+            ConstraintType::DissonanceTreatment => "dissonance_treatment",
+            ConstraintType::LeapResolution => "leap_resolution",
+            ConstraintType::VoiceLeadingDistance => "voice_leading_distance",
+            ConstraintType::ScaleStep => "scale_step",
+            ConstraintType::ChordTone => "chord_tone",
+            ConstraintType::SetClass => "set_class",
+            ConstraintType::NoteTiming => "note_timing",
+            ConstraintType::Transformation => "transformation",
+            ConstraintType::Playability => "playability",
+            ConstraintType::Comparison => "comparison",
+        }
+    }
+
+    /// Numeric identifier committed to in the proof's public inputs
+    pub fn type_id(&self) -> u32 {
+        match self {
+            ConstraintType::HarmonicRatio => 1,
+            ConstraintType::OctaveEquivalence => 2,
+            ConstraintType::Consonance => 3,
+            ConstraintType::IntervalSum => 4,
+            ConstraintType::TuningConsistency => 5,
+            ConstraintType::MeasureDuration => 6,
+            ConstraintType::Anacrusis => 7,
+            ConstraintType::TempoBounds => 8,
+            ConstraintType::ParallelMotion => 9,
+            ConstraintType::VoiceCrossing => 10,
+            ConstraintType::DissonanceTreatment => 11,
+            ConstraintType::LeapResolution => 12,
+            ConstraintType::VoiceLeadingDistance => 13,
+            ConstraintType::ScaleStep => 14,
+            ConstraintType::ChordTone => 15,
+            ConstraintType::SetClass => 16,
+            ConstraintType::NoteTiming => 17,
+            ConstraintType::Transformation => 18,
+            ConstraintType::Playability => 19,
+            ConstraintType::Comparison => 20,
+        }
+    }
+
+    /// Whether constraints of this type are exact equalities or range checks,
+    /// whose witness must match the coefficient for the statement to hold
+    pub fn is_exact(&self) -> bool {
+        !matches!(
+            self,
+            ConstraintType::HarmonicRatio
+                | ConstraintType::OctaveEquivalence
+                | ConstraintType::Consonance
+                | ConstraintType::IntervalSum
+        )
+    }
 }
 
 impl MusicalConstraint {
@@ -48,6 +165,12 @@ impl MusicalConstraint {
             });
         }
 
+        if constraint_type == ConstraintType::TuningConsistency {
+            // Range check against the 12-TET grid rather than a heuristic coefficient
+            let deviation = Self::twelve_tet_deviation_cents(ratio);
+            return Self::cents_within(deviation, DEFAULT_TUNING_TOLERANCE_CENTS);
+        }
+
         // Convert ratio to M31 field element with scaling
         let scaled_ratio = (ratio * M31_SCALE_FACTOR) as u32;
         let ratio_m31 = M31::from_u32_unchecked(scaled_ratio);
@@ -63,6 +186,90 @@ impl MusicalConstraint {
         })
     }
 
+    /// Create an exact integer equality constraint: `actual == expected`
+    ///
+    /// Used where floating point ratios would hide off-by-one errors, such as
+    /// measure durations counted in ticks.
+    pub fn equality(actual: u32, expected: u32, constraint_type: ConstraintType) -> Result<Self> {
+        if expected == 0 {
+            return Err(ZyrkomError::ConstraintError {
+                context: "Equality constraint needs a non-zero expected value".to_string(),
+            });
+        }
+
+        Ok(Self {
+            constraint_type,
+            ratio_m31: M31::from_u32_unchecked(actual),
+            ratio_f64: actual as f64 / expected as f64,
+            coefficient: M31::from_u32_unchecked(expected),
+        })
+    }
+
+    /// Create a range check constraint proving `0 <= slack < 2^30`
+    ///
+    /// The witness is the slack embedded in M31 and the coefficient is the
+    /// recomposition of the slack's low 30 bits. The bits are taken from the
+    /// integer, not its embedding: a slack in `[-(2^31 - 2), -2^30]` wraps to an
+    /// element that fits in 30 bits and would otherwise pass. Any slack outside
+    /// `[0, 2^30)` therefore yields an unsatisfied constraint.
+    pub fn range_check(slack: i64, constraint_type: ConstraintType) -> Result<Self> {
+        let modulus = i64::from(i32::MAX); // M31 prime: 2^31 - 1
+        if slack.abs() >= modulus {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!("Range check slack {} exceeds the M31 field", slack),
+            });
+        }
+
+        let mask = (1i64 << RANGE_CHECK_BITS) - 1;
+        let embedded = slack.rem_euclid(modulus) as u32;
+        let recomposed = if (0..=mask).contains(&slack) {
+            embedded
+        } else {
+            // Never equal to the embedding: a negative slack's low bits differ
+            // from it by an odd amount, a larger one's by a multiple of 2^30
+            (slack & mask) as u32
+        };
+
+        Ok(Self {
+            constraint_type,
+            ratio_m31: M31::from_u32_unchecked(embedded),
+            ratio_f64: slack as f64,
+            coefficient: M31::from_u32_unchecked(recomposed),
+        })
+    }
+
+    /// Create a tuning consistency constraint proving `|deviation| <= tolerance`
+    ///
+    /// Both values are converted to fixed point (thousandths of a cent) and the
+    /// slack `tolerance - |deviation|` is range checked.
+    pub fn cents_within(deviation_cents: f64, tolerance_cents: f64) -> Result<Self> {
+        if !deviation_cents.is_finite() || !tolerance_cents.is_finite() || tolerance_cents < 0.0 {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!(
+                    "Invalid cents tolerance check: deviation {}, tolerance {}",
+                    deviation_cents, tolerance_cents
+                ),
+            });
+        }
+
+        // Computed in floating point: the fixed point values may not fit an i64
+        let to_fixed = |cents: f64| (cents.abs() * CENTS_FIXED_POINT_SCALE).round();
+        let slack = to_fixed(tolerance_cents) - to_fixed(deviation_cents);
+        if slack.abs() >= f64::from(i32::MAX) {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!("Range check slack {} exceeds the M31 field", slack),
+            });
+        }
+
+        Self::range_check(slack as i64, ConstraintType::TuningConsistency)
+    }
+
+    /// Whether this constraint's witness matches its coefficient
+    /// (only meaningful for equality and range check constraints)
+    pub fn is_satisfied(&self) -> bool {
+        self.ratio_m31 == self.coefficient
+    }
+
     /// Generate constraint coefficient based on type and ratio
     fn generate_coefficient(constraint_type: &ConstraintType, ratio: f64) -> M31 {
         match constraint_type {
@@ -86,49 +293,40 @@ impl MusicalConstraint {
                 // For interval sums, use unity coefficient
                 M31::from_u32_unchecked(M31_SCALE_FACTOR as u32)
             }
-            ConstraintType::TuningConsistency => {
-                // Tuning consistency based on deviation from just intonation
-                let deviation = Self::calculate_tuning_deviation(ratio);
-                let coeff = (M31_SCALE_FACTOR * (1.0 - deviation)) as u32;
-                M31::from_u32_unchecked(coeff)
+            ConstraintType::TuningConsistency
+            | ConstraintType::MeasureDuration
+            | ConstraintType::Anacrusis
+            | ConstraintType::TempoBounds
+            | ConstraintType::ParallelMotion
+            | ConstraintType::VoiceCrossing
+            | ConstraintType::DissonanceTreatment
+            | ConstraintType::LeapResolution
+            | ConstraintType::VoiceLeadingDistance
+            | ConstraintType::ScaleStep
+            | ConstraintType::ChordTone
+            | ConstraintType::SetClass
+            | ConstraintType::NoteTiming
+            | ConstraintType::Transformation
+            | ConstraintType::Playability
+            | ConstraintType::Comparison => {
+                // Range checked and exact constraints carry their own coefficients;
+                // when normalised to a ratio the satisfied value is unity
+                M31::from_u32_unchecked(M31_SCALE_FACTOR as u32)
             }
         }
     }
 
-    /// Calculate harmonic complexity of a ratio (lower is more consonant)
+    /// Harmonic complexity of a ratio (lower is more consonant): one plus the
+    /// Tenney height of its simplest just reading, so unisons stay finite
     fn harmonic_complexity(ratio: f64) -> f64 {
-        // Known consonant ratios and their complexities
-        let consonant_ratios = [
-            (2.0, 1.0),      // Octave
-            (1.5, 2.0),      // Perfect fifth (3:2)
-            (4.0/3.0, 3.0),  // Perfect fourth (4:3)
-            (1.25, 4.0),     // Major third (5:4)
-            (6.0/5.0, 5.0),  // Minor third (6:5)
-        ];
-
-        // Find closest consonant ratio
-        let mut min_distance = f64::INFINITY;
-        let mut complexity = 10.0; // Default high complexity
-
-        for (consonant_ratio, consonant_complexity) in &consonant_ratios {
-            let distance = (ratio - consonant_ratio).abs();
-            if distance < min_distance {
-                min_distance = distance;
-                complexity = *consonant_complexity;
-            }
-        }
-
-        // Increase complexity based on distance from pure ratio
-        complexity + min_distance * 10.0
+        1.0 + consonance::complexity(ratio)
     }
 
-    /// Calculate tuning deviation from just intonation
-    fn calculate_tuning_deviation(ratio: f64) -> f64 {
-        // Calculate cents deviation from pure just intonation
-        let cents = 1200.0 * ratio.log2();
-        let pure_cents = (cents / 100.0).round() * 100.0; // Round to nearest 100 cents
-        let deviation = (cents - pure_cents).abs() / 1200.0; // Normalize to [0,1]
-        deviation.min(1.0)
+    /// Signed cents deviation of a ratio from the nearest 12-TET semitone
+    pub fn twelve_tet_deviation_cents(ratio: f64) -> f64 {
+        let cents = MusicalInterval::ratio_to_cents(ratio);
+        let nearest_semitone = (cents / 100.0).round() * 100.0;
+        cents - nearest_semitone
     }
 }
 
@@ -139,6 +337,17 @@ pub struct ConstraintSystem {
     pub constraints: Vec<MusicalConstraint>,
     /// Constraint relationships (which constraints must be satisfied together)
     pub relationships: Vec<ConstraintRelationship>,
+    /// Statement parameters exposed as public inputs (e.g. a cents tolerance)
+    pub public_parameters: Vec<PublicParameter>,
+}
+
+/// A named value that is part of the public statement being proved
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicParameter {
+    /// Human-readable name (e.g., "tolerance_millicents")
+    pub name: String,
+    /// Value as committed in the proof's public inputs
+    pub value: u32,
 }
 
 /// Relationship between constraints
@@ -167,6 +376,7 @@ impl ConstraintSystem {
         Self {
             constraints: Vec::new(),
             relationships: Vec::new(),
+            public_parameters: Vec::new(),
         }
     }
 
@@ -181,6 +391,124 @@ impl ConstraintSystem {
         self.relationships.push(relationship);
     }
 
+    /// Append another system's constraints and relationships to this one,
+    /// re-indexing its relationships so they keep pointing at the same constraints
+    pub fn merge(&mut self, other: ConstraintSystem) {
+        let offset = self.constraints.len();
+        self.constraints.extend(other.constraints);
+
+        for mut relationship in other.relationships {
+            for index in &mut relationship.constraint_indices {
+                *index += offset;
+            }
+            self.relationships.push(relationship);
+        }
+
+        self.public_parameters.extend(other.public_parameters);
+    }
+
+    /// Expose a value as part of the public statement
+    pub fn add_public_parameter(&mut self, name: &str, value: u32) {
+        self.public_parameters.push(PublicParameter {
+            name: name.to_string(),
+            value,
+        });
+    }
+
+    /// Expose the tuning system and its concert pitch as part of the public statement
+    pub fn declare_tuning(&mut self, tuning: &dyn TuningSystem) {
+        self.add_public_parameter("tuning_id", tuning.tuning_id());
+        self.add_public_parameter("tuning_divisions", tuning.divisions());
+        self.declare_pitch_reference(tuning.reference());
+    }
+
+    /// Expose the concert pitch as part of the public statement
+    pub fn declare_pitch_reference(&mut self, reference: PitchReference) {
+        self.public_parameters.retain(|p| p.name != PITCH_REFERENCE_PARAMETER);
+        self.add_public_parameter(PITCH_REFERENCE_PARAMETER, reference.millihertz());
+    }
+
+    /// Concert pitch declared in the public statement (standard pitch if none)
+    pub fn pitch_reference(&self) -> PitchReference {
+        self.public_parameters
+            .iter()
+            .find(|p| p.name == PITCH_REFERENCE_PARAMETER)
+            .and_then(|p| PitchReference::from_millihertz(p.value).ok())
+            .unwrap_or_default()
+    }
+
+    /// Expose the key of the passage as part of the public statement
+    pub fn declare_key(&mut self, key: Key) {
+        self.public_parameters.retain(|p| !KEY_PARAMETERS.contains(&p.name.as_str()));
+        self.add_public_parameter(KEY_PARAMETERS[0], key.tonic() as u32);
+        self.add_public_parameter(KEY_PARAMETERS[1], key.mode().id());
+    }
+
+    /// Key declared in the public statement, if any
+    pub fn key(&self) -> Option<Key> {
+        let parameter = |name: &str| self.public_parameters.iter().find(|p| p.name == name).map(|p| p.value);
+        let tonic = u8::try_from(parameter(KEY_PARAMETERS[0])?).ok()?;
+        let mode = Mode::from_id(parameter(KEY_PARAMETERS[1])?)?;
+        Key::new(tonic, mode).ok()
+    }
+
+    /// Describe the structure being proved, e.g. "Chord in F major"
+    ///
+    /// The kind is read from the constraint types present: quality-checked
+    /// chords, melodies, scales, set classes, neo-Riemannian transformations,
+    /// rhythm, counterpoint and voice leading each have their own; bare ratio
+    /// checks are an interval when there is a single ratio and a chord
+    /// otherwise. Mixed statements, and an empty system, are "Complex".
+    pub fn structure_type(&self) -> String {
+        let mut kinds: std::collections::BTreeSet<&str> = self
+            .constraints
+            .iter()
+            .map(|c| match c.constraint_type {
+                ConstraintType::HarmonicRatio
+                | ConstraintType::OctaveEquivalence
+                | ConstraintType::Consonance
+                | ConstraintType::IntervalSum
+                | ConstraintType::TuningConsistency => "Harmony",
+                ConstraintType::MeasureDuration
+                | ConstraintType::Anacrusis
+                | ConstraintType::TempoBounds => "Rhythm",
+                ConstraintType::ParallelMotion
+                | ConstraintType::VoiceCrossing
+                | ConstraintType::DissonanceTreatment
+                | ConstraintType::LeapResolution => "Counterpoint",
+                ConstraintType::VoiceLeadingDistance => "VoiceLeading",
+                ConstraintType::ScaleStep => "Scale",
+                ConstraintType::ChordTone => "Chord",
+                ConstraintType::SetClass => "SetClass",
+                ConstraintType::NoteTiming => "Melody",
+                ConstraintType::Transformation => "Transformation",
+                ConstraintType::Playability => "Playability",
+                ConstraintType::Comparison => "Comparison",
+            })
+            .collect();
+        // Chord-tone and timing checks come with ratio checks between the notes
+        if kinds.contains("Chord") || kinds.contains("Melody") {
+            kinds.remove("Harmony");
+        }
+
+        let ratios = self
+            .constraints
+            .iter()
+            .filter(|c| c.constraint_type == ConstraintType::HarmonicRatio)
+            .count();
+        let kind = match kinds.iter().next() {
+            Some(&"Harmony") if kinds.len() == 1 && ratios <= 1 => "Interval",
+            Some(&"Harmony") if kinds.len() == 1 => "Chord",
+            Some(&kind) if kinds.len() == 1 => kind,
+            _ => "Complex",
+        };
+
+        match self.key() {
+            Some(key) => format!("{} in {}", kind, key),
+            None => kind.to_string(),
+        }
+    }
+
     /// Get total number of constraints
     pub fn constraint_count(&self) -> usize {
         self.constraints.len()
@@ -208,6 +536,13 @@ impl ConstraintSystem {
 pub trait ToConstraints {
     /// Convert to a constraint system
     fn to_constraints(&self) -> Result<ConstraintSystem>;
+
+    /// Convert to a constraint system that declares the tuning it holds under
+    fn to_constraints_in(&self, tuning: &dyn TuningSystem) -> Result<ConstraintSystem> {
+        let mut system = self.to_constraints()?;
+        system.declare_tuning(tuning);
+        Ok(system)
+    }
 }
 
 /// Implementation for MusicalInterval
@@ -233,7 +568,7 @@ impl ToConstraints for MusicalInterval {
         }
 
         // Consonance constraint for pure ratios
-        if Self::is_consonant_ratio(self.ratio()) {
+        if consonance::is_consonant(self.ratio()) {
             let consonance_constraint = MusicalConstraint::from_ratio(
                 self.ratio(),
                 ConstraintType::Consonance,
@@ -244,16 +579,57 @@ impl ToConstraints for MusicalInterval {
         system.validate()?;
         Ok(system)
     }
-}
 
-impl MusicalInterval {
-    /// Check if a ratio is consonant (simple harmonic ratio)
-    fn is_consonant_ratio(ratio: f64) -> bool {
-        let consonant_ratios = [2.0, 1.5, 4.0/3.0, 1.25, 6.0/5.0];
-        consonant_ratios.iter().any(|&r| (ratio - r).abs() < 0.01)
+    fn to_constraints_in(&self, tuning: &dyn TuningSystem) -> Result<ConstraintSystem> {
+        let mut system = self.to_constraints()?;
+
+        // The interval must be available between two steps of the tuning
+        system.add_constraint(MusicalConstraint::cents_within(
+            tuning.interval_deviation_cents(self.cents()),
+            DEFAULT_TUNING_TOLERANCE_CENTS,
+        )?);
+        system.declare_tuning(tuning);
+
+        system.validate()?;
+        Ok(system)
     }
 }
 
+/// Compare the pitch classes of `notes` above `root` with those of `quality`
+///
+/// Both sets are sorted and compared position by position. Tones are counted
+/// from 1 (the root) because equalities need a non-zero expected value, and 13
+/// stands in for a missing tone, so a wrong, missing or extra tone leaves at
+/// least one `ChordTone` equality unsatisfied.
+fn chord_tone_constraints(
+    system: &mut ConstraintSystem,
+    notes: &[MusicalNote],
+    root: &MusicalNote,
+    quality: ChordQuality,
+) -> Result<Vec<usize>> {
+    const NO_TONE: u32 = 13;
+
+    let measured: Vec<u32> = notes
+        .iter()
+        .map(|note| {
+            let cents = root.interval_to(note).cents();
+            ((cents / 100.0).round() as i64).rem_euclid(12) as u32 + 1
+        })
+        .collect::<std::collections::BTreeSet<u32>>()
+        .into_iter()
+        .collect();
+    let expected: Vec<u32> = quality.pitch_classes().into_iter().map(|class| class as u32 + 1).collect();
+
+    (0..measured.len().max(expected.len()))
+        .map(|i| {
+            let actual = measured.get(i).copied().unwrap_or(NO_TONE);
+            let wanted = expected.get(i).copied().unwrap_or(NO_TONE);
+            MusicalConstraint::equality(actual, wanted, ConstraintType::ChordTone)
+                .map(|constraint| system.add_constraint(constraint))
+        })
+        .collect()
+}
+
 /// Implementation for Chord
 impl ToConstraints for Chord {
     fn to_constraints(&self) -> Result<ConstraintSystem> {
@@ -273,19 +649,406 @@ impl ToConstraints for Chord {
             }
         }
 
-        // Add chord-specific constraints
-        if notes.len() >= 3 {
-            // Ensure harmonic series relationship for triads
-            let root_to_third = notes[0].interval_to(&notes[1]);
-            let root_to_fifth = notes[0].interval_to(&notes[2]);
-            
-            // Create interval sum constraint: third + fourth should equal fifth
-            // This validates the harmonic consistency of the chord
-            let sum_constraint = MusicalConstraint::from_ratio(
-                root_to_fifth.ratio() / root_to_third.ratio(), // Should equal fourth ratio
-                ConstraintType::IntervalSum,
-            )?;
-            system.add_constraint(sum_constraint);
+        // Quality-specific constraints: the notes' pitch classes above the
+        // root are exactly those of the recognised quality
+        if let Some(recognition) = self.recognize() {
+            let indices = chord_tone_constraints(&mut system, notes, &recognition.root, recognition.quality)?;
+
+            system.add_relationship(ConstraintRelationship {
+                constraint_indices: indices,
+                relationship_type: RelationshipType::Conjunction,
+            });
+            system.add_public_parameter("chord_quality", recognition.quality.id());
+            system.add_public_parameter("chord_inversion", recognition.inversion as u32);
+        }
+
+        system.validate()?;
+        Ok(system)
+    }
+
+    fn to_constraints_in(&self, tuning: &dyn TuningSystem) -> Result<ConstraintSystem> {
+        let mut system = self.to_constraints()?;
+
+        // Every note must lie on a pitch of the tuning
+        let indices = self
+            .notes()
+            .iter()
+            .map(|note| {
+                MusicalConstraint::cents_within(tuning.deviation_cents(note.frequency()), DEFAULT_TUNING_TOLERANCE_CENTS)
+                    .map(|constraint| system.add_constraint(constraint))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: indices,
+            relationship_type: RelationshipType::Conjunction,
+        });
+        system.declare_tuning(tuning);
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// Implementation for VoiceLeading: proves every voice moves at most k semitones
+impl VoiceLeading {
+    /// Generate one range check `k - |motion| >= 0` per voice, in conjunction
+    pub fn bound_constraints(&self, max_semitones: u32) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+
+        let indices = self
+            .motions()
+            .iter()
+            .map(|motion| {
+                let slack = max_semitones as i64 - motion.unsigned_abs() as i64;
+                MusicalConstraint::range_check(slack, ConstraintType::VoiceLeadingDistance)
+                    .map(|constraint| system.add_constraint(constraint))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: indices,
+            relationship_type: RelationshipType::Conjunction,
+        });
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+impl Chord {
+    /// Prove that every voice moves by at most `max_semitones` across a
+    /// progression; the bound is exposed as a public parameter
+    pub fn voice_leading_constraints(progression: &[Chord], max_semitones: u32) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+
+        for leading in Chord::progression_voice_leading(progression)? {
+            system.merge(leading.bound_constraints(max_semitones)?);
+        }
+        system.add_public_parameter("max_voice_motion_semitones", max_semitones);
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// Implementation for Tempo: proves `min_bpm <= bpm <= max_bpm`
+impl Tempo {
+    /// Generate range checks showing this tempo lies within the bounds
+    pub fn bounds_constraints(&self, bounds: &TempoBounds) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+        let to_fixed = |bpm: f64| (bpm * TEMPO_FIXED_POINT_SCALE).round() as i64;
+
+        let bpm = to_fixed(self.bpm());
+        let lower = system.add_constraint(MusicalConstraint::range_check(
+            bpm - to_fixed(bounds.min_bpm),
+            ConstraintType::TempoBounds,
+        )?);
+        let upper = system.add_constraint(MusicalConstraint::range_check(
+            to_fixed(bounds.max_bpm) - bpm,
+            ConstraintType::TempoBounds,
+        )?);
+
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: vec![lower, upper],
+            relationship_type: RelationshipType::Conjunction,
+        });
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// Implementation for MeasureSequence
+impl ToConstraints for MeasureSequence {
+    fn to_constraints(&self) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+
+        let ticks_per_whole = self.ticks_per_whole()?;
+        let to_ticks = |ticks: Option<u64>| -> Result<u32> {
+            ticks
+                .and_then(|t| u32::try_from(t).ok())
+                .ok_or_else(|| ZyrkomError::ConstraintError {
+                    context: "Measure duration does not fit the tick resolution".to_string(),
+                })
+        };
+
+        let full = to_ticks(self.time_signature().measure_duration().to_ticks(ticks_per_whole))?;
+        let measures = self
+            .measure_durations()?
+            .iter()
+            .map(|d| to_ticks(d.to_ticks(ticks_per_whole)))
+            .collect::<Result<Vec<u32>>>()?;
+
+        for (index, &ticks) in measures.iter().enumerate() {
+            let is_first = index == 0;
+            let is_last = index == measures.len() - 1;
+
+            if self.has_anacrusis() && is_first {
+                // Pickup must be strictly between empty and a full measure
+                let lower = system.add_constraint(MusicalConstraint::range_check(
+                    ticks as i64 - 1,
+                    ConstraintType::Anacrusis,
+                )?);
+                let upper = system.add_constraint(MusicalConstraint::range_check(
+                    full as i64 - 1 - ticks as i64,
+                    ConstraintType::Anacrusis,
+                )?);
+                system.add_relationship(ConstraintRelationship {
+                    constraint_indices: vec![lower, upper],
+                    relationship_type: RelationshipType::Conjunction,
+                });
+            } else if self.has_anacrusis() && is_last && ticks != full {
+                // A short final measure must complete the pickup
+                system.add_constraint(MusicalConstraint::equality(
+                    ticks + measures[0],
+                    full,
+                    ConstraintType::Anacrusis,
+                )?);
+            } else {
+                system.add_constraint(MusicalConstraint::equality(
+                    ticks,
+                    full,
+                    ConstraintType::MeasureDuration,
+                )?);
+            }
+        }
+
+        if let (Some(tempo), Some(bounds)) = (self.tempo(), self.tempo_bounds()) {
+            system.merge(tempo.bounds_constraints(&bounds)?);
+        }
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// Implementation for Scale: commits to its declared step pattern
+///
+/// A `Scale` is its tonic plus that pattern, and its degree notes are derived
+/// from the pattern, so the per-step equalities hold by construction. The
+/// statement fixes which pattern (and tonic) is meant; it does not show that
+/// some independently chosen set of notes forms the scale. The final check
+/// shows the steps close the octave.
+impl ToConstraints for Scale {
+    fn to_constraints(&self) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+
+        let degrees = (1..=self.degree_count() as i32 + 1)
+            .map(|degree| self.degree(degree))
+            .collect::<Result<Vec<_>>>()?;
+        let measured: Vec<u32> = degrees
+            .windows(2)
+            .map(|pair| (pair[0].interval_to(&pair[1]).cents() / 100.0).round() as u32)
+            .collect();
+
+        let mut indices = measured
+            .iter()
+            .zip(self.steps())
+            .map(|(&actual, &expected)| {
+                MusicalConstraint::equality(actual, expected as u32, ConstraintType::ScaleStep)
+                    .map(|constraint| system.add_constraint(constraint))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        indices.push(system.add_constraint(MusicalConstraint::equality(
+            measured.iter().sum(),
+            crate::musical::scale::OCTAVE_SEMITONES as u32,
+            ConstraintType::ScaleStep,
+        )?));
+
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: indices,
+            relationship_type: RelationshipType::Conjunction,
+        });
+        system.add_public_parameter("scale_tonic_midi", self.tonic().midi_number() as u32);
+        system.add_public_parameter("scale_degree_count", self.degree_count() as u32);
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// Implementation for SetClassMembership: proves "this set is a member of 3-11"
+///
+/// The set's cardinality and its canonical T/I representative (the smallest
+/// bitmask over all transpositions and inversions) must both equal those of
+/// the declared class's prime form.
+impl ToConstraints for SetClassMembership {
+    fn to_constraints(&self) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+        let expected = self.class().prime_form();
+
+        let cardinality = system.add_constraint(MusicalConstraint::equality(
+            self.set().cardinality() as u32,
+            expected.cardinality() as u32,
+            ConstraintType::SetClass,
+        )?);
+        let class = system.add_constraint(MusicalConstraint::equality(
+            self.set().class_key() as u32,
+            expected.class_key() as u32,
+            ConstraintType::SetClass,
+        )?);
+
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: vec![cardinality, class],
+            relationship_type: RelationshipType::Conjunction,
+        });
+        system.add_public_parameter("set_class_cardinality", self.class().cardinality() as u32);
+        system.add_public_parameter("set_class_ordinal", self.class().ordinal() as u32);
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// Implementation for Melody: the intervals between notes and the timeline
+///
+/// Each step between consecutive notes is checked as the ratio of the upper
+/// to the lower pitch, so repeated notes are unisons rather than chord tones
+/// stacked on the first note. Each onset is range checked, in ticks, to come
+/// no earlier than the end of the previous note.
+impl ToConstraints for Melody {
+    fn to_constraints(&self) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+
+        let ticks_per_whole = self.ticks_per_whole()?;
+        let to_ticks = |duration: NoteDuration| -> Result<i64> {
+            duration
+                .to_ticks(ticks_per_whole)
+                .and_then(|t| i64::try_from(t).ok())
+                .ok_or_else(|| ZyrkomError::ConstraintError {
+                    context: "Note timing does not fit the tick resolution".to_string(),
+                })
+        };
+
+        let mut indices = Vec::new();
+        for pair in self.events().windows(2) {
+            let (lower, upper) = if pair[0].pitch().frequency() <= pair[1].pitch().frequency() {
+                (pair[0].pitch(), pair[1].pitch())
+            } else {
+                (pair[1].pitch(), pair[0].pitch())
+            };
+            indices.push(system.add_constraint(MusicalConstraint::from_ratio(
+                upper.frequency() / lower.frequency(),
+                ConstraintType::HarmonicRatio,
+            )?));
+            indices.push(system.add_constraint(MusicalConstraint::range_check(
+                to_ticks(pair[1].onset())? - to_ticks(pair[0].end()?)?,
+                ConstraintType::NoteTiming,
+            )?));
+        }
+
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: indices,
+            relationship_type: RelationshipType::Conjunction,
+        });
+        system.add_public_parameter("melody_note_count", self.events().len() as u32);
+        system.add_public_parameter(
+            "melody_ticks_per_whole",
+            u32::try_from(ticks_per_whole).map_err(|_| ZyrkomError::ConstraintError {
+                context: "Melody tick resolution does not fit a public input".to_string(),
+            })?,
+        );
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// Implementation for Score: every voice's melody constraints, side by side
+impl ToConstraints for Score {
+    fn to_constraints(&self) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+        for voice in self.voices() {
+            system.merge(voice.melody().to_constraints()?);
+        }
+        system.add_public_parameter("score_voice_count", self.voices().len() as u32);
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// Implementation for PlrDerivation: proves a progression follows a PLR word
+///
+/// Each triad after the first must equal the declared operation applied to
+/// the triad before it, compared by triad identifier. The start triad and
+/// each operation of the word are public.
+impl ToConstraints for PlrDerivation {
+    fn to_constraints(&self) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+
+        let indices = self
+            .triads()
+            .windows(2)
+            .zip(self.word().operations())
+            .map(|(pair, &operation)| {
+                MusicalConstraint::equality(
+                    pair[1].id(),
+                    pair[0].apply(operation).id(),
+                    ConstraintType::Transformation,
+                )
+                .map(|constraint| system.add_constraint(constraint))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: indices,
+            relationship_type: RelationshipType::Conjunction,
+        });
+        system.add_public_parameter("plr_start_triad", self.triads()[0].id());
+        for operation in self.word().operations() {
+            system.add_public_parameter("plr_operation", operation.id());
+        }
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// Implementation for InstrumentPart: proves a part is playable on an instrument
+///
+/// Every note is range checked, in semitones, against both ends of the
+/// instrument's range. At every onset the number of sounding notes is range
+/// checked against the polyphony and, when hands are limited, the hands the
+/// notes need against the hands available. The range and limits are public.
+impl ToConstraints for InstrumentPart {
+    fn to_constraints(&self) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+        let instrument = self.instrument();
+        let lowest = instrument.lowest().midi_number() as i64;
+        let highest = instrument.highest().midi_number() as i64;
+
+        let mut indices = Vec::new();
+        for voice in self.score().voices() {
+            for event in voice.melody().events() {
+                let midi = event.pitch().midi_number() as i64;
+                indices.push(system.add_constraint(MusicalConstraint::range_check(midi - lowest, ConstraintType::Playability)?));
+                indices.push(system.add_constraint(MusicalConstraint::range_check(highest - midi, ConstraintType::Playability)?));
+            }
+        }
+        for (_, notes) in self.simultaneities() {
+            indices.push(system.add_constraint(MusicalConstraint::range_check(
+                instrument.polyphony() as i64 - notes.len() as i64,
+                ConstraintType::Playability,
+            )?));
+            if let Some((hands, _)) = instrument.hand_span() {
+                indices.push(system.add_constraint(MusicalConstraint::range_check(
+                    hands as i64 - instrument.hands_needed(&notes) as i64,
+                    ConstraintType::Playability,
+                )?));
+            }
+        }
+
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: indices,
+            relationship_type: RelationshipType::Conjunction,
+        });
+        system.add_public_parameter("instrument_lowest_midi", lowest as u32);
+        system.add_public_parameter("instrument_highest_midi", highest as u32);
+        system.add_public_parameter("instrument_polyphony", instrument.polyphony() as u32);
+        if let Some((hands, stretch)) = instrument.hand_span() {
+            system.add_public_parameter("instrument_hands", hands as u32);
+            system.add_public_parameter("instrument_hand_stretch", stretch);
         }
 
         system.validate()?;
@@ -293,10 +1056,50 @@ impl ToConstraints for Chord {
     }
 }
 
+/// Implementation for CounterpointAnalysis: one constraint per rule check
+///
+/// Voice crossing is range checked on the semitone distance between the
+/// voices; the remaining rules are exact checks on the analysed outcome.
+impl ToConstraints for CounterpointAnalysis {
+    fn to_constraints(&self) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+
+        let indices = self
+            .checks()
+            .iter()
+            .map(|check| {
+                let constraint_type = match check.rule {
+                    CounterpointRule::ParallelFifths | CounterpointRule::ParallelOctaves => {
+                        ConstraintType::ParallelMotion
+                    }
+                    CounterpointRule::VoiceCrossing => ConstraintType::VoiceCrossing,
+                    CounterpointRule::DissonanceTreatment => ConstraintType::DissonanceTreatment,
+                    CounterpointRule::LeapResolution => ConstraintType::LeapResolution,
+                };
+
+                let constraint = match check.margin {
+                    Some(margin) => MusicalConstraint::range_check(margin, constraint_type)?,
+                    None => MusicalConstraint::equality(check.holds as u32, 1, constraint_type)?,
+                };
+                Ok(system.add_constraint(constraint))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: indices,
+            relationship_type: RelationshipType::Conjunction,
+        });
+        system.add_public_parameter("voice_count", self.voices().len() as u32);
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::musical::{MusicalInterval, MusicalNote};
+    use crate::musical::{MusicalInterval, MusicalNote, NoteValue, RhythmicValue, TimeSignature, Tuplet};
 
     #[test]
     fn test_constraint_from_perfect_fifth() {
@@ -325,14 +1128,42 @@ mod tests {
 
     #[test]
     fn test_chord_constraints() {
-        let root = MusicalNote::from_midi(60); // C4
-        let chord = Chord::major_triad(root);
+        let root = MusicalNote::from_midi(60).unwrap(); // C4
+        let chord = Chord::major_triad(root).unwrap();
         let system = chord.to_constraints().unwrap();
         
         assert!(system.constraint_count() > 0);
         system.validate().unwrap();
     }
 
+    #[test]
+    fn test_chord_constraints_follow_recognised_quality() {
+        let g7 = Chord::with_quality(MusicalNote::from_midi(55).unwrap(), ChordQuality::DominantSeventh).unwrap();
+        let system = g7.inversion(1).unwrap().to_constraints().unwrap();
+        let tones: Vec<_> = system
+            .constraints
+            .iter()
+            .filter(|c| c.constraint_type == ConstraintType::ChordTone)
+            .collect();
+        assert_eq!(tones.len(), 4);
+        assert!(tones.iter().all(|c| c.is_satisfied()));
+
+        // The notes of a G major triad are not a G minor triad
+        let g = Chord::major_triad(MusicalNote::from_midi(55).unwrap()).unwrap();
+        let mut wrong = ConstraintSystem::new();
+        let indices = chord_tone_constraints(&mut wrong, g.notes(), &g.root(), ChordQuality::Minor).unwrap();
+        assert!(indices.iter().any(|&i| !wrong.constraints[i].is_satisfied()));
+        // A missing seventh is caught too
+        let mut short = ConstraintSystem::new();
+        let indices = chord_tone_constraints(&mut short, g.notes(), &g.root(), ChordQuality::DominantSeventh).unwrap();
+        assert_eq!(indices.len(), 4);
+        assert!(!short.constraints[indices[3]].is_satisfied());
+
+        let parameter = |name: &str| system.public_parameters.iter().find(|p| p.name == name).unwrap().value;
+        assert_eq!(parameter("chord_quality"), ChordQuality::DominantSeventh.id());
+        assert_eq!(parameter("chord_inversion"), 1);
+    }
+
     #[test]
     fn test_harmonic_complexity() {
         // Perfect fifth should have low complexity
@@ -343,4 +1174,288 @@ mod tests {
         let tritone_complexity = MusicalConstraint::harmonic_complexity(1.414);
         assert!(tritone_complexity > 1.5); // Even more realistic threshold
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_measure_duration_constraints() {
+        let mut passage = MeasureSequence::new(TimeSignature::new(3, 4).unwrap());
+        passage.push_measure(vec![RhythmicValue::new(NoteValue::Quarter); 3]);
+        passage.push_measure(vec![
+            RhythmicValue::dotted(NoteValue::Quarter, 1),
+            RhythmicValue::new(NoteValue::Eighth),
+            RhythmicValue::new(NoteValue::Quarter),
+        ]);
+        passage.push_measure(vec![RhythmicValue::in_tuplet(NoteValue::Eighth, Tuplet::triplet()); 9]);
+
+        let system = passage.to_constraints().unwrap();
+        assert_eq!(system.constraint_count(), 3);
+        assert!(system.constraints.iter().all(|c| c.constraint_type == ConstraintType::MeasureDuration));
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+    }
+
+    #[test]
+    fn test_overfull_measure_is_unsatisfied() {
+        let mut passage = MeasureSequence::new(TimeSignature::common());
+        passage.push_measure(vec![RhythmicValue::new(NoteValue::Half); 3]);
+
+        let system = passage.to_constraints().unwrap();
+        assert!(!system.constraints[0].is_satisfied());
+    }
+
+    #[test]
+    fn test_anacrusis_constraints() {
+        // Pickup of one quarter, completed by a three-quarter final measure
+        let mut passage = MeasureSequence::new(TimeSignature::common()).with_anacrusis();
+        passage.push_measure(vec![RhythmicValue::new(NoteValue::Quarter)]);
+        passage.push_measure(vec![RhythmicValue::new(NoteValue::Whole)]);
+        passage.push_measure(vec![RhythmicValue::new(NoteValue::Quarter); 3]);
+
+        let system = passage.to_constraints().unwrap();
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+        assert_eq!(system.relationships.len(), 1);
+
+        // A pickup as long as a full measure is not a pickup
+        let mut full_pickup = MeasureSequence::new(TimeSignature::common()).with_anacrusis();
+        full_pickup.push_measure(vec![RhythmicValue::new(NoteValue::Whole)]);
+        let system = full_pickup.to_constraints().unwrap();
+        assert!(system.constraints.iter().any(|c| !c.is_satisfied()));
+    }
+
+    #[test]
+    fn test_tempo_bounds_constraints() {
+        let bounds = TempoBounds::new(72.0, 80.0).unwrap();
+
+        let in_range = Tempo::quarter(76.0).unwrap().bounds_constraints(&bounds).unwrap();
+        assert_eq!(in_range.constraint_count(), 2);
+        assert!(in_range.constraints.iter().all(|c| c.is_satisfied()));
+
+        let too_fast = Tempo::quarter(120.0).unwrap().bounds_constraints(&bounds).unwrap();
+        assert!(too_fast.constraints.iter().any(|c| !c.is_satisfied()));
+    }
+
+    #[test]
+    fn test_merge_reindexes_relationships() {
+        let mut system = MusicalInterval::perfect_fifth().to_constraints().unwrap();
+        let offset = system.constraint_count();
+        let tempo = Tempo::quarter(76.0).unwrap()
+            .bounds_constraints(&TempoBounds::new(60.0, 90.0).unwrap())
+            .unwrap();
+
+        system.merge(tempo);
+        assert_eq!(system.relationships[0].constraint_indices, vec![offset, offset + 1]);
+        system.validate().unwrap();
+    }
+
+    #[test]
+    fn test_tuning_consistency_is_range_checked() {
+        // Equal-tempered fifth is within 5 cents of the grid
+        let et_fifth = MusicalConstraint::from_ratio(2f64.powf(7.0 / 12.0), ConstraintType::TuningConsistency).unwrap();
+        assert!(et_fifth.is_satisfied());
+
+        // Just major third is ~13.7 cents flat of the 12-TET grid
+        let just_third = MusicalConstraint::from_ratio(1.25, ConstraintType::TuningConsistency).unwrap();
+        assert!(!just_third.is_satisfied());
+
+        assert!(MusicalConstraint::cents_within(-13.7, 15.0).unwrap().is_satisfied());
+        assert!(!MusicalConstraint::cents_within(15.001, 15.0).unwrap().is_satisfied());
+        assert!(MusicalConstraint::cents_within(1e300, 15.0).is_err());
+    }
+
+    #[test]
+    fn test_range_check_rejects_out_of_range_slack() {
+        assert!(MusicalConstraint::range_check(0, ConstraintType::Comparison).unwrap().is_satisfied());
+        assert!(MusicalConstraint::range_check((1 << 30) - 1, ConstraintType::Comparison).unwrap().is_satisfied());
+
+        // Negative slacks whose M31 embedding fits in 30 bits must still fail
+        for slack in [-1, -(1 << 30), -(1 << 30) - 1, -(i64::from(i32::MAX) - 1), 1 << 30] {
+            let constraint = MusicalConstraint::range_check(slack, ConstraintType::Comparison).unwrap();
+            assert!(!constraint.is_satisfied(), "slack {} passed", slack);
+        }
+    }
+
+    #[test]
+    fn test_counterpoint_constraints() {
+        let line = |midi: &[u8]| midi.iter().map(|&m| MusicalNote::from_midi(m).unwrap()).collect::<Vec<_>>();
+
+        let clean = CounterpointAnalysis::analyze(&[line(&[72, 71, 72]), line(&[60, 62, 64])]).unwrap();
+        let system = clean.to_constraints().unwrap();
+        assert_eq!(system.constraint_count(), clean.checks().len());
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+
+        // Parallel fifths C-G to D-A leave exactly one unsatisfied constraint
+        let parallel = CounterpointAnalysis::analyze(&[line(&[67, 69]), line(&[60, 62])]).unwrap();
+        let system = parallel.to_constraints().unwrap();
+        let failed: Vec<_> = system.constraints.iter().filter(|c| !c.is_satisfied()).collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].constraint_type, ConstraintType::ParallelMotion);
+    }
+
+    #[test]
+    fn test_voice_leading_distance_constraints() {
+        let c_major = Chord::major_triad(MusicalNote::from_midi(60).unwrap()).unwrap();
+        let f_major = Chord::major_triad(MusicalNote::from_midi(65).unwrap()).unwrap();
+
+        // C4 E4 G4 -> F4 A4 C5 in root position moves every voice by a fourth
+        let within_two = Chord::voice_leading_constraints(&[c_major.clone(), f_major.clone()], 2).unwrap();
+        assert_eq!(within_two.constraint_count(), 3);
+        assert!(within_two.constraints.iter().all(|c| !c.is_satisfied()));
+
+        let within_five = Chord::voice_leading_constraints(&[c_major, f_major], 5).unwrap();
+        assert!(within_five.constraints.iter().all(|c| c.is_satisfied()));
+        assert_eq!(within_five.public_parameters[0].value, 5);
+    }
+
+    #[test]
+    fn test_constraints_under_tuning() {
+        use crate::musical::{EqualTemperament, TwelveNoteTuning};
+
+        let just = TwelveNoteTuning::just_intonation(0).unwrap();
+        let tet = EqualTemperament::twelve_tet();
+
+        // A just C major triad lies on the just grid but not on 12-TET
+        let chord = Chord::major_triad_in(60, &just).unwrap();
+        let under_just = chord.to_constraints_in(&just).unwrap();
+        let tuning_checks = |system: &ConstraintSystem| {
+            system.constraints.iter()
+                .filter(|c| c.constraint_type == ConstraintType::TuningConsistency)
+                .all(|c| c.is_satisfied())
+        };
+        assert!(tuning_checks(&under_just));
+        assert!(!tuning_checks(&chord.to_constraints_in(&tet).unwrap()));
+
+        let id = under_just.public_parameters.iter().find(|p| p.name == "tuning_id").unwrap();
+        assert_eq!(id.value, just.tuning_id());
+
+        // Chords built from a quality use the same just ratios as the tuning
+        let c7 = Chord::with_quality(chord.root(), crate::musical::ChordQuality::DominantSeventh).unwrap();
+        assert!(tuning_checks(&c7.to_constraints_in(&just).unwrap()));
+
+        // The just major third is not available in 12-TET
+        let third = MusicalInterval::major_third();
+        assert!(third.to_constraints_in(&just).unwrap().constraints.last().unwrap().is_satisfied());
+        assert!(!third.to_constraints_in(&tet).unwrap().constraints.last().unwrap().is_satisfied());
+    }
+
+    #[test]
+    fn test_scale_step_constraints() {
+        use crate::musical::ScaleKind;
+
+        let major = Scale::major(MusicalNote::from_midi(65).unwrap()).to_constraints().unwrap();
+        assert_eq!(major.constraint_count(), 8);
+        assert!(major.constraints.iter().all(|c| c.is_satisfied()));
+        let steps: Vec<u32> = major.constraints[..7].iter().map(|c| c.coefficient.0).collect();
+        assert_eq!(steps, vec![2, 2, 1, 2, 2, 2, 1]);
+
+        let octatonic = Scale::new(MusicalNote::from_midi(60).unwrap(), ScaleKind::OctatonicHalfWhole)
+            .to_constraints()
+            .unwrap();
+        assert_eq!(octatonic.constraint_count(), 9);
+        assert_eq!(octatonic.public_parameters[1].value, 8);
+    }
+
+    #[test]
+    fn test_set_class_membership_constraints() {
+        let a_minor = Chord::minor_triad(MusicalNote::from_midi(57).unwrap()).unwrap();
+
+        let claim = SetClassMembership::for_notes(a_minor.notes(), "3-11").unwrap();
+        let system = claim.to_constraints().unwrap();
+        assert_eq!(system.constraint_count(), 2);
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+        assert_eq!(system.public_parameters[1].value, 11);
+
+        let false_claim = SetClassMembership::for_notes(a_minor.notes(), "3-12").unwrap();
+        assert!(!false_claim.to_constraints().unwrap().constraints[1].is_satisfied());
+    }
+
+    #[test]
+    fn test_structure_type_from_statement() {
+        let c4 = MusicalNote::from_midi(60).unwrap();
+        assert_eq!(MusicalInterval::perfect_fifth().to_constraints().unwrap().structure_type(), "Interval");
+        assert_eq!(Scale::major(c4).to_constraints().unwrap().structure_type(), "Scale");
+
+        let mut triad = Chord::major_triad(c4).unwrap().to_constraints().unwrap();
+        assert_eq!(triad.structure_type(), "Chord");
+        assert_eq!(triad.key(), None);
+
+        triad.declare_key(Key::parse("F major").unwrap());
+        triad.declare_key(Key::parse("C major").unwrap());
+        assert_eq!(triad.public_parameters.iter().filter(|p| p.name == KEY_PARAMETERS[0]).count(), 1);
+        assert_eq!(triad.structure_type(), "Chord in C major");
+
+        let mut mixed = triad.clone();
+        mixed.merge(Scale::major(c4).to_constraints().unwrap());
+        assert_eq!(mixed.structure_type(), "Complex in C major");
+        assert_eq!(ConstraintSystem::new().structure_type(), "Complex");
+    }
+
+    #[test]
+    fn test_melody_constraints() {
+        use crate::musical::{Voice, Score};
+
+        let quarter = RhythmicValue::new(NoteValue::Quarter);
+        // FA FA MI RE DO: a repeated note is a unison step, not a stacked chord tone
+        let notes: Vec<(MusicalNote, RhythmicValue)> = [65, 65, 64, 62, 60]
+            .iter()
+            .map(|&m| (MusicalNote::from_midi(m).unwrap(), quarter))
+            .collect();
+        let melody = Melody::from_notes(TimeSignature::common(), &notes).unwrap();
+
+        let system = melody.to_constraints().unwrap();
+        assert_eq!(system.constraint_count(), 8);
+        assert_eq!(system.constraints[0].ratio_f64, 1.0);
+        assert!(system.constraints.iter()
+            .filter(|c| c.constraint_type == ConstraintType::NoteTiming)
+            .all(|c| c.is_satisfied()));
+        assert_eq!(system.structure_type(), "Melody");
+
+        let mut score = Score::new(TimeSignature::common());
+        score.add_voice(Voice::new("upper", melody.clone())).unwrap();
+        score.add_voice(Voice::new("lower", melody.transpose(-12).unwrap())).unwrap();
+        let system = score.to_constraints().unwrap();
+        assert_eq!(system.constraint_count(), 16);
+        assert_eq!(system.relationships.len(), 2);
+    }
+
+    #[test]
+    fn test_plr_derivation_constraints() {
+        use crate::musical::{PlrWord, Triad};
+
+        let c_major = Chord::major_triad(MusicalNote::from_midi(60).unwrap()).unwrap();
+        let word = PlrWord::parse("LPR").unwrap();
+        let progression = c_major.plr_progression(&word).unwrap();
+
+        let system = PlrDerivation::new(word, &progression).unwrap().to_constraints().unwrap();
+        assert_eq!(system.constraint_count(), 3);
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+        assert_eq!(system.public_parameters[0].value, Triad::from_chord(&c_major).unwrap().id());
+        assert_eq!(system.structure_type(), "Transformation");
+
+        let wrong = PlrDerivation::new(PlrWord::parse("LRP").unwrap(), &progression).unwrap();
+        let system = wrong.to_constraints().unwrap();
+        assert!(system.constraints[0].is_satisfied());
+        assert!(!system.constraints[1].is_satisfied());
+    }
+
+    #[test]
+    fn test_instrument_part_constraints() {
+        use crate::musical::{Instrument, InstrumentPart, Melody, NoteValue, RhythmicValue, TimeSignature};
+
+        let q = RhythmicValue::new(NoteValue::Quarter);
+        let line = |midi: &[u8]| {
+            let notes: Vec<(MusicalNote, RhythmicValue)> = midi.iter().map(|&m| (MusicalNote::from_midi(m).unwrap(), q)).collect();
+            Melody::from_notes(TimeSignature::common(), &notes).unwrap()
+        };
+
+        let part = InstrumentPart::melody(Instrument::flute(), line(&[60, 72, 98])).unwrap();
+        let system = part.to_constraints().unwrap();
+        // Two range checks per note and one polyphony check per onset
+        assert_eq!(system.constraint_count(), 9);
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+        assert_eq!(system.structure_type(), "Playability");
+        assert_eq!(system.public_parameters[0].value, 60);
+
+        let unplayable = InstrumentPart::melody(Instrument::flute(), line(&[59, 72])).unwrap();
+        let system = unplayable.to_constraints().unwrap();
+        assert!(!system.constraints[0].is_satisfied());
+        assert_eq!(unplayable.violations().len(), 1);
+    }
+}
\ No newline at end of file
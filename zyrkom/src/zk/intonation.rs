@@ -0,0 +1,261 @@
+//! Intonation proofs: "every detected pitch is within ±N cents of the grid"
+//!
+//! Each detected frequency is reduced to a signed cents deviation from the
+//! nearest pitch of the declared grid, converted to fixed point, and compared
+//! against the tolerance N. The constraint system carries each comparison's
+//! slack, so it reveals how far every pitch sits inside the tolerance.
+
+use crate::musical::tuning::REFERENCE_STEP;
+use crate::musical::{constants, MusicalInterval, PitchReference, TuningSystem};
+use crate::zk::constraints::{
+    ConstraintRelationship, ConstraintSystem, MusicalConstraint, RelationshipType,
+    CENTS_FIXED_POINT_SCALE,
+};
+use crate::{Result, ZyrkomError};
+
+/// Public statement: the tolerance and the pitch grid it is measured against
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntonationClaim {
+    /// Maximum allowed deviation in cents (the public N)
+    tolerance_cents: f64,
+    /// Concert pitch: the grid's 0-cent pitch is A4 at this reference
+    reference: PitchReference,
+    /// Grid pitches in cents above A4, repeating every octave
+    grid_cents: Vec<f64>,
+}
+
+/// The pitches detected in a recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerformanceWitness {
+    /// Detected fundamental frequencies in Hz
+    detected_frequencies: Vec<f64>,
+}
+
+/// A detected pitch that falls outside the tolerance
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntonationViolation {
+    /// Position of the pitch in the witness
+    pub index: usize,
+    /// Detected frequency in Hz
+    pub frequency: f64,
+    /// Signed deviation from the nearest grid pitch in cents
+    pub deviation_cents: f64,
+}
+
+impl IntonationClaim {
+    /// Claim against 12-TET at standard pitch
+    pub fn twelve_tet(tolerance_cents: f64) -> Result<Self> {
+        Self::twelve_tet_at(tolerance_cents, PitchReference::standard())
+    }
+
+    /// Claim against 12-TET anchored at the A4 of a concert pitch
+    pub fn twelve_tet_at(tolerance_cents: f64, reference: PitchReference) -> Result<Self> {
+        let grid = (0..12).map(|semitone| semitone as f64 * 100.0).collect();
+        Self::with_grid(tolerance_cents, reference, grid)
+    }
+
+    /// Claim against the pitches of a tuning system
+    pub fn for_tuning(tolerance_cents: f64, tuning: &dyn TuningSystem) -> Result<Self> {
+        let reference = tuning.step_cents(REFERENCE_STEP);
+        let grid = (0..tuning.divisions() as i32)
+            .map(|step| (tuning.step_cents(REFERENCE_STEP + step) - reference).rem_euclid(constants::CENTS_PER_OCTAVE))
+            .collect();
+        Self::with_grid(tolerance_cents, tuning.reference(), grid)
+    }
+
+    /// Claim against a declared tuning: grid pitches in cents above A4 at
+    /// `reference`, repeating at the octave
+    pub fn with_grid(tolerance_cents: f64, reference: PitchReference, grid_cents: Vec<f64>) -> Result<Self> {
+        if !(0.0..constants::CENTS_PER_OCTAVE / 2.0).contains(&tolerance_cents) {
+            return Err(ZyrkomError::ConstraintError {
+                context: format!("Invalid cents tolerance: {}", tolerance_cents),
+            });
+        }
+        if grid_cents.is_empty()
+            || grid_cents.iter().any(|c| !(0.0..constants::CENTS_PER_OCTAVE).contains(c))
+        {
+            return Err(ZyrkomError::ConstraintError {
+                context: "Tuning grid must contain pitches in [0, 1200) cents".to_string(),
+            });
+        }
+
+        Ok(Self {
+            tolerance_cents,
+            reference,
+            grid_cents,
+        })
+    }
+
+    /// Get the tolerance in cents
+    pub fn tolerance_cents(&self) -> f64 {
+        self.tolerance_cents
+    }
+
+    /// Get the concert pitch the grid is anchored at
+    pub fn reference(&self) -> PitchReference {
+        self.reference
+    }
+
+    /// Signed cents deviation of a frequency from the nearest grid pitch
+    pub fn deviation_cents(&self, frequency: f64) -> f64 {
+        let cents = MusicalInterval::ratio_to_cents(frequency / self.reference.a4_frequency());
+        let within_octave = cents.rem_euclid(constants::CENTS_PER_OCTAVE);
+
+        self.grid_cents
+            .iter()
+            .flat_map(|&pitch| {
+                // Compare against the grid pitch in this octave and its neighbours
+                [-constants::CENTS_PER_OCTAVE, 0.0, constants::CENTS_PER_OCTAVE]
+                    .map(|shift| within_octave - (pitch + shift))
+            })
+            .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap_or(0.0)
+    }
+
+    /// List every detected pitch that falls outside the tolerance
+    pub fn violations(&self, witness: &PerformanceWitness) -> Vec<IntonationViolation> {
+        witness
+            .frequencies()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &frequency)| {
+                let deviation_cents = self.deviation_cents(frequency);
+                (deviation_cents.abs() > self.tolerance_cents).then_some(IntonationViolation {
+                    index,
+                    frequency,
+                    deviation_cents,
+                })
+            })
+            .collect()
+    }
+
+    /// Build the constraint system: one range check per detected pitch, all in
+    /// conjunction, with the tolerance and reference as public parameters
+    pub fn to_constraints(&self, witness: &PerformanceWitness) -> Result<ConstraintSystem> {
+        let mut system = ConstraintSystem::new();
+
+        let indices = witness
+            .frequencies()
+            .iter()
+            .map(|&frequency| {
+                let deviation = self.deviation_cents(frequency);
+                MusicalConstraint::cents_within(deviation, self.tolerance_cents)
+                    .map(|constraint| system.add_constraint(constraint))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+
+        system.add_relationship(ConstraintRelationship {
+            constraint_indices: indices,
+            relationship_type: RelationshipType::Conjunction,
+        });
+
+        system.add_public_parameter(
+            "tolerance_millicents",
+            (self.tolerance_cents * CENTS_FIXED_POINT_SCALE).round() as u32,
+        );
+        system.declare_pitch_reference(self.reference);
+
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+impl PerformanceWitness {
+    /// Create a witness from detected frequencies, rejecting non-physical values
+    pub fn new(detected_frequencies: Vec<f64>) -> Result<Self> {
+        if detected_frequencies.is_empty() {
+            return Err(ZyrkomError::ConstraintError {
+                context: "Performance witness has no detected pitches".to_string(),
+            });
+        }
+        if let Some(bad) = detected_frequencies.iter().find(|f| !(f.is_finite() && **f > 0.0)) {
+            return Err(ZyrkomError::PhysicsError {
+                details: format!("Invalid detected frequency: {}", bad),
+            });
+        }
+
+        Ok(Self { detected_frequencies })
+    }
+
+    /// Get the detected frequencies
+    pub fn frequencies(&self) -> &[f64] {
+        &self.detected_frequencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_tune_performance() {
+        // Slightly imperfect A4, C5 and E5 from a real take
+        let witness = PerformanceWitness::new(vec![440.8, 523.0, 660.2]).unwrap();
+        let claim = IntonationClaim::twelve_tet(10.0).unwrap();
+
+        assert!(claim.violations(&witness).is_empty());
+
+        let system = claim.to_constraints(&witness).unwrap();
+        assert_eq!(system.constraint_count(), 3);
+        assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+        assert_eq!(system.public_parameters[0].value, 10_000);
+    }
+
+    #[test]
+    fn test_out_of_tune_pitch_is_reported() {
+        // 452 Hz is ~47 cents sharp of A4
+        let witness = PerformanceWitness::new(vec![440.0, 452.0]).unwrap();
+        let claim = IntonationClaim::twelve_tet(10.0).unwrap();
+
+        let violations = claim.violations(&witness);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].index, 1);
+        assert!((violations[0].deviation_cents - 46.6).abs() < 0.1);
+
+        let system = claim.to_constraints(&witness).unwrap();
+        assert!(!system.constraints[1].is_satisfied());
+    }
+
+    #[test]
+    fn test_takes_share_the_public_statement() {
+        let claim = IntonationClaim::twelve_tet_at(10.0, PitchReference::baroque()).unwrap();
+        let takes = [vec![415.0, 493.5], vec![417.0, 491.5]];
+        let systems: Vec<_> = takes
+            .iter()
+            .map(|take| claim.to_constraints(&PerformanceWitness::new(take.clone()).unwrap()).unwrap())
+            .collect();
+
+        for system in &systems {
+            assert!(system.constraints.iter().all(|c| c.is_satisfied()));
+            assert_eq!(system.pitch_reference(), PitchReference::baroque());
+        }
+        assert_eq!(systems[0].public_parameters, systems[1].public_parameters);
+        // The slacks differ between takes: the deviations are not hidden
+        assert_ne!(systems[0].constraints[0].ratio_f64, systems[1].constraints[0].ratio_f64);
+    }
+
+    #[test]
+    fn test_declared_grid_and_octave_wrap() {
+        // Just major triad grid on A: 0, 386.3 (5:4), 702.0 (3:2)
+        let claim = IntonationClaim::with_grid(2.0, PitchReference::standard(), vec![0.0, 386.314, 701.955]).unwrap();
+        let witness = PerformanceWitness::new(vec![550.0, 660.0, 219.9]).unwrap();
+        assert!(claim.violations(&witness).is_empty());
+
+        // Equal-tempered C#5 is ~13.7 cents away from the just third
+        assert!((claim.deviation_cents(554.37) - 13.7).abs() < 0.1);
+
+        // The same grid comes from just intonation on A
+        let just = crate::musical::TwelveNoteTuning::just_intonation(9).unwrap();
+        let claim = IntonationClaim::for_tuning(2.0, &just).unwrap();
+        assert!(claim.violations(&witness).is_empty());
+        assert!((claim.deviation_cents(554.37) - 13.7).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        assert!(PerformanceWitness::new(vec![]).is_err());
+        assert!(PerformanceWitness::new(vec![440.0, -1.0]).is_err());
+        assert!(IntonationClaim::twelve_tet(-1.0).is_err());
+        assert!(IntonationClaim::with_grid(5.0, PitchReference::standard(), vec![1200.0]).is_err());
+    }
+}
@@ -9,11 +9,16 @@ pub mod constraints;
 pub mod stark;
 /// Stwo Component trait implementation for musical constraints
 pub mod component;
+/// Intonation proofs over detected pitches
+pub mod intonation;
+/// Where each constraint of a compiled program was declared
+pub mod source_map;
 
 pub use constraints::{
     MusicalConstraint,
     ConstraintSystem,
     ConstraintType,
+    PublicParameter,
     ToConstraints,
 };
 
@@ -28,4 +33,12 @@ pub use stark::{
     StarkInfo,
 };
 
-pub use component::ZyrkomComponent; 
\ No newline at end of file
+pub use component::ZyrkomComponent;
+
+pub use source_map::{SourceLocation, SourceMap};
+
+pub use intonation::{
+    IntonationClaim,
+    PerformanceWitness,
+    IntonationViolation,
+}; 
\ No newline at end of file
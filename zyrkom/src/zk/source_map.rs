@@ -0,0 +1,75 @@
+//! Source maps from constraints back to the statements that declared them
+//!
+//! A compiled program records where each of its constraints came from, so
+//! proof JSON and verification errors can name the line of the composition a
+//! constraint belongs to. Locations are plain byte ranges and line numbers,
+//! leaving the proving layer independent of the DSL front end.
+
+use std::fmt;
+use std::ops::Range;
+
+/// Where a constraint was declared
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SourceLocation {
+    /// Name of the element the constraint belongs to
+    pub element: String,
+    /// Byte range of the declaring statement
+    pub span: Range<usize>,
+    /// 1-based line of the statement
+    pub line: usize,
+    /// 1-based column of the statement, in characters
+    pub column: usize,
+}
+
+impl SourceLocation {
+    /// Locate a byte range of `source`
+    pub fn new(element: &str, span: Range<usize>, source: &str) -> Self {
+        let start = span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            element: element.to_string(),
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            span,
+        }
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` at line {}", self.element, self.line)
+    }
+}
+
+/// The declaring statement of each constraint, by constraint index
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    locations: Vec<SourceLocation>,
+}
+
+impl SourceMap {
+    /// Create a map from the location of each constraint, in constraint order
+    pub fn new(locations: Vec<SourceLocation>) -> Self {
+        Self { locations }
+    }
+
+    /// Where the constraint at `index` was declared
+    pub fn location(&self, index: usize) -> Option<&SourceLocation> {
+        self.locations.get(index)
+    }
+
+    /// Locations of every constraint, in constraint order
+    pub fn locations(&self) -> &[SourceLocation] {
+        &self.locations
+    }
+
+    /// Number of mapped constraints
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// Whether no constraint is mapped
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+}
\ No newline at end of file
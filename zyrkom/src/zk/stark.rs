@@ -3,6 +3,8 @@
 /// Integrates with Stwo framework to generate and verify ZK proofs
 /// of musical physics relationships.
 
+use crate::zk::source_map::{SourceLocation, SourceMap};
+use crate::musical::consonance;
 use crate::zk::constraints::{ConstraintSystem, MusicalConstraint};
 use crate::zk::component::ZyrkomComponent;
 use crate::{Result, ZyrkomError};
@@ -74,6 +76,9 @@ pub struct ConstraintInfo {
     pub ratio: f64,
     /// Frequency values involved
     pub frequencies: Vec<f64>,
+    /// DSL statement the constraint was compiled from, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceLocation>,
 }
 
 /// Public input information
@@ -121,6 +126,8 @@ pub struct StarkInfo {
 pub struct ZyrkomProver {
     /// Constraint system to prove
     constraint_system: ConstraintSystem,
+    /// Where each constraint was declared (empty when not compiled from DSL)
+    source_map: SourceMap,
 }
 
 /// Configuration for the prover
@@ -151,13 +158,40 @@ impl ZyrkomProver {
         
         Ok(Self {
             constraint_system,
+            source_map: SourceMap::default(),
         })
     }
 
+    /// Attach the source map of the program the constraints were compiled from
+    pub fn with_source_map(mut self, source_map: SourceMap) -> Self {
+        self.source_map = source_map;
+        self
+    }
+
 
 
     /// Generate a ZK proof for the musical constraints using real Stwo
+    ///
+    /// Fails before proving if an exact or range-checked constraint does not
+    /// hold, naming the statement it was declared by.
     pub fn prove(&self) -> Result<MusicalProof> {
+        if let Some(index) = self.constraint_system.constraints
+            .iter()
+            .position(|c| c.constraint_type.is_exact() && !c.is_satisfied()) {
+            let origin = self.source_map
+                .location(index)
+                .map(|location| format!(" ({})", location))
+                .unwrap_or_default();
+            return Err(ZyrkomError::ProofError {
+                reason: format!(
+                    "Constraint {}{} does not hold: {} check failed",
+                    index,
+                    origin,
+                    self.constraint_system.constraints[index].constraint_type.name()
+                ),
+            });
+        }
+
         // Create Zyrkom component from constraint system
         let component = ZyrkomComponent::new(self.constraint_system.clone())?;
         let components: Vec<&dyn ComponentProver<SimdBackend>> = vec![&component];
@@ -201,7 +235,7 @@ impl ZyrkomProver {
         // Generate public inputs and metadata
         let public_inputs = self.extract_public_inputs();
         let metadata = ProofMetadata {
-            structure_type: self.infer_structure_type(),
+            structure_type: self.constraint_system.structure_type(),
             constraint_count: self.constraint_system.constraint_count(),
             musical_ratios: self.extract_musical_ratios(),
             timestamp: std::time::SystemTime::now()
@@ -227,24 +261,22 @@ impl ZyrkomProver {
     ) -> Result<ZyrkomProofJson> {
         use crate::VERSION;
         
+        // Frequencies are reported against the concert pitch in the statement
+        let a4_frequency = self.constraint_system.pitch_reference().a4_frequency();
+
         // Extract musical constraints information
         let musical_constraints: Vec<ConstraintInfo> = self.constraint_system.constraints
             .iter()
             .enumerate()
             .map(|(index, constraint)| {
-                let constraint_type = match constraint.constraint_type {
-                    crate::zk::constraints::ConstraintType::HarmonicRatio => "harmonic_ratio",
-                    crate::zk::constraints::ConstraintType::OctaveEquivalence => "octave_equivalence", 
-                    crate::zk::constraints::ConstraintType::Consonance => "consonance",
-                    crate::zk::constraints::ConstraintType::IntervalSum => "interval_sum",
-                    crate::zk::constraints::ConstraintType::TuningConsistency => "tuning_consistency",
-                }.to_string();
+                let constraint_type = constraint.constraint_type.name().to_string();
                 
                 ConstraintInfo {
                     index,
                     constraint_type,
                     ratio: constraint.ratio_f64,
-                    frequencies: vec![440.0 * constraint.ratio_f64, 440.0], // A4 reference
+                    frequencies: vec![a4_frequency * constraint.ratio_f64, a4_frequency],
+                    source: self.source_map.location(index).cloned(),
                 }
             })
             .collect();
@@ -254,16 +286,21 @@ impl ZyrkomProver {
             .iter()
             .enumerate()
             .map(|(index, &value)| {
+                let first_parameter = self.constraint_system.constraint_count() + 1;
                 let description = match index {
-                    0 => "Constraint count",
-                    1 => "Musical structure identifier", 
-                    _ => "Constraint coefficient",
+                    0 => "Constraint count".to_string(),
+                    1 => "Musical structure identifier".to_string(), 
+                    i if i >= first_parameter => self.constraint_system.public_parameters
+                        .get(i - first_parameter)
+                        .map(|p| format!("Public parameter: {}", p.name))
+                        .unwrap_or_else(|| "Public parameter".to_string()),
+                    _ => "Constraint coefficient".to_string(),
                 };
                 
                 PublicInput {
                     index,
                     value: format!("0x{:x}", value),
-                    description: description.to_string(),
+                    description,
                 }
             })
             .collect();
@@ -339,12 +376,9 @@ impl ZyrkomProver {
         }
     }
 
-    /// Calculate harmonic error for a ratio
+    /// Calculate harmonic error for a ratio: distance to the nearest consonant just ratio
     fn calculate_harmonic_error(&self, ratio: f64) -> f64 {
-        let harmonic_ratios = [1.0, 2.0, 1.5, 4.0/3.0, 1.25, 6.0/5.0];
-        harmonic_ratios.iter()
-            .map(|&h| (ratio - h).abs())
-            .fold(f64::INFINITY, f64::min)
+        consonance::harmonic_distance(ratio)
     }
 
     /// Extract public inputs from constraints
@@ -356,14 +390,12 @@ impl ZyrkomProver {
         
         // Add constraint type indicators
         for constraint in &self.constraint_system.constraints {
-            let type_id = match constraint.constraint_type {
-                crate::zk::constraints::ConstraintType::HarmonicRatio => 1,
-                crate::zk::constraints::ConstraintType::OctaveEquivalence => 2,
-                crate::zk::constraints::ConstraintType::Consonance => 3,
-                crate::zk::constraints::ConstraintType::IntervalSum => 4,
-                crate::zk::constraints::ConstraintType::TuningConsistency => 5,
-            };
-            public_inputs.push(type_id);
+            public_inputs.push(constraint.constraint_type.type_id());
+        }
+        
+        // Add statement parameters (e.g. tolerances) after the type indicators
+        for parameter in &self.constraint_system.public_parameters {
+            public_inputs.push(parameter.value);
         }
         
         public_inputs
@@ -371,37 +403,6 @@ impl ZyrkomProver {
 
 
 
-    /// Validate a single constraint
-    #[allow(dead_code)]
-    fn validate_constraint(&self, constraint: &MusicalConstraint) -> bool {
-        match constraint.constraint_type {
-            crate::zk::constraints::ConstraintType::HarmonicRatio => {
-                // Check if ratio is close to a harmonic ratio
-                self.calculate_harmonic_error(constraint.ratio_f64) < 0.01
-            }
-            crate::zk::constraints::ConstraintType::OctaveEquivalence => {
-                // Check if ratio is close to 2.0
-                (constraint.ratio_f64 - 2.0).abs() < 0.001
-            }
-            crate::zk::constraints::ConstraintType::Consonance => {
-                // Check if ratio is consonant
-                let consonant_ratios = [2.0, 1.5, 4.0/3.0, 1.25, 6.0/5.0];
-                consonant_ratios.iter().any(|&r| (constraint.ratio_f64 - r).abs() < 0.01)
-            }
-            _ => true, // Other constraints always valid for now
-        }
-    }
-
-    /// Infer the type of musical structure being proved
-    fn infer_structure_type(&self) -> String {
-        match self.constraint_system.constraint_count() {
-            1 => "Interval".to_string(),
-            2..=5 => "Chord".to_string(),
-            6..=12 => "Scale".to_string(),
-            _ => "Complex".to_string(),
-        }
-    }
-
     /// Extract musical ratios for metadata
     fn extract_musical_ratios(&self) -> Vec<f64> {
         self.constraint_system.constraints
@@ -463,6 +464,8 @@ pub struct ZyrkomVerifier {
     trusted_component: ZyrkomComponent,
     /// Configuration for verification (reserved for future use)
     _config: VerifierConfig,
+    /// Where each trusted constraint was declared, cited in errors
+    source_map: SourceMap,
 }
 
 /// Configuration for the verifier
@@ -494,6 +497,7 @@ impl ZyrkomVerifier {
             trusted_constraint_system: constraint_system,
             trusted_component: component,
             _config: VerifierConfig::default(),
+            source_map: SourceMap::default(),
         })
     }
 
@@ -506,9 +510,25 @@ impl ZyrkomVerifier {
             trusted_constraint_system: constraint_system,
             trusted_component: component,
             _config: config,
+            source_map: SourceMap::default(),
         })
     }
 
+    /// Attach the source map of the trusted program, so errors name the
+    /// statement a mismatched constraint came from
+    pub fn with_source_map(mut self, source_map: SourceMap) -> Self {
+        self.source_map = source_map;
+        self
+    }
+
+    /// " (`name` at line N)" for a mapped constraint, empty otherwise
+    fn origin(&self, index: usize) -> String {
+        self.source_map
+            .location(index)
+            .map(|location| format!(" ({})", location))
+            .unwrap_or_default()
+    }
+
     /// Verify a musical proof using proper Stwo verification following official patterns
     pub fn verify(&self, proof: &MusicalProof) -> Result<bool> {
         // Step 1: Validate proof claims against TRUSTED constraints (not proof's own claims)
@@ -551,8 +571,8 @@ impl ZyrkomVerifier {
             if (proof_ratio - trusted_ratio).abs() > 0.001 {
                 return Err(ZyrkomError::ProofError {
                     reason: format!(
-                        "Musical ratio mismatch at index {}: proof has {} but trusted system has {}",
-                        i, proof_ratio, trusted_ratio
+                        "Musical ratio mismatch at index {}{}: proof has {} but trusted system has {}",
+                        i, self.origin(i), proof_ratio, trusted_ratio
                     ),
                 });
             }
@@ -581,20 +601,14 @@ impl ZyrkomVerifier {
         
         // Validate constraint type encoding matches trusted system
         for (i, constraint) in self.trusted_constraint_system.constraints.iter().enumerate() {
-            let expected_type_id = match constraint.constraint_type {
-                crate::zk::constraints::ConstraintType::HarmonicRatio => 1,
-                crate::zk::constraints::ConstraintType::OctaveEquivalence => 2,
-                crate::zk::constraints::ConstraintType::Consonance => 3,
-                crate::zk::constraints::ConstraintType::IntervalSum => 4,
-                crate::zk::constraints::ConstraintType::TuningConsistency => 5,
-            };
+            let expected_type_id = constraint.constraint_type.type_id();
             
             if let Some(&actual_type_id) = proof.public_inputs.get(i + 1) {
                 if actual_type_id != expected_type_id {
                     return Err(ZyrkomError::ProofError {
                         reason: format!(
-                            "Constraint type mismatch at index {}: proof has {} but trusted system expects {}",
-                            i, actual_type_id, expected_type_id
+                            "Constraint type mismatch at index {}{}: proof has {} but trusted system expects {}",
+                            i, self.origin(i), actual_type_id, expected_type_id
                         ),
                     });
         }
@@ -605,6 +619,27 @@ impl ZyrkomVerifier {
             }
         }
         
+        // Validate statement parameters match the trusted system
+        let first_parameter = self.trusted_constraint_system.constraint_count() + 1;
+        for (j, parameter) in self.trusted_constraint_system.public_parameters.iter().enumerate() {
+            match proof.public_inputs.get(first_parameter + j) {
+                Some(&value) if value == parameter.value => {}
+                Some(&value) => {
+                    return Err(ZyrkomError::ProofError {
+                        reason: format!(
+                            "Public parameter mismatch for {}: proof has {} but trusted system expects {}",
+                            parameter.name, value, parameter.value
+                        ),
+                    });
+                }
+                None => {
+                    return Err(ZyrkomError::ProofError {
+                        reason: format!("Missing public parameter {}", parameter.name),
+                    });
+                }
+            }
+        }
+        
         Ok(())
     }
     
@@ -666,7 +701,15 @@ mod tests {
         
         assert!(proof.stark_proof.size_estimate() > 0);
         assert!(!proof.public_inputs.is_empty());
-        assert_eq!(proof.metadata.structure_type, "Chord");
+        assert_eq!(proof.metadata.structure_type, "Interval");
+    }
+
+    #[test]
+    fn test_unsatisfied_range_check_is_not_proved() {
+        let mut constraints = ConstraintSystem::new();
+        constraints.add_constraint(MusicalConstraint::cents_within(12.0, 10.0).unwrap());
+        let error = ZyrkomProver::new(constraints).unwrap().prove().unwrap_err().to_string();
+        assert!(error.contains("tuning_consistency check failed"), "{}", error);
     }
 
     #[test]